@@ -0,0 +1,216 @@
+//! The canonical JSON encoding of messages holding `google.protobuf.Any`s, as used by
+//! [`JsonMapping::Canonical`](crate::invoke::JsonMapping::Canonical).
+//!
+//! An `Any` is encoded as its message with an `@type` field, which takes the type of the
+//! message. Types missing from the descriptor pool of the message holding the `Any` are
+//! looked up in the [`AnyTypes`] resolved through reflection, and an `Any` of a type that is
+//! still unknown is encoded as its `@type` and base64 encoded `value`, instead of failing the
+//! whole message.
+
+use base64::Engine;
+use prost_reflect::{DynamicMessage, Kind, MessageDescriptor, ReflectMessage};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashMap};
+
+/// The message types of `Any`s resolved apart from the descriptor pools of their messages,
+/// by type URL
+pub(crate) type AnyTypes = HashMap<String, MessageDescriptor>;
+
+const ANY: &str = "google.protobuf.Any";
+
+/// Converts `message` to its canonical JSON encoding, looking up the types of its `Any`s in
+/// its descriptor pool and then in `types`.
+///
+/// # Errors
+///
+/// This function will return an error if a message has no canonical JSON encoding, e.g. a
+/// `Timestamp` out of range.
+pub(crate) fn to_value(
+    message: &DynamicMessage,
+    types: &AnyTypes,
+) -> Result<Value, serde_json::Error> {
+    // prost-reflect encodes messages whose `Any`s are all in the pools of their messages
+    if !needs_types(message) {
+        return serde_json::to_value(message);
+    }
+    if message.descriptor().full_name() == ANY {
+        return any_to_value(message, types);
+    }
+
+    let descriptor = message.descriptor();
+    let mut fields = Map::new();
+    for (field, value) in message.fields() {
+        let value = if value_needs_types(value) {
+            field_to_value(&field.kind(), value, types)?
+        } else {
+            // encoded on its own so the field is mapped exactly like prost-reflect maps it
+            let mut single = DynamicMessage::new(descriptor.clone());
+            single.set_field(&field, value.clone());
+            match serde_json::to_value(&single)? {
+                Value::Object(mut single) => single.remove(field.json_name()),
+                _ => None,
+            }
+            .unwrap_or(Value::Null)
+        };
+        fields.insert(field.json_name().to_string(), value);
+    }
+    Ok(Value::Object(fields))
+}
+
+/// Returns the type URLs of the `Any`s in `message` whose types are neither in the
+/// descriptor pools of their messages nor in `types`.
+pub(crate) fn unknown_type_urls(message: &DynamicMessage, types: &AnyTypes) -> BTreeSet<String> {
+    let mut urls = BTreeSet::new();
+    visit_anys(message, types, &mut |url| {
+        urls.insert(url.to_string());
+    });
+    urls
+}
+
+/// Converts the `value` of a field of the `kind` holding an `Any` whose type is not in the
+/// pool of its message, which is a message, a list of messages or a map with message values.
+fn field_to_value(
+    kind: &Kind,
+    value: &prost_reflect::Value,
+    types: &AnyTypes,
+) -> Result<Value, serde_json::Error> {
+    use prost_reflect::Value as Proto;
+
+    match value {
+        Proto::Message(message) => to_value(message, types),
+        Proto::List(values) => values
+            .iter()
+            .map(|value| field_to_value(kind, value, types))
+            .collect(),
+        Proto::Map(entries) => {
+            let value_kind = match kind {
+                Kind::Message(entry) => entry.map_entry_value_field().kind(),
+                kind => kind.clone(),
+            };
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        prost_reflect::MapKey::String(key) => key.clone(),
+                        prost_reflect::MapKey::Bool(key) => key.to_string(),
+                        prost_reflect::MapKey::I32(key) => key.to_string(),
+                        prost_reflect::MapKey::I64(key) => key.to_string(),
+                        prost_reflect::MapKey::U32(key) => key.to_string(),
+                        prost_reflect::MapKey::U64(key) => key.to_string(),
+                    };
+                    Ok((key, field_to_value(&value_kind, value, types)?))
+                })
+                .collect::<Result<Map<_, _>, _>>()
+                .map(Value::Object)
+        }
+        // only messages hold `Any`s
+        _ => Ok(Value::Null),
+    }
+}
+
+/// Converts the `Any` `any` to its message with an `@type` field, or to its `@type` and
+/// base64 encoded `value` if its type is unknown.
+fn any_to_value(any: &DynamicMessage, types: &AnyTypes) -> Result<Value, serde_json::Error> {
+    let (url, value) = any_fields(any);
+    let mut fields = match payload(any, &url, &value, types) {
+        // a nested `Any` is an object with an `@type` of its own
+        Some(payload) if payload.descriptor().full_name() == ANY => {
+            let mut fields = Map::new();
+            fields.insert("value".to_string(), to_value(&payload, types)?);
+            fields
+        }
+        Some(payload) => match to_value(&payload, types)? {
+            Value::Object(fields) => fields,
+            value => {
+                let mut fields = Map::new();
+                fields.insert("value".to_string(), value);
+                fields
+            }
+        },
+        None => {
+            let mut fields = Map::new();
+            let value = base64::engine::general_purpose::STANDARD.encode(value);
+            fields.insert("value".to_string(), Value::String(value));
+            fields
+        }
+    };
+    fields.insert("@type".to_string(), Value::String(url));
+    Ok(Value::Object(fields))
+}
+
+/// Returns whether `message` holds an `Any` whose type is not in the pool of its message.
+fn needs_types(message: &DynamicMessage) -> bool {
+    let mut unknown = false;
+    visit_anys(message, &AnyTypes::new(), &mut |_| unknown = true);
+    unknown
+}
+
+/// Returns whether the field `value` holds an `Any` whose type is not in the pool of its
+/// message.
+fn value_needs_types(value: &prost_reflect::Value) -> bool {
+    let mut unknown = false;
+    visit_value(value, &AnyTypes::new(), &mut |_| unknown = true);
+    unknown
+}
+
+/// Calls `unknown` with the type URL of each `Any` of an unknown type in `message`,
+/// including those in the messages of `Any`s of known types.
+fn visit_anys(message: &DynamicMessage, types: &AnyTypes, unknown: &mut impl FnMut(&str)) {
+    if message.descriptor().full_name() == ANY {
+        let (url, value) = any_fields(message);
+        match payload(message, &url, &value, types) {
+            Some(payload) => visit_anys(&payload, types, unknown),
+            None => unknown(&url),
+        }
+        return;
+    }
+    for (_, value) in message.fields() {
+        visit_value(value, types, unknown);
+    }
+}
+
+/// Calls `unknown` like [`visit_anys`] for the messages of the field `value`.
+fn visit_value(value: &prost_reflect::Value, types: &AnyTypes, unknown: &mut impl FnMut(&str)) {
+    use prost_reflect::Value as Proto;
+
+    match value {
+        Proto::Message(message) => visit_anys(message, types, unknown),
+        Proto::List(values) => values
+            .iter()
+            .for_each(|value| visit_value(value, types, unknown)),
+        Proto::Map(entries) => entries
+            .values()
+            .for_each(|value| visit_value(value, types, unknown)),
+        _ => {}
+    }
+}
+
+/// Returns the `type_url` and `value` fields of the `Any` `any`.
+fn any_fields(any: &DynamicMessage) -> (String, Vec<u8>) {
+    let url = any
+        .get_field_by_name("type_url")
+        .and_then(|url| url.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let value = any
+        .get_field_by_name("value")
+        .and_then(|value| value.as_bytes().map(|value| value.to_vec()))
+        .unwrap_or_default();
+    (url, value)
+}
+
+/// Decodes the message of the `Any` `any` of the type `url`, or returns `None` if the type is
+/// unknown or the message is not of the type.
+fn payload(
+    any: &DynamicMessage,
+    url: &str,
+    value: &[u8],
+    types: &AnyTypes,
+) -> Option<DynamicMessage> {
+    let name = url.rsplit_once('/').map_or(url, |(_, name)| name);
+    let descriptor = any
+        .descriptor()
+        .parent_pool()
+        .get_message_by_name(name)
+        .or_else(|| types.get(url).cloned())?;
+    DynamicMessage::decode(descriptor, value).ok()
+}
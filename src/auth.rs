@@ -0,0 +1,70 @@
+//! Credentials attached to reflection requests as `authorization` metadata.
+
+use crate::error::ReflectionError;
+use base64::Engine;
+use std::fmt;
+use std::sync::Arc;
+use tonic::metadata::{AsciiMetadataValue, MetadataMap};
+
+const AUTHORIZATION: &str = "authorization";
+
+/// The credentials sent with every reflection request.
+#[derive(Clone)]
+pub(crate) enum Credentials {
+    /// A fixed `authorization` value
+    Static(String),
+    /// A callback returning the current bearer token, called for every request
+    Bearer(Arc<dyn Fn() -> String + Send + Sync>),
+}
+
+impl Credentials {
+    pub(crate) fn bearer(token: &str) -> Self {
+        Self::Static(format!("Bearer {}", token))
+    }
+
+    pub(crate) fn basic(user: &str, password: &str) -> Self {
+        let credentials = format!("{}:{}", user, password);
+        Self::Static(format!(
+            "Basic {}",
+            base64::engine::general_purpose::STANDARD.encode(credentials)
+        ))
+    }
+
+    /// Checks that static credentials are a valid metadata value, so invalid tokens are
+    /// rejected when building the client rather than on the first call.
+    pub(crate) fn validate(&self) -> Result<(), ReflectionError> {
+        match self {
+            Self::Static(value) => parse(value).map(|_| ()),
+            Self::Bearer(_) => Ok(()),
+        }
+    }
+
+    /// Sets the `authorization` entry of `metadata`, marked as sensitive so it is not logged.
+    pub(crate) fn apply(&self, metadata: &mut MetadataMap) -> Result<(), ReflectionError> {
+        let mut value = match self {
+            Self::Static(value) => parse(value)?,
+            Self::Bearer(token) => parse(&format!("Bearer {}", token()))?,
+        };
+        value.set_sensitive(true);
+        metadata.insert(AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Static(_) => f.write_str("Static(..)"),
+            Self::Bearer(_) => f.write_str("Bearer(..)"),
+        }
+    }
+}
+
+fn parse(value: &str) -> Result<AsciiMetadataValue, ReflectionError> {
+    value
+        .parse()
+        .map_err(|source| ReflectionError::InvalidMetadata {
+            key: AUTHORIZATION.to_string(),
+            source: Box::new(source),
+        })
+}
@@ -0,0 +1,27 @@
+//! The `grpc-ease` command line tool, whose commands live in [`grpc_ease::cli`].
+//!
+//! Logs are written to stderr as configured by the `RUST_LOG` environment variable, e.g.
+//! `RUST_LOG=grpc_ease=debug`.
+
+use clap::Parser;
+use grpc_ease::cli::Cli;
+use std::io;
+use std::process::ExitCode;
+use tracing_subscriber::EnvFilter;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+    match cli.run(&mut io::stdout().lock()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(error.exit_code())
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Caching file descriptors fetched from the server.
+
+use crate::message_info::TypeInfo;
+use prost_types::FileDescriptorProto;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use tonic_reflection::pb::server_reflection_request::MessageRequest;
+
+/// File descriptors fetched from the server, keyed by the symbol or file name they were
+/// requested for.
+///
+/// Clones share their entries, so clones of a client fill the same cache.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DescriptorCache {
+    ttl: Option<Duration>,
+    entries: Arc<Mutex<Entries>>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    symbols: HashMap<String, Entry>,
+    files: HashMap<String, Entry>,
+    /// The index of the types defined in the files of the services
+    types: Option<Entry<Vec<TypeInfo>>>,
+}
+
+#[derive(Debug)]
+struct Entry<T = Vec<FileDescriptorProto>> {
+    value: T,
+    /// When the entry was added, only tracked if entries expire, since `Instant` is not
+    /// available on every platform
+    added: Option<Instant>,
+}
+
+impl DescriptorCache {
+    /// Creates an empty cache whose entries expire after `ttl`, if set.
+    #[cfg(feature = "transport")]
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the cached response to `request`, if it was cached and has not expired.
+    pub(crate) fn get(&self, request: &MessageRequest) -> Option<Vec<FileDescriptorProto>> {
+        self.entries()
+            .get(request, self.ttl)
+            .map(|descriptors| descriptors.to_vec())
+    }
+
+    /// Returns the cached index of types, if it was cached and has not expired.
+    pub(crate) fn types(&self) -> Option<Vec<TypeInfo>> {
+        let entries = self.entries();
+        let entry = entries.types.as_ref()?;
+        entry.is_fresh(self.ttl).then(|| entry.value.clone())
+    }
+
+    /// Caches `types` as the index of types.
+    pub(crate) fn insert_types(&self, types: &[TypeInfo]) {
+        self.entries().types = Some(Entry {
+            value: types.to_vec(),
+            added: self.ttl.map(|_| Instant::now()),
+        });
+    }
+
+    /// Caches `descriptors` as the response to `request`.
+    ///
+    /// Each file of the response is also cached by its name, unless it is cached already.
+    pub(crate) fn insert(&self, request: &MessageRequest, descriptors: &[FileDescriptorProto]) {
+        let added = self.ttl.map(|_| Instant::now());
+        let entry = |value: Vec<FileDescriptorProto>| Entry { value, added };
+        let mut entries = self.entries();
+
+        match request {
+            MessageRequest::FileContainingSymbol(symbol) => {
+                entries
+                    .symbols
+                    .insert(symbol.clone(), entry(descriptors.to_vec()));
+            }
+            MessageRequest::FileByFilename(name) => {
+                entries
+                    .files
+                    .insert(name.clone(), entry(descriptors.to_vec()));
+            }
+            _ => return,
+        }
+
+        for file in descriptors {
+            let Some(name) = &file.name else {
+                continue;
+            };
+            let request = MessageRequest::FileByFilename(name.clone());
+            if entries.get(&request, self.ttl).is_none() {
+                entries
+                    .files
+                    .insert(name.clone(), entry(vec![file.clone()]));
+            }
+        }
+    }
+
+    /// Removes all entries.
+    pub(crate) fn clear(&self) {
+        let mut entries = self.entries();
+        entries.symbols.clear();
+        entries.files.clear();
+        entries.types = None;
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        // the entries stay consistent even if a thread panicked while holding the lock
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Entries {
+    fn get(
+        &self,
+        request: &MessageRequest,
+        ttl: Option<Duration>,
+    ) -> Option<&[FileDescriptorProto]> {
+        let entry = match request {
+            MessageRequest::FileContainingSymbol(symbol) => self.symbols.get(symbol),
+            MessageRequest::FileByFilename(name) => self.files.get(name),
+            _ => None,
+        }?;
+
+        entry.is_fresh(ttl).then_some(&entry.value)
+    }
+}
+
+impl<T> Entry<T> {
+    /// Returns whether the entry has not expired after `ttl`.
+    fn is_fresh(&self, ttl: Option<Duration>) -> bool {
+        match (ttl, self.added) {
+            (Some(ttl), Some(added)) => added.elapsed() < ttl,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Browsing schemas without a server, from file descriptor sets saved earlier.
+
+use crate::comments::Source;
+use crate::error::ReflectionError;
+use crate::lookup;
+use crate::message_info::{EnumInfo, MessageInfo, TypeInfo};
+use crate::reflection::{in_package, parse_method_path, service_info};
+use crate::service_info::{MethodInfo, ServiceInfo, Syntax};
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A source of the services and types of a schema, either a server queried with a
+/// [`ReflectionClient`](crate::reflection::ReflectionClient) or an [`OfflineCatalog`]
+///
+/// The trait is object safe, so tools can accept either source as a
+/// `Box<dyn ReflectionSource>`. The methods behave like those of the same name of
+/// `ReflectionClient`, including their errors.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// use grpc_ease::catalog::{OfflineCatalog, ReflectionSource};
+/// use prost_types::{FileDescriptorProto, FileDescriptorSet, ServiceDescriptorProto};
+///
+/// async fn print_services(source: &mut dyn ReflectionSource) -> Result<(), Box<dyn std::error::Error>> {
+///     for service in source.list_services().await? {
+///         println!("{}", service);
+///     }
+///     Ok(())
+/// }
+///
+/// let mut source: Box<dyn ReflectionSource> = match std::env::var("GRPC_SERVER") {
+///     Ok(endpoint) => Box::new(grpc_ease::reflection::ReflectionClient::new(endpoint).await?),
+///     Err(_) => Box::new(OfflineCatalog::new(FileDescriptorSet {
+///         file: vec![FileDescriptorProto {
+///             name: Some("shop.proto".to_string()),
+///             package: Some("shop".to_string()),
+///             service: vec![ServiceDescriptorProto {
+///                 name: Some("Orders".to_string()),
+///                 ..Default::default()
+///             }],
+///             ..Default::default()
+///         }],
+///     })),
+/// };
+/// print_services(source.as_mut()).await?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[tonic::async_trait]
+pub trait ReflectionSource: Send {
+    /// Lists the services of the schema with their methods.
+    async fn list_services(&mut self) -> Result<Vec<ServiceInfo>, ReflectionError>;
+
+    /// Retrieves the service with the fully qualified name `full_name`.
+    async fn get_service(&mut self, full_name: &str) -> Result<ServiceInfo, ReflectionError>;
+
+    /// Retrieves the method at `path`, e.g. `shop.Orders.PlaceOrder` or
+    /// `/shop.Orders/PlaceOrder`, along with the service defining it.
+    async fn get_method(
+        &mut self,
+        path: &str,
+    ) -> Result<(ServiceInfo, MethodInfo), ReflectionError>;
+
+    /// Describes the message `symbol`, e.g. `shop.Order`, with its fields.
+    async fn describe_message(&mut self, symbol: &str) -> Result<MessageInfo, ReflectionError>;
+
+    /// Describes the enum `symbol`, e.g. `shop.Order.Status`, with its values.
+    async fn describe_enum(&mut self, symbol: &str) -> Result<EnumInfo, ReflectionError>;
+}
+
+#[cfg(feature = "transport")]
+#[tonic::async_trait]
+impl ReflectionSource for crate::reflection::ReflectionClient {
+    async fn list_services(&mut self) -> Result<Vec<ServiceInfo>, ReflectionError> {
+        crate::reflection::ReflectionClient::list_services(self).await
+    }
+
+    async fn get_service(&mut self, full_name: &str) -> Result<ServiceInfo, ReflectionError> {
+        crate::reflection::ReflectionClient::get_service(self, full_name).await
+    }
+
+    async fn get_method(
+        &mut self,
+        path: &str,
+    ) -> Result<(ServiceInfo, MethodInfo), ReflectionError> {
+        crate::reflection::ReflectionClient::get_method(self, path).await
+    }
+
+    async fn describe_message(&mut self, symbol: &str) -> Result<MessageInfo, ReflectionError> {
+        crate::reflection::ReflectionClient::describe_message(self, symbol).await
+    }
+
+    async fn describe_enum(&mut self, symbol: &str) -> Result<EnumInfo, ReflectionError> {
+        crate::reflection::ReflectionClient::describe_enum(self, symbol).await
+    }
+}
+
+/// The services and types of a schema read from a `FileDescriptorSet`, e.g. written earlier
+/// by [`ReflectionClient::write_descriptor_set`](crate::reflection::ReflectionClient::write_descriptor_set)
+/// or by `buf build`, for when the server is unreachable
+///
+/// The catalog offers the lookups of the [`ReflectionClient`](crate::reflection::ReflectionClient)
+/// without any requests, and implements [`ReflectionSource`] to stand in for it. Unlike a
+/// client, it lists every service of the set, including infrastructure services. The set
+/// should include the files imported by its files, as `protoc --include_imports` and
+/// `buf build` do, so the enum types of fields can be described.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::catalog::OfflineCatalog;
+/// use grpc_ease::error::ReflectionError;
+///
+/// let catalog = OfflineCatalog::from_bytes(tonic_reflection::pb::FILE_DESCRIPTOR_SET)?;
+///
+/// let services = catalog.list_services()?;
+/// assert_eq!(services[0].full_name(), "grpc.reflection.v1alpha.ServerReflection");
+///
+/// let (_, method) = catalog.get_method("/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo")?;
+/// let request = catalog.describe_message(&method.request)?;
+/// assert_eq!(request.fields[0].name, "host");
+///
+/// let error = catalog.describe_message("grpc.reflection.v1alpha.ServerReflectionRequst").unwrap_err();
+/// assert!(matches!(
+///     error,
+///     ReflectionError::TypeNotFound { suggestions, .. }
+///         if suggestions[0] == "grpc.reflection.v1alpha.ServerReflectionRequest"
+/// ));
+/// # Ok::<(), ReflectionError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OfflineCatalog {
+    files: Vec<FileDescriptorProto>,
+}
+
+impl OfflineCatalog {
+    /// Creates a catalog of the files of `set`, keeping the first file of each name.
+    pub fn new(set: FileDescriptorSet) -> Self {
+        let mut files: Vec<FileDescriptorProto> = Vec::new();
+        for file in set.file {
+            if !files.iter().any(|kept| kept.name() == file.name()) {
+                files.push(file);
+            }
+        }
+        Self { files }
+    }
+
+    /// Creates a catalog of the encoded `FileDescriptorSet` `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::Decode`] if the bytes are not an encoded
+    /// `FileDescriptorSet`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReflectionError> {
+        let set = FileDescriptorSet::decode(bytes).map_err(ReflectionError::Decode)?;
+        Ok(Self::new(set))
+    }
+
+    /// Creates a catalog of the encoded `FileDescriptorSet` in the file at `path`, e.g.
+    /// `descriptor_set.bin`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The file cannot be read, as [`ReflectionError::ReadFile`]
+    /// - The file is not an encoded `FileDescriptorSet`, as [`ReflectionError::Decode`]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ReflectionError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| ReflectionError::ReadFile {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the file descriptors of the catalog.
+    pub fn files(&self) -> &[FileDescriptorProto] {
+        &self.files
+    }
+
+    /// Lists the services of the catalog, sorted by package and name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::MalformedDescriptor`] if a service or
+    /// method name is missing.
+    pub fn list_services(&self) -> Result<Vec<ServiceInfo>, ReflectionError> {
+        let mut services = Vec::new();
+        for file in &self.files {
+            for (index, service) in file.service.iter().enumerate() {
+                services.push(service_info(file, index, service.clone())?);
+            }
+        }
+        services.sort_by(|a, b| (&a.package, &a.service).cmp(&(&b.package, &b.service)));
+        Ok(services)
+    }
+
+    /// Retrieves the service with the fully qualified name `full_name`, like
+    /// [`ReflectionClient::get_service`](crate::reflection::ReflectionClient::get_service).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The catalog does not define a service of that name, as a
+    ///   [`ReflectionError::TypeNotFound`] suggesting the most similar service names
+    /// - The service or method names are missing
+    pub fn get_service(&self, full_name: &str) -> Result<ServiceInfo, ReflectionError> {
+        let name = full_name.trim_start_matches('.');
+        let services = lookup::services(&self.files);
+        match services
+            .iter()
+            .find(|(service_name, ..)| service_name == name)
+        {
+            Some((_, file, service)) => {
+                let index = file
+                    .service
+                    .iter()
+                    .position(|candidate| std::ptr::eq(candidate, *service))
+                    .unwrap_or_default();
+                service_info(file, index, (*service).clone())
+            }
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(
+                    name,
+                    services.iter().map(|(name, ..)| name.as_str()),
+                ),
+                name: name.to_string(),
+                kind: "service",
+            }),
+        }
+    }
+
+    /// Retrieves the method at `path` along with the service defining it, like
+    /// [`ReflectionClient::get_method`](crate::reflection::ReflectionClient::get_method).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`get_service`](Self::get_service), and:
+    /// - [`ReflectionError::InvalidMethodPath`] if the path names no service and method
+    /// - [`ReflectionError::MethodNotFound`], listing the methods of the service, if the
+    ///   service does not define the method
+    pub fn get_method(&self, path: &str) -> Result<(ServiceInfo, MethodInfo), ReflectionError> {
+        let (service_name, method_name) =
+            parse_method_path(path).ok_or_else(|| ReflectionError::InvalidMethodPath {
+                path: path.to_string(),
+            })?;
+        let service = self.get_service(service_name)?;
+
+        match service.method(method_name) {
+            Some(method) => {
+                let method = method.clone();
+                Ok((service, method))
+            }
+            None => Err(ReflectionError::MethodNotFound {
+                service: service_name.trim_start_matches('.').to_string(),
+                method: method_name.to_string(),
+                methods: service
+                    .methods
+                    .into_iter()
+                    .map(|method| method.name)
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Describes the message `symbol`, e.g. `shop.Order`, with its fields, like
+    /// [`ReflectionClient::describe_message`](crate::reflection::ReflectionClient::describe_message).
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::TypeNotFound`], suggesting similar
+    /// message names, if the catalog does not define a message of that name.
+    pub fn describe_message(&self, symbol: &str) -> Result<MessageInfo, ReflectionError> {
+        let name = symbol.trim_start_matches('.');
+        let messages = lookup::messages(&self.files);
+
+        match messages
+            .iter()
+            .find(|(message_name, _)| message_name == name)
+        {
+            Some((full_name, message)) => {
+                let enums: HashMap<String, EnumInfo> = lookup::enums(&self.files)
+                    .into_iter()
+                    .map(|(name, enumeration)| (name.clone(), EnumInfo::new(name, enumeration)))
+                    .collect();
+                let (source, syntax) = self
+                    .files
+                    .iter()
+                    .find_map(|file| Some((Source::message(file, message)?, Syntax::of(file))))
+                    .unwrap_or((Source::default(), Syntax::Proto2));
+                Ok(MessageInfo::new(
+                    full_name.clone(),
+                    message,
+                    &enums,
+                    &source,
+                    syntax,
+                ))
+            }
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(
+                    name,
+                    messages.iter().map(|(name, _)| name.as_str()),
+                ),
+                name: name.to_string(),
+                kind: "message",
+            }),
+        }
+    }
+
+    /// Describes the enum `symbol`, e.g. `shop.Order.Status`, with its values, like
+    /// [`ReflectionClient::describe_enum`](crate::reflection::ReflectionClient::describe_enum).
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::TypeNotFound`], suggesting similar enum
+    /// names, if the catalog does not define an enum of that name.
+    pub fn describe_enum(&self, symbol: &str) -> Result<EnumInfo, ReflectionError> {
+        let name = symbol.trim_start_matches('.');
+        let enums = lookup::enums(&self.files);
+
+        match enums.iter().find(|(enum_name, _)| enum_name == name) {
+            Some((full_name, enumeration)) => Ok(EnumInfo::new(full_name.clone(), enumeration)),
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(name, enums.iter().map(|(name, _)| name.as_str())),
+                name: name.to_string(),
+                kind: "enum",
+            }),
+        }
+    }
+
+    /// Lists the message and enum types in `package_prefix` or one of its subpackages, or
+    /// all types if the prefix is empty, like
+    /// [`ReflectionClient::list_types`](crate::reflection::ReflectionClient::list_types) but
+    /// including the types of every file of the catalog.
+    pub fn list_types(&self, package_prefix: &str) -> Vec<TypeInfo> {
+        let mut types: Vec<TypeInfo> = self
+            .files
+            .iter()
+            .flat_map(TypeInfo::defined_in)
+            .filter(|type_info| in_package(&type_info.full_name, package_prefix))
+            .collect();
+        types.sort();
+        types
+    }
+}
+
+#[tonic::async_trait]
+impl ReflectionSource for OfflineCatalog {
+    async fn list_services(&mut self) -> Result<Vec<ServiceInfo>, ReflectionError> {
+        OfflineCatalog::list_services(self)
+    }
+
+    async fn get_service(&mut self, full_name: &str) -> Result<ServiceInfo, ReflectionError> {
+        OfflineCatalog::get_service(self, full_name)
+    }
+
+    async fn get_method(
+        &mut self,
+        path: &str,
+    ) -> Result<(ServiceInfo, MethodInfo), ReflectionError> {
+        OfflineCatalog::get_method(self, path)
+    }
+
+    async fn describe_message(&mut self, symbol: &str) -> Result<MessageInfo, ReflectionError> {
+        OfflineCatalog::describe_message(self, symbol)
+    }
+
+    async fn describe_enum(&mut self, symbol: &str) -> Result<EnumInfo, ReflectionError> {
+        OfflineCatalog::describe_enum(self, symbol)
+    }
+}
@@ -0,0 +1,362 @@
+//! The commands of the `grpc-ease` binary, built with the `cli` feature.
+//!
+//! The commands write their output to any [`Write`]r rather than to stdout, so they can be run
+//! and tested without spawning the binary, which only parses its arguments into a [`Cli`],
+//! runs it, and exits with the [`exit_code`](CliError::exit_code) of its error.
+
+use crate::error::ReflectionError;
+use crate::reflection::ReflectionClient;
+use clap::{Args, Parser, Subcommand};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Explores gRPC servers through server reflection
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::cli::{Cli, Command};
+/// use std::time::Duration;
+///
+/// let cli = Cli::try_parse_from([
+///     "grpc-ease",
+///     "list",
+///     "http://localhost:50051",
+///     "--package",
+///     "shop.v1",
+///     "--no-infrastructure",
+///     "--connect-timeout",
+///     "1.5",
+/// ])?;
+/// let Command::List(args) = cli.command else {
+///     panic!("expected the list command");
+/// };
+/// assert_eq!(args.endpoint, "http://localhost:50051");
+/// assert_eq!(args.package.as_deref(), Some("shop.v1"));
+/// assert!(args.no_infrastructure);
+/// assert_eq!(args.connect_timeout, Duration::from_millis(1500));
+///
+/// let cli = Cli::try_parse_from(["grpc-ease", "describe", "http://localhost:50051", "shop.Orders"])?;
+/// let Command::Describe(args) = cli.command else {
+///     panic!("expected the describe command");
+/// };
+/// assert_eq!(args.connect_timeout, Duration::from_secs(5));
+///
+/// let error = Cli::try_parse_from(["grpc-ease", "list", "http://localhost:50051", "--connect-timeout", "0"]);
+/// assert!(error.is_err());
+/// # Ok::<(), clap::Error>(())
+/// ```
+#[derive(Debug, Clone, Parser)]
+#[command(
+    name = "grpc-ease",
+    version,
+    about = "Explores gRPC servers through server reflection",
+    long_about = None
+)]
+pub struct Cli {
+    /// The command to run
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// A command of the `grpc-ease` binary
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// List the services of a server along with their methods
+    List(ListArgs),
+    /// Describe a service, method, message or enum of a server
+    Describe(DescribeArgs),
+}
+
+/// The arguments of `grpc-ease list`
+#[derive(Debug, Clone, Args)]
+pub struct ListArgs {
+    /// The endpoint of the server, e.g. http://localhost:50051
+    pub endpoint: String,
+    /// Only list the services in this package and its subpackages, e.g. shop for shop.Orders
+    /// and shop.v1.Carts
+    #[arg(long, value_name = "PREFIX")]
+    pub package: Option<String>,
+    /// Hide the reflection and health services
+    #[arg(long)]
+    pub no_infrastructure: bool,
+    /// How long to wait for the connection to the server, in seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds, default_value = "5")]
+    pub connect_timeout: Duration,
+}
+
+/// The arguments of `grpc-ease describe`
+#[derive(Debug, Clone, Args)]
+pub struct DescribeArgs {
+    /// The endpoint of the server, e.g. http://localhost:50051
+    pub endpoint: String,
+    /// The fully qualified name of the symbol, e.g. shop.Orders, shop.Orders.Place or
+    /// shop.Order, or the path of a method, e.g. /shop.Orders/Place
+    pub symbol: String,
+    /// How long to wait for the connection to the server, in seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_seconds, default_value = "5")]
+    pub connect_timeout: Duration,
+}
+
+impl Cli {
+    /// Parses the command line arguments `args`, starting with the name of the binary.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`clap::Error`] if the arguments are invalid, or if they
+    /// ask for the help or version, which the error prints.
+    pub fn try_parse_from<I, S>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString> + Clone,
+    {
+        <Self as Parser>::try_parse_from(args)
+    }
+
+    /// Runs the command, writing its output to `out`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the command fails, see [`CliError`].
+    pub async fn run(self, out: &mut impl Write) -> Result<(), CliError> {
+        match self.command {
+            Command::List(args) => list(&args, out).await,
+            Command::Describe(args) => describe(&args, out).await,
+        }
+    }
+}
+
+/// Lists the services of the server at the endpoint of `args`, writing each service with the
+/// signatures of its methods to `out`, as formatted by
+/// [`ServiceInfo`](crate::service_info::ServiceInfo).
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The endpoint is invalid or cannot be reached, as [`CliError::Reflection`]
+/// - The services cannot be listed, as [`CliError::Reflection`]
+/// - Writing to `out` fails, as [`CliError::Output`]
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::cli::{list, ListArgs};
+/// use std::time::{Duration, Instant};
+///
+/// let (_, health) = tonic_health::server::health_reporter();
+/// # let reflection = support::reflection(&[
+/// #     tonic_health::pb::FILE_DESCRIPTOR_SET,
+/// #     tonic_reflection::pb::FILE_DESCRIPTOR_SET,
+/// # ]);
+/// # let endpoint = support::serve(
+/// #     tonic::transport::Server::builder()
+/// #         .add_service(health)
+/// #         .add_service(reflection),
+/// # )
+/// # .await?;
+///
+/// let args = ListArgs {
+///     endpoint,
+///     package: Some("grpc.health".to_string()),
+///     no_infrastructure: false,
+///     connect_timeout: Duration::from_secs(5),
+/// };
+/// let mut out = Vec::new();
+/// list(&args, &mut out).await?;
+/// assert_eq!(
+///     String::from_utf8(out)?,
+///     "grpc.health.v1.Health\n  \
+///      rpc Check(grpc.health.v1.HealthCheckRequest) returns (grpc.health.v1.HealthCheckResponse)\n  \
+///      rpc Watch(grpc.health.v1.HealthCheckRequest) returns (stream grpc.health.v1.HealthCheckResponse)\n"
+/// );
+///
+/// // the health and reflection services are all there is
+/// let args = ListArgs { package: None, no_infrastructure: true, ..args };
+/// let mut out = Vec::new();
+/// list(&args, &mut out).await?;
+/// assert!(out.is_empty());
+///
+/// // nothing listens on port 1
+/// let args = ListArgs { endpoint: "http://127.0.0.1:1".to_string(), ..args };
+/// let error = list(&args, &mut Vec::new()).await.unwrap_err();
+/// assert_eq!(error.exit_code(), 3);
+///
+/// // nor does anything answer on a port whose listener is swamped, which is given up on
+/// # let blackhole = support::blackhole().await?;
+/// let args = ListArgs {
+///     endpoint: blackhole.endpoint.clone(),
+///     connect_timeout: Duration::from_millis(200),
+///     ..args
+/// };
+/// let started = Instant::now();
+/// let error = list(&args, &mut Vec::new()).await.unwrap_err();
+/// assert_eq!(error.exit_code(), 3);
+/// assert!(started.elapsed() < Duration::from_secs(2));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+pub async fn list(args: &ListArgs, out: &mut impl Write) -> Result<(), CliError> {
+    let mut client = connect(
+        &args.endpoint,
+        !args.no_infrastructure,
+        args.connect_timeout,
+    )
+    .await?;
+    let services = match &args.package {
+        Some(package) => client.list_services_in_package(package).await?,
+        None => client.list_services().await?,
+    };
+
+    for service in services {
+        writeln!(out, "{}", service)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Describes the symbol of `args` on the server at its endpoint, writing the description to
+/// `out` as formatted by [`SymbolDescription`](crate::describe::SymbolDescription).
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The endpoint is invalid or cannot be reached, as [`CliError::Reflection`]
+/// - The server defines no such symbol, as [`CliError::Reflection`] suggesting similar names
+/// - Writing to `out` fails, as [`CliError::Output`]
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::cli::{describe, DescribeArgs};
+/// use std::time::Duration;
+///
+/// let (_, health) = tonic_health::server::health_reporter();
+/// # let reflection = support::reflection(&[
+/// #     tonic_health::pb::FILE_DESCRIPTOR_SET,
+/// #     tonic_reflection::pb::FILE_DESCRIPTOR_SET,
+/// # ]);
+/// # let endpoint = support::serve(
+/// #     tonic::transport::Server::builder()
+/// #         .add_service(health)
+/// #         .add_service(reflection),
+/// # )
+/// # .await?;
+///
+/// let args = DescribeArgs {
+///     endpoint,
+///     symbol: "grpc.health.v1.HealthCheckRequest".to_string(),
+///     connect_timeout: Duration::from_secs(5),
+/// };
+/// let mut out = Vec::new();
+/// describe(&args, &mut out).await?;
+/// assert_eq!(
+///     String::from_utf8(out)?,
+///     "message grpc.health.v1.HealthCheckRequest {\n  string service = 1;\n}\n"
+/// );
+///
+/// let args = DescribeArgs { symbol: "grpc.health.v1.Healht".to_string(), ..args };
+/// let error = describe(&args, &mut Vec::new()).await.unwrap_err();
+/// assert_eq!(error.exit_code(), 1);
+/// assert_eq!(
+///     error.to_string(),
+///     "No symbol named \"grpc.health.v1.Healht\" was found, did you mean grpc.health.v1.Health?"
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+pub async fn describe(args: &DescribeArgs, out: &mut impl Write) -> Result<(), CliError> {
+    let mut client = connect(&args.endpoint, true, args.connect_timeout).await?;
+    let description = client.describe(&args.symbol).await?;
+
+    writeln!(out, "{}", description)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Connects to the server at `endpoint` within `timeout`, listing its reflection and health
+/// services along with the others if `infrastructure` is set.
+async fn connect(
+    endpoint: &str,
+    infrastructure: bool,
+    timeout: Duration,
+) -> Result<ReflectionClient, CliError> {
+    let client = ReflectionClient::builder(endpoint)
+        .include_infrastructure_services(infrastructure)
+        .connect_timeout(timeout)
+        .build()
+        .await?;
+    Ok(client)
+}
+
+/// Parses a positive number of seconds like `2.5`.
+fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    seconds
+        .parse::<f64>()
+        .ok()
+        .filter(|seconds| *seconds > 0.0)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("{:?} is not a positive number of seconds", seconds))
+}
+
+/// The error of a command of the `grpc-ease` binary
+#[derive(Debug)]
+pub enum CliError {
+    /// Talking to the server failed, or it does not define the requested symbol
+    Reflection(ReflectionError),
+    /// The output could not be written, e.g. because stdout was closed
+    Output(io::Error),
+}
+
+impl CliError {
+    /// Returns the exit code of the binary for this error:
+    /// - `3` if the endpoint cannot be reached
+    /// - `1` for any other error
+    ///
+    /// Invalid arguments exit with `2`, as reported by clap.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Reflection(
+                ReflectionError::Connect { .. }
+                | ReflectionError::ConnectTimeout { .. }
+                | ReflectionError::AllEndpointsFailed { .. },
+            ) => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Reflection(error) => write!(f, "{}", error),
+            Self::Output(error) => write!(f, "Failed to write the output: {}", error),
+        }
+    }
+}
+
+impl Error for CliError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Reflection(error) => Some(error),
+            Self::Output(error) => Some(error),
+        }
+    }
+}
+
+impl From<ReflectionError> for CliError {
+    fn from(error: ReflectionError) -> Self {
+        Self::Reflection(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        Self::Output(error)
+    }
+}
@@ -0,0 +1,138 @@
+//! Locating the comments of definitions in the source code info of file descriptors.
+
+use prost_types::{DescriptorProto, FileDescriptorProto, SourceCodeInfo};
+
+/// The field number of the package in `FileDescriptorProto`.
+pub(crate) const FILE_PACKAGE: i32 = 2;
+/// The field number of the messages in `FileDescriptorProto`.
+pub(crate) const FILE_MESSAGE_TYPE: i32 = 4;
+/// The field number of the enums in `FileDescriptorProto`.
+pub(crate) const FILE_ENUM_TYPE: i32 = 5;
+/// The field number of the services in `FileDescriptorProto`.
+pub(crate) const FILE_SERVICE: i32 = 6;
+/// The field number of the extensions in `FileDescriptorProto`.
+pub(crate) const FILE_EXTENSION: i32 = 7;
+/// The field number of the syntax in `FileDescriptorProto`.
+pub(crate) const FILE_SYNTAX: i32 = 12;
+/// The field number of the fields in `DescriptorProto`.
+pub(crate) const MESSAGE_FIELD: i32 = 2;
+/// The field number of the nested messages in `DescriptorProto`.
+pub(crate) const MESSAGE_NESTED_TYPE: i32 = 3;
+/// The field number of the nested enums in `DescriptorProto`.
+pub(crate) const MESSAGE_ENUM_TYPE: i32 = 4;
+/// The field number of the extensions in `DescriptorProto`.
+pub(crate) const MESSAGE_EXTENSION: i32 = 6;
+/// The field number of the oneofs in `DescriptorProto`.
+pub(crate) const MESSAGE_ONEOF_DECL: i32 = 8;
+/// The field number of the values in `EnumDescriptorProto`.
+pub(crate) const ENUM_VALUE: i32 = 2;
+/// The field number of the methods in `ServiceDescriptorProto`.
+pub(crate) const SERVICE_METHOD: i32 = 2;
+
+/// A definition within a file, identified by its path in the source code info: the field
+/// numbers and indices leading from the file descriptor to the definition, e.g.
+/// `[6, 0, 2, 1]` for the second method of the first service.
+///
+/// The default source has no comments.
+#[derive(Default)]
+pub(crate) struct Source<'a> {
+    info: Option<&'a SourceCodeInfo>,
+    path: Vec<i32>,
+}
+
+/// The comments of a definition, as recorded by protoc, which keeps the leading space and
+/// the line breaks of the comment lines
+#[derive(Default)]
+pub(crate) struct Comments {
+    pub(crate) leading: Option<String>,
+    pub(crate) trailing: Option<String>,
+}
+
+impl<'a> Source<'a> {
+    /// Returns `file` itself, whose definitions are found with [`child`](Self::child).
+    pub(crate) fn file(file: &'a FileDescriptorProto) -> Self {
+        Self {
+            info: file.source_code_info.as_ref(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Returns the service of `file` at `index`.
+    pub(crate) fn service(file: &'a FileDescriptorProto, index: usize) -> Self {
+        Self {
+            info: file.source_code_info.as_ref(),
+            path: vec![FILE_SERVICE, index as i32],
+        }
+    }
+
+    /// Returns the definition of `message` in `file`, which may be nested in other messages,
+    /// if `file` defines it.
+    pub(crate) fn message(
+        file: &'a FileDescriptorProto,
+        message: &DescriptorProto,
+    ) -> Option<Self> {
+        fn find(
+            messages: &[DescriptorProto],
+            target: &DescriptorProto,
+            path: &mut Vec<i32>,
+        ) -> bool {
+            for (index, message) in messages.iter().enumerate() {
+                path.push(index as i32);
+                if std::ptr::eq(message, target) {
+                    return true;
+                }
+                path.push(MESSAGE_NESTED_TYPE);
+                if find(&message.nested_type, target, path) {
+                    return true;
+                }
+                path.truncate(path.len() - 2);
+            }
+            false
+        }
+
+        let mut path = vec![FILE_MESSAGE_TYPE];
+        find(&file.message_type, message, &mut path).then_some(Self {
+            info: file.source_code_info.as_ref(),
+            path,
+        })
+    }
+
+    /// Returns the definition at `index` of the definitions of the kind with the field number
+    /// `field` within this one, e.g. the fields of a message.
+    pub(crate) fn child(&self, field: i32, index: usize) -> Self {
+        let mut path = self.path.clone();
+        path.extend([field, index as i32]);
+        Self {
+            info: self.info,
+            path,
+        }
+    }
+
+    /// Returns the singular field numbered `field` of this definition, like the package of a
+    /// file.
+    pub(crate) fn field(&self, field: i32) -> Self {
+        let mut path = self.path.clone();
+        path.push(field);
+        Self {
+            info: self.info,
+            path,
+        }
+    }
+
+    /// Returns the comments of the definition, which are missing if the server stripped the
+    /// source code info.
+    pub(crate) fn comments(&self) -> Comments {
+        let location = self.info.and_then(|info| {
+            info.location
+                .iter()
+                .find(|location| location.path == self.path)
+        });
+        match location {
+            Some(location) => Comments {
+                leading: location.leading_comments.clone(),
+                trailing: location.trailing_comments.clone(),
+            },
+            None => Comments::default(),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Resolving the files imported by file descriptors, directly or transitively.
+
+use crate::error::ReflectionError;
+use prost_types::FileDescriptorProto;
+use std::collections::{BTreeSet, HashMap};
+
+/// Collects file descriptors until the imports of the seed files are complete, and orders
+/// them so each file comes after the files it imports
+///
+/// Files are added as seeds, whose imports are resolved, or as further files, which are only
+/// included if a seed imports them, directly or transitively, so files a server sends along
+/// without being asked for are left out. Each file is kept once, by its name.
+///
+/// [`ReflectionClient::resolve_dependencies`](crate::reflection::ReflectionClient::resolve_dependencies)
+/// fetches the missing files from the server.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::dependencies::DependencyResolver;
+/// use grpc_ease::error::ReflectionError;
+/// use prost_types::FileDescriptorProto;
+///
+/// let file = |name: &str, dependency: &[&str]| FileDescriptorProto {
+///     name: Some(format!("{}.proto", name)),
+///     dependency: dependency.iter().map(|name| format!("{}.proto", name)).collect(),
+///     ..Default::default()
+/// };
+///
+/// // a diamond: app imports left and right, which both import base
+/// let mut resolver = DependencyResolver::new();
+/// resolver.add_seed(file("app", &["left", "right"]))?;
+/// assert_eq!(resolver.missing(), ["left.proto", "right.proto"]);
+///
+/// resolver.add(file("left", &["base"]))?;
+/// resolver.add(file("right", &["base"]))?;
+/// // a file nothing imports, whose imports are not needed either
+/// resolver.add(file("unrelated", &["elsewhere"]))?;
+/// assert_eq!(resolver.missing(), ["base.proto"]);
+///
+/// resolver.add(file("base", &[]))?;
+/// assert!(resolver.missing().is_empty());
+/// let names: Vec<_> = resolver
+///     .into_sorted()?
+///     .iter()
+///     .map(|file| file.name().to_string())
+///     .collect();
+/// assert_eq!(names, ["base.proto", "left.proto", "right.proto", "app.proto"]);
+///
+/// // imports that cannot be resolved, or that form a cycle, are errors
+/// let mut resolver = DependencyResolver::new();
+/// resolver.add_seed(file("app", &["left"]))?;
+/// let error = resolver.clone().into_sorted().unwrap_err();
+/// assert!(matches!(error, ReflectionError::NotFound { name } if name == "left.proto"));
+///
+/// resolver.add(file("left", &["right"]))?;
+/// resolver.add(file("right", &["app"]))?;
+/// let error = resolver.into_sorted().unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "Import cycle: app.proto -> left.proto -> right.proto -> app.proto"
+/// );
+/// # Ok::<(), ReflectionError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DependencyResolver {
+    seeds: Vec<String>,
+    files: HashMap<String, FileDescriptorProto>,
+}
+
+impl DependencyResolver {
+    /// Creates a resolver without any files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `file`, whose imports are resolved, unless a file of the same name was added
+    /// already.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::MalformedDescriptor`] if the file has no
+    /// name.
+    pub fn add_seed(&mut self, file: FileDescriptorProto) -> Result<(), ReflectionError> {
+        let name = file_name(&file)?;
+        if !self.seeds.contains(&name) {
+            self.seeds.push(name.clone());
+        }
+        self.files.entry(name).or_insert(file);
+        Ok(())
+    }
+
+    /// Adds `file`, which is only included if a seed imports it, directly or transitively,
+    /// unless a file of the same name was added already.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::MalformedDescriptor`] if the file has no
+    /// name.
+    pub fn add(&mut self, file: FileDescriptorProto) -> Result<(), ReflectionError> {
+        self.files.entry(file_name(&file)?).or_insert(file);
+        Ok(())
+    }
+
+    /// Returns whether a file named `name` was added.
+    pub fn contains(&self, name: &str) -> bool {
+        self.files.contains_key(name)
+    }
+
+    /// Returns the names of the files the seeds import, directly or through the files added
+    /// so far, that are missing, in alphabetical order.
+    pub fn missing(&self) -> Vec<String> {
+        let mut missing = BTreeSet::new();
+        let mut visited = BTreeSet::new();
+        let mut pending: Vec<&str> = self.seeds.iter().map(String::as_str).collect();
+        while let Some(name) = pending.pop() {
+            if !visited.insert(name) {
+                continue;
+            }
+            match self.files.get(name) {
+                Some(file) => pending.extend(file.dependency.iter().map(String::as_str)),
+                None => {
+                    missing.insert(name.to_string());
+                }
+            }
+        }
+        missing.into_iter().collect()
+    }
+
+    /// Returns the seeds and the files they import, directly or transitively, each once and
+    /// after the files it imports.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - An imported file is missing, as [`ReflectionError::NotFound`] naming the first
+    ///   missing file
+    /// - The imports form a cycle, as [`ReflectionError::MalformedDescriptor`] naming the
+    ///   files of the cycle
+    pub fn into_sorted(self) -> Result<Vec<FileDescriptorProto>, ReflectionError> {
+        if let Some(name) = self.missing().into_iter().next() {
+            return Err(ReflectionError::NotFound { name });
+        }
+
+        fn visit(
+            name: &str,
+            files: &mut HashMap<String, FileDescriptorProto>,
+            visiting: &mut Vec<String>,
+            sorted: &mut Vec<FileDescriptorProto>,
+        ) -> Result<(), ReflectionError> {
+            if visiting.iter().any(|visiting| visiting == name) {
+                return Err(ReflectionError::MalformedDescriptor(format!(
+                    "Import cycle: {} -> {}",
+                    visiting.join(" -> "),
+                    name
+                )));
+            }
+            // files are removed once sorted, so each is only added once
+            let Some(file) = files.remove(name) else {
+                return Ok(());
+            };
+
+            visiting.push(name.to_string());
+            for dependency in &file.dependency {
+                visit(dependency, files, visiting, sorted)?;
+            }
+            visiting.pop();
+
+            sorted.push(file);
+            Ok(())
+        }
+
+        let mut files = self.files;
+        let mut sorted = Vec::new();
+        for seed in &self.seeds {
+            visit(seed, &mut files, &mut Vec::new(), &mut sorted)?;
+        }
+        Ok(sorted)
+    }
+}
+
+/// Returns the name of `file`.
+pub(crate) fn file_name(file: &FileDescriptorProto) -> Result<String, ReflectionError> {
+    file.name.clone().ok_or_else(|| {
+        ReflectionError::MalformedDescriptor(format!(
+            "File name is missing for a file of package {:?}",
+            file.package()
+        ))
+    })
+}
@@ -0,0 +1,86 @@
+//! Descriptions of the symbols of a server, whatever kind of definition they name.
+
+use crate::message_info::{EnumInfo, MessageInfo};
+use crate::service_info::{MethodInfo, ServiceInfo};
+use std::fmt;
+
+/// The definition of a symbol of a server, as described by
+/// [`ReflectionClient::describe`](crate::reflection::ReflectionClient::describe)
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::describe::SymbolDescription;
+/// use grpc_ease::message_info::EnumInfo;
+///
+/// let status = SymbolDescription::Enum(EnumInfo {
+///     name: "Status".to_string(),
+///     full_name: "shop.Status".to_string(),
+///     values: vec![("STATUS_UNSPECIFIED".to_string(), 0)],
+///     deprecated: false,
+/// });
+/// assert_eq!(status.full_name(), "shop.Status");
+/// assert_eq!(status.to_string(), "enum shop.Status {\n  STATUS_UNSPECIFIED = 0;\n}");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SymbolDescription {
+    /// A service, with the signatures of its methods
+    Service(ServiceInfo),
+    /// A method, along with its request and response messages
+    Method {
+        /// The fully qualified name of the service defining the method, e.g. `shop.Orders`
+        service: String,
+        /// The method
+        method: MethodInfo,
+        /// The request message of the method
+        request: Box<MessageInfo>,
+        /// The response message of the method
+        response: Box<MessageInfo>,
+    },
+    /// A message, which may be nested in another message
+    Message(MessageInfo),
+    /// An enum, which may be nested in a message
+    Enum(EnumInfo),
+}
+
+impl SymbolDescription {
+    /// Returns the fully qualified name of the symbol, e.g. `shop.Orders.Place` for a method.
+    pub fn full_name(&self) -> String {
+        match self {
+            Self::Service(service) => service.full_name(),
+            Self::Method {
+                service, method, ..
+            } => format!("{}.{}", service, method.name),
+            Self::Message(message) => message.full_name.clone(),
+            Self::Enum(enumeration) => enumeration.full_name.clone(),
+        }
+    }
+}
+
+impl fmt::Display for SymbolDescription {
+    /// Formats the definition with the `Display` implementation of its kind, which for a
+    /// method is its fully qualified name and signature followed by its request and response
+    /// messages, separated by blank lines.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Service(service) => write!(f, "{}", service),
+            Self::Method {
+                method,
+                request,
+                response,
+                ..
+            } => {
+                write!(f, "{}\n  {}\n\n{}", self.full_name(), method, request)?;
+                // e.g. methods exchanging the same message in both directions
+                if response.full_name != request.full_name {
+                    write!(f, "\n\n{}", response)?;
+                }
+                Ok(())
+            }
+            Self::Message(message) => write!(f, "{}", message),
+            Self::Enum(enumeration) => write!(f, "{}", enumeration),
+        }
+    }
+}
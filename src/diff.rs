@@ -0,0 +1,918 @@
+//! Comparing two schemas, e.g. of two servers or of a server at two points in time.
+
+use crate::lookup::{self, qualify, strip_leading_dot};
+use crate::message_info::{is_map_entry, Label};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The differences between an old and a new schema, as computed by [`diff`]
+///
+/// Services, methods and messages are named by their fully qualified names, methods as
+/// `billing.v1.LedgerService.Charge`. Fields are matched by their numbers, so a renamed field
+/// is a changed field, while a field moved to another number is removed and added. Enum values
+/// are matched by their names instead, so a value moved to another number is a renumbered
+/// value, while a renamed value is removed and added. The methods of added or removed
+/// services, the fields of added or removed messages, and the values of added or removed enums
+/// are not listed separately.
+///
+/// All lists are sorted, so diffing the same schemas always gives the same result.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SchemaDiff {
+    /// The services only the new schema defines
+    pub added_services: Vec<String>,
+    /// The services only the old schema defines
+    pub removed_services: Vec<String>,
+    /// The methods only the new schema defines, of services both schemas define
+    pub added_methods: Vec<String>,
+    /// The methods only the old schema defines, of services both schemas define
+    pub removed_methods: Vec<String>,
+    /// The changes of the methods both schemas define
+    pub changed_methods: Vec<MethodChange>,
+    /// The messages only the new schema defines
+    pub added_messages: Vec<String>,
+    /// The messages only the old schema defines
+    pub removed_messages: Vec<String>,
+    /// The fields only the new schema defines, of messages both schemas define
+    pub added_fields: Vec<FieldRef>,
+    /// The fields only the old schema defines, of messages both schemas define
+    pub removed_fields: Vec<FieldRef>,
+    /// The changes of the fields both schemas define
+    pub changed_fields: Vec<FieldChange>,
+    /// The enums only the new schema defines
+    pub added_enums: Vec<String>,
+    /// The enums only the old schema defines
+    pub removed_enums: Vec<String>,
+    /// The enum values only the new schema defines, of enums both schemas define
+    pub added_enum_values: Vec<EnumValueRef>,
+    /// The enum values only the old schema defines, of enums both schemas define
+    pub removed_enum_values: Vec<EnumValueRef>,
+    /// The enum values both schemas define with different numbers
+    pub renumbered_enum_values: Vec<EnumValueChange>,
+}
+
+/// A change of a method both schemas define
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MethodChange {
+    /// The fully qualified name of the method, e.g. `billing.v1.LedgerService.Charge`
+    pub method: String,
+    /// What changed
+    pub kind: MethodChangeKind,
+}
+
+/// What changed about a method
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MethodChangeKind {
+    /// The fully qualified name of the request message changed
+    RequestType { old: String, new: String },
+    /// The fully qualified name of the response message changed
+    ResponseType { old: String, new: String },
+    /// Whether the client sends a stream of requests changed
+    ClientStreaming { old: bool, new: bool },
+    /// Whether the server answers with a stream of responses changed
+    ServerStreaming { old: bool, new: bool },
+}
+
+/// A field only one of the schemas defines
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldRef {
+    /// The fully qualified name of the message of the field
+    pub message: String,
+    /// The number of the field
+    pub number: i32,
+    /// The name of the field
+    pub name: String,
+    /// Whether the field is optional, required, or repeated
+    pub label: Label,
+}
+
+/// A change of a field both schemas define with the same number in the same message
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldChange {
+    /// The fully qualified name of the message of the field
+    pub message: String,
+    /// The number of the field
+    pub number: i32,
+    /// The name of the field in the old schema
+    pub name: String,
+    /// What changed
+    pub kind: FieldChangeKind,
+}
+
+/// What changed about a field
+///
+/// Types are named like in a `.proto` file, i.e. by their keyword for scalars, e.g. `int64`,
+/// and by their fully qualified name for messages and enums.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FieldChangeKind {
+    /// The name of the field changed
+    Name { old: String, new: String },
+    /// The type of the field changed
+    Type { old: String, new: String },
+    /// Whether the field is optional, required, or repeated changed
+    Label { old: Label, new: Label },
+}
+
+/// An enum value only one of the schemas defines
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumValueRef {
+    /// The fully qualified name of the enum of the value
+    pub enumeration: String,
+    /// The name of the value
+    pub name: String,
+    /// The number of the value
+    pub number: i32,
+}
+
+/// An enum value both schemas define with the same name in the same enum, but with different
+/// numbers
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumValueChange {
+    /// The fully qualified name of the enum of the value
+    pub enumeration: String,
+    /// The name of the value
+    pub name: String,
+    /// The number of the value in the old schema
+    pub old: i32,
+    /// The number of the value in the new schema
+    pub new: i32,
+}
+
+/// Whether a change breaks clients or servers built against the old schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Severity {
+    /// Old clients or servers may fail to call, serve, encode or decode the changed
+    /// definition
+    Breaking,
+    /// Old clients and servers keep working, e.g. because a method or an optional field was
+    /// added
+    NonBreaking,
+}
+
+/// A change of a [`SchemaDiff`], classified by whether it is breaking
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Finding {
+    /// Whether the change is breaking
+    pub severity: Severity,
+    /// The fully qualified name of the changed definition, e.g.
+    /// `billing.v1.LedgerService.Charge` for a method or `billing.v1.ChargeRequest.amount`
+    /// for a field, named as in the old schema unless it was added
+    pub location: String,
+    /// What changed, e.g. `method removed`
+    pub description: String,
+}
+
+impl SchemaDiff {
+    /// Returns whether the schemas are the same, as far as the diff tells.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns every change, classified by the compatibility rules of protobuf, in the order
+    /// they are displayed.
+    ///
+    /// These changes are breaking:
+    /// - Removing a service, method, message, field, enum or enum value, including moving a
+    ///   field to another number and renaming an enum value
+    /// - Changing the request or response type of a method, or whether it streams requests
+    ///   or responses
+    /// - Changing the type, name or label of a field, since renaming a field changes its JSON
+    ///   name
+    /// - Changing the number of an enum value
+    /// - Adding a required field
+    ///
+    /// Adding services, methods, messages, fields other than required ones, enums and enum
+    /// values is not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::diff::{diff, Severity};
+    /// use prost_types::field_descriptor_proto::{Label, Type};
+    /// use prost_types::{
+    ///     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+    ///     ServiceDescriptorProto,
+    /// };
+    ///
+    /// let field = |name: &str, number, label: Label| FieldDescriptorProto {
+    ///     name: Some(name.to_string()),
+    ///     number: Some(number),
+    ///     label: Some(label.into()),
+    ///     r#type: Some(Type::String.into()),
+    ///     ..Default::default()
+    /// };
+    /// let method = |name: &str| MethodDescriptorProto {
+    ///     name: Some(name.to_string()),
+    ///     input_type: Some(".billing.v1.ChargeRequest".to_string()),
+    ///     output_type: Some(".billing.v1.ChargeRequest".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let file = |field, method| FileDescriptorProto {
+    ///     name: Some("billing.proto".to_string()),
+    ///     package: Some("billing.v1".to_string()),
+    ///     message_type: vec![DescriptorProto {
+    ///         name: Some("ChargeRequest".to_string()),
+    ///         field,
+    ///         ..Default::default()
+    ///     }],
+    ///     service: vec![ServiceDescriptorProto {
+    ///         name: Some("LedgerService".to_string()),
+    ///         method,
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let old = file(
+    ///     vec![field("account", 1, Label::Optional)],
+    ///     vec![method("Charge"), method("Refund")],
+    /// );
+    /// let new = file(
+    ///     vec![field("account", 1, Label::Optional), field("note", 2, Label::Optional)],
+    ///     vec![method("Charge"), method("Refund"), method("Void")],
+    /// );
+    /// let compatible = diff(&[old.clone()], &[new.clone()]);
+    /// assert_eq!(compatible.findings().len(), 2);
+    /// assert!(compatible.breaking_changes().is_empty());
+    ///
+    /// let broken = diff(&[new], &[old]);
+    /// let findings: Vec<_> = broken
+    ///     .breaking_changes()
+    ///     .into_iter()
+    ///     .map(|finding| finding.to_string())
+    ///     .collect();
+    /// assert_eq!(
+    ///     findings,
+    ///     [
+    ///         "breaking: billing.v1.LedgerService.Void: method removed",
+    ///         "breaking: billing.v1.ChargeRequest.note: field 2 removed",
+    ///     ]
+    /// );
+    /// assert!(broken
+    ///     .findings()
+    ///     .iter()
+    ///     .all(|finding| finding.severity == Severity::Breaking));
+    /// ```
+    ///
+    /// Enum values are matched by their names, which their JSON encoding uses, while their
+    /// numbers are what is sent on the wire:
+    ///
+    /// ```
+    /// use grpc_ease::diff::diff;
+    /// use prost_types::{EnumDescriptorProto, EnumValueDescriptorProto, FileDescriptorProto};
+    ///
+    /// let file = |values: &[(&str, i32)]| FileDescriptorProto {
+    ///     name: Some("shop.proto".to_string()),
+    ///     package: Some("shop".to_string()),
+    ///     enum_type: vec![EnumDescriptorProto {
+    ///         name: Some("Status".to_string()),
+    ///         value: values
+    ///             .iter()
+    ///             .map(|(name, number)| EnumValueDescriptorProto {
+    ///                 name: Some(name.to_string()),
+    ///                 number: Some(*number),
+    ///                 ..Default::default()
+    ///             })
+    ///             .collect(),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let old = file(&[("STATUS_UNSPECIFIED", 0), ("STATUS_PAID", 1), ("STATUS_SHIPPED", 2)]);
+    /// let new = file(&[("STATUS_UNSPECIFIED", 0), ("STATUS_SHIPPED", 3), ("STATUS_REFUNDED", 4)]);
+    /// let changes = diff(&[old], &[new]);
+    /// let findings: Vec<_> = changes
+    ///     .findings()
+    ///     .into_iter()
+    ///     .map(|finding| finding.to_string())
+    ///     .collect();
+    /// assert_eq!(
+    ///     findings,
+    ///     [
+    ///         "non-breaking: shop.Status.STATUS_REFUNDED: enum value 4 added",
+    ///         "breaking: shop.Status.STATUS_PAID: enum value 1 removed",
+    ///         "breaking: shop.Status.STATUS_SHIPPED: enum value renumbered 2 -> 3",
+    ///     ]
+    /// );
+    /// assert_eq!(
+    ///     changes.to_string(),
+    ///     "\
+    /// + enum value shop.Status.STATUS_REFUNDED = 4
+    /// - enum value shop.Status.STATUS_PAID = 1
+    /// ~ enum value shop.Status.STATUS_SHIPPED: number 2 -> 3"
+    /// );
+    ///
+    /// let removed = diff(&[file(&[("STATUS_UNSPECIFIED", 0)])], &[]);
+    /// assert_eq!(
+    ///     removed.findings()[0].to_string(),
+    ///     "breaking: shop.Status: enum removed"
+    /// );
+    /// ```
+    pub fn findings(&self) -> Vec<Finding> {
+        let finding = |severity, location: &str, description: String| Finding {
+            severity,
+            location: location.to_string(),
+            description,
+        };
+
+        let mut findings = Vec::new();
+        for service in &self.added_services {
+            findings.push(finding(
+                Severity::NonBreaking,
+                service,
+                "service added".to_string(),
+            ));
+        }
+        for service in &self.removed_services {
+            findings.push(finding(
+                Severity::Breaking,
+                service,
+                "service removed".to_string(),
+            ));
+        }
+        for method in &self.added_methods {
+            findings.push(finding(
+                Severity::NonBreaking,
+                method,
+                "method added".to_string(),
+            ));
+        }
+        for method in &self.removed_methods {
+            findings.push(finding(
+                Severity::Breaking,
+                method,
+                "method removed".to_string(),
+            ));
+        }
+        for change in &self.changed_methods {
+            findings.push(finding(
+                Severity::Breaking,
+                &change.method,
+                change.kind.to_string(),
+            ));
+        }
+        for message in &self.added_messages {
+            findings.push(finding(
+                Severity::NonBreaking,
+                message,
+                "message added".to_string(),
+            ));
+        }
+        for message in &self.removed_messages {
+            findings.push(finding(
+                Severity::Breaking,
+                message,
+                "message removed".to_string(),
+            ));
+        }
+        for field in &self.added_fields {
+            let severity = match field.label {
+                Label::Required => Severity::Breaking,
+                Label::Optional | Label::Repeated => Severity::NonBreaking,
+            };
+            findings.push(finding(
+                severity,
+                &qualify(&field.message, &field.name),
+                format!("{} field {} added", keyword(field.label), field.number),
+            ));
+        }
+        for field in &self.removed_fields {
+            findings.push(finding(
+                Severity::Breaking,
+                &qualify(&field.message, &field.name),
+                format!("field {} removed", field.number),
+            ));
+        }
+        for change in &self.changed_fields {
+            findings.push(finding(
+                Severity::Breaking,
+                &qualify(&change.message, &change.name),
+                change.kind.to_string(),
+            ));
+        }
+        for enumeration in &self.added_enums {
+            findings.push(finding(
+                Severity::NonBreaking,
+                enumeration,
+                "enum added".to_string(),
+            ));
+        }
+        for enumeration in &self.removed_enums {
+            findings.push(finding(
+                Severity::Breaking,
+                enumeration,
+                "enum removed".to_string(),
+            ));
+        }
+        for value in &self.added_enum_values {
+            findings.push(finding(
+                Severity::NonBreaking,
+                &qualify(&value.enumeration, &value.name),
+                format!("enum value {} added", value.number),
+            ));
+        }
+        for value in &self.removed_enum_values {
+            findings.push(finding(
+                Severity::Breaking,
+                &qualify(&value.enumeration, &value.name),
+                format!("enum value {} removed", value.number),
+            ));
+        }
+        for change in &self.renumbered_enum_values {
+            findings.push(finding(
+                Severity::Breaking,
+                &qualify(&change.enumeration, &change.name),
+                format!("enum value renumbered {} -> {}", change.old, change.new),
+            ));
+        }
+        findings
+    }
+
+    /// Returns the [`findings`](Self::findings) that are breaking.
+    ///
+    /// To fail a CI build on breaking changes of a server against a baseline, see
+    /// [`ReflectionClient::diff_against_file`](crate::reflection::ReflectionClient::diff_against_file).
+    pub fn breaking_changes(&self) -> Vec<Finding> {
+        self.findings()
+            .into_iter()
+            .filter(|finding| finding.severity == Severity::Breaking)
+            .collect()
+    }
+}
+
+impl fmt::Display for Finding {
+    /// Formats the finding like `breaking: billing.v1.LedgerService.Charge: method removed`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Breaking => "breaking",
+            Severity::NonBreaking => "non-breaking",
+        };
+        write!(f, "{}: {}: {}", severity, self.location, self.description)
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    /// Formats each difference on a line, marked with `+` if added, `-` if removed and `~`
+    /// if changed, or `no changes` if the schemas are the same.
+    ///
+    /// See [`diff`] for an example.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+
+        let mut lines = Vec::new();
+        for service in &self.added_services {
+            lines.push(format!("+ service {}", service));
+        }
+        for service in &self.removed_services {
+            lines.push(format!("- service {}", service));
+        }
+        for method in &self.added_methods {
+            lines.push(format!("+ method {}", method));
+        }
+        for method in &self.removed_methods {
+            lines.push(format!("- method {}", method));
+        }
+        for change in &self.changed_methods {
+            lines.push(format!("~ method {}: {}", change.method, change.kind));
+        }
+        for message in &self.added_messages {
+            lines.push(format!("+ message {}", message));
+        }
+        for message in &self.removed_messages {
+            lines.push(format!("- message {}", message));
+        }
+        for field in &self.added_fields {
+            lines.push(format!("+ field {}", field));
+        }
+        for field in &self.removed_fields {
+            lines.push(format!("- field {}", field));
+        }
+        for change in &self.changed_fields {
+            lines.push(format!(
+                "~ field {}.{} = {}: {}",
+                change.message, change.name, change.number, change.kind
+            ));
+        }
+        for enumeration in &self.added_enums {
+            lines.push(format!("+ enum {}", enumeration));
+        }
+        for enumeration in &self.removed_enums {
+            lines.push(format!("- enum {}", enumeration));
+        }
+        for value in &self.added_enum_values {
+            lines.push(format!("+ enum value {}", value));
+        }
+        for value in &self.removed_enum_values {
+            lines.push(format!("- enum value {}", value));
+        }
+        for change in &self.renumbered_enum_values {
+            lines.push(format!(
+                "~ enum value {}.{}: number {} -> {}",
+                change.enumeration, change.name, change.old, change.new
+            ));
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl fmt::Display for MethodChangeKind {
+    /// Formats the old and the new value, e.g. `request type shop.A -> shop.B`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RequestType { old, new } => write!(f, "request type {} -> {}", old, new),
+            Self::ResponseType { old, new } => write!(f, "response type {} -> {}", old, new),
+            Self::ClientStreaming { old, new } => {
+                write!(f, "client streaming {} -> {}", old, new)
+            }
+            Self::ServerStreaming { old, new } => {
+                write!(f, "server streaming {} -> {}", old, new)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FieldRef {
+    /// Formats the field like `shop.Order.total = 3`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{} = {}", self.message, self.name, self.number)
+    }
+}
+
+impl fmt::Display for EnumValueRef {
+    /// Formats the value like `shop.Status.STATUS_SHIPPED = 3`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{} = {}", self.enumeration, self.name, self.number)
+    }
+}
+
+impl fmt::Display for FieldChangeKind {
+    /// Formats the old and the new value, e.g. `type int32 -> int64`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name { old, new } => write!(f, "name {} -> {}", old, new),
+            Self::Type { old, new } => write!(f, "type {} -> {}", old, new),
+            Self::Label { old, new } => write!(f, "label {} -> {}", keyword(*old), keyword(*new)),
+        }
+    }
+}
+
+/// Computes the differences between the schema made up of the files `old` and the one made
+/// up of the files `new`.
+///
+/// Both schemas should be complete, i.e. include the files imported by their files, like the
+/// files exported by
+/// [`ReflectionClient::get_all_file_descriptors`](crate::reflection::ReflectionClient::get_all_file_descriptors).
+/// Files of the same name are only taken into account once.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::diff::{diff, MethodChangeKind};
+/// use prost_types::field_descriptor_proto::Type;
+/// use prost_types::{
+///     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+///     ServiceDescriptorProto,
+/// };
+///
+/// let field = |name: &str, number, r#type: Type| FieldDescriptorProto {
+///     name: Some(name.to_string()),
+///     number: Some(number),
+///     r#type: Some(r#type.into()),
+///     ..Default::default()
+/// };
+/// let message = |name: &str, field: Vec<FieldDescriptorProto>| DescriptorProto {
+///     name: Some(name.to_string()),
+///     field,
+///     ..Default::default()
+/// };
+/// let method = |name: &str, server_streaming| MethodDescriptorProto {
+///     name: Some(name.to_string()),
+///     input_type: Some(".billing.v1.ChargeRequest".to_string()),
+///     output_type: Some(".billing.v1.ChargeRequest".to_string()),
+///     server_streaming: Some(server_streaming),
+///     ..Default::default()
+/// };
+/// let file = |message_type, method| FileDescriptorProto {
+///     name: Some("billing.proto".to_string()),
+///     package: Some("billing.v1".to_string()),
+///     message_type,
+///     service: vec![ServiceDescriptorProto {
+///         name: Some("LedgerService".to_string()),
+///         method,
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+///
+/// let old = file(
+///     vec![message("ChargeRequest", vec![field("amount", 1, Type::Int32), field("note", 2, Type::String)])],
+///     vec![method("Charge", false), method("Refund", false)],
+/// );
+/// let new = file(
+///     vec![
+///         message("ChargeRequest", vec![field("amount", 1, Type::Int64), field("currency", 3, Type::String)]),
+///         message("Receipt", vec![]),
+///     ],
+///     vec![method("Charge", true), method("Void", false)],
+/// );
+///
+/// let changes = diff(&[old.clone()], &[new]);
+/// assert_eq!(changes.added_methods, ["billing.v1.LedgerService.Void"]);
+/// assert_eq!(
+///     changes.changed_methods[0].kind,
+///     MethodChangeKind::ServerStreaming { old: false, new: true }
+/// );
+/// assert_eq!(
+///     changes.to_string(),
+///     "\
+/// + method billing.v1.LedgerService.Void
+/// - method billing.v1.LedgerService.Refund
+/// ~ method billing.v1.LedgerService.Charge: server streaming false -> true
+/// + message billing.v1.Receipt
+/// + field billing.v1.ChargeRequest.currency = 3
+/// - field billing.v1.ChargeRequest.note = 2
+/// ~ field billing.v1.ChargeRequest.amount = 1: type int32 -> int64"
+/// );
+///
+/// let unchanged = diff(&[old.clone()], &[old]);
+/// assert!(unchanged.is_empty());
+/// assert_eq!(unchanged.to_string(), "no changes");
+/// ```
+pub fn diff(old: &[FileDescriptorProto], new: &[FileDescriptorProto]) -> SchemaDiff {
+    let old = dedup(old);
+    let new = dedup(new);
+    let mut diff = SchemaDiff::default();
+
+    let old_services: BTreeMap<_, _> = lookup::services(&old)
+        .into_iter()
+        .map(|(name, _, service)| (name, service))
+        .collect();
+    let new_services: BTreeMap<_, _> = lookup::services(&new)
+        .into_iter()
+        .map(|(name, _, service)| (name, service))
+        .collect();
+    for (name, old_service) in &old_services {
+        let Some(new_service) = new_services.get(name) else {
+            diff.removed_services.push(name.clone());
+            continue;
+        };
+
+        let old_methods: BTreeMap<_, _> = old_service
+            .method
+            .iter()
+            .map(|method| (qualify(name, method.name()), method))
+            .collect();
+        let new_methods: BTreeMap<_, _> = new_service
+            .method
+            .iter()
+            .map(|method| (qualify(name, method.name()), method))
+            .collect();
+        for (method, old_method) in &old_methods {
+            let Some(new_method) = new_methods.get(method) else {
+                diff.removed_methods.push(method.clone());
+                continue;
+            };
+
+            let mut change = |kind| {
+                diff.changed_methods.push(MethodChange {
+                    method: method.clone(),
+                    kind,
+                })
+            };
+            if old_method.input_type() != new_method.input_type() {
+                change(MethodChangeKind::RequestType {
+                    old: strip_leading_dot(old_method.input_type().to_string()),
+                    new: strip_leading_dot(new_method.input_type().to_string()),
+                });
+            }
+            if old_method.output_type() != new_method.output_type() {
+                change(MethodChangeKind::ResponseType {
+                    old: strip_leading_dot(old_method.output_type().to_string()),
+                    new: strip_leading_dot(new_method.output_type().to_string()),
+                });
+            }
+            if old_method.client_streaming() != new_method.client_streaming() {
+                change(MethodChangeKind::ClientStreaming {
+                    old: old_method.client_streaming(),
+                    new: new_method.client_streaming(),
+                });
+            }
+            if old_method.server_streaming() != new_method.server_streaming() {
+                change(MethodChangeKind::ServerStreaming {
+                    old: old_method.server_streaming(),
+                    new: new_method.server_streaming(),
+                });
+            }
+        }
+        diff.added_methods.extend(
+            new_methods
+                .into_keys()
+                .filter(|method| !old_methods.contains_key(method)),
+        );
+    }
+    diff.added_services.extend(
+        new_services
+            .into_keys()
+            .filter(|service| !old_services.contains_key(service)),
+    );
+
+    let old_messages = messages(&old);
+    let new_messages = messages(&new);
+    for (name, old_message) in &old_messages {
+        let Some(new_message) = new_messages.get(name) else {
+            diff.removed_messages.push(name.clone());
+            continue;
+        };
+
+        let old_fields = fields(old_message);
+        let new_fields = fields(new_message);
+        for (number, old_field) in &old_fields {
+            let Some(new_field) = new_fields.get(number) else {
+                diff.removed_fields.push(field_ref(name, old_field));
+                continue;
+            };
+
+            let mut change = |kind| {
+                diff.changed_fields.push(FieldChange {
+                    message: name.clone(),
+                    number: *number,
+                    name: old_field.name().to_string(),
+                    kind,
+                })
+            };
+            if old_field.name() != new_field.name() {
+                change(FieldChangeKind::Name {
+                    old: old_field.name().to_string(),
+                    new: new_field.name().to_string(),
+                });
+            }
+            if type_name(old_field) != type_name(new_field) {
+                change(FieldChangeKind::Type {
+                    old: type_name(old_field),
+                    new: type_name(new_field),
+                });
+            }
+            if old_field.label() != new_field.label() {
+                change(FieldChangeKind::Label {
+                    old: old_field.label().into(),
+                    new: new_field.label().into(),
+                });
+            }
+        }
+        diff.added_fields.extend(
+            new_fields
+                .iter()
+                .filter(|(number, _)| !old_fields.contains_key(number))
+                .map(|(_, field)| field_ref(name, field)),
+        );
+    }
+    diff.added_messages.extend(
+        new_messages
+            .into_keys()
+            .filter(|message| !old_messages.contains_key(message)),
+    );
+
+    let old_enums = enums(&old);
+    let new_enums = enums(&new);
+    for (name, old_enum) in &old_enums {
+        let Some(new_enum) = new_enums.get(name) else {
+            diff.removed_enums.push(name.clone());
+            continue;
+        };
+
+        let old_values = values(old_enum);
+        let new_values = values(new_enum);
+        for old_value in &old_values {
+            let new_value = new_values
+                .iter()
+                .find(|value| value.name() == old_value.name());
+            match new_value {
+                None => diff.removed_enum_values.push(value_ref(name, old_value)),
+                Some(new_value) if new_value.number() != old_value.number() => {
+                    diff.renumbered_enum_values.push(EnumValueChange {
+                        enumeration: name.clone(),
+                        name: old_value.name().to_string(),
+                        old: old_value.number(),
+                        new: new_value.number(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added_enum_values.extend(
+            new_values
+                .iter()
+                .filter(|value| !old_values.iter().any(|old| old.name() == value.name()))
+                .map(|value| value_ref(name, value)),
+        );
+    }
+    diff.added_enums.extend(
+        new_enums
+            .into_keys()
+            .filter(|enumeration| !old_enums.contains_key(enumeration)),
+    );
+
+    diff.removed_services.sort();
+    diff.removed_methods.sort();
+    diff.removed_messages.sort();
+    diff.added_methods.sort();
+    diff
+}
+
+/// Returns the keyword of `label` in a `.proto` file.
+fn keyword(label: Label) -> &'static str {
+    match label {
+        Label::Optional => "optional",
+        Label::Required => "required",
+        Label::Repeated => "repeated",
+    }
+}
+
+/// Returns `files` without the files whose names came before.
+fn dedup(files: &[FileDescriptorProto]) -> Vec<FileDescriptorProto> {
+    let mut deduped: Vec<FileDescriptorProto> = Vec::new();
+    for file in files {
+        if !deduped.iter().any(|kept| kept.name() == file.name()) {
+            deduped.push(file.clone());
+        }
+    }
+    deduped
+}
+
+/// Returns the messages defined in `files`, including nested ones but not the entries of
+/// maps, keyed by their fully qualified names.
+fn messages(files: &[FileDescriptorProto]) -> BTreeMap<String, &DescriptorProto> {
+    lookup::messages(files)
+        .into_iter()
+        .filter(|(_, message)| !is_map_entry(message))
+        .collect()
+}
+
+/// Returns the fields of `message`, keyed by their numbers.
+fn fields(message: &DescriptorProto) -> BTreeMap<i32, &FieldDescriptorProto> {
+    message
+        .field
+        .iter()
+        .map(|field| (field.number(), field))
+        .collect()
+}
+
+/// Returns the enums defined in `files`, including nested ones, keyed by their fully
+/// qualified names.
+fn enums(files: &[FileDescriptorProto]) -> BTreeMap<String, &EnumDescriptorProto> {
+    lookup::enums(files).into_iter().collect()
+}
+
+/// Returns the values of `enumeration`, ordered by their numbers.
+fn values(enumeration: &EnumDescriptorProto) -> Vec<&EnumValueDescriptorProto> {
+    let mut values: Vec<_> = enumeration.value.iter().collect();
+    values.sort_by_key(|value| value.number());
+    values
+}
+
+fn value_ref(enumeration: &str, value: &EnumValueDescriptorProto) -> EnumValueRef {
+    EnumValueRef {
+        enumeration: enumeration.to_string(),
+        name: value.name().to_string(),
+        number: value.number(),
+    }
+}
+
+fn field_ref(message: &str, field: &FieldDescriptorProto) -> FieldRef {
+    FieldRef {
+        message: message.to_string(),
+        number: field.number(),
+        name: field.name().to_string(),
+        label: field.label().into(),
+    }
+}
+
+/// Returns the type of `field` as named in a `.proto` file.
+fn type_name(field: &FieldDescriptorProto) -> String {
+    match (&field.type_name, field.r#type()) {
+        (Some(type_name), _) => strip_leading_dot(type_name.clone()),
+        (None, scalar) => scalar
+            .as_str_name()
+            .trim_start_matches("TYPE_")
+            .to_lowercase(),
+    }
+}
@@ -0,0 +1,91 @@
+//! Normalizing the endpoint strings users pass to the client.
+
+use crate::error::ReflectionError;
+
+/// Normalizes `endpoint` into the form tonic expects, rejecting endpoints that cannot work.
+///
+/// The following rules are applied:
+/// - Surrounding whitespace and trailing slashes are removed
+/// - Endpoints without a scheme default to `http://`, and bare IPv6 addresses are wrapped in
+///   brackets
+/// - The `grpc://` and `grpcs://` schemes are treated as `http://` and `https://`
+/// - Other schemes than `http` and `https` are rejected with
+///   [`ReflectionError::UnsupportedEndpoint`]
+/// - The unspecified addresses `0.0.0.0` and `[::]`, which servers listen on but clients cannot
+///   connect to, are rejected with [`ReflectionError::UnsupportedEndpoint`]
+///
+/// Endpoints that are not valid URIs are returned unchanged, so parsing them reports the
+/// actual problem.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::endpoint::normalize;
+///
+/// let cases = [
+///     ("localhost:50051", "http://localhost:50051"),
+///     ("localhost", "http://localhost"),
+///     ("  http://localhost:50051/  ", "http://localhost:50051"),
+///     ("https://api.example.com//", "https://api.example.com"),
+///     ("grpc://localhost:50051", "http://localhost:50051"),
+///     ("grpcs://api.example.com", "https://api.example.com"),
+///     ("[::1]:50051", "http://[::1]:50051"),
+///     ("::1", "http://[::1]"),
+///     ("http://[fe80::1]", "http://[fe80::1]"),
+///     ("HTTP://LOCALHOST:50051", "HTTP://LOCALHOST:50051"),
+///     ("10.0.0.5:443/prefix/", "http://10.0.0.5:443/prefix"),
+/// ];
+/// for (input, expected) in cases {
+///     assert_eq!(normalize(input).unwrap(), expected, "normalizing {:?}", input);
+/// }
+///
+/// for input in ["0.0.0.0:50051", "http://0.0.0.0", "[::]:50051", "ftp://localhost"] {
+///     assert!(normalize(input).is_err(), "normalizing {:?}", input);
+/// }
+/// ```
+pub fn normalize(endpoint: &str) -> Result<String, ReflectionError> {
+    let unsupported = |reason: &str| ReflectionError::UnsupportedEndpoint {
+        endpoint: endpoint.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let trimmed = endpoint.trim().trim_end_matches('/');
+    let (scheme, rest) = match trimmed.split_once("://") {
+        Some((scheme, rest)) => (scheme.to_string(), rest),
+        None => ("http".to_string(), trimmed),
+    };
+    let scheme = match scheme.to_ascii_lowercase().as_str() {
+        "http" | "https" => scheme,
+        "grpc" => "http".to_string(),
+        "grpcs" => "https".to_string(),
+        _ => {
+            return Err(unsupported(
+                "only the http, https, grpc and grpcs schemes are supported",
+            ))
+        }
+    };
+
+    // a bare IPv6 address, whose colons would otherwise be mistaken for a port
+    let bracketed;
+    let rest = if rest.matches(':').count() > 1 && !rest.contains(['[', '/']) {
+        bracketed = format!("[{}]", rest);
+        &bracketed
+    } else {
+        rest
+    };
+
+    let normalized = format!("{}://{}", scheme, rest);
+    let Ok(uri) = normalized.parse::<tonic::codegen::http::Uri>() else {
+        return Ok(endpoint.to_string());
+    };
+
+    match uri.host() {
+        Some("0.0.0.0") => Err(unsupported(
+            "0.0.0.0 is the address servers listen on, connect to 127.0.0.1 instead",
+        )),
+        Some("[::]") => Err(unsupported(
+            "[::] is the address servers listen on, connect to [::1] instead",
+        )),
+        _ => Ok(normalized),
+    }
+}
@@ -0,0 +1,641 @@
+use crate::rich_status::RichStatus;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors returned by the [`ReflectionClient`](crate::reflection::ReflectionClient)
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReflectionError {
+    /// The endpoint could not be parsed into a valid URI
+    #[cfg(feature = "transport")]
+    InvalidEndpoint {
+        /// The endpoint as given by the caller
+        endpoint: String,
+        /// The underlying parse error
+        source: tonic::transport::Error,
+    },
+    /// The endpoint is a valid URI, but cannot be connected to
+    UnsupportedEndpoint {
+        /// The endpoint as given by the caller
+        endpoint: String,
+        /// Why the endpoint was rejected
+        reason: String,
+    },
+    /// The configured user agent is not a valid HTTP header value
+    #[cfg(feature = "transport")]
+    InvalidUserAgent {
+        /// The user agent as given by the caller
+        user_agent: String,
+        /// The underlying validation error
+        source: tonic::transport::Error,
+    },
+    /// The configured authority is not a valid URI authority
+    InvalidAuthority {
+        /// The authority as given by the caller
+        authority: String,
+        /// The underlying parse error
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// A configured metadata entry, such as the credentials, is not valid gRPC metadata
+    InvalidMetadata {
+        /// The key of the invalid entry
+        key: String,
+        /// The underlying validation error
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The configured proxy URL is invalid or unsupported
+    InvalidProxy {
+        /// The proxy URL as given by the caller
+        proxy: String,
+        /// Why the proxy URL was rejected
+        reason: String,
+    },
+    /// The endpoint uses the `https` scheme, but the crate was built without the `tls` feature
+    TlsNotEnabled {
+        /// The endpoint as given by the caller
+        endpoint: String,
+    },
+    /// The TLS configuration (CA certificates or client identity) was rejected
+    InvalidTlsConfig(Box<dyn Error + Send + Sync>),
+    /// The TLS handshake with the server failed, e.g. because the server certificate was not
+    /// trusted or the server rejected the client identity
+    #[cfg(feature = "transport")]
+    TlsHandshake {
+        /// The endpoint the handshake was attempted with
+        endpoint: String,
+        /// The underlying transport error, or the status of the call the handshake failed in
+        /// if the server only rejected it afterwards, as servers requiring mutual TLS do with
+        /// TLS 1.3
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The connection to the server was not established within the configured connect timeout
+    ConnectTimeout {
+        /// The endpoint the connection was attempted with
+        endpoint: String,
+        /// The connect timeout that elapsed
+        timeout: Duration,
+    },
+    /// The connection to the server could not be established
+    #[cfg(feature = "transport")]
+    Connect {
+        /// The endpoint the connection was attempted with
+        endpoint: String,
+        /// The underlying transport error
+        source: tonic::transport::Error,
+    },
+    /// None of the endpoints of a client created with
+    /// [`ReflectionClient::new_multi`](crate::reflection::ReflectionClient::new_multi) could be
+    /// reached
+    AllEndpointsFailed {
+        /// Each endpoint along with the error it failed with
+        failures: Vec<(String, ReflectionError)>,
+    },
+    /// The reflection call did not complete within the configured request timeout
+    DeadlineExceeded {
+        /// The request timeout that elapsed
+        timeout: Duration,
+    },
+    /// The server answered the reflection call with a gRPC error status, either by failing the
+    /// call or by sending an error response carrying the status code and message
+    Status(Box<tonic::Status>),
+    /// A file descriptor returned by the server could not be decoded
+    Decode(prost::DecodeError),
+    /// The server does not know the requested file or symbol
+    NotFound {
+        /// The name of the requested file or symbol
+        name: String,
+    },
+    /// The file containing the requested type, or its enclosing scope, does not define a type
+    /// of the requested kind with that name
+    TypeNotFound {
+        /// The fully qualified name of the requested type
+        name: String,
+        /// The kind of the requested type, e.g. `message`
+        kind: &'static str,
+        /// The most similar names of types of that kind, closest first
+        suggestions: Vec<String>,
+    },
+    /// The service exists, but does not define the requested method
+    MethodNotFound {
+        /// The fully qualified name of the service
+        service: String,
+        /// The name of the requested method
+        method: String,
+        /// The names of the methods the service does define
+        methods: Vec<String>,
+    },
+    /// A method path is neither of the form `package.Service.Method` nor
+    /// `/package.Service/Method`
+    InvalidMethodPath {
+        /// The method path as given by the caller
+        path: String,
+    },
+    /// A type URL of a `google.protobuf.Any` does not end with a message name, as in
+    /// `type.googleapis.com/package.Message`
+    InvalidTypeUrl {
+        /// The type URL as given by the caller
+        url: String,
+    },
+    /// A call of a method failed with a status, as returned by the server or by the
+    /// transport
+    Call {
+        /// The path of the method, e.g. `/shop.Orders/Place`
+        path: String,
+        /// The status of the call, with its code, message and details
+        status: Box<tonic::Status>,
+        /// The decoded details of the status, if the server sent any
+        details: Option<Box<RichStatus>>,
+    },
+    /// A call of a method failed after it was retried according to the retry policy of its
+    /// [`CallOptions`](crate::invoke::CallOptions)
+    Retried {
+        /// The status of each attempt, in order, ending with the last one
+        attempts: Vec<tonic::Status>,
+        /// The error of the last attempt, usually a [`ReflectionError::Call`]
+        source: Box<ReflectionError>,
+    },
+    /// A method was called in a way its streaming does not support, e.g. a server streaming
+    /// method as a unary one
+    StreamingMismatch {
+        /// The path of the method, e.g. `/shop.Orders/Watch`
+        path: String,
+        /// Whether the client of the method streams its requests
+        client_streaming: bool,
+        /// Whether the server of the method streams its responses
+        server_streaming: bool,
+    },
+    /// A message in the protobuf text format is invalid for the message type it is parsed as
+    #[cfg(feature = "prost-reflect")]
+    InvalidTextFormat {
+        /// The fully qualified name of the message type, e.g. `shop.Order`
+        message_type: String,
+        /// Why the text is invalid
+        source: prost_reflect::text_format::ParseError,
+    },
+    /// A request of the stream of a client streaming call is invalid, which cancelled the call
+    #[cfg(feature = "prost-reflect")]
+    InvalidStreamRequest {
+        /// The index of the request in the stream, starting at 0
+        index: usize,
+        /// Why the request is invalid
+        source: Box<ReflectionError>,
+    },
+    /// A line of the JSON Lines requests of a client streaming call is not JSON or not a
+    /// request, which cancelled the call
+    #[cfg(feature = "prost-reflect")]
+    InvalidJsonLine {
+        /// The number of the line, starting at 1
+        line: usize,
+        /// Why the line is invalid
+        source: Box<ReflectionError>,
+    },
+    /// The JSON Lines requests of a client streaming call could not be read, which cancelled
+    /// the call
+    #[cfg(feature = "prost-reflect")]
+    ReadJsonLines {
+        /// The number of the line being read, starting at 1
+        line: usize,
+        /// The underlying I/O error
+        source: std::io::Error,
+    },
+    /// A message passed to a call is not of the request type of the method
+    #[cfg(feature = "prost-reflect")]
+    MessageTypeMismatch {
+        /// The fully qualified name of the request type
+        expected: String,
+        /// The fully qualified name of the type of the message
+        actual: String,
+    },
+    /// A JSON value does not represent a message of the expected type, or a message has no
+    /// JSON representation, e.g. because it holds an `Any` of an unknown type
+    #[cfg(feature = "prost-reflect")]
+    InvalidJson {
+        /// The fully qualified name of the message type
+        message_type: String,
+        /// The underlying JSON error
+        source: serde_json::Error,
+    },
+    /// A `bytes` field of a JSON request is neither base64 nor hex prefixed by `0x`
+    #[cfg(feature = "prost-reflect")]
+    InvalidBytes {
+        /// The fully qualified name of the message type of the request
+        message_type: String,
+        /// The path of the field in the request, e.g. `files[2].content`
+        field: String,
+        /// The position of the offending character in the value of the field
+        position: usize,
+        /// The offending character, or `None` if the value ends too early
+        character: Option<char>,
+    },
+    /// A JSON request template has placeholders of variables that are not defined, see
+    /// [`template::substitute`](crate::template::substitute)
+    UnresolvedPlaceholders {
+        /// The placeholders, like `{{user_id}}`, in the order they appear in the request
+        placeholders: Vec<String>,
+    },
+    /// The server closed the reflection stream without sending a response
+    NoResponse,
+    /// The server sent a different kind of response than the request asked for
+    UnexpectedResponse {
+        /// The name of the expected response variant
+        expected: &'static str,
+    },
+    /// A descriptor returned by the server is missing a required field
+    MalformedDescriptor(String),
+    /// A file could not be read
+    ReadFile {
+        /// The path of the file
+        path: PathBuf,
+        /// The underlying I/O error
+        source: std::io::Error,
+    },
+    /// A file could not be written
+    #[cfg(feature = "transport")]
+    WriteFile {
+        /// The path of the file
+        path: PathBuf,
+        /// The underlying I/O error
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "transport")]
+            Self::InvalidEndpoint { endpoint, source } => {
+                write!(f, "Invalid endpoint {:?}: {}", endpoint, source)
+            }
+            Self::UnsupportedEndpoint { endpoint, reason } => {
+                write!(f, "Unsupported endpoint {:?}: {}", endpoint, reason)
+            }
+            #[cfg(feature = "transport")]
+            Self::InvalidUserAgent { user_agent, .. } => {
+                write!(f, "Invalid user agent {:?}", user_agent)
+            }
+            Self::InvalidAuthority { authority, source } => {
+                write!(f, "Invalid authority {:?}: {}", authority, source)
+            }
+            Self::InvalidMetadata { key, source } => {
+                write!(f, "Invalid metadata {:?}: {}", key, source)
+            }
+            Self::InvalidProxy { proxy, reason } => {
+                write!(f, "Invalid proxy {:?}: {}", proxy, reason)
+            }
+            Self::TlsNotEnabled { endpoint } => write!(
+                f,
+                "Endpoint {} uses https, but grpc-ease was built without the `tls` feature",
+                endpoint
+            ),
+            Self::InvalidTlsConfig(source) => write!(f, "Invalid TLS configuration: {}", source),
+            #[cfg(feature = "transport")]
+            Self::TlsHandshake { endpoint, .. } => {
+                write!(f, "TLS handshake with {} failed", endpoint)
+            }
+            Self::ConnectTimeout { endpoint, timeout } => write!(
+                f,
+                "Timed out connecting to {} after {:?}",
+                endpoint, timeout
+            ),
+            #[cfg(feature = "transport")]
+            Self::Connect { endpoint, .. } => write!(f, "Failed to connect to {}", endpoint),
+            Self::AllEndpointsFailed { failures } if failures.is_empty() => {
+                write!(f, "No endpoints given")
+            }
+            Self::AllEndpointsFailed { failures } => {
+                write!(f, "All endpoints failed: ")?;
+                for (i, (endpoint, error)) in failures.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}: {}", endpoint, error)?;
+                }
+                Ok(())
+            }
+            Self::DeadlineExceeded { timeout } => {
+                write!(
+                    f,
+                    "Reflection request did not complete within {:?}",
+                    timeout
+                )
+            }
+            Self::Status(status) => write!(
+                f,
+                "Reflection request failed with status {:?}: {}",
+                status.code(),
+                status.message()
+            ),
+            Self::Decode(source) => write!(f, "Failed to decode file descriptor: {}", source),
+            Self::NotFound { name } => write!(f, "{:?} was not found on the server", name),
+            Self::TypeNotFound {
+                name,
+                kind,
+                suggestions,
+            } => {
+                write!(f, "No {} named {:?} was found", kind, name)?;
+                if !suggestions.is_empty() {
+                    write!(f, ", did you mean {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::MethodNotFound {
+                service,
+                method,
+                methods,
+            } => {
+                write!(f, "Service {:?} has no method {:?}", service, method)?;
+                if !methods.is_empty() {
+                    write!(f, ", its methods are {}", methods.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::InvalidMethodPath { path } => write!(
+                f,
+                "Invalid method path {:?}, expected package.Service.Method or /package.Service/Method",
+                path
+            ),
+            Self::InvalidTypeUrl { url } => write!(
+                f,
+                "Invalid type URL {:?}, expected type.googleapis.com/package.Message",
+                url
+            ),
+            Self::Call {
+                path,
+                status,
+                details,
+            } => {
+                write!(
+                    f,
+                    "Call to {} failed with status {:?}: {}",
+                    path,
+                    status.code(),
+                    status.message()
+                )?;
+                let details = details.iter().flat_map(|details| &details.details);
+                let details: Vec<String> = details.map(ToString::to_string).collect();
+                if !details.is_empty() {
+                    write!(f, " ({})", details.join("; "))?;
+                }
+                Ok(())
+            }
+            Self::Retried { attempts, source } => {
+                let attempts: Vec<String> = attempts
+                    .iter()
+                    .map(|status| format!("{:?}: {}", status.code(), status.message()))
+                    .collect();
+                write!(
+                    f,
+                    "{}, after {} attempts ({})",
+                    source,
+                    attempts.len(),
+                    attempts.join("; ")
+                )
+            }
+            Self::StreamingMismatch {
+                path,
+                client_streaming,
+                server_streaming,
+            } => {
+                let kind = match (client_streaming, server_streaming) {
+                    (false, false) => "unary",
+                    (true, false) => "client streaming",
+                    (false, true) => "server streaming",
+                    (true, true) => "bidirectional streaming",
+                };
+                write!(f, "Method {} is {}, which this call does not support", path, kind)
+            }
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidTextFormat {
+                message_type,
+                source,
+            } => write!(
+                f,
+                "Invalid text format for message {}: {}",
+                message_type, source
+            ),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidStreamRequest { index, source } => {
+                write!(f, "Request {} of the stream is invalid: {}", index, source)
+            }
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidJsonLine { line, source } => {
+                write!(f, "Line {} of the requests is invalid: {}", line, source)
+            }
+            #[cfg(feature = "prost-reflect")]
+            Self::ReadJsonLines { line, source } => {
+                write!(f, "Failed to read line {} of the requests: {}", line, source)
+            }
+            #[cfg(feature = "prost-reflect")]
+            Self::MessageTypeMismatch { expected, actual } => write!(
+                f,
+                "Expected a message of type {}, got {}",
+                expected, actual
+            ),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidJson {
+                message_type,
+                source,
+            } => write!(f, "Invalid JSON for message {}: {}", message_type, source),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidBytes {
+                message_type,
+                field,
+                position,
+                character,
+            } => {
+                write!(f, "Invalid bytes in field {} of message {}: ", field, message_type)?;
+                match character {
+                    Some(character) => write!(
+                        f,
+                        "unexpected character {:?} at position {}",
+                        character, position
+                    )?,
+                    None => write!(f, "the value ends early at position {}", position)?,
+                }
+                write!(f, ", expected base64 or hex prefixed by 0x")
+            }
+            Self::UnresolvedPlaceholders { placeholders } => write!(
+                f,
+                "Unresolved placeholders in the request: {}",
+                placeholders.join(", ")
+            ),
+            Self::NoResponse => write!(f, "No response received"),
+            Self::UnexpectedResponse { expected } => write!(f, "Expected a {} variant", expected),
+            Self::MalformedDescriptor(message) => write!(f, "{}", message),
+            Self::ReadFile { path, source } => write!(f, "Failed to read {:?}: {}", path, source),
+            #[cfg(feature = "transport")]
+            Self::WriteFile { path, source } => {
+                write!(f, "Failed to write {:?}: {}", path, source)
+            }
+        }
+    }
+}
+
+impl ReflectionError {
+    /// Returns whether the error is caused by the connection to the server, e.g. because it
+    /// could not be established or broke down during the call, rather than by a gRPC status
+    /// the server answered with.
+    ///
+    /// These are the errors retried according to the client's
+    /// [`ReconnectPolicy`](crate::reconnect::ReconnectPolicy).
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            #[cfg(feature = "transport")]
+            Self::TlsHandshake { .. } | Self::Connect { .. } => true,
+            Self::ConnectTimeout { .. } | Self::AllEndpointsFailed { .. } => true,
+            Self::Status(status) => is_transport_failure(status),
+            _ => false,
+        }
+    }
+}
+
+/// Returns whether `status` reports a failure of the connection rather than a status the
+/// server answered with.
+pub(crate) fn is_transport_failure(status: &tonic::Status) -> bool {
+    if is_timeout_expiry(status) {
+        return false;
+    }
+    // tonic reports transport failures as a status carrying the transport error
+    #[cfg(feature = "transport")]
+    if caused_by::<tonic::transport::Error>(status) {
+        return true;
+    }
+    caused_by::<std::io::Error>(status)
+}
+
+/// Returns whether `status` reports that the deadline of a request passed before the
+/// server answered, which tonic's channel enforces itself, as a `CANCELLED` status carrying a
+/// transport error.
+pub(crate) fn is_timeout_expiry(status: &tonic::Status) -> bool {
+    #[cfg(feature = "transport")]
+    return caused_by::<tonic::transport::TimeoutExpired>(status);
+    #[cfg(not(feature = "transport"))]
+    {
+        let _ = status;
+        false
+    }
+}
+
+/// Returns whether `status` rejects a request for its compression, as servers answer requests
+/// compressed with an encoding they do not accept: with `UNIMPLEMENTED` and the encodings
+/// they do accept.
+pub(crate) fn is_compression_rejection(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::Unimplemented
+        && status.metadata().contains_key("grpc-accept-encoding")
+}
+
+impl Error for ReflectionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            #[cfg(feature = "transport")]
+            Self::InvalidEndpoint { source, .. }
+            | Self::InvalidUserAgent { source, .. }
+            | Self::Connect { source, .. } => Some(source),
+            #[cfg(feature = "transport")]
+            Self::TlsHandshake { source, .. } => Some(source.as_ref()),
+            Self::ReadFile { source, .. } => Some(source),
+            #[cfg(feature = "transport")]
+            Self::WriteFile { source, .. } => Some(source),
+            Self::InvalidAuthority { source, .. }
+            | Self::InvalidMetadata { source, .. }
+            | Self::InvalidTlsConfig(source) => Some(source.as_ref()),
+            Self::Status(status) | Self::Call { status, .. } => Some(status.as_ref()),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidJson { source, .. } => Some(source),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidTextFormat { source, .. } => Some(source),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidStreamRequest { source, .. } => Some(source.as_ref()),
+            #[cfg(feature = "prost-reflect")]
+            Self::InvalidJsonLine { source, .. } => Some(source.as_ref()),
+            #[cfg(feature = "prost-reflect")]
+            Self::ReadJsonLines { source, .. } => Some(source),
+            Self::Retried { source, .. } => Some(source.as_ref()),
+            Self::Decode(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// A service that [`ReflectionClient::list_services_lenient`] could not resolve
+///
+/// [`ReflectionClient::list_services_lenient`]: crate::reflection::ReflectionClient::list_services_lenient
+#[derive(Debug)]
+pub struct ServiceListError {
+    /// The fully qualified name of the service, as listed by the server
+    pub service: String,
+    /// Why the service could not be resolved
+    pub error: ReflectionError,
+}
+
+impl fmt::Display for ServiceListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to resolve service {:?}: {}",
+            self.service, self.error
+        )
+    }
+}
+
+impl Error for ServiceListError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<tonic::Status> for ReflectionError {
+    fn from(status: tonic::Status) -> Self {
+        Self::Status(Box::new(status))
+    }
+}
+
+impl From<prost::DecodeError> for ReflectionError {
+    fn from(error: prost::DecodeError) -> Self {
+        Self::Decode(error)
+    }
+}
+
+/// Returns whether `error` or any of its sources is a `T`, including errors wrapped in an
+/// `io::Error`, whose `source` skips over the wrapped error itself.
+pub(crate) fn caused_by<T: Error + 'static>(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        let is_cause = error.is::<T>()
+            || error
+                .downcast_ref::<std::io::Error>()
+                .and_then(|error| error.get_ref())
+                .is_some_and(|inner| inner.is::<T>());
+        if is_cause {
+            return true;
+        }
+
+        source = error.source();
+    }
+
+    false
+}
+
+/// Returns whether `error` was caused by a fatal TLS alert of the server.
+///
+/// Alerts received after the handshake are passed on by h2, which keeps only the kind and
+/// message of I/O errors, so they are recognized by the message of the rustls error.
+#[cfg(feature = "tls")]
+pub(crate) fn caused_by_tls_alert(error: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        let is_alert = error.downcast_ref::<std::io::Error>().is_some_and(|error| {
+            error.kind() == std::io::ErrorKind::InvalidData
+                && error.to_string().starts_with("received fatal alert")
+        });
+        if is_alert {
+            return true;
+        }
+
+        source = error.source();
+    }
+
+    false
+}
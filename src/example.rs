@@ -0,0 +1,312 @@
+//! Generating example messages, e.g. as templates for requests.
+
+use crate::catalog::OfflineCatalog;
+use crate::message_info::{EnumInfo, FieldInfo, FieldType, Label, MessageInfo, WellKnownType};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use serde_json::{json, Map, Value};
+
+/// Options of the generated example messages
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::example::ExampleOptions;
+///
+/// let options = ExampleOptions {
+///     max_depth: 1,
+///     ..Default::default()
+/// };
+/// assert!(options.oneof_markers);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExampleOptions {
+    /// How deeply messages are nested in the example, beyond which fields of message types
+    /// are empty objects, which ends the recursion of recursive messages
+    ///
+    /// Fields of the example message itself are at depth 1. Defaults to 3.
+    pub max_depth: usize,
+    /// Whether the fields of each oneof are explained by a marker property, e.g.
+    /// `"// method": "oneof: set one of cardToken, iban"`
+    ///
+    /// Servers reject the markers as unknown fields, so they have to be removed before
+    /// sending the example. Defaults to `true`.
+    pub oneof_markers: bool,
+}
+
+impl Default for ExampleOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            oneof_markers: true,
+        }
+    }
+}
+
+/// Returns an example of the protobuf JSON encoding of `message`, looking up the message and
+/// enum types its fields refer to in `files`.
+///
+/// Every field is set to a placeholder, named by its JSON name:
+/// - scalars have their default values, e.g. `0`, `""` or `false`, and 64-bit integers are
+///   strings like `"0"`
+/// - enums are set to the name of their first value
+/// - repeated fields have a single element, and maps a single entry whose key is the default
+///   value of its type
+/// - messages are examples themselves, down to [`ExampleOptions::max_depth`]
+/// - well-known types have their special representations, e.g. a `Timestamp` is
+///   `"1970-01-01T00:00:00Z"` and an `Int32Value` is `0`
+/// - of the fields of a oneof, only the first is set, along with a marker property naming
+///   the others if [`ExampleOptions::oneof_markers`] is enabled
+///
+/// Fields of types not found in `files` are empty objects.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::catalog::OfflineCatalog;
+/// use grpc_ease::example::{example_message, ExampleOptions};
+/// use prost_types::field_descriptor_proto::{Label, Type};
+/// use prost_types::{
+///     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+///     FileDescriptorProto, FileDescriptorSet, OneofDescriptorProto,
+/// };
+/// use serde_json::json;
+///
+/// let field = |name: &str, number, label: Label, r#type: Type, type_name: Option<&str>| {
+///     FieldDescriptorProto {
+///         name: Some(name.to_string()),
+///         number: Some(number),
+///         label: Some(label.into()),
+///         r#type: Some(r#type.into()),
+///         type_name: type_name.map(str::to_string),
+///         ..Default::default()
+///     }
+/// };
+/// // message Node {
+/// //   enum Kind {
+/// //     FILE = 0;
+/// //     DIRECTORY = 1;
+/// //   }
+/// //   string name = 1;
+/// //   Kind kind = 2;
+/// //   repeated Node children = 3;
+/// //   oneof size {
+/// //     uint64 bytes = 4;
+/// //     uint32 entries = 5;
+/// //   }
+/// // }
+/// let value = |name: &str, number| EnumValueDescriptorProto {
+///     name: Some(name.to_string()),
+///     number: Some(number),
+///     ..Default::default()
+/// };
+/// let node = DescriptorProto {
+///     name: Some("Node".to_string()),
+///     field: vec![
+///         field("name", 1, Label::Optional, Type::String, None),
+///         field("kind", 2, Label::Optional, Type::Enum, Some(".tree.Node.Kind")),
+///         field("children", 3, Label::Repeated, Type::Message, Some(".tree.Node")),
+///         FieldDescriptorProto {
+///             oneof_index: Some(0),
+///             ..field("bytes", 4, Label::Optional, Type::Uint64, None)
+///         },
+///         FieldDescriptorProto {
+///             oneof_index: Some(0),
+///             ..field("entries", 5, Label::Optional, Type::Uint32, None)
+///         },
+///     ],
+///     enum_type: vec![EnumDescriptorProto {
+///         name: Some("Kind".to_string()),
+///         value: vec![value("FILE", 0), value("DIRECTORY", 1)],
+///         ..Default::default()
+///     }],
+///     oneof_decl: vec![OneofDescriptorProto {
+///         name: Some("size".to_string()),
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+/// let tree = FileDescriptorProto {
+///     name: Some("tree.proto".to_string()),
+///     package: Some("tree".to_string()),
+///     syntax: Some("proto3".to_string()),
+///     message_type: vec![node],
+///     ..Default::default()
+/// };
+/// let catalog = OfflineCatalog::new(FileDescriptorSet { file: vec![tree] });
+/// let node = catalog.describe_message("tree.Node")?;
+///
+/// let options = ExampleOptions {
+///     max_depth: 2,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     example_message(&node, catalog.files(), &options),
+///     json!({
+///         "name": "",
+///         "kind": "FILE",
+///         "children": [{
+///             "name": "",
+///             "kind": "FILE",
+///             "children": [{}],
+///             "// size": "oneof: set one of bytes, entries",
+///             "bytes": "0",
+///         }],
+///         "// size": "oneof: set one of bytes, entries",
+///         "bytes": "0",
+///     })
+/// );
+/// # Ok::<(), grpc_ease::error::ReflectionError>(())
+/// ```
+pub fn example_message(
+    message: &MessageInfo,
+    files: &[FileDescriptorProto],
+    options: &ExampleOptions,
+) -> Value {
+    let generator = Generator {
+        catalog: OfflineCatalog::new(FileDescriptorSet {
+            file: files.to_vec(),
+        }),
+        options,
+    };
+    generator.message(message, 1)
+}
+
+struct Generator<'a> {
+    catalog: OfflineCatalog,
+    options: &'a ExampleOptions,
+}
+
+impl Generator<'_> {
+    /// Returns an example of `message`, whose fields are at `depth`.
+    fn message(&self, message: &MessageInfo, depth: usize) -> Value {
+        let mut example = Map::new();
+        if depth > self.options.max_depth {
+            return Value::Object(example);
+        }
+
+        for field in &message.fields {
+            example.insert(field.json_name.clone(), self.field(field, depth));
+        }
+        for oneof in &message.oneofs {
+            let Some(first) = oneof.fields.first() else {
+                continue;
+            };
+            if self.options.oneof_markers {
+                let names: Vec<&str> = oneof
+                    .fields
+                    .iter()
+                    .map(|field| field.json_name.as_str())
+                    .collect();
+                example.insert(
+                    format!("// {}", oneof.name),
+                    json!(format!("oneof: set one of {}", names.join(", "))),
+                );
+            }
+            example.insert(first.json_name.clone(), self.field(first, depth));
+        }
+        Value::Object(example)
+    }
+
+    fn field(&self, field: &FieldInfo, depth: usize) -> Value {
+        if let Some(map) = &field.map {
+            let key = match self.value(map.key_type, None, None, depth) {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            let value = self.value(map.value_type, map.value_type_name.as_deref(), None, depth);
+            return json!({ key: value });
+        }
+
+        let value = self.value(
+            field.field_type,
+            field.type_name.as_deref(),
+            field.enum_info.as_ref(),
+            depth,
+        );
+        match field.label {
+            Label::Repeated => json!([value]),
+            _ => value,
+        }
+    }
+
+    /// Returns an example value of `field_type`, whose message or enum types are named
+    /// `type_name`, of a field at `depth`.
+    fn value(
+        &self,
+        field_type: FieldType,
+        type_name: Option<&str>,
+        enum_info: Option<&EnumInfo>,
+        depth: usize,
+    ) -> Value {
+        if let Some(well_known_type) = type_name.and_then(WellKnownType::from_type_name) {
+            return well_known(well_known_type);
+        }
+
+        match field_type {
+            FieldType::Message | FieldType::Group => {
+                match type_name.and_then(|name| self.catalog.describe_message(name).ok()) {
+                    Some(message) => self.message(&message, depth + 1),
+                    None => json!({}),
+                }
+            }
+            FieldType::Enum => {
+                let described = match (enum_info, type_name) {
+                    (None, Some(name)) => self.catalog.describe_enum(name).ok(),
+                    _ => None,
+                };
+                match enum_info
+                    .or(described.as_ref())
+                    .and_then(|enumeration| enumeration.values.first())
+                {
+                    Some((name, _)) => json!(name),
+                    None => json!(0),
+                }
+            }
+            scalar_type => scalar(scalar_type),
+        }
+    }
+}
+
+/// Returns the default value of the scalar `field_type`.
+fn scalar(field_type: FieldType) -> Value {
+    match field_type {
+        FieldType::Double | FieldType::Float => json!(0.0),
+        FieldType::Int32
+        | FieldType::Sint32
+        | FieldType::Sfixed32
+        | FieldType::Uint32
+        | FieldType::Fixed32 => json!(0),
+        // 64-bit integers are strings, since JSON numbers lose precision beyond 2^53
+        FieldType::Int64
+        | FieldType::Sint64
+        | FieldType::Sfixed64
+        | FieldType::Uint64
+        | FieldType::Fixed64 => json!("0"),
+        FieldType::Bool => json!(false),
+        FieldType::String | FieldType::Bytes => json!(""),
+        FieldType::Group | FieldType::Message => json!({}),
+        FieldType::Enum => json!(0),
+    }
+}
+
+/// Returns an example of the special JSON representation of `well_known_type`.
+fn well_known(well_known_type: WellKnownType) -> Value {
+    match well_known_type {
+        WellKnownType::Any => json!({ "@type": "" }),
+        WellKnownType::Timestamp => json!("1970-01-01T00:00:00Z"),
+        WellKnownType::Duration => json!("0s"),
+        WellKnownType::Empty | WellKnownType::Struct => json!({}),
+        WellKnownType::FieldMask => json!(""),
+        WellKnownType::Value | WellKnownType::NullValue => Value::Null,
+        WellKnownType::ListValue => json!([]),
+        WellKnownType::DoubleValue => scalar(FieldType::Double),
+        WellKnownType::FloatValue => scalar(FieldType::Float),
+        WellKnownType::Int64Value => scalar(FieldType::Int64),
+        WellKnownType::UInt64Value => scalar(FieldType::Uint64),
+        WellKnownType::Int32Value => scalar(FieldType::Int32),
+        WellKnownType::UInt32Value => scalar(FieldType::Uint32),
+        WellKnownType::BoolValue => scalar(FieldType::Bool),
+        WellKnownType::StringValue => scalar(FieldType::String),
+        WellKnownType::BytesValue => scalar(FieldType::Bytes),
+    }
+}
@@ -0,0 +1,87 @@
+//! Content hashes of the schemas of servers, to tell whether a schema changed.
+
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// A SHA-256 hash of a schema, the same for the same file descriptors no matter in which
+/// order they were fetched
+///
+/// The schema is canonicalized before hashing:
+/// - Files of the same name are kept once
+/// - The files are sorted by their names
+/// - The source code info is removed, so only changing comments keeps the fingerprint
+/// - The files are serialized as a `FileDescriptorSet`, whose fields protobuf encodes in
+///   the order of their field numbers
+///
+/// The fingerprint is displayed as 64 lowercase hex digits, which [`from_hex`](Self::from_hex)
+/// parses back, e.g. to compare against a fingerprint stored by an earlier run.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::fingerprint::Fingerprint;
+/// use prost_types::FileDescriptorProto;
+///
+/// let file = |name: &str| FileDescriptorProto {
+///     name: Some(name.to_string()),
+///     ..Default::default()
+/// };
+///
+/// let fingerprint = Fingerprint::of(&[file("a.proto"), file("b.proto")]);
+/// assert_eq!(fingerprint, Fingerprint::of(&[file("b.proto"), file("a.proto")]));
+/// assert_ne!(fingerprint, Fingerprint::of(&[file("a.proto")]));
+///
+/// let hex = fingerprint.to_string();
+/// assert_eq!(hex.len(), 64);
+/// assert_eq!(Fingerprint::from_hex(&hex), Some(fingerprint));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Computes the fingerprint of the schema made up of `files`.
+    pub fn of(files: &[FileDescriptorProto]) -> Self {
+        let mut files: Vec<_> = files
+            .iter()
+            .map(|file| FileDescriptorProto {
+                source_code_info: None,
+                ..file.clone()
+            })
+            .collect();
+        files.sort_by(|a, b| a.name().cmp(b.name()));
+        files.dedup_by(|a, b| a.name() == b.name());
+
+        let encoded = FileDescriptorSet { file: files }.encode_to_vec();
+        Self(Sha256::digest(encoded).into())
+    }
+
+    /// Parses a fingerprint from its 64 hex digits, as displayed, returning `None` if `hex`
+    /// is not a fingerprint.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() != 64 || !hex.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+            return None;
+        }
+        let mut bytes = [0; 32];
+        for (byte, digits) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+        }
+        Some(Self(bytes))
+    }
+
+    /// Returns the bytes of the hash.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
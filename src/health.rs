@@ -0,0 +1,207 @@
+//! The health of servers and their services, as reported by the standard gRPC health checking
+//! service, `grpc.health.v1.Health`.
+//!
+//! The descriptors of the health service are bundled, so servers are checked even if they do
+//! not reflect them.
+
+use crate::invoke::{CallHandle, JsonStream};
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor};
+use prost_types::field_descriptor_proto::Type;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+    FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto, ServiceDescriptorProto,
+};
+use serde_json::Value;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use tokio_stream::Stream;
+use tonic::Status;
+
+/// The fully qualified name of the health checking service
+pub(crate) const HEALTH_SERVICE: &str = "grpc.health.v1.Health";
+
+/// The serving status of a server or of one of its services
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::health::HealthStatus;
+///
+/// assert!(HealthStatus::Serving.is_serving());
+/// assert!(!HealthStatus::ServiceUnknown.is_serving());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HealthStatus {
+    /// The server did not report a status, or one this crate does not know
+    Unknown,
+    /// The server or service serves requests
+    Serving,
+    /// The server or service does not serve requests, e.g. while starting or shutting down
+    NotServing,
+    /// The health checking service does not know the service
+    ServiceUnknown,
+}
+
+impl HealthStatus {
+    /// Returns whether the server or service serves requests.
+    pub fn is_serving(self) -> bool {
+        self == Self::Serving
+    }
+
+    /// Returns the status of a `grpc.health.v1.HealthCheckResponse`.
+    pub(crate) fn of_response(response: &DynamicMessage) -> Self {
+        let number = response
+            .get_field_by_name("status")
+            .and_then(|status| status.as_enum_number());
+        number.map_or(Self::Unknown, Self::of_number)
+    }
+
+    /// Returns the status of the JSON encoding of a `grpc.health.v1.HealthCheckResponse`,
+    /// which leaves out the `UNKNOWN` default.
+    fn of_json(response: &Value) -> Self {
+        match &response["status"] {
+            Value::String(name) => match name.as_str() {
+                "SERVING" => Self::Serving,
+                "NOT_SERVING" => Self::NotServing,
+                "SERVICE_UNKNOWN" => Self::ServiceUnknown,
+                _ => Self::Unknown,
+            },
+            Value::Number(number) => number
+                .as_i64()
+                .and_then(|number| i32::try_from(number).ok())
+                .map_or(Self::Unknown, Self::of_number),
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns the status of a number of the `ServingStatus` enum.
+    fn of_number(number: i32) -> Self {
+        match number {
+            1 => Self::Serving,
+            2 => Self::NotServing,
+            3 => Self::ServiceUnknown,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The statuses of a server or service as the server reports changes, as returned by
+/// [`ReflectionClient::watch_health`](crate::reflection::ReflectionClient::watch_health)
+///
+/// The stream starts with the current status and ends with the status of the call if it
+/// fails. Dropping it cancels the call, like dropping a [`JsonStream`].
+#[derive(Debug)]
+pub struct HealthStream {
+    responses: JsonStream,
+}
+
+impl HealthStream {
+    pub(crate) fn new(responses: JsonStream) -> Self {
+        Self { responses }
+    }
+
+    /// Returns a handle cancelling the call, like [`JsonStream::handle`].
+    pub fn handle(&self) -> CallHandle {
+        self.responses.handle()
+    }
+}
+
+impl Stream for HealthStream {
+    type Item = Result<HealthStatus, Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.responses)
+            .poll_next(cx)
+            .map_ok(|response| HealthStatus::of_json(&response))
+    }
+}
+
+/// Returns the request of the health checking method `method` for `service`, the server as
+/// a whole if `None`.
+pub(crate) fn request(method: &MethodDescriptor, service: Option<&str>) -> DynamicMessage {
+    let mut request = DynamicMessage::new(method.input());
+    request.set_field_by_name(
+        "service",
+        prost_reflect::Value::String(service.unwrap_or_default().to_string()),
+    );
+    request
+}
+
+/// Returns the bundled descriptor of the method `name` of the health checking service.
+pub(crate) fn bundled_method(name: &str) -> MethodDescriptor {
+    static POOL: OnceLock<DescriptorPool> = OnceLock::new();
+
+    let pool = POOL.get_or_init(|| {
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+            file: vec![health_file()],
+        })
+        .expect("the bundled health descriptors are valid")
+    });
+    pool.get_service_by_name(HEALTH_SERVICE)
+        .and_then(|service| service.methods().find(|method| method.name() == name))
+        .expect("the bundled health service has the method")
+}
+
+/// Returns the descriptor of `grpc/health/v1/health.proto`.
+fn health_file() -> FileDescriptorProto {
+    let status = EnumDescriptorProto {
+        name: Some("ServingStatus".to_string()),
+        value: ["UNKNOWN", "SERVING", "NOT_SERVING", "SERVICE_UNKNOWN"]
+            .into_iter()
+            .zip(0..)
+            .map(|(name, number)| EnumValueDescriptorProto {
+                name: Some(name.to_string()),
+                number: Some(number),
+                options: None,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let field = |name: &str, r#type: Type, type_name: Option<&str>| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(1),
+        r#type: Some(r#type.into()),
+        type_name: type_name.map(str::to_string),
+        json_name: Some(name.to_string()),
+        ..Default::default()
+    };
+    let method = |name: &str, server_streaming| MethodDescriptorProto {
+        name: Some(name.to_string()),
+        input_type: Some(".grpc.health.v1.HealthCheckRequest".to_string()),
+        output_type: Some(".grpc.health.v1.HealthCheckResponse".to_string()),
+        server_streaming: Some(server_streaming),
+        ..Default::default()
+    };
+
+    FileDescriptorProto {
+        name: Some("grpc/health/v1/health.proto".to_string()),
+        package: Some("grpc.health.v1".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![
+            DescriptorProto {
+                name: Some("HealthCheckRequest".to_string()),
+                field: vec![field("service", Type::String, None)],
+                ..Default::default()
+            },
+            DescriptorProto {
+                name: Some("HealthCheckResponse".to_string()),
+                field: vec![field(
+                    "status",
+                    Type::Enum,
+                    Some(".grpc.health.v1.HealthCheckResponse.ServingStatus"),
+                )],
+                enum_type: vec![status],
+                ..Default::default()
+            },
+        ],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Health".to_string()),
+            method: vec![method("Check", false), method("Watch", true)],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
@@ -0,0 +1,36 @@
+//! Sharing a tonic [`Interceptor`] between the clones of a builder and the clients it builds.
+
+use crate::error::ReflectionError;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tonic::service::Interceptor;
+use tonic::Request;
+
+/// An interceptor applied to every reflection request, shared by all clients built from the
+/// same builder.
+#[derive(Clone)]
+pub(crate) struct SharedInterceptor(Arc<Mutex<dyn Interceptor + Send>>);
+
+impl SharedInterceptor {
+    pub(crate) fn new(interceptor: impl Interceptor + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(interceptor)))
+    }
+
+    /// Runs the interceptor on the metadata and extensions of `request`, keeping its message.
+    pub(crate) fn intercept<T>(&self, request: Request<T>) -> Result<Request<T>, ReflectionError> {
+        let (metadata, extensions, message) = request.into_parts();
+
+        let mut interceptor = self.0.lock().unwrap_or_else(|error| error.into_inner());
+        let (metadata, extensions, ()) = interceptor
+            .call(Request::from_parts(metadata, extensions, ()))?
+            .into_parts();
+
+        Ok(Request::from_parts(metadata, extensions, message))
+    }
+}
+
+impl fmt::Debug for SharedInterceptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedInterceptor")
+    }
+}
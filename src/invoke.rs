@@ -0,0 +1,1683 @@
+//! Calling the methods of a server dynamically, with requests and responses described by
+//! reflection rather than generated code.
+//!
+//! Calls with JSON requests and responses, like `ReflectionClient::call_unary`, require the
+//! `prost-reflect` feature, while `ReflectionClient::call_raw` sends encoded messages as they
+//! are.
+
+#[cfg(feature = "prost-reflect")]
+use crate::any_json::{self, AnyTypes};
+#[cfg(feature = "prost-reflect")]
+use crate::error::ReflectionError;
+use crate::retry::RetryPolicy;
+#[cfg(feature = "prost-reflect")]
+use crate::{json_bytes, raw_json, template};
+use base64::Engine;
+use prost::bytes::{Buf, BufMut};
+#[cfg(feature = "prost-reflect")]
+use prost::Message;
+#[cfg(feature = "prost-reflect")]
+use prost_reflect::{DynamicMessage, MessageDescriptor, ReflectMessage};
+#[cfg(feature = "prost-reflect")]
+use serde_json::Value;
+#[cfg(feature = "prost-reflect")]
+use std::collections::HashMap;
+use std::future::Future;
+#[cfg(feature = "prost-reflect")]
+use std::pin::Pin;
+#[cfg(feature = "prost-reflect")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "prost-reflect")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "prost-reflect")]
+use std::task::{Context, Poll};
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+#[cfg(feature = "prost-reflect")]
+use tokio::sync::oneshot;
+#[cfg(feature = "prost-reflect")]
+use tokio_stream::Stream;
+#[cfg(feature = "prost-reflect")]
+use tonic::codec::Streaming;
+use tonic::codec::{Codec, CompressionEncoding, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::Bytes;
+use tonic::metadata::{KeyAndValueRef, MetadataMap};
+use tonic::Status;
+#[cfg(feature = "prost-reflect")]
+use tonic::{Code, Response};
+
+/// Options of a single call, applied on top of the configuration of the client
+///
+/// Options that are not set fall back to those of the client, as configured on the
+/// [`ReflectionClientBuilder`](crate::builder::ReflectionClientBuilder).
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::error::ReflectionError;
+/// use grpc_ease::invoke::CallOptions;
+/// use grpc_ease::reflection::ReflectionClient;
+/// use std::time::{Duration, Instant};
+/// use tonic::codec::CompressionEncoding;
+/// use tonic::metadata::{MetadataMap, MetadataValue};
+/// use tonic::Code;
+///
+/// // answers `/fixture.Fixed/Get` after a while, however long the call may take
+/// let fixed = support::Fixed::new(support::Reply { text: "hello".to_string(), details: vec![] });
+/// # let endpoint = support::serve_fixed(fixed.clone().delay(Duration::from_secs(10))).await?;
+/// let mut metadata = MetadataMap::new();
+/// metadata.insert("x-tenant", MetadataValue::from_static("acme"));
+/// metadata.insert("x-client", MetadataValue::from_static("grpc-ease"));
+/// let mut client = ReflectionClient::builder(&endpoint).metadata(metadata).build_lazy()?;
+///
+/// let mut options = CallOptions::default();
+/// options.deadline = Some(Duration::from_millis(200));
+/// options.metadata.insert("x-tenant", MetadataValue::from_static("globex"));
+/// options.compression = Some(CompressionEncoding::Gzip);
+/// let started = Instant::now();
+/// let error = client
+///     .call_raw("/fixture.Fixed/Get", Default::default(), options)
+///     .await
+///     .unwrap_err();
+/// assert!(matches!(
+///     error,
+///     ReflectionError::Call { status, .. } if status.code() == Code::DeadlineExceeded
+/// ));
+/// assert!(started.elapsed() < Duration::from_secs(2));
+///
+/// // the entries of the call replace those of the client, and the request is compressed and
+/// // carries the deadline, which the server ignored
+/// let request = &fixed.requests()[0];
+/// assert_eq!(request.get_all("x-tenant").iter().collect::<Vec<_>>(), ["globex"]);
+/// assert_eq!(request["x-client"], "grpc-ease");
+/// assert_eq!(request["grpc-encoding"], "gzip");
+/// assert!(request.contains_key("grpc-timeout"));
+///
+/// // the response is seven bytes long
+/// let mut options = CallOptions::default();
+/// options.max_response_size = Some(1);
+/// # let endpoint = support::serve_fixed(fixed.clone()).await?;
+/// let mut client = ReflectionClient::new_lazy(endpoint)?;
+/// let error = client
+///     .call_raw("/fixture.Fixed/Get", Default::default(), options)
+///     .await
+///     .unwrap_err();
+/// assert!(matches!(
+///     error,
+///     ReflectionError::Call { status, .. } if status.code() == Code::OutOfRange
+/// ));
+///
+/// // a call waiting for the server, which only starts listening after the call is sent
+/// # let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+/// let mut client = ReflectionClient::new_lazy(format!("http://{}", port))?;
+/// let mut options = CallOptions::default();
+/// options.wait_for_ready = true;
+/// options.deadline = Some(Duration::from_secs(10));
+/// let call = client.call_raw("/fixture.Fixed/Get", Default::default(), options);
+/// # let server = async {
+/// #     tokio::time::sleep(Duration::from_millis(300)).await;
+/// #     let listener = tokio::net::TcpListener::bind(port).await?;
+/// #     let incoming = tokio_stream::wrappers::TcpListenerStream::new(listener);
+/// #     tokio::spawn(support::fixed_router(fixed.clone()).serve_with_incoming(incoming));
+/// #     Ok::<_, std::io::Error>(())
+/// # };
+/// # let (response, started) = tokio::join!(call, server);
+/// # started?;
+/// let (response, _, _) = response?;
+/// assert_eq!(response, b"\x0a\x05hello"[..]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// How long the call may take, sent to the server as the gRPC deadline and enforced by
+    /// the client as well, even if the server ignores it, defaulting to the request timeout
+    /// of the client. Platforms without a clock, like `wasm32-unknown-unknown`, only send it.
+    ///
+    /// A call exceeding its deadline fails with a `DEADLINE_EXCEEDED` status. The deadline of
+    /// a streaming call covers the whole stream.
+    pub deadline: Option<Duration>,
+    /// Metadata sent along with the request, replacing the entries of the same keys of the
+    /// client's metadata
+    pub metadata: MetadataMap,
+    /// The encoding the request is compressed with, replacing the one of the client
+    pub compression: Option<CompressionEncoding>,
+    /// The maximum size of a response message in bytes, replacing the limit of the client
+    pub max_response_size: Option<usize>,
+    /// Whether to wait for the server to become reachable instead of failing once the
+    /// connection cannot be established
+    ///
+    /// Calls failing because of the connection are sent again, with growing delays, until
+    /// they reach the server or their deadline. Client streaming calls are never sent again
+    /// since their requests are consumed by the first attempt.
+    pub wait_for_ready: bool,
+    /// How failed unary calls are retried, if at all
+    ///
+    /// Only calls of methods marked with `option idempotency_level` as `IDEMPOTENT` or
+    /// `NO_SIDE_EFFECTS` are retried, unless the policy opts in to
+    /// [retrying all methods](RetryPolicy::retry_non_idempotent). Each attempt has the
+    /// deadline of the call and sends the request as it was encoded for the first one.
+    /// Connection failures are retried according to
+    /// [`wait_for_ready`](Self::wait_for_ready) within each attempt.
+    pub retry: Option<RetryPolicy>,
+    /// How the JSON requests and responses of the call are mapped to and from messages
+    #[cfg(feature = "prost-reflect")]
+    pub json_mapping: JsonMapping,
+    /// How the `bytes` fields of the JSON responses of the call are encoded
+    ///
+    /// Requests may encode them in any of the [`BytesEncoding`]s, with or without padding.
+    #[cfg(feature = "prost-reflect")]
+    pub bytes_encoding: BytesEncoding,
+    /// The variables substituted for the `{{name}}` placeholders of the JSON requests of the
+    /// call before they are converted, along with the built-ins like `{{uuid}}`, as done by
+    /// [`template::substitute`](crate::template::substitute)
+    ///
+    /// Without variables, requests are sent as they are, placeholders included. Each request
+    /// of a client streaming call is substituted on its own.
+    #[cfg(feature = "prost-reflect")]
+    pub variables: Option<HashMap<String, Value>>,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl CallOptions {
+    /// Converts the JSON `request` to a message of the type `descriptor`, substituting the
+    /// variables first if there are any.
+    pub(crate) fn request_from_json(
+        &self,
+        descriptor: MessageDescriptor,
+        request: Value,
+    ) -> Result<DynamicMessage, ReflectionError> {
+        request_from_json(
+            descriptor,
+            request,
+            self.json_mapping,
+            self.variables.as_ref(),
+        )
+    }
+}
+
+/// The point in time a call has to complete by
+///
+/// Platforms without a clock, like `wasm32-unknown-unknown`, leave enforcing the deadline to
+/// the server.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Deadline {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    at: tokio::time::Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    /// Returns the deadline of a call starting now and taking at most `timeout`.
+    pub(crate) fn after(timeout: Duration) -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            at: tokio::time::Instant::now() + timeout,
+            timeout,
+        }
+    }
+
+    /// Returns the time left until the deadline, the whole timeout without a clock.
+    pub(crate) fn remaining(&self) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self
+            .at
+            .saturating_duration_since(tokio::time::Instant::now());
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        return self.timeout;
+    }
+}
+
+/// Runs `call` until it completes or the `deadline` passes, failing with a
+/// `DEADLINE_EXCEEDED` status then.
+pub(crate) async fn until_deadline<R>(
+    deadline: Option<Deadline>,
+    call: impl Future<Output = Result<R, Status>>,
+) -> Result<R, Status> {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    if let Some(deadline) = deadline {
+        return match tokio::time::timeout_at(deadline.at, call).await {
+            Ok(Err(status)) if !crate::error::is_timeout_expiry(&status) => Err(status),
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) | Err(_) => Err(Status::deadline_exceeded(format!(
+                "Call did not complete within {:?}",
+                deadline.timeout
+            ))),
+        };
+    }
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    let _ = deadline;
+
+    call.await
+}
+
+/// The response of a call along with everything else the server sent, as returned by
+/// [`ReflectionClient::call_unary_with_response`](crate::reflection::ReflectionClient::call_unary_with_response)
+///
+/// A call failing with a status still has a response, whose `message` is `null` and whose
+/// trailers hold the `grpc-status`, `grpc-message` and `grpc-status-details-bin` entries of
+/// the status.
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone)]
+pub struct CallResponse {
+    /// The JSON encoding of the response message, in the mapping of the call options
+    pub message: Value,
+    /// The headers the server sent before the response
+    pub headers: MetadataMap,
+    /// The trailers the server sent after the response
+    pub trailers: MetadataMap,
+    /// The status code the call ended with
+    pub status: Code,
+    /// How long it took from sending the request until the trailers arrived, zero on
+    /// platforms without a clock, like `wasm32-unknown-unknown`
+    pub duration: Duration,
+    /// How long it took from sending the request until the response message arrived, or
+    /// `None` if it did not
+    pub time_to_first_response: Option<Duration>,
+    /// The size of the encoded request messages in bytes, of all of them for client streaming
+    /// calls
+    pub request_size: usize,
+    /// The size of the encoded response message in bytes, zero if there is none
+    pub response_size: usize,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl CallResponse {
+    /// Reads the response message and trailers of a unary `response` until the `deadline`,
+    /// as returned by a server streaming call so the headers are kept apart from the trailers,
+    /// converting the message with `to_json`.
+    ///
+    /// The request size is left for the caller to set.
+    pub(crate) async fn collect<F>(
+        response: Result<Response<Streaming<DynamicMessage>>, Status>,
+        stopwatch: Stopwatch,
+        deadline: Option<Deadline>,
+        to_json: impl FnOnce(DynamicMessage) -> F,
+    ) -> Result<Self, ReflectionError>
+    where
+        F: Future<Output = Result<Value, ReflectionError>>,
+    {
+        let (headers, mut responses) = match response {
+            Ok(response) => {
+                let (headers, responses, _) = response.into_parts();
+                (headers, responses)
+            }
+            // the server sent the status without any headers
+            Err(status) => return Ok(Self::failed(MetadataMap::new(), status, stopwatch)),
+        };
+
+        let message = match until_deadline(deadline, responses.message()).await {
+            Ok(Some(message)) => message,
+            Ok(None) => {
+                let status = Status::internal("Missing response message.");
+                return Ok(Self::failed(headers, status, stopwatch));
+            }
+            Err(status) => return Ok(Self::failed(headers, status, stopwatch)),
+        };
+        let time_to_first_response = stopwatch.elapsed();
+        let trailers = match until_deadline(deadline, responses.trailers()).await {
+            Ok(trailers) => trailers.unwrap_or_default(),
+            Err(status) => return Ok(Self::failed(headers, status, stopwatch)),
+        };
+
+        let response_size = message.encoded_len();
+        Ok(Self {
+            message: to_json(message).await?,
+            headers,
+            trailers,
+            status: Code::Ok,
+            duration: stopwatch.elapsed(),
+            time_to_first_response: Some(time_to_first_response),
+            request_size: 0,
+            response_size,
+        })
+    }
+
+    /// The response of a call that failed with `status`.
+    fn failed(headers: MetadataMap, status: Status, stopwatch: Stopwatch) -> Self {
+        // tonic moves the status entries out of the metadata
+        let mut trailers = tonic::codegen::http::HeaderMap::new();
+        let trailers = match status.add_header(&mut trailers) {
+            Ok(()) => MetadataMap::from_headers(trailers),
+            Err(_) => status.metadata().clone(),
+        };
+        Self {
+            message: Value::Null,
+            headers,
+            trailers,
+            status: status.code(),
+            duration: stopwatch.elapsed(),
+            time_to_first_response: None,
+            request_size: 0,
+            response_size: 0,
+        }
+    }
+
+    /// Emits the [`trace_call`] event of the call of the method `path`.
+    pub(crate) fn trace(&self, path: &str) {
+        trace_call(
+            path,
+            self.status,
+            self.duration,
+            self.time_to_first_response,
+            self.request_size,
+            self.response_size,
+        );
+    }
+
+    /// Returns the headers as printable pairs of keys and values, like
+    /// [`printable_metadata`].
+    pub fn printable_headers(&self) -> Vec<(String, String)> {
+        printable_metadata(&self.headers)
+    }
+
+    /// Returns the trailers as printable pairs of keys and values, like
+    /// [`printable_metadata`].
+    pub fn printable_trailers(&self) -> Vec<(String, String)> {
+        printable_metadata(&self.trailers)
+    }
+}
+
+/// The statistics of a batch of identical calls, as returned by
+/// [`ReflectionClient::call_unary_n`](crate::reflection::ReflectionClient::call_unary_n)
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::invoke::CallStats;
+/// use std::time::Duration;
+///
+/// let stats = CallStats {
+///     calls: 4,
+///     latencies: [10, 20, 30, 400].map(Duration::from_millis).to_vec(),
+///     ..Default::default()
+/// };
+/// assert_eq!(stats.p50(), Some(Duration::from_millis(20)));
+/// assert_eq!(stats.p99(), Some(Duration::from_millis(400)));
+/// assert_eq!(CallStats::default().p50(), None);
+/// # }
+/// ```
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallStats {
+    /// The number of calls made
+    pub calls: usize,
+    /// The durations of the calls that succeeded, shortest first
+    pub latencies: Vec<Duration>,
+    /// The number of failed calls by status code, where errors without a status, like a
+    /// response without a JSON encoding, count as `UNKNOWN`
+    pub errors: HashMap<Code, usize>,
+    /// How long it took from sending the first request until all calls completed
+    pub duration: Duration,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl CallStats {
+    /// Returns the duration the `percentile`, between `0.0` and `100.0`, of the successful
+    /// calls completed within, or `None` if no call succeeded.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        // the nearest rank
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.latencies.len() as f64).ceil();
+        let index = (rank as usize).clamp(1, self.latencies.len().max(1)) - 1;
+        self.latencies.get(index).copied()
+    }
+
+    /// Returns the median duration of the successful calls.
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    /// Returns the duration 95% of the successful calls completed within.
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    /// Returns the duration 99% of the successful calls completed within.
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    /// Returns the number of failed calls.
+    pub fn failed(&self) -> usize {
+        self.errors.values().sum()
+    }
+}
+
+/// Emits a tracing event with the metrics of a completed call of the method `path`, at the
+/// debug level, with one field per metric so they can be scraped.
+pub(crate) fn trace_call(
+    path: &str,
+    status: tonic::Code,
+    duration: Duration,
+    time_to_first_response: Option<Duration>,
+    request_size: usize,
+    response_size: usize,
+) {
+    tracing::debug!(
+        method = path,
+        status = ?status,
+        duration_ms = duration.as_secs_f64() * 1000.0,
+        time_to_first_response_ms = time_to_first_response.map(|time| time.as_secs_f64() * 1000.0),
+        request_size,
+        response_size,
+        "call completed"
+    );
+}
+
+/// Measures the duration of a call, where the platform has a clock
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    started: Instant,
+}
+
+impl Stopwatch {
+    /// Starts measuring.
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the time since the stopwatch was started, or zero without a clock.
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self.started.elapsed();
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        return Duration::ZERO;
+    }
+}
+
+/// Returns the entries of `metadata` as printable pairs of keys and values, in the order
+/// they were sent.
+///
+/// The values of binary entries, whose keys end in `-bin`, are base64 encoded, and ASCII
+/// values that are not visible text are converted lossily.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::invoke::printable_metadata;
+/// use tonic::metadata::{MetadataMap, MetadataValue};
+///
+/// let mut metadata = MetadataMap::new();
+/// metadata.insert("x-ratelimit-remaining", MetadataValue::from_static("41"));
+/// metadata.insert_bin("trace-bin", MetadataValue::from_bytes(&[0xde, 0xad, 0xbe, 0xef]));
+/// assert_eq!(
+///     printable_metadata(&metadata),
+///     vec![
+///         ("x-ratelimit-remaining".to_string(), "41".to_string()),
+///         ("trace-bin".to_string(), "3q2+7w==".to_string()),
+///     ]
+/// );
+/// ```
+pub fn printable_metadata(metadata: &MetadataMap) -> Vec<(String, String)> {
+    let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    metadata
+        .iter()
+        .map(|entry| match entry {
+            KeyAndValueRef::Ascii(key, value) => {
+                let value = match value.to_str() {
+                    Ok(value) => value.to_string(),
+                    Err(_) => lossy(value.as_encoded_bytes()),
+                };
+                (key.as_str().to_string(), value)
+            }
+            KeyAndValueRef::Binary(key, value) => {
+                let value = match value.to_bytes() {
+                    Ok(bytes) => base64::engine::general_purpose::STANDARD.encode(bytes),
+                    Err(_) => lossy(value.as_encoded_bytes()),
+                };
+                (key.as_str().to_string(), value)
+            }
+        })
+        .collect()
+}
+
+/// The responses of a server streaming call, converted to their canonical protobuf JSON
+/// encodings as the stream is polled, as returned by
+/// [`ReflectionClient::call_server_streaming`](crate::reflection::ReflectionClient::call_server_streaming)
+///
+/// The stream ends after the last response, or with the status of the call if it fails. A
+/// response without a JSON encoding, e.g. because it holds an `Any` of an unknown type, is an
+/// `Internal` status, after which further responses follow.
+///
+/// Dropping the stream before its end cancels the call, resetting its HTTP/2 stream so the
+/// server stops sending responses. The call can also be cancelled through a [`CallHandle`],
+/// e.g. while another task reads the responses.
+#[cfg(feature = "prost-reflect")]
+pub struct JsonStream {
+    call: Arc<Mutex<StreamCall>>,
+    headers: MetadataMap,
+    request_size: usize,
+    progress: Arc<Mutex<StreamProgress>>,
+}
+
+#[cfg(feature = "prost-reflect")]
+type JsonResponses = Pin<Box<dyn Stream<Item = Result<Value, Status>> + Send>>;
+
+/// The responses of a [`JsonStream`] until they end or the call is cancelled, shared with its
+/// [`CallHandle`]s
+#[cfg(feature = "prost-reflect")]
+#[derive(Default)]
+struct StreamCall {
+    responses: Option<JsonResponses>,
+    cancelled: bool,
+    /// Whether the stream yielded the `CANCELLED` status after the call was cancelled
+    reported: bool,
+    /// The waker of the task that polled the stream last
+    waker: Option<std::task::Waker>,
+}
+
+/// What a [`JsonStream`] received so far, shared with its inner stream
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Default)]
+struct StreamProgress {
+    trailers: Option<MetadataMap>,
+    time_to_first_response: Option<Duration>,
+    response_size: usize,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl JsonStream {
+    /// Wraps the `response` of a call of the method `path`, converting its messages to JSON
+    /// in the `mapping` with their `bytes` in the encoding `bytes`, and ending it with a
+    /// `DEADLINE_EXCEEDED` status once the `deadline` passes.
+    ///
+    /// The `stopwatch` started when the request of `request_size` bytes was sent, and the
+    /// [`trace_call`] event is emitted once the stream ends.
+    pub(crate) fn new(
+        response: Response<Streaming<DynamicMessage>>,
+        deadline: Option<Deadline>,
+        mapping: JsonMapping,
+        bytes: BytesEncoding,
+        path: String,
+        stopwatch: Stopwatch,
+        request_size: usize,
+    ) -> Self {
+        let (headers, responses, _) = response.into_parts();
+        let progress = Arc::new(Mutex::new(StreamProgress::default()));
+        let shared = Arc::clone(&progress);
+        let inner = futures_util::stream::unfold(Some(responses), move |responses| {
+            let shared = Arc::clone(&shared);
+            let path = path.clone();
+            async move {
+                let mut responses = responses?;
+                let lock = || shared.lock().unwrap_or_else(|error| error.into_inner());
+                let trace = |status: Code, progress: &StreamProgress| {
+                    trace_call(
+                        &path,
+                        status,
+                        stopwatch.elapsed(),
+                        progress.time_to_first_response,
+                        request_size,
+                        progress.response_size,
+                    );
+                };
+                match until_deadline(deadline, responses.message()).await {
+                    Ok(Some(response)) => {
+                        let mut progress = lock();
+                        progress.response_size += response.encoded_len();
+                        progress
+                            .time_to_first_response
+                            .get_or_insert_with(|| stopwatch.elapsed());
+                        drop(progress);
+
+                        let response = mapping
+                            .to_json_with_bytes(&response, bytes)
+                            .map_err(|error| Status::internal(error.to_string()));
+                        Some((response, Some(responses)))
+                    }
+                    Ok(None) => {
+                        let trailers = until_deadline(deadline, responses.trailers()).await;
+                        let mut progress = lock();
+                        match trailers {
+                            Ok(trailers) => {
+                                progress.trailers = trailers;
+                                trace(Code::Ok, &progress);
+                            }
+                            Err(status) => trace(status.code(), &progress),
+                        }
+                        None
+                    }
+                    // the status ends the stream
+                    Err(status) => {
+                        trace(status.code(), &lock());
+                        Some((Err(status), None))
+                    }
+                }
+            }
+        });
+
+        let call = StreamCall {
+            responses: Some(Box::pin(inner)),
+            ..Default::default()
+        };
+        Self {
+            call: Arc::new(Mutex::new(call)),
+            headers,
+            request_size,
+            progress,
+        }
+    }
+
+    /// Returns a handle cancelling the call, which may be kept after the stream is moved,
+    /// e.g. to another task.
+    pub fn handle(&self) -> CallHandle {
+        CallHandle {
+            call: Arc::clone(&self.call),
+        }
+    }
+
+    /// Returns the headers the server sent before the first response.
+    pub fn headers(&self) -> &MetadataMap {
+        &self.headers
+    }
+
+    /// Returns the trailers the server sent after the last response, once the stream ended
+    /// without an error.
+    pub fn trailers(&self) -> Option<MetadataMap> {
+        self.progress().trailers.clone()
+    }
+
+    /// Returns how long it took from sending the request until the first response arrived,
+    /// or `None` if none did so far.
+    pub fn time_to_first_response(&self) -> Option<Duration> {
+        self.progress().time_to_first_response
+    }
+
+    /// Returns the size of the encoded request message in bytes.
+    pub fn request_size(&self) -> usize {
+        self.request_size
+    }
+
+    /// Returns the size of the encoded responses received so far in bytes.
+    pub fn response_size(&self) -> usize {
+        self.progress().response_size
+    }
+
+    fn progress(&self) -> std::sync::MutexGuard<'_, StreamProgress> {
+        self.progress
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+impl Stream for JsonStream {
+    type Item = Result<Value, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut call = lock_call(&self.call);
+        if let Some(responses) = call.responses.as_mut() {
+            let polled = responses.as_mut().poll_next(cx);
+            if polled.is_pending()
+                && !call
+                    .waker
+                    .as_ref()
+                    .is_some_and(|waker| waker.will_wake(cx.waker()))
+            {
+                call.waker = Some(cx.waker().clone());
+            }
+            return polled;
+        }
+        if call.cancelled && !call.reported {
+            call.reported = true;
+            return Poll::Ready(Some(Err(Status::cancelled("The call was cancelled"))));
+        }
+        Poll::Ready(None)
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+impl Drop for JsonStream {
+    fn drop(&mut self) {
+        // the handles must not keep the call alive
+        let responses = lock_call(&self.call).responses.take();
+        drop(responses);
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+impl std::fmt::Debug for JsonStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JsonStream")
+            .field("headers", &self.headers)
+            .field("trailers", &self.trailers())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle cancelling a streaming call, as returned by [`JsonStream::handle`]
+///
+/// Cancelling resets the HTTP/2 stream of the call right away, so the server stops sending
+/// responses even if the [`JsonStream`] is not polled, and ends the stream with a
+/// `CANCELLED` status. Handles are cheap to clone, and cancelling a call that ended already
+/// does nothing.
+///
+/// # Example
+///
+/// A server producing messages until the client goes away stops shortly after the call is
+/// cancelled, whether the stream is dropped or the call is cancelled through its handle.
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::invoke::CallOptions;
+/// use grpc_ease::reflection::ReflectionClient;
+/// use serde_json::json;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use tokio::sync::Notify;
+/// use tokio_stream::StreamExt;
+/// # use prost_types::field_descriptor_proto::Type;
+/// # use prost_types::{
+/// #     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+/// #     ServiceDescriptorProto,
+/// # };
+/// # use tokio_stream::wrappers::ReceiverStream;
+/// # use tonic::codegen::{http, BoxFuture, Service};
+/// # use tonic::server::{Grpc, NamedService, ServerStreamingService};
+/// # use tonic::{Request, Response, Status};
+/// #
+/// # #[derive(Clone, PartialEq, prost::Message)]
+/// # struct Tick {
+/// #     #[prost(bytes = "vec", tag = "1")]
+/// #     payload: Vec<u8>,
+/// # }
+/// #
+/// # /// Streams ticks of 16 KiB until the client goes away, counting them.
+/// # #[derive(Clone)]
+/// # struct Ticks {
+/// #     produced: Arc<AtomicUsize>,
+/// #     stopped: Arc<Notify>,
+/// # }
+/// # impl ServerStreamingService<Tick> for Ticks {
+/// #     type Response = Tick;
+/// #     type ResponseStream = ReceiverStream<Result<Tick, Status>>;
+/// #     type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+/// #     fn call(&mut self, _: Request<Tick>) -> Self::Future {
+/// #         let (sender, receiver) = tokio::sync::mpsc::channel(1);
+/// #         let ticks = self.clone();
+/// #         tokio::spawn(async move {
+/// #             let tick = Tick { payload: vec![0; 16 * 1024] };
+/// #             while sender.send(Ok(tick.clone())).await.is_ok() {
+/// #                 ticks.produced.fetch_add(1, Ordering::SeqCst);
+/// #             }
+/// #             ticks.stopped.notify_one();
+/// #         });
+/// #         Box::pin(async move { Ok(Response::new(ReceiverStream::new(receiver))) })
+/// #     }
+/// # }
+/// # impl NamedService for Ticks {
+/// #     const NAME: &'static str = "fixture.Clock";
+/// # }
+/// # impl Service<http::Request<tonic::transport::Body>> for Ticks {
+/// #     type Response = http::Response<tonic::body::BoxBody>;
+/// #     type Error = std::convert::Infallible;
+/// #     type Future = BoxFuture<Self::Response, Self::Error>;
+/// #     fn poll_ready(
+/// #         &mut self,
+/// #         _: &mut std::task::Context<'_>,
+/// #     ) -> std::task::Poll<Result<(), Self::Error>> {
+/// #         std::task::Poll::Ready(Ok(()))
+/// #     }
+/// #     fn call(&mut self, request: http::Request<tonic::transport::Body>) -> Self::Future {
+/// #         let ticks = self.clone();
+/// #         Box::pin(async move {
+/// #             let mut grpc = Grpc::new(tonic::codec::ProstCodec::default());
+/// #             Ok(grpc.server_streaming(ticks, request).await)
+/// #         })
+/// #     }
+/// # }
+/// // service Clock {
+/// //   rpc Tick(Tick) returns (stream Tick);
+/// // }
+/// # let clock = FileDescriptorProto {
+/// #     name: Some("clock.proto".to_string()),
+/// #     package: Some("fixture".to_string()),
+/// #     syntax: Some("proto3".to_string()),
+/// #     message_type: vec![DescriptorProto {
+/// #         name: Some("Tick".to_string()),
+/// #         field: vec![FieldDescriptorProto {
+/// #             name: Some("payload".to_string()),
+/// #             number: Some(1),
+/// #             r#type: Some(Type::Bytes.into()),
+/// #             ..Default::default()
+/// #         }],
+/// #         ..Default::default()
+/// #     }],
+/// #     service: vec![ServiceDescriptorProto {
+/// #         name: Some("Clock".to_string()),
+/// #         method: vec![MethodDescriptorProto {
+/// #             name: Some("Tick".to_string()),
+/// #             input_type: Some(".fixture.Tick".to_string()),
+/// #             output_type: Some(".fixture.Tick".to_string()),
+/// #             server_streaming: Some(true),
+/// #             ..Default::default()
+/// #         }],
+/// #         ..Default::default()
+/// #     }],
+/// #     ..Default::default()
+/// # };
+/// let (produced, stopped) = (Arc::new(AtomicUsize::new(0)), Arc::new(Notify::new()));
+/// let ticks = Ticks { produced: Arc::clone(&produced), stopped: Arc::clone(&stopped) };
+/// # let reflection = support::reflection_for(vec![clock]);
+/// # let endpoint = support::serve(
+/// #     tonic::transport::Server::builder()
+/// #         .add_service(ticks)
+/// #         .add_service(reflection),
+/// # )
+/// # .await?;
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// // HTTP/2 flow control lets the server run ahead of the client by a few megabytes
+/// let bound = 4 * 1024 / 16 + 16;
+///
+/// // dropping the stream
+/// let mut ticks = client
+///     .call_server_streaming("/fixture.Clock/Tick", json!({}), CallOptions::default())
+///     .await?;
+/// for _ in 0..3 {
+///     ticks.next().await.unwrap()?;
+/// }
+/// drop(ticks);
+/// tokio::time::timeout(Duration::from_secs(5), stopped.notified()).await?;
+/// assert!(produced.swap(0, Ordering::SeqCst) < 3 + bound);
+///
+/// // cancelling the call through its handle while another task reads the stream
+/// let mut ticks = client
+///     .call_server_streaming("/fixture.Clock/Tick", json!({}), CallOptions::default())
+///     .await?;
+/// let handle = ticks.handle();
+/// let reader = tokio::spawn(async move {
+///     let mut received = 0;
+///     loop {
+///         match ticks.next().await {
+///             Some(Ok(_)) => received += 1,
+///             Some(Err(status)) => break (received, status.code()),
+///             None => break (received, tonic::Code::Ok),
+///         }
+///     }
+/// });
+/// tokio::time::sleep(Duration::from_millis(50)).await;
+/// handle.cancel();
+/// let (received, code) = reader.await?;
+/// assert_eq!(code, tonic::Code::Cancelled);
+/// tokio::time::timeout(Duration::from_secs(5), stopped.notified()).await?;
+/// assert!(produced.load(Ordering::SeqCst) < received + bound);
+/// assert!(handle.is_cancelled());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[cfg(feature = "prost-reflect")]
+#[derive(Clone)]
+pub struct CallHandle {
+    call: Arc<Mutex<StreamCall>>,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl CallHandle {
+    /// Cancels the call, unless it ended already.
+    pub fn cancel(&self) {
+        let mut call = lock_call(&self.call);
+        let Some(responses) = call.responses.take() else {
+            return;
+        };
+        call.cancelled = true;
+        let waker = call.waker.take();
+        drop(call);
+
+        // dropping the responses resets the stream
+        drop(responses);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Returns whether the call was cancelled through a handle.
+    pub fn is_cancelled(&self) -> bool {
+        lock_call(&self.call).cancelled
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+impl std::fmt::Debug for CallHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallHandle")
+            .field("cancelled", &self.is_cancelled())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+fn lock_call(call: &Mutex<StreamCall>) -> std::sync::MutexGuard<'_, StreamCall> {
+    call.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// The encoding of the messages passed to and returned by calls like
+/// [`ReflectionClient::call_unary_formatted`](crate::reflection::ReflectionClient::call_unary_formatted)
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::invoke::MessageFormat;
+/// use prost_reflect::DescriptorPool;
+///
+/// let descriptor = DescriptorPool::global()
+///     .get_message_by_name("google.protobuf.FileDescriptorProto")
+///     .unwrap();
+/// // nested and repeated messages, enums by name and escaped bytes
+/// let fixture = r#"name: "shop.proto"
+/// message_type {
+///   name: "Order"
+///   field {
+///     name: "items"
+///     number: 1
+///     label: LABEL_REPEATED
+///     type: TYPE_STRING
+///   }
+///   options {
+///     uninterpreted_option {
+///       string_value: "\000\377rush"
+///     }
+///   }
+/// }
+/// "#;
+///
+/// let message = MessageFormat::Text.parse(descriptor.clone(), fixture)?;
+/// let text = MessageFormat::Text.render(&message)?;
+/// // repeated fields are rendered as lists
+/// assert!(text.contains("field: [{"));
+/// assert!(text.contains(r#"string_value: "\000\377rush""#));
+/// assert_eq!(MessageFormat::Text.parse(descriptor.clone(), &text)?, message);
+///
+/// let json = MessageFormat::Json.render(&message)?;
+/// assert!(json.contains(r#""label": "LABEL_REPEATED""#));
+/// assert!(json.contains(r#""stringValue": "AP9ydXNo""#));
+/// assert_eq!(MessageFormat::Json.parse(descriptor, &json)?, message);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MessageFormat {
+    /// The canonical protobuf JSON encoding, pretty-printed when rendered
+    #[default]
+    Json,
+    /// The protobuf text format, as used by textproto files and `protoc --encode`, with one
+    /// field per line when rendered
+    Text,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl MessageFormat {
+    /// Parses `input` in this format as a message of the type `descriptor`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`ReflectionError::InvalidJson`] or a
+    /// [`ReflectionError::InvalidTextFormat`] if `input` is not a message of the type in this
+    /// format.
+    pub fn parse(
+        self,
+        descriptor: MessageDescriptor,
+        input: &str,
+    ) -> Result<DynamicMessage, ReflectionError> {
+        let message_type = descriptor.full_name().to_string();
+        match self {
+            Self::Json => {
+                let value =
+                    serde_json::from_str(input).map_err(|source| ReflectionError::InvalidJson {
+                        message_type,
+                        source,
+                    })?;
+                JsonMapping::Canonical.from_json(descriptor, value)
+            }
+            Self::Text => DynamicMessage::parse_text_format(descriptor, input).map_err(|source| {
+                ReflectionError::InvalidTextFormat {
+                    message_type,
+                    source,
+                }
+            }),
+        }
+    }
+
+    /// Renders `message` in this format.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`ReflectionError::InvalidJson`] if the message has no
+    /// JSON encoding, e.g. because it holds an `Any` of an unknown type.
+    pub fn render(self, message: &DynamicMessage) -> Result<String, ReflectionError> {
+        match self {
+            Self::Json => serde_json::to_string_pretty(message).map_err(|source| {
+                ReflectionError::InvalidJson {
+                    message_type: message.descriptor().full_name().to_string(),
+                    source,
+                }
+            }),
+            Self::Text => {
+                let options = prost_reflect::text_format::FormatOptions::new().pretty(true);
+                Ok(message.to_text_format_with_options(&options))
+            }
+        }
+    }
+}
+
+/// How messages are mapped to and from JSON by calls like
+/// [`ReflectionClient::call_unary`](crate::reflection::ReflectionClient::call_unary), set by
+/// [`CallOptions::json_mapping`]
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::invoke::JsonMapping;
+/// use prost_reflect::DescriptorPool;
+/// use serde_json::json;
+///
+/// let pool = DescriptorPool::global();
+/// let canonical = |name: &str, json| -> Result<_, grpc_ease::error::ReflectionError> {
+///     let descriptor = pool.get_message_by_name(name).unwrap();
+///     let message = JsonMapping::Canonical.from_json(descriptor, json)?;
+///     Ok((
+///         JsonMapping::Canonical.to_json(&message)?,
+///         JsonMapping::Raw.to_json(&message)?,
+///     ))
+/// };
+///
+/// let (timestamp, raw) = canonical("google.protobuf.Timestamp", json!("2024-05-01T10:00:00Z"))?;
+/// assert_eq!(timestamp, json!("2024-05-01T10:00:00Z"));
+/// assert_eq!(raw, json!({ "seconds": "1714557600" }));
+///
+/// let (duration, raw) = canonical("google.protobuf.Duration", json!("3.5s"))?;
+/// assert_eq!(duration, json!("3.500s"));
+/// assert_eq!(raw, json!({ "seconds": "3", "nanos": 500000000 }));
+///
+/// let (count, raw) = canonical("google.protobuf.Int64Value", json!("5"))?;
+/// assert_eq!(count, json!("5"));
+/// assert_eq!(raw, json!({ "value": "5" }));
+///
+/// let (mask, raw) = canonical("google.protobuf.FieldMask", json!("user.displayName,photo"))?;
+/// assert_eq!(mask, json!("user.displayName,photo"));
+/// assert_eq!(raw, json!({ "paths": ["user.display_name", "photo"] }));
+///
+/// let (payload, raw) = canonical("google.protobuf.BytesValue", json!("AP8="))?;
+/// assert_eq!(payload, json!("AP8="));
+/// assert_eq!(raw, json!({ "value": "AP8=" }));
+///
+/// let (object, raw) = canonical("google.protobuf.Struct", json!({ "rush": true }))?;
+/// assert_eq!(object, json!({ "rush": true }));
+/// assert_eq!(raw, json!({ "fields": { "rush": { "boolValue": true } } }));
+///
+/// // raw JSON converts back to the same message
+/// let descriptor = pool.get_message_by_name("google.protobuf.Struct").unwrap();
+/// let message = JsonMapping::Raw.from_json(descriptor, raw)?;
+/// assert_eq!(JsonMapping::Canonical.to_json(&message)?, object);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum JsonMapping {
+    /// The canonical proto3 JSON mapping, as used by other gRPC tooling
+    ///
+    /// Well-known types have JSON encodings of their own: a `Timestamp` is an RFC 3339 string
+    /// like `"2024-05-01T10:00:00Z"`, a `Duration` a string of seconds like `"3.5s"`, wrappers
+    /// are their value or `null`, `Struct`, `Value` and `ListValue` are plain JSON, a
+    /// `FieldMask` is a string of comma-separated camelCase paths, and an `Any` is the
+    /// message with an `@type` field, or its `@type` and base64 encoded `value` if the type
+    /// is unknown. `bytes` are base64 and 64-bit integers strings.
+    #[default]
+    Canonical,
+    /// The canonical mapping without the special cases of the well-known types, for
+    /// debugging
+    ///
+    /// Every message is an object of its fields, so a `Timestamp` is
+    /// `{"seconds": "1714557600", "nanos": 5}` and an `Any` is its type URL and base64
+    /// encoded value, even if its type is not known.
+    Raw,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl JsonMapping {
+    /// Converts the JSON `value` in this mapping to a message of the type `descriptor`.
+    ///
+    /// `bytes` fields may be encoded in any of the [`BytesEncoding`]s, with or without
+    /// padding.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`ReflectionError::InvalidBytes`] if a `bytes` field is not
+    /// encoded in any of them, or a [`ReflectionError::InvalidJson`] if `value` is not a
+    /// message of the type in this mapping.
+    pub fn from_json(
+        self,
+        descriptor: MessageDescriptor,
+        mut value: Value,
+    ) -> Result<DynamicMessage, ReflectionError> {
+        json_bytes::normalize(&descriptor, &mut value, self)?;
+        let message_type = descriptor.full_name().to_string();
+        let message = match self {
+            Self::Canonical => DynamicMessage::deserialize(descriptor, value),
+            Self::Raw => raw_json::from_value(descriptor, &value),
+        };
+        message.map_err(|source| ReflectionError::InvalidJson {
+            message_type,
+            source,
+        })
+    }
+
+    /// Converts `message` to JSON in this mapping, with its `bytes` fields in standard base64.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`ReflectionError::InvalidJson`] if the message has no
+    /// canonical JSON encoding, e.g. because it holds a `Timestamp` out of range.
+    pub fn to_json(self, message: &DynamicMessage) -> Result<Value, ReflectionError> {
+        self.to_json_with_types(message, BytesEncoding::Base64, &AnyTypes::new())
+    }
+
+    /// Converts `message` to JSON in this mapping like [`to_json`](Self::to_json), with its
+    /// `bytes` fields in the `encoding`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`to_json`](Self::to_json).
+    pub fn to_json_with_bytes(
+        self,
+        message: &DynamicMessage,
+        encoding: BytesEncoding,
+    ) -> Result<Value, ReflectionError> {
+        self.to_json_with_types(message, encoding, &AnyTypes::new())
+    }
+
+    /// Converts `message` to JSON in this mapping like
+    /// [`to_json_with_bytes`](Self::to_json_with_bytes), looking up the types of `Any`s
+    /// missing from the descriptor pool of the message in `types`.
+    pub(crate) fn to_json_with_types(
+        self,
+        message: &DynamicMessage,
+        encoding: BytesEncoding,
+        types: &AnyTypes,
+    ) -> Result<Value, ReflectionError> {
+        let mut value = match self {
+            Self::Canonical => any_json::to_value(message, types).map_err(|source| {
+                ReflectionError::InvalidJson {
+                    message_type: message.descriptor().full_name().to_string(),
+                    source,
+                }
+            })?,
+            Self::Raw => raw_json::to_value(message),
+        };
+        json_bytes::encode(&message.descriptor(), &mut value, self, encoding, types);
+        Ok(value)
+    }
+}
+
+/// The encoding of the `bytes` fields of JSON messages
+///
+/// JSON requests may use any of them, with or without padding, while responses use the
+/// [`CallOptions::bytes_encoding`]. A value prefixed by `0x` is always hex.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::error::ReflectionError;
+/// use grpc_ease::invoke::{BytesEncoding, JsonMapping};
+/// use prost_reflect::DescriptorPool;
+/// use serde_json::json;
+///
+/// let descriptor = DescriptorPool::global()
+///     .get_message_by_name("google.protobuf.FileDescriptorProto")
+///     .unwrap();
+/// let options = |value: &str| {
+///     json!({ "messageType": [{ "options": { "uninterpretedOption": [{
+///         "stringValue": value,
+///     }] } }] })
+/// };
+///
+/// // the same bytes in standard and URL-safe base64, without padding, and in hex
+/// let message = JsonMapping::Canonical.from_json(descriptor.clone(), options("+/8="))?;
+/// for encoded in ["-_8", "+/8", "0xFBFF"] {
+///     let other = JsonMapping::Canonical.from_json(descriptor.clone(), options(encoded))?;
+///     assert_eq!(other, message);
+/// }
+///
+/// let json = JsonMapping::Canonical.to_json_with_bytes(&message, BytesEncoding::Hex)?;
+/// assert_eq!(json, options("0xfbff"));
+/// let json = JsonMapping::Canonical.to_json_with_bytes(&message, BytesEncoding::Base64Url)?;
+/// assert_eq!(json, options("-_8="));
+///
+/// let error = JsonMapping::Canonical
+///     .from_json(descriptor, options("+/8*"))
+///     .unwrap_err();
+/// assert!(matches!(
+///     &error,
+///     ReflectionError::InvalidBytes { field, position: 3, character: Some('*'), .. }
+///         if field == "messageType[0].options.uninterpretedOption[0].stringValue"
+/// ));
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BytesEncoding {
+    /// Standard base64 with padding, as the canonical proto3 JSON mapping encodes `bytes`
+    #[default]
+    Base64,
+    /// URL-safe base64 with padding, with `-` and `_` in place of `+` and `/`
+    Base64Url,
+    /// Lowercase hex prefixed by `0x`, e.g. `0x00ff`
+    Hex,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl BytesEncoding {
+    /// Encodes `bytes` in this encoding.
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+            Self::Base64Url => base64::engine::general_purpose::URL_SAFE.encode(bytes),
+            Self::Hex => {
+                let digits: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("0x{}", digits)
+            }
+        }
+    }
+}
+
+/// Requests of a client streaming call read from JSON Lines, one JSON request per line
+///
+/// The lines are read as the call sends the requests, so the input is never held in memory
+/// as a whole, and blank lines are skipped. Each request is converted to its message like
+/// the requests of `ReflectionClient::call_client_streaming`, and the first line that is not
+/// JSON or not a JSON encoding of the request message cancels the call.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::invoke::JsonLines;
+///
+/// let requests = "{\"data\": \"aGVsbG8=\"}\n\n{\"data\": \"d29ybGQ=\"}\n";
+/// // at most 10 requests a second
+/// let lines = JsonLines::new(requests.as_bytes()).throttle(10);
+/// # let _ = lines;
+/// # }
+/// ```
+///
+/// Reading the requests from stdin instead:
+///
+/// ```no_run
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::invoke::JsonLines;
+///
+/// let lines = JsonLines::new(tokio::io::stdin());
+/// # let _ = lines;
+/// # }
+/// ```
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug)]
+pub struct JsonLines<R> {
+    reader: R,
+    per_second: Option<u32>,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl<R> JsonLines<R>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    /// Creates requests read from the JSON Lines of `reader`, e.g. a `tokio::fs::File` or
+    /// `tokio::io::stdin()`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            per_second: None,
+        }
+    }
+
+    /// Sends at most `per_second` requests a second, evenly spaced, e.g. to test endpoints
+    /// that are rate limited.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `per_second` is 0.
+    pub fn throttle(mut self, per_second: u32) -> Self {
+        assert!(per_second > 0, "cannot throttle to 0 requests a second");
+        self.per_second = Some(per_second);
+        self
+    }
+
+    /// Converts the lines to messages of the type `descriptor` as they are sent, like
+    /// [`from_json_stream`].
+    ///
+    /// The receiver gets a [`ReflectionError::InvalidJsonLine`] for the first line that
+    /// cannot be converted, or a [`ReflectionError::ReadJsonLines`] if reading fails.
+    pub(crate) fn into_requests(
+        self,
+        descriptor: MessageDescriptor,
+        options: &CallOptions,
+    ) -> (
+        impl Stream<Item = DynamicMessage> + Send + 'static,
+        oneshot::Receiver<ReflectionError>,
+    ) {
+        use tokio::io::AsyncBufReadExt;
+
+        let message_type = descriptor.full_name().to_string();
+        let lines = tokio::io::BufReader::new(self.reader).lines();
+        let throttle = self.per_second.map(|per_second| {
+            let mut interval = tokio::time::interval(Duration::from_secs(1) / per_second);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval
+        });
+        let requests = futures_util::stream::unfold(Some((lines, 0, throttle)), move |state| {
+            let message_type = message_type.clone();
+            async move {
+                let (mut lines, mut line, mut throttle) = state?;
+                let text = loop {
+                    line += 1;
+                    match lines.next_line().await {
+                        Ok(Some(text)) if text.trim().is_empty() => continue,
+                        Ok(Some(text)) => break text,
+                        Ok(None) => return None,
+                        Err(source) => {
+                            let error = ReflectionError::ReadJsonLines { line, source };
+                            return Some((Err(error), None));
+                        }
+                    }
+                };
+                if let Some(throttle) = &mut throttle {
+                    throttle.tick().await;
+                }
+                let request = serde_json::from_str(&text)
+                    .map(|request| (line, request))
+                    .map_err(|source| ReflectionError::InvalidJsonLine {
+                        line,
+                        source: Box::new(ReflectionError::InvalidJson {
+                            message_type,
+                            source,
+                        }),
+                    });
+                Some((request, Some((lines, line, throttle))))
+            }
+        });
+        convert_stream(descriptor, requests, options, |line, source| {
+            ReflectionError::InvalidJsonLine { line, source }
+        })
+    }
+}
+
+/// Converts the JSON `request` to a message of the type `descriptor` in the `mapping`,
+/// substituting the `variables` first if there are any.
+#[cfg(feature = "prost-reflect")]
+fn request_from_json(
+    descriptor: MessageDescriptor,
+    request: Value,
+    mapping: JsonMapping,
+    variables: Option<&HashMap<String, Value>>,
+) -> Result<DynamicMessage, ReflectionError> {
+    let request = match variables {
+        Some(variables) => template::substitute(request, variables)?,
+        None => request,
+    };
+    mapping.from_json(descriptor, request)
+}
+
+/// Converts the JSON `requests` to messages of the type `descriptor` as they are sent.
+///
+/// If a request cannot be converted, the receiver gets a
+/// [`ReflectionError::InvalidStreamRequest`] and the messages never end, so the call does not
+/// complete with the requests converted so far and can be cancelled instead.
+#[cfg(feature = "prost-reflect")]
+pub(crate) fn from_json_stream(
+    descriptor: MessageDescriptor,
+    requests: impl Stream<Item = Value> + Send + 'static,
+    options: &CallOptions,
+) -> (
+    impl Stream<Item = DynamicMessage> + Send + 'static,
+    oneshot::Receiver<ReflectionError>,
+) {
+    use futures_util::StreamExt;
+
+    let requests = requests.enumerate().map(Ok);
+    convert_stream(descriptor, requests, options, |index, source| {
+        ReflectionError::InvalidStreamRequest { index, source }
+    })
+}
+
+/// Converts the JSON `requests`, each along with its position, to messages of the type
+/// `descriptor` as they are sent, like [`from_json_stream`].
+///
+/// The receiver gets the errors of the stream as they are, and the errors converting a
+/// request as located by `locate`.
+#[cfg(feature = "prost-reflect")]
+fn convert_stream(
+    descriptor: MessageDescriptor,
+    requests: impl Stream<Item = Result<(usize, Value), ReflectionError>> + Send + 'static,
+    options: &CallOptions,
+    locate: fn(usize, Box<ReflectionError>) -> ReflectionError,
+) -> (
+    impl Stream<Item = DynamicMessage> + Send + 'static,
+    oneshot::Receiver<ReflectionError>,
+) {
+    use futures_util::StreamExt;
+
+    let (abort, aborted) = oneshot::channel();
+    let requests = Box::pin(requests);
+    let mapping = options.json_mapping;
+    let variables = Arc::new(options.variables.clone());
+    let messages = futures_util::stream::unfold((requests, Some(abort)), move |state| {
+        let descriptor = descriptor.clone();
+        let variables = Arc::clone(&variables);
+        async move {
+            let (mut requests, abort) = state;
+            let converted = requests.next().await?.and_then(|(position, request)| {
+                request_from_json(descriptor, request, mapping, variables.as_ref().as_ref())
+                    .map_err(|error| locate(position, Box::new(error)))
+            });
+            match converted {
+                Ok(message) => Some((message, (requests, abort))),
+                Err(error) => {
+                    if let Some(abort) = abort {
+                        let _ = abort.send(error);
+                    }
+                    std::future::pending().await
+                }
+            }
+        }
+    });
+    (messages, aborted)
+}
+
+/// Counts the encoded sizes of the `requests` as they are sent, adding them up in the
+/// returned counter.
+#[cfg(feature = "prost-reflect")]
+pub(crate) fn counting_sizes(
+    requests: impl Stream<Item = DynamicMessage> + Send + 'static,
+) -> (
+    impl Stream<Item = DynamicMessage> + Send + 'static,
+    Arc<AtomicUsize>,
+) {
+    use futures_util::StreamExt;
+
+    let size = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&size);
+    let requests = requests.map(move |request| {
+        counter.fetch_add(request.encoded_len(), Ordering::Relaxed);
+        request
+    });
+    (requests, size)
+}
+
+/// Runs `call` until it completes or a request of its stream cannot be converted, as
+/// reported by the receiver of [`from_json_stream`], cancelling the call.
+#[cfg(feature = "prost-reflect")]
+pub(crate) async fn until_aborted<R>(
+    call: impl Future<Output = Result<R, ReflectionError>>,
+    aborted: oneshot::Receiver<ReflectionError>,
+) -> Result<R, ReflectionError> {
+    use futures_util::future::{select, Either};
+
+    // dropping the call cancels it
+    match select(std::pin::pin!(call), aborted).await {
+        Either::Left((response, _)) => response,
+        Either::Right((Ok(error), _)) => Err(error),
+        // all requests were converted
+        Either::Right((Err(_), call)) => call.await,
+    }
+}
+
+/// A codec encoding `DynamicMessage`s and decoding them as messages of the type `response`
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone)]
+pub(crate) struct DynamicCodec {
+    response: MessageDescriptor,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl DynamicCodec {
+    /// Creates a codec decoding messages of the type `response`.
+    pub(crate) fn new(response: MessageDescriptor) -> Self {
+        Self { response }
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = DynamicEncoder;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        DynamicEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder {
+            response: self.response.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug)]
+pub(crate) struct DynamicEncoder;
+
+#[cfg(feature = "prost-reflect")]
+impl Encoder for DynamicEncoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|error| Status::internal(format!("Failed to encode request: {}", error)))
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug)]
+pub(crate) struct DynamicDecoder {
+    response: MessageDescriptor,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl Decoder for DynamicDecoder {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.response.clone(), src)
+            .map(Some)
+            .map_err(|error| Status::internal(format!("Failed to decode response: {}", error)))
+    }
+}
+
+/// A codec sending requests that are encoded already, e.g. to send them again on retries, and
+/// decoding responses as messages of the type `response`
+#[cfg(feature = "prost-reflect")]
+#[derive(Debug, Clone)]
+pub(crate) struct EncodedCodec {
+    response: MessageDescriptor,
+}
+
+#[cfg(feature = "prost-reflect")]
+impl EncodedCodec {
+    /// Creates a codec decoding messages of the type `response`.
+    pub(crate) fn new(response: MessageDescriptor) -> Self {
+        Self { response }
+    }
+}
+
+#[cfg(feature = "prost-reflect")]
+impl Codec for EncodedCodec {
+    type Encode = Bytes;
+    type Decode = DynamicMessage;
+    type Encoder = BytesCodec;
+    type Decoder = DynamicDecoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        DynamicDecoder {
+            response: self.response.clone(),
+        }
+    }
+}
+
+/// A codec passing encoded messages through as they are
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BytesCodec;
+
+impl Codec for BytesCodec {
+    type Encode = Bytes;
+    type Decode = Bytes;
+    type Encoder = BytesCodec;
+    type Decoder = BytesCodec;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        BytesCodec
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        BytesCodec
+    }
+}
+
+impl Encoder for BytesCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        dst.put(item);
+        Ok(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Bytes;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(src.copy_to_bytes(src.remaining())))
+    }
+}
@@ -0,0 +1,288 @@
+//! The encodings of `bytes` fields in JSON messages.
+//!
+//! Requests may encode `bytes` in standard or URL-safe base64, with or without padding, or in
+//! hex prefixed by `0x`. They are normalized to standard base64 before the requests are
+//! converted to messages, and responses are encoded in the [`BytesEncoding`] of the call.
+
+use crate::any_json::AnyTypes;
+use crate::error::ReflectionError;
+use crate::invoke::{BytesEncoding, JsonMapping};
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::{DecodeError, Engine};
+use prost_reflect::{Kind, MessageDescriptor};
+use serde_json::Value;
+
+/// Rewrites the `bytes` fields of the JSON `value` of a message of the type `descriptor` in
+/// standard base64.
+///
+/// # Errors
+///
+/// This function will return a [`ReflectionError::InvalidBytes`] for the first `bytes` field
+/// that is neither base64 nor hex.
+pub(crate) fn normalize(
+    descriptor: &MessageDescriptor,
+    value: &mut Value,
+    mapping: JsonMapping,
+) -> Result<(), ReflectionError> {
+    visit(
+        descriptor,
+        value,
+        mapping,
+        &AnyTypes::new(),
+        &mut String::new(),
+        &mut |field, encoded| {
+            let bytes =
+                decode(encoded).map_err(|(position, character)| ReflectionError::InvalidBytes {
+                    message_type: descriptor.full_name().to_string(),
+                    field: field.to_string(),
+                    position,
+                    character,
+                })?;
+            *encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+            Ok(())
+        },
+    )
+}
+
+/// Rewrites the `bytes` fields of the JSON `value` of a message of the type `descriptor`,
+/// encoded in standard base64, in the `encoding`, looking up the types of `Any`s missing
+/// from the pool of the descriptor in `types`.
+pub(crate) fn encode(
+    descriptor: &MessageDescriptor,
+    value: &mut Value,
+    mapping: JsonMapping,
+    encoding: BytesEncoding,
+    types: &AnyTypes,
+) {
+    if encoding == BytesEncoding::Base64 {
+        return;
+    }
+    let _ = visit(
+        descriptor,
+        value,
+        mapping,
+        types,
+        &mut String::new(),
+        &mut |_, encoded| {
+            if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(&*encoded) {
+                *encoded = encoding.encode(&bytes);
+            }
+            Ok(())
+        },
+    );
+}
+
+/// Calls `bytes` with the path and value of each `bytes` field of the JSON `value` of a
+/// message of the type `descriptor`, skipping values that are not of the expected JSON types.
+fn visit(
+    descriptor: &MessageDescriptor,
+    value: &mut Value,
+    mapping: JsonMapping,
+    types: &AnyTypes,
+    path: &mut String,
+    bytes: &mut impl FnMut(&str, &mut String) -> Result<(), ReflectionError>,
+) -> Result<(), ReflectionError> {
+    if mapping == JsonMapping::Canonical {
+        match descriptor.full_name() {
+            "google.protobuf.BytesValue" => {
+                return match value {
+                    Value::String(encoded) => bytes(path, encoded),
+                    _ => Ok(()),
+                };
+            }
+            "google.protobuf.Any" => return visit_any(descriptor, value, types, path, bytes),
+            name if has_special_json(name) => return Ok(()),
+            _ => {}
+        }
+    }
+
+    let Value::Object(fields) = value else {
+        return Ok(());
+    };
+    for (name, value) in fields.iter_mut() {
+        let Some(field) = descriptor
+            .get_field_by_json_name(name)
+            .or_else(|| descriptor.get_field_by_name(name))
+        else {
+            continue;
+        };
+        let len = path.len();
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(name);
+
+        match (field.kind(), value) {
+            (Kind::Message(entry), Value::Object(entries)) if field.is_map() => {
+                let value_kind = entry.map_entry_value_field().kind();
+                for (key, value) in entries.iter_mut() {
+                    let len = path.len();
+                    path.push_str(&format!("[{:?}]", key));
+                    visit_kind(&value_kind, value, mapping, types, path, bytes)?;
+                    path.truncate(len);
+                }
+            }
+            (kind, Value::Array(values)) if field.is_list() => {
+                for (index, value) in values.iter_mut().enumerate() {
+                    let len = path.len();
+                    path.push_str(&format!("[{}]", index));
+                    visit_kind(&kind, value, mapping, types, path, bytes)?;
+                    path.truncate(len);
+                }
+            }
+            (kind, value) if !field.is_map() && !field.is_list() => {
+                visit_kind(&kind, value, mapping, types, path, bytes)?;
+            }
+            _ => {}
+        }
+        path.truncate(len);
+    }
+    Ok(())
+}
+
+fn visit_kind(
+    kind: &Kind,
+    value: &mut Value,
+    mapping: JsonMapping,
+    types: &AnyTypes,
+    path: &mut String,
+    bytes: &mut impl FnMut(&str, &mut String) -> Result<(), ReflectionError>,
+) -> Result<(), ReflectionError> {
+    match (kind, value) {
+        (Kind::Bytes, Value::String(encoded)) => bytes(path, encoded),
+        (Kind::Message(descriptor), value) => visit(descriptor, value, mapping, types, path, bytes),
+        _ => Ok(()),
+    }
+}
+
+/// Visits the message of the canonical JSON encoding of an `Any`, whose `@type` names its
+/// type, and which is the `value` field for types with JSON encodings of their own.
+fn visit_any(
+    descriptor: &MessageDescriptor,
+    value: &mut Value,
+    types: &AnyTypes,
+    path: &mut String,
+    bytes: &mut impl FnMut(&str, &mut String) -> Result<(), ReflectionError>,
+) -> Result<(), ReflectionError> {
+    let Value::Object(fields) = value else {
+        return Ok(());
+    };
+    let Some(message_type) = fields.get("@type").and_then(Value::as_str).and_then(|url| {
+        let name = url.rsplit_once('/').map_or(url, |(_, name)| name);
+        descriptor
+            .parent_pool()
+            .get_message_by_name(name)
+            .or_else(|| types.get(url).cloned())
+    }) else {
+        return Ok(());
+    };
+
+    if has_special_json(message_type.full_name())
+        || message_type.full_name() == "google.protobuf.BytesValue"
+    {
+        match fields.get_mut("value") {
+            Some(value) => {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str("value");
+                let visited = visit(
+                    &message_type,
+                    value,
+                    JsonMapping::Canonical,
+                    types,
+                    path,
+                    bytes,
+                );
+                path.truncate(len);
+                visited
+            }
+            None => Ok(()),
+        }
+    } else {
+        visit(
+            &message_type,
+            value,
+            JsonMapping::Canonical,
+            types,
+            path,
+            bytes,
+        )
+    }
+}
+
+/// Returns whether the well-known type `name` has a canonical JSON encoding other than an
+/// object of its fields, without any `bytes`.
+fn has_special_json(name: &str) -> bool {
+    matches!(
+        name,
+        "google.protobuf.Timestamp"
+            | "google.protobuf.Duration"
+            | "google.protobuf.FieldMask"
+            | "google.protobuf.Struct"
+            | "google.protobuf.Value"
+            | "google.protobuf.ListValue"
+            | "google.protobuf.DoubleValue"
+            | "google.protobuf.FloatValue"
+            | "google.protobuf.Int64Value"
+            | "google.protobuf.UInt64Value"
+            | "google.protobuf.Int32Value"
+            | "google.protobuf.UInt32Value"
+            | "google.protobuf.BoolValue"
+            | "google.protobuf.StringValue"
+    )
+}
+
+/// Decodes `0x`-prefixed hex, or standard or URL-safe base64 with or without padding, or
+/// returns the position of the first offending character, `None` if the value ends early.
+pub(crate) fn decode(encoded: &str) -> Result<Vec<u8>, (usize, Option<char>)> {
+    if let Some(hex) = encoded
+        .strip_prefix("0x")
+        .or_else(|| encoded.strip_prefix("0X"))
+    {
+        return decode_hex(hex).map_err(|(position, character)| (position + 2, character));
+    }
+
+    let alphabet = if encoded.contains(['-', '_']) {
+        &base64::alphabet::URL_SAFE
+    } else {
+        &base64::alphabet::STANDARD
+    };
+    let config =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    let offender = |offset: usize| {
+        let position = encoded[..offset].chars().count();
+        (position, encoded[offset..].chars().next())
+    };
+    GeneralPurpose::new(alphabet, config)
+        .decode(encoded)
+        .map_err(|error| match error {
+            DecodeError::InvalidByte(offset, _) | DecodeError::InvalidLastSymbol(offset, _) => {
+                offender(offset)
+            }
+            DecodeError::InvalidLength => (encoded.chars().count(), None),
+            // misplaced padding
+            DecodeError::InvalidPadding => offender(encoded.find('=').unwrap_or(encoded.len())),
+        })
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, (usize, Option<char>)> {
+    let digits = hex
+        .chars()
+        .enumerate()
+        .map(|(position, character)| {
+            character
+                .to_digit(16)
+                .map(|digit| digit as u8)
+                .ok_or((position, Some(character)))
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+    if digits.len() % 2 != 0 {
+        return Err((digits.len(), None));
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
@@ -1,7 +1,74 @@
+//! Wrappers and helper structures for working with gRPC in Rust using the Tonic library.
 //!
+//! The [`reflection::ReflectionClient`] talks to servers exposing the gRPC server reflection
+//! service and turns its responses into the plain structures found in [`service_info`] and
+//! [`message_info`].
 //!
+//! # Features
 //!
-//!
+//! * `transport` (enabled by default) - connect to servers using tonic's native transport,
+//!   configured through the [`builder`]; disable it to use the client with another transport
+//!   such as gRPC-Web, e.g. when compiling to WebAssembly
+//! * `grpc-web` - connect to servers from the browser through gRPC-Web using
+//!   `tonic-web-wasm-client`, see
+//!   `ReflectionClient::new_grpc_web`
+//! * `tls` - connect to `https` endpoints using rustls and the system's native root certificates,
+//!   optionally with custom CA certificates and client identities for mutual TLS
+//! * `gzip`, `zstd` - compress reflection messages with the respective encoding, see
+//!   [`accept_compression`](builder::ReflectionClientBuilder::accept_compression)
+//! * `serde` - serialize and deserialize the structures of [`service_info`],
+//!   [`message_info`] and [`diff`], and [`fingerprint::Fingerprint`]s, with serde, and
+//!   serialize [`openapi::OpenApiDocument`]s, e.g. to YAML
+//! * `cli` - the `grpc-ease` binary, e.g. `grpc-ease list http://localhost:50051`, whose
+//!   commands are found in [`cli`]
+//! * `prost-reflect` - build `prost_reflect::DescriptorPool`s from the file descriptors of
+//!   the server, e.g. to encode and decode `DynamicMessage`s, see
+//!   `ReflectionClient::descriptor_pool_for`, and call methods with requests in JSON or the
+//!   protobuf text format, see `ReflectionClient::call_unary`, and check the [`health`] of
+//!   servers, see `ReflectionClient::check_health`
 
+// credentials and interceptors are only configured through the builder
+#[cfg(feature = "prost-reflect")]
+mod any_json;
+#[cfg_attr(not(feature = "transport"), allow(dead_code))]
+mod auth;
+#[cfg(feature = "transport")]
+pub mod builder;
+mod cache;
+pub mod catalog;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod comments;
+pub mod dependencies;
+pub mod describe;
+pub mod diff;
+pub mod endpoint;
+pub mod error;
+pub mod example;
+pub mod fingerprint;
+#[cfg(feature = "prost-reflect")]
+pub mod health;
+#[cfg_attr(not(feature = "transport"), allow(dead_code))]
+mod interceptor;
+pub mod invoke;
+#[cfg(feature = "prost-reflect")]
+mod json_bytes;
+mod lookup;
+pub mod message_info;
+pub mod openapi;
+pub mod options;
+pub mod proto_printer;
+#[cfg(feature = "transport")]
+mod proxy;
+#[cfg(feature = "prost-reflect")]
+mod raw_json;
+pub mod reconnect;
 pub mod reflection;
+pub mod retry;
+pub mod rich_status;
+pub mod schema;
 pub mod service_info;
+pub mod template;
+#[cfg(feature = "tls")]
+mod tls;
+pub mod watch;
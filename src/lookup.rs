@@ -0,0 +1,116 @@
+//! Locating message and enum definitions within file descriptors.
+
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FileDescriptorProto, ServiceDescriptorProto,
+};
+
+/// The maximum number of similar names suggested for a type that was not found.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns every message defined in `files`, including nested ones, along with its fully
+/// qualified name.
+pub(crate) fn messages(files: &[FileDescriptorProto]) -> Vec<(String, &DescriptorProto)> {
+    fn collect<'a>(
+        scope: &str,
+        messages: &'a [DescriptorProto],
+        found: &mut Vec<(String, &'a DescriptorProto)>,
+    ) {
+        for message in messages {
+            let name = qualify(scope, message.name());
+            found.push((name.clone(), message));
+            collect(&name, &message.nested_type, found);
+        }
+    }
+
+    let mut found = Vec::new();
+    for file in files {
+        collect(file.package(), &file.message_type, &mut found);
+    }
+    found
+}
+
+/// Returns every enum defined in `files`, including those nested in messages, along with its
+/// fully qualified name.
+pub(crate) fn enums(files: &[FileDescriptorProto]) -> Vec<(String, &EnumDescriptorProto)> {
+    let mut found = Vec::new();
+    for file in files {
+        for enumeration in &file.enum_type {
+            found.push((qualify(file.package(), enumeration.name()), enumeration));
+        }
+    }
+    for (scope, message) in messages(files) {
+        for enumeration in &message.enum_type {
+            found.push((qualify(&scope, enumeration.name()), enumeration));
+        }
+    }
+    found
+}
+
+/// Returns every service defined in `files` along with its fully qualified name and the file
+/// defining it.
+pub(crate) fn services(
+    files: &[FileDescriptorProto],
+) -> Vec<(String, &FileDescriptorProto, &ServiceDescriptorProto)> {
+    files
+        .iter()
+        .flat_map(|file| {
+            file.service
+                .iter()
+                .map(move |service| (qualify(file.package(), service.name()), file, service))
+        })
+        .collect()
+}
+
+/// Returns the `candidates` most similar to `name`, closest first, leaving out those too
+/// different to be a plausible typo.
+pub(crate) fn suggestions<'a>(
+    name: &str,
+    candidates: impl Iterator<Item = &'a str>,
+) -> Vec<String> {
+    let threshold = (name.len() / 5).max(2);
+    let mut scored: Vec<_> = candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Returns the type name of an absolute reference like `.billing.v1.ChargeRequest` without its
+/// leading dot.
+pub(crate) fn strip_leading_dot(type_name: String) -> String {
+    match type_name.strip_prefix('.') {
+        Some(name) => name.to_string(),
+        None => type_name,
+    }
+}
+
+/// Returns `name` qualified by the package or message `scope`.
+pub(crate) fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Returns the Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
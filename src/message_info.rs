@@ -0,0 +1,1229 @@
+use crate::comments::{Source, MESSAGE_FIELD, MESSAGE_NESTED_TYPE};
+use crate::lookup::{self, qualify, strip_leading_dot};
+use crate::service_info::Syntax;
+use prost_types::field_descriptor_proto::{Label as ProtoLabel, Type as ProtoType};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Represents the definition of a message type, including its fields and the messages nested
+/// in it
+///
+/// Fields of message types refer to them by name rather than containing their definitions,
+/// so recursive messages, like a tree node containing further nodes, are described once.
+///
+/// # Comments
+///
+/// Servers that register file descriptors with their source code info, e.g. generated with
+/// `protoc --include_source_info`, also serve the comments of the definitions, which are
+/// attached to the messages, fields, services and methods. Comments are `None` if the server
+/// stripped them.
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use prost_types::field_descriptor_proto::Type;
+/// use prost_types::source_code_info::Location;
+/// use prost_types::{
+///     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MethodDescriptorProto,
+///     ServiceDescriptorProto, SourceCodeInfo,
+/// };
+///
+/// let location = |path: &[i32], leading: &str, trailing: Option<&str>| Location {
+///     path: path.to_vec(),
+///     leading_comments: Some(leading.to_string()),
+///     trailing_comments: trailing.map(str::to_string),
+///     ..Default::default()
+/// };
+/// // // Places and tracks orders.
+/// // service Orders {
+/// //   // Places an order.
+/// //   rpc Place(Order) returns (Order);
+/// // }
+/// //
+/// // // An order of a customer.
+/// // message Order {
+/// //   // A line of the order.
+/// //   message Item {}
+/// //
+/// //   // The customer placing the order.
+/// //   string customer = 1; // e.g. an email address
+/// // }
+/// let shop = FileDescriptorProto {
+///     name: Some("shop.proto".to_string()),
+///     package: Some("shop".to_string()),
+///     syntax: Some("proto3".to_string()),
+///     message_type: vec![DescriptorProto {
+///         name: Some("Order".to_string()),
+///         field: vec![FieldDescriptorProto {
+///             name: Some("customer".to_string()),
+///             number: Some(1),
+///             r#type: Some(Type::String.into()),
+///             ..Default::default()
+///         }],
+///         nested_type: vec![DescriptorProto {
+///             name: Some("Item".to_string()),
+///             ..Default::default()
+///         }],
+///         ..Default::default()
+///     }],
+///     service: vec![ServiceDescriptorProto {
+///         name: Some("Orders".to_string()),
+///         method: vec![MethodDescriptorProto {
+///             name: Some("Place".to_string()),
+///             input_type: Some(".shop.Order".to_string()),
+///             output_type: Some(".shop.Order".to_string()),
+///             ..Default::default()
+///         }],
+///         ..Default::default()
+///     }],
+///     source_code_info: Some(SourceCodeInfo {
+///         location: vec![
+///             location(&[6, 0], " Places and tracks orders.\n", None),
+///             location(&[6, 0, 2, 0], " Places an order.\n", None),
+///             location(&[4, 0], " An order of a customer.\n", None),
+///             location(&[4, 0, 3, 0], " A line of the order.\n", None),
+///             location(
+///                 &[4, 0, 2, 0],
+///                 " The customer placing the order.\n",
+///                 Some(" e.g. an email address\n"),
+///             ),
+///         ],
+///     }),
+///     ..Default::default()
+/// };
+/// # let endpoint = support::serve_files(vec![shop]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let order = client.describe_message("shop.Order").await?;
+/// assert_eq!(order.leading_comments.as_deref(), Some(" An order of a customer.\n"));
+/// assert_eq!(
+///     order.nested_messages[0].leading_comments.as_deref(),
+///     Some(" A line of the order.\n")
+/// );
+/// let customer = &order.fields[0];
+/// assert_eq!(
+///     customer.leading_comments.as_deref(),
+///     Some(" The customer placing the order.\n")
+/// );
+/// assert_eq!(customer.trailing_comments.as_deref(), Some(" e.g. an email address\n"));
+///
+/// let orders = client.get_service("shop.Orders").await?;
+/// assert_eq!(orders.leading_comments.as_deref(), Some(" Places and tracks orders.\n"));
+/// assert_eq!(orders.methods[0].leading_comments.as_deref(), Some(" Places an order.\n"));
+/// assert_eq!(orders.methods[0].trailing_comments, None);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageInfo {
+    /// The name of the message, e.g. `Item`
+    pub name: String,
+    /// The fully qualified name of the message, e.g. `shop.Order.Item`
+    pub full_name: String,
+    /// The fields of the message that are not part of a oneof, in declaration order
+    pub fields: Vec<FieldInfo>,
+    /// The oneofs of the message with their fields, in declaration order
+    ///
+    /// The oneofs proto3 generates for `optional` fields are left out, their fields are listed
+    /// along with the other fields instead.
+    pub oneofs: Vec<OneofInfo>,
+    /// The messages nested in the message
+    pub nested_messages: Vec<MessageInfo>,
+    /// The comment preceding the message in its `.proto` file, if the server keeps comments
+    pub leading_comments: Option<String>,
+    /// The comment following the opening line of the message, if the server keeps comments
+    pub trailing_comments: Option<String>,
+}
+
+impl MessageInfo {
+    /// Describes `message`, whose fully qualified name is `full_name`, with the definitions
+    /// of the enum types of its fields found in `enums`, keyed by their fully qualified names,
+    /// and with the comments of its definition `source` in a file of the given `syntax`.
+    pub(crate) fn new(
+        full_name: String,
+        message: &DescriptorProto,
+        enums: &HashMap<String, EnumInfo>,
+        source: &Source,
+        syntax: Syntax,
+    ) -> Self {
+        let mut fields = Vec::new();
+        let mut oneofs: Vec<OneofInfo> = message
+            .oneof_decl
+            .iter()
+            .map(|oneof| OneofInfo {
+                name: oneof.name().to_string(),
+                fields: Vec::new(),
+            })
+            .collect();
+        let mut synthetic_oneofs = HashSet::new();
+        let map_entries: HashMap<String, &DescriptorProto> = message
+            .nested_type
+            .iter()
+            .filter(|nested| is_map_entry(nested))
+            .map(|entry| (qualify(&full_name, entry.name()), entry))
+            .collect();
+
+        for (index, field) in message.field.iter().enumerate() {
+            let mut info = FieldInfo::new(field, enums, syntax);
+            let comments = source.child(MESSAGE_FIELD, index).comments();
+            info.leading_comments = comments.leading;
+            info.trailing_comments = comments.trailing;
+            let entry = info
+                .type_name
+                .as_ref()
+                .and_then(|name| map_entries.get(name));
+            if let Some(map) = entry.and_then(|entry| MapInfo::new(entry)) {
+                info.map = Some(map);
+                info.type_name = None;
+            }
+            match field.oneof_index {
+                Some(index) if field.proto3_optional() => {
+                    synthetic_oneofs.insert(index);
+                    fields.push(info);
+                }
+                Some(index) if (index as usize) < oneofs.len() => {
+                    oneofs[index as usize].fields.push(info);
+                }
+                _ => fields.push(info),
+            }
+        }
+
+        let oneofs = oneofs
+            .into_iter()
+            .zip(0..)
+            .filter(|(_, index)| !synthetic_oneofs.contains(index))
+            .map(|(oneof, _)| oneof)
+            .collect();
+        let comments = source.comments();
+
+        Self {
+            name: message.name().to_string(),
+            fields,
+            oneofs,
+            nested_messages: message
+                .nested_type
+                .iter()
+                .enumerate()
+                .filter(|(_, nested)| !is_map_entry(nested))
+                .map(|(index, nested)| {
+                    MessageInfo::new(
+                        qualify(&full_name, nested.name()),
+                        nested,
+                        enums,
+                        &source.child(MESSAGE_NESTED_TYPE, index),
+                        syntax,
+                    )
+                })
+                .collect(),
+            full_name,
+            leading_comments: comments.leading,
+            trailing_comments: comments.trailing,
+        }
+    }
+
+    /// Returns the fully qualified names of the enum types of the fields of `message` and of
+    /// the messages nested in it.
+    pub(crate) fn enum_types(message: &DescriptorProto) -> Vec<String> {
+        let mut enum_types: Vec<String> = message
+            .field
+            .iter()
+            .filter(|field| field.r#type() == ProtoType::Enum)
+            .map(|field| strip_leading_dot(field.type_name().to_string()))
+            .collect();
+        for nested in &message.nested_type {
+            enum_types.extend(Self::enum_types(nested));
+        }
+        enum_types
+    }
+}
+
+impl fmt::Display for MessageInfo {
+    /// Formats the fields of the message like in a `.proto` file, after its fully qualified
+    /// name, with the fields that are not part of a oneof first.
+    ///
+    /// The messages nested in the message are left out, they are described on their own.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::message_info::{
+    ///     FieldInfo, FieldPresence, FieldType, Label, MessageInfo, OneofInfo,
+    /// };
+    ///
+    /// let field = |name: &str, number, field_type, type_name: Option<&str>| FieldInfo {
+    ///     name: name.to_string(),
+    ///     number,
+    ///     field_type,
+    ///     type_name: type_name.map(str::to_string),
+    ///     label: Label::Optional,
+    ///     json_name: name.to_string(),
+    ///     map: None,
+    ///     proto3_optional: false,
+    ///     presence: FieldPresence::Implicit,
+    ///     enum_info: None,
+    ///     well_known_type: None,
+    ///     leading_comments: None,
+    ///     trailing_comments: None,
+    /// };
+    /// let order = MessageInfo {
+    ///     name: "Order".to_string(),
+    ///     full_name: "shop.Order".to_string(),
+    ///     fields: vec![
+    ///         field("id", 1, FieldType::String, None),
+    ///         FieldInfo {
+    ///             label: Label::Repeated,
+    ///             ..field("items", 2, FieldType::Message, Some("shop.Order.Item"))
+    ///         },
+    ///         FieldInfo {
+    ///             proto3_optional: true,
+    ///             ..field("note", 5, FieldType::String, None)
+    ///         },
+    ///     ],
+    ///     oneofs: vec![OneofInfo {
+    ///         name: "payment".to_string(),
+    ///         fields: vec![
+    ///             field("card_token", 3, FieldType::String, None),
+    ///             field("iban", 4, FieldType::String, None),
+    ///         ],
+    ///     }],
+    ///     nested_messages: Vec::new(),
+    ///     leading_comments: None,
+    ///     trailing_comments: None,
+    /// };
+    /// assert_eq!(
+    ///     order.to_string(),
+    ///     "message shop.Order {\n  \
+    ///        string id = 1;\n  \
+    ///        repeated shop.Order.Item items = 2;\n  \
+    ///        optional string note = 5;\n  \
+    ///        oneof payment {\n    \
+    ///          string card_token = 3;\n    \
+    ///          string iban = 4;\n  \
+    ///        }\n\
+    ///      }"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.fields.is_empty() && self.oneofs.is_empty() {
+            return write!(f, "message {} {{}}", self.full_name);
+        }
+        write!(f, "message {} {{", self.full_name)?;
+        for field in &self.fields {
+            write!(f, "\n  {};", field)?;
+        }
+        for oneof in &self.oneofs {
+            write!(f, "\n  oneof {} {{", oneof.name)?;
+            for field in &oneof.fields {
+                write!(f, "\n    {};", field)?;
+            }
+            write!(f, "\n  }}")?;
+        }
+        write!(f, "\n}}")
+    }
+}
+
+/// Represents a oneof of a message, of whose fields at most one is set
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use prost_types::field_descriptor_proto::Type;
+/// use prost_types::{
+///     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, OneofDescriptorProto,
+/// };
+///
+/// let field = |name: &str, number, oneof_index| FieldDescriptorProto {
+///     name: Some(name.to_string()),
+///     number: Some(number),
+///     r#type: Some(Type::String.into()),
+///     oneof_index,
+///     ..Default::default()
+/// };
+/// let oneof = |name: &str| OneofDescriptorProto {
+///     name: Some(name.to_string()),
+///     ..Default::default()
+/// };
+/// // message Payment {
+/// //   oneof method {
+/// //     string card_token = 1;
+/// //     string iban = 2;
+/// //   }
+/// //   optional string note = 3;
+/// //   string reference = 4;
+/// // }
+/// let payment = DescriptorProto {
+///     name: Some("Payment".to_string()),
+///     field: vec![
+///         field("card_token", 1, Some(0)),
+///         field("iban", 2, Some(0)),
+///         FieldDescriptorProto {
+///             proto3_optional: Some(true),
+///             ..field("note", 3, Some(1))
+///         },
+///         field("reference", 4, None),
+///     ],
+///     oneof_decl: vec![oneof("method"), oneof("_note")],
+///     ..Default::default()
+/// };
+/// let billing = FileDescriptorProto {
+///     name: Some("billing.proto".to_string()),
+///     package: Some("billing".to_string()),
+///     syntax: Some("proto3".to_string()),
+///     message_type: vec![payment],
+///     ..Default::default()
+/// };
+/// # let endpoint = support::serve_files(vec![billing]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let payment = client.describe_message("billing.Payment").await?;
+/// assert_eq!(payment.oneofs.len(), 1);
+/// assert_eq!(payment.oneofs[0].name, "method");
+/// let method: Vec<_> = payment.oneofs[0].fields.iter().map(|field| &field.name).collect();
+/// assert_eq!(method, ["card_token", "iban"]);
+///
+/// let fields: Vec<_> = payment
+///     .fields
+///     .iter()
+///     .map(|field| (field.name.as_str(), field.proto3_optional))
+///     .collect();
+/// assert_eq!(fields, [("note", true), ("reference", false)]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OneofInfo {
+    /// The name of the oneof, e.g. `payment_method`
+    pub name: String,
+    /// The fields of the oneof, in declaration order
+    pub fields: Vec<FieldInfo>,
+}
+
+/// Represents a field of a message
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldInfo {
+    /// The name of the field, e.g. `order_id`
+    pub name: String,
+    /// The field number
+    pub number: i32,
+    /// The type of the field's values
+    pub field_type: FieldType,
+    /// The fully qualified name of the message or enum type of the field, e.g. `shop.Order`,
+    /// for fields of such types
+    pub type_name: Option<String>,
+    /// Whether the field is optional, required, or repeated
+    pub label: Label,
+    /// The name of the field in the JSON encoding, e.g. `orderId`
+    pub json_name: String,
+    /// The key and value types of map fields
+    ///
+    /// Map fields are repeated fields of a message type generated for the map, which is left
+    /// out of the messages nested in the message, and whose name is left out of `type_name`.
+    pub map: Option<MapInfo>,
+    /// Whether the field is declared `optional` in a proto3 file, making it track whether it
+    /// is set like in proto2
+    pub proto3_optional: bool,
+    /// Whether the field tracks if it is set, which tells an unset field from one set to its
+    /// default value
+    pub presence: FieldPresence,
+    /// The definition of the enum type of the field, for enum fields whose type the server
+    /// knows
+    pub enum_info: Option<EnumInfo>,
+    /// The well-known type of the field, like `google.protobuf.Timestamp`, for fields of
+    /// such a type
+    pub well_known_type: Option<WellKnownType>,
+    /// The comment preceding the field in its `.proto` file, if the server keeps comments
+    pub leading_comments: Option<String>,
+    /// The comment following the field on the same or the next line, if the server keeps
+    /// comments
+    pub trailing_comments: Option<String>,
+}
+
+impl fmt::Display for FieldInfo {
+    /// Formats the field like in a `.proto` file without the trailing semicolon, e.g.
+    /// `repeated string tags = 3` or `map<string, shop.Product> products = 1`.
+    ///
+    /// Singular fields are labeled `optional` only if they are declared so in a proto3 file.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(map) = &self.map {
+            let value = match &map.value_type_name {
+                Some(type_name) => type_name.clone(),
+                None => map.value_type.to_string(),
+            };
+            return write!(
+                f,
+                "map<{}, {}> {} = {}",
+                map.key_type, value, self.name, self.number
+            );
+        }
+
+        let label = match self.label {
+            Label::Repeated => "repeated ",
+            Label::Required => "required ",
+            Label::Optional if self.proto3_optional => "optional ",
+            Label::Optional => "",
+        };
+        let field_type = match &self.type_name {
+            Some(type_name) => type_name.clone(),
+            None => self.field_type.to_string(),
+        };
+        write!(f, "{}{} {} = {}", label, field_type, self.name, self.number)
+    }
+}
+
+impl FieldInfo {
+    /// Describes `field` of a file of the given `syntax`, looking up its enum type in `enums`.
+    fn new(
+        field: &FieldDescriptorProto,
+        enums: &HashMap<String, EnumInfo>,
+        syntax: Syntax,
+    ) -> Self {
+        let type_name = field.type_name.clone().map(strip_leading_dot);
+        let well_known_type = type_name.as_deref().and_then(WellKnownType::from_type_name);
+        Self {
+            enum_info: match (field.r#type(), &type_name) {
+                (ProtoType::Enum, Some(type_name)) => enums.get(type_name).cloned(),
+                _ => None,
+            },
+            name: field.name().to_string(),
+            number: field.number(),
+            field_type: field.r#type().into(),
+            type_name,
+            label: field.label().into(),
+            map: None,
+            well_known_type,
+            proto3_optional: field.proto3_optional(),
+            presence: FieldPresence::of(field, syntax),
+            leading_comments: None,
+            trailing_comments: None,
+            json_name: field
+                .json_name
+                .clone()
+                .unwrap_or_else(|| json_name(field.name())),
+        }
+    }
+}
+
+/// Represents the key and value types of a map field
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::message_info::{FieldType, MapInfo};
+/// use grpc_ease::reflection::ReflectionClient;
+/// use prost_types::field_descriptor_proto::{Label, Type};
+/// use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MessageOptions};
+///
+/// let field = |name: &str, number, label: Label, kind: Type, type_name: Option<&str>| {
+///     FieldDescriptorProto {
+///         name: Some(name.to_string()),
+///         number: Some(number),
+///         label: Some(label.into()),
+///         r#type: Some(kind.into()),
+///         type_name: type_name.map(str::to_string),
+///         ..Default::default()
+///     }
+/// };
+/// // the message protoc generates for a map field
+/// let entry = |name: &str, key: Type, value: Type, value_type_name: Option<&str>| {
+///     DescriptorProto {
+///         name: Some(name.to_string()),
+///         field: vec![
+///             field("key", 1, Label::Optional, key, None),
+///             field("value", 2, Label::Optional, value, value_type_name),
+///         ],
+///         options: Some(MessageOptions {
+///             map_entry: Some(true),
+///             ..Default::default()
+///         }),
+///         ..Default::default()
+///     }
+/// };
+/// // message Catalog {
+/// //   map<string, Product> products = 1;
+/// //   map<int64, string> labels = 2;
+/// // }
+/// let products = Some(".shop.Catalog.ProductsEntry");
+/// let labels = Some(".shop.Catalog.LabelsEntry");
+/// let catalog = DescriptorProto {
+///     name: Some("Catalog".to_string()),
+///     field: vec![
+///         field("products", 1, Label::Repeated, Type::Message, products),
+///         field("labels", 2, Label::Repeated, Type::Message, labels),
+///     ],
+///     nested_type: vec![
+///         entry("ProductsEntry", Type::String, Type::Message, Some(".shop.Product")),
+///         entry("LabelsEntry", Type::Int64, Type::String, None),
+///     ],
+///     ..Default::default()
+/// };
+/// let product = DescriptorProto {
+///     name: Some("Product".to_string()),
+///     ..Default::default()
+/// };
+/// let shop = FileDescriptorProto {
+///     name: Some("shop.proto".to_string()),
+///     package: Some("shop".to_string()),
+///     message_type: vec![catalog, product],
+///     ..Default::default()
+/// };
+/// # let endpoint = support::serve_files(vec![shop]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let catalog = client.describe_message("shop.Catalog").await?;
+/// assert_eq!(
+///     catalog.fields[0].map,
+///     Some(MapInfo {
+///         key_type: FieldType::String,
+///         value_type: FieldType::Message,
+///         value_type_name: Some("shop.Product".to_string()),
+///     })
+/// );
+/// assert_eq!(
+///     catalog.fields[1].map,
+///     Some(MapInfo {
+///         key_type: FieldType::Int64,
+///         value_type: FieldType::String,
+///         value_type_name: None,
+///     })
+/// );
+/// assert_eq!(catalog.fields[0].type_name, None);
+/// assert!(catalog.nested_messages.is_empty());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapInfo {
+    /// The type of the keys, which is an integral type, `bool`, or `string`
+    pub key_type: FieldType,
+    /// The type of the values
+    pub value_type: FieldType,
+    /// The fully qualified name of the message or enum type of the values, for values of
+    /// such types
+    pub value_type_name: Option<String>,
+}
+
+impl MapInfo {
+    /// Describes the map whose entries are the messages `entry`, or returns `None` if it lacks
+    /// the key or value field.
+    fn new(entry: &DescriptorProto) -> Option<Self> {
+        let field = |number| entry.field.iter().find(|field| field.number() == number);
+        let (key, value) = (field(1)?, field(2)?);
+        Some(Self {
+            key_type: key.r#type().into(),
+            value_type: value.r#type().into(),
+            value_type_name: value.type_name.clone().map(strip_leading_dot),
+        })
+    }
+}
+
+/// Represents the definition of an enum type
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumInfo {
+    /// The name of the enum, e.g. `Status`
+    pub name: String,
+    /// The fully qualified name of the enum, e.g. `shop.Order.Status`
+    pub full_name: String,
+    /// The names and numbers of the values, in declaration order
+    ///
+    /// With `option allow_alias = true`, several names may share a number.
+    pub values: Vec<(String, i32)>,
+    /// Whether the enum is marked with `option deprecated = true`
+    pub deprecated: bool,
+}
+
+impl EnumInfo {
+    /// Describes `enumeration`, whose fully qualified name is `full_name`.
+    pub(crate) fn new(full_name: String, enumeration: &EnumDescriptorProto) -> Self {
+        Self {
+            name: enumeration.name().to_string(),
+            full_name,
+            values: enumeration
+                .value
+                .iter()
+                .map(|value| (value.name().to_string(), value.number()))
+                .collect(),
+            deprecated: enumeration
+                .options
+                .as_ref()
+                .is_some_and(|options| options.deprecated()),
+        }
+    }
+}
+
+impl fmt::Display for EnumInfo {
+    /// Formats the values of the enum like in a `.proto` file, after its fully qualified name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::message_info::EnumInfo;
+    ///
+    /// let status = EnumInfo {
+    ///     name: "Status".to_string(),
+    ///     full_name: "shop.Order.Status".to_string(),
+    ///     values: vec![("STATUS_UNSPECIFIED".to_string(), 0), ("PLACED".to_string(), 1)],
+    ///     deprecated: false,
+    /// };
+    /// assert_eq!(
+    ///     status.to_string(),
+    ///     "enum shop.Order.Status {\n  STATUS_UNSPECIFIED = 0;\n  PLACED = 1;\n}"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "enum {} {{", self.full_name)?;
+        for (name, number) in &self.values {
+            write!(f, "\n  {} = {};", name, number)?;
+        }
+        write!(f, "\n}}")
+    }
+}
+
+/// A message or enum type defined in a file of the server, as listed by
+/// [`ReflectionClient::list_types`](crate::reflection::ReflectionClient::list_types)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TypeInfo {
+    // the derived ordering compares the fields in the order they are declared
+    /// The fully qualified name of the type, e.g. `shop.Order.Status`
+    pub full_name: String,
+    /// Whether the type is a message or an enum
+    pub kind: TypeKind,
+    /// The name of the file defining the type, e.g. `shop/v1/orders.proto`
+    pub file_name: String,
+}
+
+/// Whether a type is a message or an enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TypeKind {
+    /// A message, which may be nested in another message
+    Message,
+    /// An enum, which may be nested in a message
+    Enum,
+}
+
+impl TypeInfo {
+    /// Returns the message and enum types defined in `file`, including nested ones but not
+    /// the entries of maps.
+    pub(crate) fn defined_in(file: &FileDescriptorProto) -> Vec<Self> {
+        let files = std::slice::from_ref(file);
+        let type_info = |full_name, kind| Self {
+            full_name,
+            kind,
+            file_name: file.name().to_string(),
+        };
+
+        let messages = lookup::messages(files)
+            .into_iter()
+            .filter(|(_, message)| !is_map_entry(message))
+            .map(|(full_name, _)| type_info(full_name, TypeKind::Message));
+        let enums = lookup::enums(files)
+            .into_iter()
+            .map(|(full_name, _)| type_info(full_name, TypeKind::Enum));
+        messages.chain(enums).collect()
+    }
+}
+
+/// The type of the values of a field, mirroring the types of the protobuf wire format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FieldType {
+    /// `double`
+    Double,
+    /// `float`
+    Float,
+    /// `int64`
+    Int64,
+    /// `uint64`
+    Uint64,
+    /// `int32`
+    Int32,
+    /// `fixed64`
+    Fixed64,
+    /// `fixed32`
+    Fixed32,
+    /// `bool`
+    Bool,
+    /// `string`
+    String,
+    /// `group`, a proto2 group, whose type is a message
+    Group,
+    /// `message`
+    Message,
+    /// `bytes`
+    Bytes,
+    /// `uint32`
+    Uint32,
+    /// `enum`
+    Enum,
+    /// `sfixed32`
+    Sfixed32,
+    /// `sfixed64`
+    Sfixed64,
+    /// `sint32`
+    Sint32,
+    /// `sint64`
+    Sint64,
+}
+
+impl fmt::Display for FieldType {
+    /// Formats the type as named in a `.proto` file, e.g. `sfixed64`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int64 => "int64",
+            Self::Uint64 => "uint64",
+            Self::Int32 => "int32",
+            Self::Fixed64 => "fixed64",
+            Self::Fixed32 => "fixed32",
+            Self::Bool => "bool",
+            Self::String => "string",
+            Self::Group => "group",
+            Self::Message => "message",
+            Self::Bytes => "bytes",
+            Self::Uint32 => "uint32",
+            Self::Enum => "enum",
+            Self::Sfixed32 => "sfixed32",
+            Self::Sfixed64 => "sfixed64",
+            Self::Sint32 => "sint32",
+            Self::Sint64 => "sint64",
+        };
+        f.write_str(name)
+    }
+}
+
+impl From<ProtoType> for FieldType {
+    fn from(field_type: ProtoType) -> Self {
+        match field_type {
+            ProtoType::Double => Self::Double,
+            ProtoType::Float => Self::Float,
+            ProtoType::Int64 => Self::Int64,
+            ProtoType::Uint64 => Self::Uint64,
+            ProtoType::Int32 => Self::Int32,
+            ProtoType::Fixed64 => Self::Fixed64,
+            ProtoType::Fixed32 => Self::Fixed32,
+            ProtoType::Bool => Self::Bool,
+            ProtoType::String => Self::String,
+            ProtoType::Group => Self::Group,
+            ProtoType::Message => Self::Message,
+            ProtoType::Bytes => Self::Bytes,
+            ProtoType::Uint32 => Self::Uint32,
+            ProtoType::Enum => Self::Enum,
+            ProtoType::Sfixed32 => Self::Sfixed32,
+            ProtoType::Sfixed64 => Self::Sfixed64,
+            ProtoType::Sint32 => Self::Sint32,
+            ProtoType::Sint64 => Self::Sint64,
+        }
+    }
+}
+
+/// A well-known type of `google/protobuf`, whose fields are usually presented as a plain value
+/// rather than as a nested message, e.g. a `Timestamp` as a date
+///
+/// The types describing protobuf definitions themselves, like `Type` or `Api`, are not included.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::message_info::WellKnownType;
+///
+/// assert_eq!(
+///     WellKnownType::from_type_name(".google.protobuf.Timestamp"),
+///     Some(WellKnownType::Timestamp)
+/// );
+/// assert_eq!(WellKnownType::from_type_name("google.protobuf.Int32Value"), Some(WellKnownType::Int32Value));
+/// assert_eq!(WellKnownType::from_type_name("shop.Timestamp"), None);
+/// assert_eq!(WellKnownType::from_type_name("google.protobuf.timestamp"), None);
+///
+/// assert_eq!(WellKnownType::ALL.len(), 18);
+/// for well_known_type in WellKnownType::ALL {
+///     let full_name = well_known_type.full_name();
+///     assert_eq!(WellKnownType::from_type_name(full_name), Some(*well_known_type));
+/// }
+/// let wrappers: Vec<_> = WellKnownType::ALL
+///     .iter()
+///     .filter(|well_known_type| well_known_type.is_wrapper())
+///     .map(|well_known_type| well_known_type.full_name())
+///     .collect();
+/// assert_eq!(
+///     wrappers,
+///     [
+///         "google.protobuf.DoubleValue",
+///         "google.protobuf.FloatValue",
+///         "google.protobuf.Int64Value",
+///         "google.protobuf.UInt64Value",
+///         "google.protobuf.Int32Value",
+///         "google.protobuf.UInt32Value",
+///         "google.protobuf.BoolValue",
+///         "google.protobuf.StringValue",
+///         "google.protobuf.BytesValue",
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WellKnownType {
+    /// `google.protobuf.Any`, a message of any type along with its type URL
+    Any,
+    /// `google.protobuf.Timestamp`, a point in time
+    Timestamp,
+    /// `google.protobuf.Duration`, a signed span of time
+    Duration,
+    /// `google.protobuf.Empty`, a message without fields
+    Empty,
+    /// `google.protobuf.FieldMask`, a list of field paths
+    FieldMask,
+    /// `google.protobuf.Struct`, a JSON object
+    Struct,
+    /// `google.protobuf.Value`, any JSON value
+    Value,
+    /// `google.protobuf.ListValue`, a JSON array
+    ListValue,
+    /// `google.protobuf.NullValue`, the enum of the JSON `null`
+    NullValue,
+    /// `google.protobuf.DoubleValue`, a wrapped `double`
+    DoubleValue,
+    /// `google.protobuf.FloatValue`, a wrapped `float`
+    FloatValue,
+    /// `google.protobuf.Int64Value`, a wrapped `int64`
+    Int64Value,
+    /// `google.protobuf.UInt64Value`, a wrapped `uint64`
+    UInt64Value,
+    /// `google.protobuf.Int32Value`, a wrapped `int32`
+    Int32Value,
+    /// `google.protobuf.UInt32Value`, a wrapped `uint32`
+    UInt32Value,
+    /// `google.protobuf.BoolValue`, a wrapped `bool`
+    BoolValue,
+    /// `google.protobuf.StringValue`, a wrapped `string`
+    StringValue,
+    /// `google.protobuf.BytesValue`, a wrapped `bytes`
+    BytesValue,
+}
+
+impl WellKnownType {
+    /// All well-known types, in declaration order.
+    pub const ALL: &'static [Self] = &[
+        Self::Any,
+        Self::Timestamp,
+        Self::Duration,
+        Self::Empty,
+        Self::FieldMask,
+        Self::Struct,
+        Self::Value,
+        Self::ListValue,
+        Self::NullValue,
+        Self::DoubleValue,
+        Self::FloatValue,
+        Self::Int64Value,
+        Self::UInt64Value,
+        Self::Int32Value,
+        Self::UInt32Value,
+        Self::BoolValue,
+        Self::StringValue,
+        Self::BytesValue,
+    ];
+
+    /// Returns the well-known type with the fully qualified name `type_name`, which may start
+    /// with a dot like the type names of field descriptors.
+    pub fn from_type_name(type_name: &str) -> Option<Self> {
+        let type_name = type_name.strip_prefix('.').unwrap_or(type_name);
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|well_known_type| well_known_type.full_name() == type_name)
+    }
+
+    /// Returns the fully qualified name of the type, e.g. `google.protobuf.Timestamp`.
+    pub fn full_name(&self) -> &'static str {
+        match self {
+            Self::Any => "google.protobuf.Any",
+            Self::Timestamp => "google.protobuf.Timestamp",
+            Self::Duration => "google.protobuf.Duration",
+            Self::Empty => "google.protobuf.Empty",
+            Self::FieldMask => "google.protobuf.FieldMask",
+            Self::Struct => "google.protobuf.Struct",
+            Self::Value => "google.protobuf.Value",
+            Self::ListValue => "google.protobuf.ListValue",
+            Self::NullValue => "google.protobuf.NullValue",
+            Self::DoubleValue => "google.protobuf.DoubleValue",
+            Self::FloatValue => "google.protobuf.FloatValue",
+            Self::Int64Value => "google.protobuf.Int64Value",
+            Self::UInt64Value => "google.protobuf.UInt64Value",
+            Self::Int32Value => "google.protobuf.Int32Value",
+            Self::UInt32Value => "google.protobuf.UInt32Value",
+            Self::BoolValue => "google.protobuf.BoolValue",
+            Self::StringValue => "google.protobuf.StringValue",
+            Self::BytesValue => "google.protobuf.BytesValue",
+        }
+    }
+
+    /// Returns whether the type wraps a single scalar value, like `Int32Value`, which makes
+    /// the value of a field of the type distinguishable from being unset.
+    pub fn is_wrapper(&self) -> bool {
+        matches!(
+            self,
+            Self::DoubleValue
+                | Self::FloatValue
+                | Self::Int64Value
+                | Self::UInt64Value
+                | Self::Int32Value
+                | Self::UInt32Value
+                | Self::BoolValue
+                | Self::StringValue
+                | Self::BytesValue
+        )
+    }
+}
+
+/// Whether a field tracks if it is set, following the field presence rules of protobuf
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::message_info::FieldPresence;
+/// use grpc_ease::reflection::ReflectionClient;
+/// use prost_types::field_descriptor_proto::{Label, Type};
+/// use prost_types::{
+///     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MessageOptions,
+///     OneofDescriptorProto,
+/// };
+///
+/// let field = |name: &str, number, label: Label, r#type: Type| FieldDescriptorProto {
+///     name: Some(name.to_string()),
+///     number: Some(number),
+///     label: Some(label.into()),
+///     r#type: Some(r#type.into()),
+///     ..Default::default()
+/// };
+/// let message_field = |name: &str, number, label, type_name: &str| FieldDescriptorProto {
+///     type_name: Some(type_name.to_string()),
+///     ..field(name, number, label, Type::Message)
+/// };
+/// let message = |name: &str, fields: Vec<FieldDescriptorProto>| DescriptorProto {
+///     name: Some(name.to_string()),
+///     field: fields,
+///     ..Default::default()
+/// };
+/// // map<string, int32> counts
+/// let counts_entry = DescriptorProto {
+///     options: Some(MessageOptions {
+///         map_entry: Some(true),
+///         ..Default::default()
+///     }),
+///     ..message(
+///         "CountsEntry",
+///         vec![
+///             field("key", 1, Label::Optional, Type::String),
+///             field("value", 2, Label::Optional, Type::Int32),
+///         ],
+///     )
+/// };
+/// let file = |syntax: &str, fields: Vec<FieldDescriptorProto>| FileDescriptorProto {
+///     name: Some(format!("{}.proto", syntax)),
+///     package: Some(syntax.to_string()),
+///     syntax: Some(syntax.to_string()),
+///     message_type: vec![
+///         DescriptorProto {
+///             nested_type: vec![counts_entry.clone()],
+///             oneof_decl: vec![
+///                 OneofDescriptorProto {
+///                     name: Some("choice".to_string()),
+///                     ..Default::default()
+///                 },
+///                 OneofDescriptorProto {
+///                     name: Some("_maybe".to_string()),
+///                     ..Default::default()
+///                 },
+///             ],
+///             ..message("Form", fields)
+///         },
+///         message("Other", Vec::new()),
+///     ],
+///     ..Default::default()
+/// };
+/// let common = |package: &str| {
+///     vec![
+///         message_field("other", 3, Label::Optional, &format!(".{}.Other", package)),
+///         field("list", 4, Label::Repeated, Type::Int32),
+///         message_field("counts", 5, Label::Repeated, &format!(".{}.Form.CountsEntry", package)),
+///         FieldDescriptorProto {
+///             oneof_index: Some(0),
+///             ..field("chosen", 6, Label::Optional, Type::String)
+///         },
+///     ]
+/// };
+/// let proto2 = file(
+///     "proto2",
+///     [
+///         vec![
+///             field("plain", 1, Label::Optional, Type::Int32),
+///             field("required", 2, Label::Required, Type::String),
+///         ],
+///         common("proto2"),
+///     ]
+///     .concat(),
+/// );
+/// let proto3 = file(
+///     "proto3",
+///     [
+///         vec![
+///             field("plain", 1, Label::Optional, Type::Int32),
+///             FieldDescriptorProto {
+///                 oneof_index: Some(1),
+///                 proto3_optional: Some(true),
+///                 ..field("maybe", 2, Label::Optional, Type::Int32)
+///             },
+///         ],
+///         common("proto3"),
+///     ]
+///     .concat(),
+/// );
+///
+/// # let endpoint = support::serve_files(vec![proto2, proto3]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let presence = |form: grpc_ease::message_info::MessageInfo| {
+///     let oneof_fields = form.oneofs.into_iter().flat_map(|oneof| oneof.fields);
+///     let mut fields: Vec<_> = form
+///         .fields
+///         .into_iter()
+///         .chain(oneof_fields)
+///         .map(|field| (field.number, field.name, field.presence))
+///         .collect();
+///     fields.sort();
+///     fields.into_iter().map(|(_, name, presence)| (name, presence)).collect::<Vec<_>>()
+/// };
+///
+/// let proto2 = presence(client.describe_message("proto2.Form").await?);
+/// assert_eq!(
+///     proto2,
+///     [
+///         ("plain".to_string(), FieldPresence::Explicit),
+///         ("required".to_string(), FieldPresence::Explicit),
+///         ("other".to_string(), FieldPresence::Explicit),
+///         ("list".to_string(), FieldPresence::Repeated),
+///         ("counts".to_string(), FieldPresence::Repeated),
+///         ("chosen".to_string(), FieldPresence::Explicit),
+///     ]
+/// );
+///
+/// let proto3 = presence(client.describe_message("proto3.Form").await?);
+/// assert_eq!(
+///     proto3,
+///     [
+///         ("plain".to_string(), FieldPresence::Implicit),
+///         ("maybe".to_string(), FieldPresence::Explicit),
+///         ("other".to_string(), FieldPresence::Explicit),
+///         ("list".to_string(), FieldPresence::Repeated),
+///         ("counts".to_string(), FieldPresence::Repeated),
+///         ("chosen".to_string(), FieldPresence::Explicit),
+///     ]
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FieldPresence {
+    /// The field tracks whether it is set, even to its default value, which is the case for
+    /// singular proto2 fields, proto3 fields declared `optional`, message fields, and fields
+    /// of a oneof
+    Explicit,
+    /// The field is unset whenever it holds its default value, like `0` or `""`, which is the
+    /// case for the other singular proto3 fields, so setting it to the default value is the
+    /// same as leaving it unset
+    Implicit,
+    /// The field is repeated or a map, which is unset when it holds no values
+    Repeated,
+}
+
+impl FieldPresence {
+    /// Returns the presence of `field` of a file of the given `syntax`.
+    ///
+    /// Files using editions are treated like proto2 files, since explicit presence is the
+    /// default of edition 2023.
+    fn of(field: &FieldDescriptorProto, syntax: Syntax) -> Self {
+        if field.label() == ProtoLabel::Repeated {
+            Self::Repeated
+        } else if syntax == Syntax::Proto3
+            && !field.proto3_optional()
+            && field.oneof_index.is_none()
+            && !matches!(field.r#type(), ProtoType::Message | ProtoType::Group)
+        {
+            Self::Implicit
+        } else {
+            Self::Explicit
+        }
+    }
+}
+
+/// Whether a field is optional, required, or repeated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Label {
+    /// The field holds at most one value, which is the case for all singular proto3 fields
+    Optional,
+    /// The field must hold a value, which only proto2 supports
+    Required,
+    /// The field holds any number of values
+    Repeated,
+}
+
+impl From<ProtoLabel> for Label {
+    fn from(label: ProtoLabel) -> Self {
+        match label {
+            ProtoLabel::Optional => Self::Optional,
+            ProtoLabel::Required => Self::Required,
+            ProtoLabel::Repeated => Self::Repeated,
+        }
+    }
+}
+
+/// Returns the JSON name protoc derives from the field name `name`, for descriptors lacking
+/// it, which removes the underscores and capitalizes the letters following them.
+pub(crate) fn json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalize = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+/// Returns whether `message` is the entry message generated for a map field.
+pub(crate) fn is_map_entry(message: &DescriptorProto) -> bool {
+    message
+        .options
+        .as_ref()
+        .is_some_and(|options| options.map_entry())
+}
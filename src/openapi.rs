@@ -0,0 +1,230 @@
+//! Describing the services of a schema as an OpenAPI document, e.g. for a JSON gateway.
+
+use crate::catalog::OfflineCatalog;
+use crate::error::ReflectionError;
+use crate::fingerprint::Fingerprint;
+use crate::message_info::{FieldType, WellKnownType};
+use crate::schema::{describe, well_known, Generator};
+use crate::service_info::{MethodInfo, ServiceInfo};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// The prefix of the references to the schemas of messages in an OpenAPI document.
+const SCHEMAS: &str = "#/components/schemas/";
+
+/// An OpenAPI 3.1 document describing the unary methods of gRPC services, as created by
+/// [`from_files`]
+///
+/// Every method is a `POST` operation on its gRPC path, e.g. `/shop.Orders/Place`, taking
+/// and returning the JSON encodings of its request and response messages, which is how
+/// JSON gateways commonly expose gRPC services:
+/// - the schemas of the messages are generated like by
+///   [`message_to_json_schema`](crate::schema::message_to_json_schema) and defined in
+///   `components/schemas`, keyed by their fully qualified names
+/// - failed calls respond with a `google.rpc.Status` shaped error, the `Error` response of
+///   `components/responses`
+/// - the operations are tagged with the fully qualified names of their services, and
+///   described by the comments of the methods
+/// - `info.version` is the [`Fingerprint`] of the schema, so it changes along with it
+///
+/// Streaming methods have no single request or response to describe, so they are left out
+/// and listed by [`skipped_methods`](Self::skipped_methods) instead.
+///
+/// The document is displayed as pretty-printed JSON. With the `serde` feature, it is
+/// serialized as the document itself, e.g. to YAML.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct OpenApiDocument {
+    document: Value,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    skipped_methods: Vec<String>,
+}
+
+impl OpenApiDocument {
+    /// Returns the JSON of the document.
+    pub fn as_json(&self) -> &Value {
+        &self.document
+    }
+
+    /// Returns the JSON of the document, consuming it.
+    pub fn into_json(self) -> Value {
+        self.document
+    }
+
+    /// Returns the paths of the streaming methods left out of the document, e.g.
+    /// `/shop.Orders/Watch`.
+    pub fn skipped_methods(&self) -> &[String] {
+        &self.skipped_methods
+    }
+}
+
+impl fmt::Display for OpenApiDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match serde_json::to_string_pretty(&self.document) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+/// Creates an OpenAPI document describing the unary methods of the services defined in
+/// `files`, looking up their messages in `files` as well.
+///
+/// Use [`ReflectionClient::openapi_document`](crate::reflection::ReflectionClient::openapi_document)
+/// to describe the services of a server.
+///
+/// # Errors
+///
+/// This function will return [`ReflectionError::MalformedDescriptor`] if a service or method
+/// name is missing.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::openapi;
+/// use prost_types::{
+///     DescriptorProto, FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto,
+/// };
+/// use serde_json::json;
+///
+/// let method = |name: &str, server_streaming| MethodDescriptorProto {
+///     name: Some(name.to_string()),
+///     input_type: Some(".shop.Order".to_string()),
+///     output_type: Some(".shop.Order".to_string()),
+///     server_streaming: Some(server_streaming),
+///     ..Default::default()
+/// };
+/// // service Orders {
+/// //   rpc Place(Order) returns (Order);
+/// //   rpc Watch(Order) returns (stream Order);
+/// // }
+/// let shop = FileDescriptorProto {
+///     name: Some("shop.proto".to_string()),
+///     package: Some("shop".to_string()),
+///     syntax: Some("proto3".to_string()),
+///     message_type: vec![DescriptorProto {
+///         name: Some("Order".to_string()),
+///         ..Default::default()
+///     }],
+///     service: vec![ServiceDescriptorProto {
+///         name: Some("Orders".to_string()),
+///         method: vec![method("Place", false), method("Watch", true)],
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+///
+/// let document = openapi::from_files(&[shop])?;
+/// assert_eq!(document.skipped_methods(), ["/shop.Orders/Watch"]);
+///
+/// let json = document.as_json();
+/// assert_eq!(json["openapi"], "3.1.0");
+/// let place = &json["paths"]["/shop.Orders/Place"]["post"];
+/// assert_eq!(place["operationId"], "shop.Orders.Place");
+/// assert_eq!(
+///     place["requestBody"]["content"]["application/json"]["schema"],
+///     json!({ "$ref": "#/components/schemas/shop.Order" })
+/// );
+/// assert_eq!(json["components"]["schemas"]["shop.Order"]["type"], "object");
+/// assert!(json["paths"]["/shop.Orders/Watch"].is_null());
+/// # Ok::<(), grpc_ease::error::ReflectionError>(())
+/// ```
+pub fn from_files(files: &[FileDescriptorProto]) -> Result<OpenApiDocument, ReflectionError> {
+    document(files, |_| true)
+}
+
+/// Creates an OpenAPI document like [`from_files`], describing only the services for which
+/// `include` returns `true`, given their fully qualified names.
+pub(crate) fn document(
+    files: &[FileDescriptorProto],
+    include: impl Fn(&str) -> bool,
+) -> Result<OpenApiDocument, ReflectionError> {
+    let catalog = OfflineCatalog::new(FileDescriptorSet {
+        file: files.to_vec(),
+    });
+    let mut generator = Generator::new(&catalog, SCHEMAS);
+    let mut paths = Map::new();
+    let mut tags = Vec::new();
+    let mut skipped_methods = Vec::new();
+
+    let services = catalog.list_services()?;
+    for service in services
+        .iter()
+        .filter(|service| include(&service.full_name()))
+    {
+        let mut tag = json!({ "name": service.full_name() });
+        describe(&mut tag, service.leading_comments.as_deref());
+        tags.push(tag);
+
+        for method in &service.methods {
+            if method.is_client_streaming || method.is_server_streaming {
+                skipped_methods.push(method.full_path(service));
+                continue;
+            }
+            let operation = operation(&mut generator, service, method);
+            paths.insert(method.full_path(service), json!({ "post": operation }));
+        }
+    }
+
+    let document = json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "gRPC services",
+            "version": Fingerprint::of(files).to_string(),
+        },
+        "tags": tags,
+        "paths": paths,
+        "components": {
+            "schemas": generator.definitions(),
+            "responses": {
+                "Error": {
+                    "description": "The status of a failed call",
+                    "content": { "application/json": { "schema": status() } },
+                },
+            },
+        },
+    });
+    Ok(OpenApiDocument {
+        document,
+        skipped_methods,
+    })
+}
+
+/// Returns the operation calling the unary `method` of `service`.
+fn operation(generator: &mut Generator, service: &ServiceInfo, method: &MethodInfo) -> Value {
+    let mut message = |name: &str| {
+        let schema = generator.value(FieldType::Message, Some(name), None);
+        json!({ "application/json": { "schema": schema } })
+    };
+    let request = message(&method.request);
+    let response = message(&method.response);
+
+    let mut operation = json!({
+        "operationId": format!("{}.{}", service.full_name(), method.name),
+        "tags": [service.full_name()],
+        "requestBody": { "required": true, "content": request },
+        "responses": {
+            "200": { "description": "The response message", "content": response },
+            "default": { "$ref": "#/components/responses/Error" },
+        },
+    });
+    if method.deprecated || service.deprecated {
+        operation["deprecated"] = json!(true);
+    }
+    describe(&mut operation, method.leading_comments.as_deref());
+    operation
+}
+
+/// Returns the schema of the JSON encoding of a `google.rpc.Status`.
+fn status() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "code": { "type": "integer", "format": "int32" },
+            "message": { "type": "string" },
+            "details": { "type": "array", "items": well_known(WellKnownType::Any) },
+        },
+    })
+}
@@ -0,0 +1,523 @@
+//! Custom options of services and methods, declared with extensions of the options messages,
+//! e.g. `option (acl.required_role) = "admin";`.
+
+use crate::error::ReflectionError;
+use crate::lookup::{self, qualify};
+use crate::message_info::json_name;
+use base64::Engine;
+use prost::encoding::{decode_key, decode_varint, skip_field, DecodeContext, WireType};
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+
+/// The options message extended by the custom options of services.
+pub const SERVICE_OPTIONS: &str = "google.protobuf.ServiceOptions";
+/// The options message extended by the custom options of methods.
+pub const METHOD_OPTIONS: &str = "google.protobuf.MethodOptions";
+
+/// The first field number of the extension range of the options messages.
+const FIRST_EXTENSION: u32 = 1000;
+
+/// The custom options of a service or method, as the raw values of the extension fields set in
+/// its options message, keyed by their field numbers
+///
+/// The standard options, like `deprecated`, are left out. To turn the values into JSON, decode
+/// them with the descriptor of the extension, either with [`decode`](Self::decode) or by
+/// letting the server look up the extensions with
+/// [`ReflectionClient::decode_options`](crate::reflection::ReflectionClient::decode_options).
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::options::{CustomOptions, RawValue, METHOD_OPTIONS};
+/// use prost::encoding::{bytes, int32, string};
+/// use prost_types::field_descriptor_proto::{Label, Type};
+/// use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto};
+/// use serde_json::json;
+///
+/// let field = |name: &str, number, r#type: Type| FieldDescriptorProto {
+///     name: Some(name.to_string()),
+///     number: Some(number),
+///     r#type: Some(r#type.into()),
+///     ..Default::default()
+/// };
+/// let extension = |name: &str, number, r#type: Type| FieldDescriptorProto {
+///     extendee: Some(".google.protobuf.MethodOptions".to_string()),
+///     ..field(name, number, r#type)
+/// };
+/// // message Rule {
+/// //   string role = 1;
+/// //   repeated int32 levels = 2;
+/// // }
+/// // extend google.protobuf.MethodOptions {
+/// //   string required_role = 50001;
+/// //   Rule rule = 50002;
+/// // }
+/// let acl = FileDescriptorProto {
+///     name: Some("acl.proto".to_string()),
+///     package: Some("acl".to_string()),
+///     message_type: vec![DescriptorProto {
+///         name: Some("Rule".to_string()),
+///         field: vec![
+///             field("role", 1, Type::String),
+///             FieldDescriptorProto {
+///                 label: Some(Label::Repeated.into()),
+///                 ..field("levels", 2, Type::Int32)
+///             },
+///         ],
+///         ..Default::default()
+///     }],
+///     extension: vec![
+///         extension("required_role", 50001, Type::String),
+///         FieldDescriptorProto {
+///             type_name: Some(".acl.Rule".to_string()),
+///             ..extension("rule", 50002, Type::Message)
+///         },
+///     ],
+///     ..Default::default()
+/// };
+///
+/// // option (acl.required_role) = "admin";
+/// // option (acl.rule) = { role: "admin", levels: [1, 2] };
+/// let mut rule = Vec::new();
+/// string::encode(1, &"admin".to_string(), &mut rule);
+/// int32::encode_packed(2, &[1, 2], &mut rule);
+/// let mut encoded = Vec::new();
+/// string::encode(50001, &"admin".to_string(), &mut encoded);
+/// bytes::encode(50002, &rule, &mut encoded);
+///
+/// let options = CustomOptions::parse(METHOD_OPTIONS, &encoded)?;
+/// assert_eq!(options.numbers().collect::<Vec<_>>(), [50001, 50002]);
+/// assert_eq!(options.get(50001), [RawValue::LengthDelimited(b"admin".to_vec())]);
+///
+/// let files = [acl.clone()];
+/// assert_eq!(options.decode(&acl.extension[0], &files)?, Some(json!("admin")));
+/// assert_eq!(
+///     options.decode(&acl.extension[1], &files)?,
+///     Some(json!({ "role": "admin", "levels": [1, 2] }))
+/// );
+/// # Ok::<(), grpc_ease::error::ReflectionError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CustomOptions {
+    /// The fully qualified name of the extended options message, e.g.
+    /// `google.protobuf.MethodOptions`
+    pub extendee: String,
+    fields: BTreeMap<u32, Vec<RawValue>>,
+}
+
+/// A value of a field as encoded on the wire, whose meaning depends on the type of the field
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RawValue {
+    /// A varint, used for integers, booleans and enums
+    Varint(u64),
+    /// Eight bytes, used for `fixed64`, `sfixed64` and `double`
+    Fixed64(u64),
+    /// A length-prefixed value, used for strings, bytes, messages and packed repeated fields
+    LengthDelimited(Vec<u8>),
+    /// Four bytes, used for `fixed32`, `sfixed32` and `float`
+    Fixed32(u32),
+}
+
+impl CustomOptions {
+    /// Parses the custom options set in `options`, an encoded options message extending
+    /// `extendee`, like the `options` of a service or method in an encoded file descriptor.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`ReflectionError::Decode`] if `options` is not a valid
+    /// encoded message.
+    pub fn parse(extendee: &str, options: &[u8]) -> Result<Self, ReflectionError> {
+        let mut fields = parse_fields(options)?;
+        fields.retain(|number, _| *number >= FIRST_EXTENSION);
+        Ok(Self {
+            extendee: extendee.to_string(),
+            fields,
+        })
+    }
+
+    /// Returns whether no custom options are set.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns the field numbers of the custom options that are set, in ascending order.
+    pub fn numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.fields.keys().copied()
+    }
+
+    /// Returns the raw values of the custom option numbered `number`, in the order they were
+    /// encoded, or an empty slice if it is not set.
+    pub fn get(&self, number: u32) -> &[RawValue] {
+        self.fields.get(&number).map_or(&[], Vec::as_slice)
+    }
+
+    /// Decodes the value of the custom option declared by `extension`, looking up the message
+    /// and enum types it refers to in `files`, or returns `None` if it is not set.
+    ///
+    /// Values are turned into JSON like in the JSON encoding of protobuf: messages become
+    /// objects keyed by the JSON names of their fields, repeated fields arrays, bytes base64
+    /// strings, and enum values their names, or their numbers if the enum type is not found.
+    /// Unlike the JSON encoding, 64-bit integers are kept as numbers.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - A value is not a valid encoding of its type, as [`ReflectionError::Decode`] or
+    ///   [`ReflectionError::MalformedDescriptor`]
+    /// - A message type is not defined in `files`, as [`ReflectionError::NotFound`]
+    pub fn decode(
+        &self,
+        extension: &FieldDescriptorProto,
+        files: &[FileDescriptorProto],
+    ) -> Result<Option<Value>, ReflectionError> {
+        let values = self.get(extension.number() as u32);
+        if values.is_empty() {
+            return Ok(None);
+        }
+        Types::new(files).field(extension, values).map(Some)
+    }
+}
+
+/// Returns the fully qualified name and the descriptor of the extension of `extendee` with the
+/// field number `number`, declared in one of `files` or in one of their messages.
+pub(crate) fn extension<'a>(
+    files: &'a [FileDescriptorProto],
+    extendee: &str,
+    number: u32,
+) -> Option<(String, &'a FieldDescriptorProto)> {
+    let matches = |extension: &&FieldDescriptorProto| {
+        extension.extendee().trim_start_matches('.') == extendee
+            && extension.number() as u32 == number
+    };
+
+    for file in files {
+        if let Some(extension) = file.extension.iter().find(matches) {
+            return Some((qualify(file.package(), extension.name()), extension));
+        }
+    }
+    lookup::messages(files)
+        .into_iter()
+        .find_map(|(full_name, message)| {
+            let extension = message.extension.iter().find(matches)?;
+            Some((qualify(&full_name, extension.name()), extension))
+        })
+}
+
+/// Returns the custom options of the service `full_name`, or of its method `method`, found in
+/// the encoded file descriptors `files`, or `None` if no file defines the service.
+///
+/// The method is looked up in the first file defining the service, and is `Some(Err(..))`
+/// with the names of the methods of the service if the service does not define it.
+pub(crate) fn find(
+    files: &[Vec<u8>],
+    full_name: &str,
+    method: Option<&str>,
+) -> Result<Option<Result<CustomOptions, Vec<String>>>, ReflectionError> {
+    for file in files {
+        let file: RawFile = prost::Message::decode(&file[..])?;
+        let Some(service) = file
+            .service
+            .iter()
+            .find(|service| qualify(file.package(), service.name()) == full_name)
+        else {
+            continue;
+        };
+
+        let options = match method {
+            None => Ok(CustomOptions::parse(
+                SERVICE_OPTIONS,
+                service.options.as_deref().unwrap_or_default(),
+            )?),
+            Some(method) => match service.method.iter().find(|m| m.name() == method) {
+                Some(method) => Ok(CustomOptions::parse(
+                    METHOD_OPTIONS,
+                    method.options.as_deref().unwrap_or_default(),
+                )?),
+                None => Err(service
+                    .method
+                    .iter()
+                    .map(|method| method.name().to_string())
+                    .collect()),
+            },
+        };
+        return Ok(Some(options));
+    }
+    Ok(None)
+}
+
+/// A file descriptor decoded only as far as needed to find the options of its services and
+/// methods, with the options kept encoded, since decoding them as `ServiceOptions` or
+/// `MethodOptions` drops the custom options
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawFile {
+    #[prost(string, optional, tag = "2")]
+    package: Option<String>,
+    #[prost(message, repeated, tag = "6")]
+    service: Vec<RawService>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawService {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+    #[prost(message, repeated, tag = "2")]
+    method: Vec<RawMethod>,
+    #[prost(bytes = "vec", optional, tag = "3")]
+    options: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct RawMethod {
+    #[prost(string, optional, tag = "1")]
+    name: Option<String>,
+    #[prost(bytes = "vec", optional, tag = "4")]
+    options: Option<Vec<u8>>,
+}
+
+/// The message and enum types values are decoded with, keyed by their fully qualified names
+struct Types<'a> {
+    messages: HashMap<String, &'a DescriptorProto>,
+    enums: HashMap<String, &'a EnumDescriptorProto>,
+}
+
+impl<'a> Types<'a> {
+    fn new(files: &'a [FileDescriptorProto]) -> Self {
+        Self {
+            messages: lookup::messages(files).into_iter().collect(),
+            enums: lookup::enums(files).into_iter().collect(),
+        }
+    }
+
+    /// Decodes the value of `field` from all of its encoded `values`.
+    fn field(
+        &self,
+        field: &FieldDescriptorProto,
+        values: &[RawValue],
+    ) -> Result<Value, ReflectionError> {
+        let repeated = field.label() == Label::Repeated;
+
+        // repeated occurrences of a singular message are merged, just like their fields are
+        // when concatenating their encodings
+        if field.r#type() == Type::Message && !repeated {
+            let mut merged = Vec::new();
+            for value in values {
+                match value {
+                    RawValue::LengthDelimited(bytes) => merged.extend_from_slice(bytes),
+                    value => return Err(mismatch(field, value)),
+                }
+            }
+            return self.message(field.type_name(), &merged);
+        }
+
+        let mut decoded = Vec::new();
+        for value in values {
+            match (value, packed_wire_type(field.r#type())) {
+                (RawValue::LengthDelimited(bytes), Some(wire_type)) => {
+                    for value in unpack(bytes, wire_type)? {
+                        decoded.push(self.value(field, &value)?);
+                    }
+                }
+                (value, _) => decoded.push(self.value(field, value)?),
+            }
+        }
+
+        if repeated {
+            Ok(Value::Array(decoded))
+        } else {
+            // the last occurrence of a singular field wins
+            Ok(decoded.pop().unwrap_or(Value::Null))
+        }
+    }
+
+    /// Decodes a single encoded `value` of `field`.
+    fn value(
+        &self,
+        field: &FieldDescriptorProto,
+        value: &RawValue,
+    ) -> Result<Value, ReflectionError> {
+        let decoded = match (field.r#type(), value) {
+            (Type::Double, RawValue::Fixed64(bits)) => Value::from(f64::from_bits(*bits)),
+            (Type::Float, RawValue::Fixed32(bits)) => Value::from(f32::from_bits(*bits) as f64),
+            (Type::Int64, RawValue::Varint(value)) => Value::from(*value as i64),
+            (Type::Uint64, RawValue::Varint(value)) => Value::from(*value),
+            (Type::Int32, RawValue::Varint(value)) => Value::from(*value as i32),
+            (Type::Uint32, RawValue::Varint(value)) => Value::from(*value as u32),
+            (Type::Sint32, RawValue::Varint(value)) => Value::from(zigzag(*value) as i32),
+            (Type::Sint64, RawValue::Varint(value)) => Value::from(zigzag(*value)),
+            (Type::Fixed64, RawValue::Fixed64(value)) => Value::from(*value),
+            (Type::Fixed32, RawValue::Fixed32(value)) => Value::from(*value),
+            (Type::Sfixed64, RawValue::Fixed64(value)) => Value::from(*value as i64),
+            (Type::Sfixed32, RawValue::Fixed32(value)) => Value::from(*value as i32),
+            (Type::Bool, RawValue::Varint(value)) => Value::from(*value != 0),
+            (Type::String, RawValue::LengthDelimited(bytes)) => {
+                Value::from(String::from_utf8_lossy(bytes))
+            }
+            (Type::Bytes, RawValue::LengthDelimited(bytes)) => {
+                Value::from(base64::engine::general_purpose::STANDARD.encode(bytes))
+            }
+            (Type::Enum, RawValue::Varint(value)) => {
+                let number = *value as i32;
+                self.enums
+                    .get(field.type_name().trim_start_matches('.'))
+                    .and_then(|enumeration| {
+                        enumeration
+                            .value
+                            .iter()
+                            .find(|value| value.number() == number)
+                    })
+                    .map_or(Value::from(number), |value| Value::from(value.name()))
+            }
+            (Type::Message, RawValue::LengthDelimited(bytes)) => {
+                self.message(field.type_name(), bytes)?
+            }
+            (_, value) => return Err(mismatch(field, value)),
+        };
+        Ok(decoded)
+    }
+
+    /// Decodes the encoded message `bytes` of the type `type_name` into an object, leaving out
+    /// the fields it does not declare.
+    fn message(&self, type_name: &str, bytes: &[u8]) -> Result<Value, ReflectionError> {
+        let type_name = type_name.trim_start_matches('.');
+        let message = self
+            .messages
+            .get(type_name)
+            .ok_or_else(|| ReflectionError::NotFound {
+                name: type_name.to_string(),
+            })?;
+
+        let fields = parse_fields(bytes)?;
+        let mut object = Map::new();
+        for field in &message.field {
+            if let Some(values) = fields.get(&(field.number() as u32)) {
+                let name = field
+                    .json_name
+                    .clone()
+                    .unwrap_or_else(|| json_name(field.name()));
+                object.insert(name, self.field(field, values)?);
+            }
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+/// Parses the fields of the encoded message `bytes`, skipping groups.
+fn parse_fields(mut bytes: &[u8]) -> Result<BTreeMap<u32, Vec<RawValue>>, ReflectionError> {
+    let mut fields: BTreeMap<u32, Vec<RawValue>> = BTreeMap::new();
+    while !bytes.is_empty() {
+        let (number, wire_type) = decode_key(&mut bytes)?;
+        let value = match wire_type {
+            WireType::Varint => RawValue::Varint(decode_varint(&mut bytes)?),
+            WireType::SixtyFourBit => {
+                let mut value = 0;
+                prost::encoding::fixed64::merge(
+                    wire_type,
+                    &mut value,
+                    &mut bytes,
+                    DecodeContext::default(),
+                )?;
+                RawValue::Fixed64(value)
+            }
+            WireType::LengthDelimited => {
+                let mut value = Vec::new();
+                prost::encoding::bytes::merge(
+                    wire_type,
+                    &mut value,
+                    &mut bytes,
+                    DecodeContext::default(),
+                )?;
+                RawValue::LengthDelimited(value)
+            }
+            WireType::ThirtyTwoBit => {
+                let mut value = 0;
+                prost::encoding::fixed32::merge(
+                    wire_type,
+                    &mut value,
+                    &mut bytes,
+                    DecodeContext::default(),
+                )?;
+                RawValue::Fixed32(value)
+            }
+            WireType::StartGroup | WireType::EndGroup => {
+                skip_field(wire_type, number, &mut bytes, DecodeContext::default())?;
+                continue;
+            }
+        };
+        fields.entry(number).or_default().push(value);
+    }
+    Ok(fields)
+}
+
+/// Returns the wire type of the elements of packed repeated fields of `field_type`, or `None`
+/// if fields of the type cannot be packed.
+fn packed_wire_type(field_type: Type) -> Option<WireType> {
+    match field_type {
+        Type::Double | Type::Fixed64 | Type::Sfixed64 => Some(WireType::SixtyFourBit),
+        Type::Float | Type::Fixed32 | Type::Sfixed32 => Some(WireType::ThirtyTwoBit),
+        Type::Int64
+        | Type::Uint64
+        | Type::Int32
+        | Type::Uint32
+        | Type::Sint32
+        | Type::Sint64
+        | Type::Bool
+        | Type::Enum => Some(WireType::Varint),
+        Type::String | Type::Bytes | Type::Message | Type::Group => None,
+    }
+}
+
+/// Splits the packed `bytes` into values of `wire_type`.
+fn unpack(mut bytes: &[u8], wire_type: WireType) -> Result<Vec<RawValue>, ReflectionError> {
+    let mut values = Vec::new();
+    while !bytes.is_empty() {
+        let value = match wire_type {
+            WireType::SixtyFourBit => {
+                let mut value = 0;
+                prost::encoding::fixed64::merge(
+                    wire_type,
+                    &mut value,
+                    &mut bytes,
+                    DecodeContext::default(),
+                )?;
+                RawValue::Fixed64(value)
+            }
+            WireType::ThirtyTwoBit => {
+                let mut value = 0;
+                prost::encoding::fixed32::merge(
+                    wire_type,
+                    &mut value,
+                    &mut bytes,
+                    DecodeContext::default(),
+                )?;
+                RawValue::Fixed32(value)
+            }
+            _ => RawValue::Varint(decode_varint(&mut bytes)?),
+        };
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// Decodes the zigzag encoding of `sint32` and `sint64` values.
+fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn mismatch(field: &FieldDescriptorProto, value: &RawValue) -> ReflectionError {
+    let encoding = match value {
+        RawValue::Varint(_) => "a varint",
+        RawValue::Fixed64(_) => "eight bytes",
+        RawValue::LengthDelimited(_) => "a length-delimited value",
+        RawValue::Fixed32(_) => "four bytes",
+    };
+    ReflectionError::MalformedDescriptor(format!(
+        "Field {:?} of type {} is encoded as {}",
+        field.name(),
+        field.r#type().as_str_name(),
+        encoding
+    ))
+}
@@ -0,0 +1,881 @@
+//! Rendering file descriptors as `.proto` source text, like `grpcurl describe` does.
+//!
+//! The rendered source declares the same definitions as the descriptor, so it can be compiled
+//! again, e.g. with protoc, but it is not formatted like the original file. Type names are
+//! written fully qualified, with a leading dot, and only the standard options are included,
+//! since the custom ones are dropped when decoding descriptors. Comments are included if the
+//! descriptor has source code info.
+//!
+//! Files of editions are rendered with an `// unsupported` comment and an unknown edition,
+//! since their descriptors do not record which edition they were written in, so that compiling
+//! the source fails rather than declaring something else.
+
+use crate::comments::{
+    Comments, Source, ENUM_VALUE, FILE_ENUM_TYPE, FILE_EXTENSION, FILE_MESSAGE_TYPE, FILE_PACKAGE,
+    FILE_SERVICE, FILE_SYNTAX, MESSAGE_ENUM_TYPE, MESSAGE_EXTENSION, MESSAGE_FIELD,
+    MESSAGE_NESTED_TYPE, MESSAGE_ONEOF_DECL, SERVICE_METHOD,
+};
+use crate::lookup::qualify;
+use crate::message_info::{is_map_entry, json_name};
+use crate::service_info::Syntax;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    ServiceDescriptorProto,
+};
+use std::collections::HashMap;
+
+/// The highest field number, which `max` stands for in the ranges of messages.
+const MAX_FIELD_NUMBER: i32 = 536_870_911;
+
+/// Renders `file` as `.proto` source text.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::proto_printer::render_file;
+/// use prost_types::field_descriptor_proto::{Label, Type};
+/// use prost_types::source_code_info::Location;
+/// use prost_types::{
+///     DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+///     FileDescriptorProto, FileOptions, MessageOptions, MethodDescriptorProto, MethodOptions,
+///     OneofDescriptorProto, ServiceDescriptorProto, SourceCodeInfo,
+/// };
+///
+/// let field = |name: &str, number, label: Label, r#type: Type| FieldDescriptorProto {
+///     name: Some(name.to_string()),
+///     number: Some(number),
+///     label: Some(label.into()),
+///     r#type: Some(r#type.into()),
+///     ..Default::default()
+/// };
+/// let typed = |name: &str, number, label, r#type, type_name: &str| FieldDescriptorProto {
+///     type_name: Some(type_name.to_string()),
+///     ..field(name, number, label, r#type)
+/// };
+/// let order = DescriptorProto {
+///     name: Some("Order".to_string()),
+///     field: vec![
+///         field("id", 1, Label::Optional, Type::String),
+///         typed("items", 2, Label::Repeated, Type::Message, ".shop.Order.Item"),
+///         typed("labels", 3, Label::Repeated, Type::Message, ".shop.Order.LabelsEntry"),
+///         FieldDescriptorProto {
+///             oneof_index: Some(0),
+///             ..field("card_token", 4, Label::Optional, Type::String)
+///         },
+///         FieldDescriptorProto {
+///             oneof_index: Some(0),
+///             ..field("iban", 5, Label::Optional, Type::String)
+///         },
+///         FieldDescriptorProto {
+///             oneof_index: Some(1),
+///             proto3_optional: Some(true),
+///             ..field("note", 6, Label::Optional, Type::String)
+///         },
+///         typed("status", 7, Label::Optional, Type::Enum, ".shop.Status"),
+///     ],
+///     nested_type: vec![
+///         DescriptorProto {
+///             name: Some("Item".to_string()),
+///             field: vec![field("quantity", 1, Label::Optional, Type::Uint32)],
+///             ..Default::default()
+///         },
+///         DescriptorProto {
+///             name: Some("LabelsEntry".to_string()),
+///             field: vec![
+///                 field("key", 1, Label::Optional, Type::String),
+///                 field("value", 2, Label::Optional, Type::String),
+///             ],
+///             options: Some(MessageOptions {
+///                 map_entry: Some(true),
+///                 ..Default::default()
+///             }),
+///             ..Default::default()
+///         },
+///     ],
+///     oneof_decl: vec![
+///         OneofDescriptorProto {
+///             name: Some("payment".to_string()),
+///             ..Default::default()
+///         },
+///         OneofDescriptorProto {
+///             name: Some("_note".to_string()),
+///             ..Default::default()
+///         },
+///     ],
+///     reserved_range: vec![prost_types::descriptor_proto::ReservedRange {
+///         start: Some(10),
+///         end: Some(13),
+///     }],
+///     reserved_name: vec!["legacy".to_string()],
+///     ..Default::default()
+/// };
+/// let status = EnumDescriptorProto {
+///     name: Some("Status".to_string()),
+///     value: ["STATUS_UNSPECIFIED", "STATUS_OPEN"]
+///         .iter()
+///         .zip(0..)
+///         .map(|(name, number)| EnumValueDescriptorProto {
+///             name: Some(name.to_string()),
+///             number: Some(number),
+///             ..Default::default()
+///         })
+///         .collect(),
+///     ..Default::default()
+/// };
+/// let orders = ServiceDescriptorProto {
+///     name: Some("Orders".to_string()),
+///     method: vec![
+///         MethodDescriptorProto {
+///             name: Some("Place".to_string()),
+///             input_type: Some(".shop.Order".to_string()),
+///             output_type: Some(".google.protobuf.Empty".to_string()),
+///             ..Default::default()
+///         },
+///         MethodDescriptorProto {
+///             name: Some("Watch".to_string()),
+///             input_type: Some(".google.protobuf.Empty".to_string()),
+///             output_type: Some(".shop.Order".to_string()),
+///             server_streaming: Some(true),
+///             options: Some(MethodOptions {
+///                 deprecated: Some(true),
+///                 ..Default::default()
+///             }),
+///             ..Default::default()
+///         },
+///     ],
+///     ..Default::default()
+/// };
+/// let comment = |path: &[i32], text: &str| Location {
+///     path: path.to_vec(),
+///     leading_comments: Some(text.to_string()),
+///     ..Default::default()
+/// };
+/// let shop = FileDescriptorProto {
+///     name: Some("shop.proto".to_string()),
+///     package: Some("shop".to_string()),
+///     dependency: vec!["google/protobuf/empty.proto".to_string()],
+///     syntax: Some("proto3".to_string()),
+///     options: Some(FileOptions {
+///         go_package: Some("example.com/shop".to_string()),
+///         ..Default::default()
+///     }),
+///     message_type: vec![order],
+///     enum_type: vec![status],
+///     service: vec![orders],
+///     source_code_info: Some(SourceCodeInfo {
+///         location: vec![
+///             comment(&[4, 0], " An order of a customer.\n"),
+///             comment(&[6, 0, 2, 1], " Streams the orders\n as they are placed.\n"),
+///         ],
+///     }),
+///     ..Default::default()
+/// };
+///
+/// let source = render_file(&shop);
+/// assert_eq!(
+///     source,
+///     r#"syntax = "proto3";
+///
+/// package shop;
+///
+/// import "google/protobuf/empty.proto";
+///
+/// option go_package = "example.com/shop";
+///
+/// // An order of a customer.
+/// message Order {
+///   string id = 1;
+///   repeated .shop.Order.Item items = 2;
+///   map<string, string> labels = 3;
+///   oneof payment {
+///     string card_token = 4;
+///     string iban = 5;
+///   }
+///   optional string note = 6;
+///   .shop.Status status = 7;
+///
+///   message Item {
+///     uint32 quantity = 1;
+///   }
+///
+///   reserved 10 to 12;
+///   reserved "legacy";
+/// }
+///
+/// enum Status {
+///   STATUS_UNSPECIFIED = 0;
+///   STATUS_OPEN = 1;
+/// }
+///
+/// service Orders {
+///   rpc Place(.shop.Order) returns (.google.protobuf.Empty);
+///   // Streams the orders
+///   // as they are placed.
+///   rpc Watch(.google.protobuf.Empty) returns (stream .shop.Order) {
+///     option deprecated = true;
+///   }
+/// }
+/// "#
+/// );
+///
+/// // the source parses again into the same definitions
+/// let parsed = protox_parse::parse("shop.proto", &source).unwrap();
+/// assert_eq!(parsed.message_type[0].field.len(), 7);
+/// assert_eq!(parsed.message_type[0].nested_type.len(), 2);
+/// assert_eq!(parsed.service[0].method[1].server_streaming, Some(true));
+/// ```
+pub fn render_file(file: &FileDescriptorProto) -> String {
+    let mut printer = Printer {
+        syntax: Syntax::of(file),
+        out: String::new(),
+        depth: 0,
+    };
+    printer.file(file);
+    printer.out
+}
+
+struct Printer {
+    syntax: Syntax,
+    out: String,
+    /// The nesting depth of the current line
+    depth: usize,
+}
+
+impl Printer {
+    fn file(&mut self, file: &FileDescriptorProto) {
+        let root = Source::file(file);
+
+        let syntax = match self.syntax {
+            Syntax::Proto2 => "syntax = \"proto2\";",
+            Syntax::Proto3 => "syntax = \"proto3\";",
+            // the edition is not available in the descriptors of prost-types, and guessing one
+            // would compile the source to different definitions
+            Syntax::Editions => {
+                self.line("// unsupported: the edition of the file is not in its descriptor");
+                "edition = \"unknown\";"
+            }
+        };
+        let comments = self.leading(&root.field(FILE_SYNTAX));
+        self.statement(syntax, comments);
+
+        if !file.package().is_empty() {
+            self.separate();
+            let comments = self.leading(&root.field(FILE_PACKAGE));
+            self.statement(&format!("package {};", file.package()), comments);
+        }
+
+        if !file.dependency.is_empty() {
+            self.separate();
+            for (index, dependency) in file.dependency.iter().enumerate() {
+                let index = index as i32;
+                let kind = if file.public_dependency.contains(&index) {
+                    "public "
+                } else if file.weak_dependency.contains(&index) {
+                    "weak "
+                } else {
+                    ""
+                };
+                self.line(&format!("import {}{};", kind, quote(dependency)));
+            }
+        }
+
+        let options = file_options(file);
+        if !options.is_empty() {
+            self.separate();
+            for option in options {
+                self.line(&format!("option {};", option));
+            }
+        }
+
+        let package = file.package();
+        let groups = group_types(&file.extension, package, &file.message_type);
+        for (index, message) in file.message_type.iter().enumerate() {
+            if groups.contains(&index) {
+                continue;
+            }
+            self.separate();
+            let full_name = qualify(package, message.name());
+            self.message(message, &full_name, &root.child(FILE_MESSAGE_TYPE, index));
+        }
+        for (index, enumeration) in file.enum_type.iter().enumerate() {
+            self.separate();
+            self.enumeration(enumeration, &root.child(FILE_ENUM_TYPE, index));
+        }
+        self.extensions(
+            &file.extension,
+            (package, &file.message_type),
+            &root,
+            (FILE_EXTENSION, FILE_MESSAGE_TYPE),
+        );
+        for (index, service) in file.service.iter().enumerate() {
+            self.separate();
+            self.service(service, &root.child(FILE_SERVICE, index));
+        }
+    }
+
+    fn message(&mut self, message: &DescriptorProto, full_name: &str, source: &Source) {
+        let comments = self.leading(source);
+        self.open(&format!("message {}", message.name()), comments);
+        self.message_body(message, full_name, source);
+        self.close();
+    }
+
+    fn message_body(&mut self, message: &DescriptorProto, full_name: &str, source: &Source) {
+        if let Some(options) = &message.options {
+            if options.deprecated() {
+                self.line("option deprecated = true;");
+            }
+        }
+
+        // nested messages that are declared by map fields and groups rather than on their own
+        let nested: HashMap<String, (usize, &DescriptorProto)> = message
+            .nested_type
+            .iter()
+            .enumerate()
+            .map(|(index, nested)| (qualify(full_name, nested.name()), (index, nested)))
+            .collect();
+        let mut implicit = group_types(&message.extension, full_name, &message.nested_type);
+        let mut printed_oneofs = Vec::new();
+
+        let nested_of = |field: &FieldDescriptorProto| {
+            nested
+                .get(field.type_name().trim_start_matches('.'))
+                .filter(|(_, nested)| field.r#type() == Type::Group || is_map_entry(nested))
+                .map(|(index, nested)| (*nested, source.child(MESSAGE_NESTED_TYPE, *index)))
+        };
+
+        for (index, field) in message.field.iter().enumerate() {
+            if let Some((nested_index, _)) = nested.get(field.type_name().trim_start_matches('.')) {
+                if nested_of(field).is_some() {
+                    implicit.push(*nested_index);
+                }
+            }
+
+            match field.oneof_index {
+                Some(oneof) if !field.proto3_optional() => {
+                    if printed_oneofs.contains(&oneof) {
+                        continue;
+                    }
+                    printed_oneofs.push(oneof);
+                    let oneof_source = source.child(MESSAGE_ONEOF_DECL, oneof as usize);
+                    let comments = self.leading(&oneof_source);
+                    let name = message
+                        .oneof_decl
+                        .get(oneof as usize)
+                        .map_or("", |oneof| oneof.name());
+                    self.open(&format!("oneof {}", name), comments);
+                    for (index, field) in message.field.iter().enumerate() {
+                        if field.oneof_index == Some(oneof) {
+                            self.field(
+                                field,
+                                nested_of(field),
+                                full_name,
+                                &source.child(MESSAGE_FIELD, index),
+                                false,
+                            );
+                        }
+                    }
+                    self.close();
+                }
+                _ => self.field(
+                    field,
+                    nested_of(field),
+                    full_name,
+                    &source.child(MESSAGE_FIELD, index),
+                    true,
+                ),
+            }
+        }
+
+        for (index, nested) in message.nested_type.iter().enumerate() {
+            if !implicit.contains(&index) {
+                self.separate();
+                let nested_name = qualify(full_name, nested.name());
+                self.message(
+                    nested,
+                    &nested_name,
+                    &source.child(MESSAGE_NESTED_TYPE, index),
+                );
+            }
+        }
+        for (index, enumeration) in message.enum_type.iter().enumerate() {
+            self.separate();
+            self.enumeration(enumeration, &source.child(MESSAGE_ENUM_TYPE, index));
+        }
+        self.extensions(
+            &message.extension,
+            (full_name, &message.nested_type),
+            source,
+            (MESSAGE_EXTENSION, MESSAGE_NESTED_TYPE),
+        );
+
+        if !message.extension_range.is_empty() {
+            self.separate();
+            let ranges: Vec<String> = message
+                .extension_range
+                .iter()
+                .map(|range| range_text(range.start(), range.end() - 1, MAX_FIELD_NUMBER))
+                .collect();
+            self.line(&format!("extensions {};", ranges.join(", ")));
+        }
+        if !message.reserved_range.is_empty() || !message.reserved_name.is_empty() {
+            self.separate();
+        }
+        if !message.reserved_range.is_empty() {
+            let ranges: Vec<String> = message
+                .reserved_range
+                .iter()
+                .map(|range| range_text(range.start(), range.end() - 1, MAX_FIELD_NUMBER))
+                .collect();
+            self.line(&format!("reserved {};", ranges.join(", ")));
+        }
+        if !message.reserved_name.is_empty() {
+            let names: Vec<String> = message
+                .reserved_name
+                .iter()
+                .map(|name| quote(name))
+                .collect();
+            self.line(&format!("reserved {};", names.join(", ")));
+        }
+    }
+
+    /// Prints `field` of the message `scope`, with its label unless it is part of a oneof.
+    ///
+    /// The type of map fields and groups is passed as `nested`, along with its source, since
+    /// it is declared by the field itself.
+    fn field(
+        &mut self,
+        field: &FieldDescriptorProto,
+        nested: Option<(&DescriptorProto, Source)>,
+        scope: &str,
+        source: &Source,
+        labeled: bool,
+    ) {
+        // protox records the comments of groups on their types rather than their fields
+        let comments = match &nested {
+            Some((_, group_source))
+                if field.r#type() == Type::Group && source.comments().leading.is_none() =>
+            {
+                self.leading(group_source)
+            }
+            _ => self.leading(source),
+        };
+        let map_entry = nested
+            .as_ref()
+            .map(|(nested, _)| *nested)
+            .filter(|nested| is_map_entry(nested));
+
+        let label = match (field.label(), self.syntax) {
+            _ if !labeled || map_entry.is_some() => "",
+            (Label::Repeated, _) => "repeated ",
+            (Label::Required, _) => "required ",
+            (Label::Optional, Syntax::Proto3) if field.proto3_optional() => "optional ",
+            (Label::Optional, Syntax::Proto3 | Syntax::Editions) => "",
+            (Label::Optional, Syntax::Proto2) => "optional ",
+        };
+        let field_type = match map_entry {
+            Some(entry) => {
+                let entry_type = |number| {
+                    entry
+                        .field
+                        .iter()
+                        .find(|field| field.number() == number)
+                        .map_or_else(String::new, type_text)
+                };
+                format!("map<{}, {}>", entry_type(1), entry_type(2))
+            }
+            None => type_text(field),
+        };
+        let options = field_options(field, self.syntax);
+        let options = if options.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", options.join(", "))
+        };
+
+        match nested.filter(|_| field.r#type() == Type::Group) {
+            Some((group, group_source)) => {
+                let header = format!(
+                    "{}group {} = {}{}",
+                    label,
+                    group.name(),
+                    field.number(),
+                    options
+                );
+                self.open(&header, comments);
+                self.message_body(group, &qualify(scope, group.name()), &group_source);
+                self.close();
+            }
+            None => {
+                let statement = format!(
+                    "{}{} {} = {}{};",
+                    label,
+                    field_type,
+                    field.name(),
+                    field.number(),
+                    options
+                );
+                self.statement(&statement, comments);
+            }
+        }
+    }
+
+    /// Prints the `extensions` declared in the scope `source`, grouped by the message they
+    /// extend.
+    ///
+    /// The name of the scope is passed along with its messages, among which are the types of
+    /// groups, and the comments of the extensions and messages are found under the field
+    /// numbers in `fields`.
+    fn extensions(
+        &mut self,
+        extensions: &[FieldDescriptorProto],
+        (scope, messages): (&str, &[DescriptorProto]),
+        source: &Source,
+        (field, message_field): (i32, i32),
+    ) {
+        let mut extendees: Vec<&str> = Vec::new();
+        for extension in extensions {
+            if !extendees.contains(&extension.extendee()) {
+                extendees.push(extension.extendee());
+            }
+        }
+
+        for extendee in extendees {
+            self.separate();
+            self.open(&format!("extend {}", extendee), Comments::default());
+            for (index, extension) in extensions.iter().enumerate() {
+                if extension.extendee() != extendee {
+                    continue;
+                }
+                let group = messages
+                    .iter()
+                    .enumerate()
+                    .filter(|_| extension.r#type() == Type::Group)
+                    .find(|(_, message)| {
+                        qualify(scope, message.name())
+                            == extension.type_name().trim_start_matches('.')
+                    })
+                    .map(|(index, message)| (message, source.child(message_field, index)));
+                self.field(extension, group, scope, &source.child(field, index), true);
+            }
+            self.close();
+        }
+    }
+
+    fn enumeration(&mut self, enumeration: &EnumDescriptorProto, source: &Source) {
+        let comments = self.leading(source);
+        self.open(&format!("enum {}", enumeration.name()), comments);
+        if let Some(options) = &enumeration.options {
+            if options.allow_alias() {
+                self.line("option allow_alias = true;");
+            }
+            if options.deprecated() {
+                self.line("option deprecated = true;");
+            }
+        }
+
+        for (index, value) in enumeration.value.iter().enumerate() {
+            let comments = self.leading(&source.child(ENUM_VALUE, index));
+            let deprecated = value
+                .options
+                .as_ref()
+                .is_some_and(|options| options.deprecated());
+            let options = if deprecated {
+                " [deprecated = true]"
+            } else {
+                ""
+            };
+            let statement = format!("{} = {}{};", value.name(), value.number(), options);
+            self.statement(&statement, comments);
+        }
+
+        if !enumeration.reserved_range.is_empty() || !enumeration.reserved_name.is_empty() {
+            self.separate();
+        }
+        if !enumeration.reserved_range.is_empty() {
+            // unlike those of messages, the ranges of enums include their end
+            let ranges: Vec<String> = enumeration
+                .reserved_range
+                .iter()
+                .map(|range| range_text(range.start(), range.end(), i32::MAX))
+                .collect();
+            self.line(&format!("reserved {};", ranges.join(", ")));
+        }
+        if !enumeration.reserved_name.is_empty() {
+            let names: Vec<String> = enumeration
+                .reserved_name
+                .iter()
+                .map(|name| quote(name))
+                .collect();
+            self.line(&format!("reserved {};", names.join(", ")));
+        }
+        self.close();
+    }
+
+    fn service(&mut self, service: &ServiceDescriptorProto, source: &Source) {
+        let comments = self.leading(source);
+        self.open(&format!("service {}", service.name()), comments);
+        if service
+            .options
+            .as_ref()
+            .is_some_and(|options| options.deprecated())
+        {
+            self.line("option deprecated = true;");
+        }
+
+        for (index, method) in service.method.iter().enumerate() {
+            let comments = self.leading(&source.child(SERVICE_METHOD, index));
+            let stream = |streaming| if streaming { "stream " } else { "" };
+            let signature = format!(
+                "rpc {}({}{}) returns ({}{})",
+                method.name(),
+                stream(method.client_streaming()),
+                method.input_type(),
+                stream(method.server_streaming()),
+                method.output_type()
+            );
+
+            let mut options = Vec::new();
+            if let Some(method_options) = &method.options {
+                if method_options.deprecated() {
+                    options.push("option deprecated = true;".to_string());
+                }
+                if method_options.idempotency_level.is_some() {
+                    options.push(format!(
+                        "option idempotency_level = {};",
+                        method_options.idempotency_level().as_str_name()
+                    ));
+                }
+            }
+
+            if options.is_empty() {
+                self.statement(&format!("{};", signature), comments);
+            } else {
+                self.open(&signature, comments);
+                for option in options {
+                    self.line(&option);
+                }
+                self.close();
+            }
+        }
+        self.close();
+    }
+
+    /// Prints the leading comments of the definition at `source` and returns its comments,
+    /// whose trailing ones are printed along with the definition.
+    fn leading(&mut self, source: &Source) -> Comments {
+        let comments = source.comments();
+        if let Some(leading) = &comments.leading {
+            for line in comment_lines(leading) {
+                self.line(&format!("//{}", line));
+            }
+        }
+        comments
+    }
+
+    /// Prints the single line `statement` followed by its trailing comments.
+    fn statement(&mut self, statement: &str, comments: Comments) {
+        match comments.trailing.as_deref().map(comment_lines) {
+            Some(lines) if lines.len() == 1 => {
+                self.line(&format!("{} //{}", statement, lines[0]));
+            }
+            Some(lines) => {
+                self.line(statement);
+                for line in lines {
+                    self.line(&format!("//{}", line));
+                }
+            }
+            None => self.line(statement),
+        }
+    }
+
+    /// Opens the block `header`, followed by its trailing comments.
+    fn open(&mut self, header: &str, comments: Comments) {
+        self.statement(&format!("{} {{", header), comments);
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    /// Separates the following declaration from the previous one with an empty line, unless
+    /// it is the first one of a block.
+    fn separate(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with("{\n") && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+}
+
+/// Returns the standard options set in `file`, as `name = value`.
+fn file_options(file: &FileDescriptorProto) -> Vec<String> {
+    let Some(options) = &file.options else {
+        return Vec::new();
+    };
+
+    let strings = [
+        ("java_package", &options.java_package),
+        ("java_outer_classname", &options.java_outer_classname),
+        ("go_package", &options.go_package),
+        ("objc_class_prefix", &options.objc_class_prefix),
+        ("csharp_namespace", &options.csharp_namespace),
+        ("swift_prefix", &options.swift_prefix),
+        ("php_class_prefix", &options.php_class_prefix),
+        ("php_namespace", &options.php_namespace),
+        ("php_metadata_namespace", &options.php_metadata_namespace),
+        ("ruby_package", &options.ruby_package),
+    ];
+    let bools = [
+        ("java_multiple_files", options.java_multiple_files),
+        ("java_string_check_utf8", options.java_string_check_utf8),
+        ("cc_generic_services", options.cc_generic_services),
+        ("java_generic_services", options.java_generic_services),
+        ("py_generic_services", options.py_generic_services),
+        ("cc_enable_arenas", options.cc_enable_arenas),
+        ("deprecated", options.deprecated),
+    ];
+
+    let mut rendered: Vec<String> = strings
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("{} = {}", name, quote(value.as_ref()?))))
+        .collect();
+    if options.optimize_for.is_some() {
+        rendered.push(format!(
+            "optimize_for = {}",
+            options.optimize_for().as_str_name()
+        ));
+    }
+    rendered.extend(
+        bools
+            .into_iter()
+            .filter_map(|(name, value)| Some(format!("{} = {}", name, value?))),
+    );
+    rendered
+}
+
+/// Returns the options of `field` of a file of the given `syntax`, as `name = value`.
+fn field_options(field: &FieldDescriptorProto, syntax: Syntax) -> Vec<String> {
+    let mut rendered = Vec::new();
+    if let Some(default) = &field.default_value {
+        let default = match field.r#type() {
+            Type::String => quote(default),
+            // protoc already escapes the default values of bytes fields
+            Type::Bytes => format!("\"{}\"", default),
+            _ => default.clone(),
+        };
+        rendered.push(format!("default = {}", default));
+    }
+    // protoc fills in the JSON name of every field, which only needs to be declared if it
+    // differs from the derived one
+    if let Some(name) = &field.json_name {
+        if *name != json_name(field.name()) && field.extendee.is_none() {
+            rendered.push(format!("json_name = {}", quote(name)));
+        }
+    }
+    if let Some(options) = &field.options {
+        if let Some(packed) = options.packed {
+            // proto3 packs repeated scalars by default
+            if !(packed && syntax == Syntax::Proto3) {
+                rendered.push(format!("packed = {}", packed));
+            }
+        }
+        if options.lazy() {
+            rendered.push("lazy = true".to_string());
+        }
+        if options.deprecated() {
+            rendered.push("deprecated = true".to_string());
+        }
+    }
+    rendered
+}
+
+/// Returns the type of `field` as written in a field declaration.
+fn type_text(field: &FieldDescriptorProto) -> String {
+    let keyword = match field.r#type() {
+        Type::Double => "double",
+        Type::Float => "float",
+        Type::Int64 => "int64",
+        Type::Uint64 => "uint64",
+        Type::Int32 => "int32",
+        Type::Fixed64 => "fixed64",
+        Type::Fixed32 => "fixed32",
+        Type::Bool => "bool",
+        Type::String => "string",
+        Type::Bytes => "bytes",
+        Type::Uint32 => "uint32",
+        Type::Sfixed32 => "sfixed32",
+        Type::Sfixed64 => "sfixed64",
+        Type::Sint32 => "sint32",
+        Type::Sint64 => "sint64",
+        Type::Group | Type::Message | Type::Enum => field.type_name(),
+    };
+    keyword.to_string()
+}
+
+/// Returns the indexes of the `messages` declared in `scope` that are the types of groups
+/// among `fields`, which are declared along with the groups rather than on their own.
+fn group_types(
+    fields: &[FieldDescriptorProto],
+    scope: &str,
+    messages: &[DescriptorProto],
+) -> Vec<usize> {
+    let groups: Vec<&str> = fields
+        .iter()
+        .filter(|field| field.r#type() == Type::Group)
+        .map(|field| field.type_name().trim_start_matches('.'))
+        .collect();
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| groups.contains(&qualify(scope, message.name()).as_str()))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Returns the range from `start` to `end`, both included, as written in `reserved` and
+/// `extensions` declarations, where `max` stands for the number `max`.
+fn range_text(start: i32, end: i32, max: i32) -> String {
+    if start == end {
+        start.to_string()
+    } else if end >= max {
+        format!("{} to max", start)
+    } else {
+        format!("{} to {}", start, end)
+    }
+}
+
+/// Returns `text` as a string literal.
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the lines of a comment as recorded by protoc, which ends with a line break.
+fn comment_lines(comment: &str) -> Vec<&str> {
+    comment
+        .strip_suffix('\n')
+        .unwrap_or(comment)
+        .split('\n')
+        .collect()
+}
@@ -0,0 +1,225 @@
+//! Tunneling connections through HTTP proxies using `CONNECT` requests.
+
+use crate::error::ReflectionError;
+use base64::Engine;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tonic::transport::Uri;
+use tracing::debug;
+
+/// The maximum size of the proxy's response to the `CONNECT` request.
+const MAX_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// An HTTP proxy connections are tunneled through.
+#[derive(Debug, Clone)]
+pub(crate) struct Proxy {
+    /// The `host:port` of the proxy
+    authority: String,
+    /// The value of the `Proxy-Authorization` header, if the proxy URL contains credentials
+    authorization: Option<String>,
+}
+
+impl Proxy {
+    /// Parses a proxy URL of the form `http://[user:password@]host[:port]`.
+    pub(crate) fn parse(url: &str) -> Result<Self, ReflectionError> {
+        let invalid = |reason: &str| ReflectionError::InvalidProxy {
+            proxy: url.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let uri = url.parse::<Uri>().map_err(|_| invalid("not a valid URI"))?;
+        match uri.scheme_str() {
+            Some("http") | None => {}
+            Some(_) => return Err(invalid("only http:// proxies are supported")),
+        }
+
+        let authority = uri.authority().ok_or_else(|| invalid("missing host"))?;
+        let (credentials, host) = match authority.as_str().rsplit_once('@') {
+            Some((credentials, host)) => (Some(credentials), host),
+            None => (None, authority.as_str()),
+        };
+        let authority = match authority.port_u16() {
+            Some(_) => host.to_string(),
+            None => format!("{}:80", host),
+        };
+
+        let authorization = credentials.map(|credentials| {
+            let credentials = percent_decode(credentials);
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            )
+        });
+
+        Ok(Self {
+            authority,
+            authorization,
+        })
+    }
+
+    /// Looks up the proxy to use for `target` from the `HTTPS_PROXY`/`HTTP_PROXY` environment
+    /// variables (or their lowercase variants), honoring `NO_PROXY`.
+    pub(crate) fn from_env(target: &Uri) -> Option<Result<Self, ReflectionError>> {
+        let names: &[&str] = match target.scheme_str() {
+            Some("https") => &["HTTPS_PROXY", "https_proxy"],
+            _ => &["HTTP_PROXY", "http_proxy"],
+        };
+        let url = names
+            .iter()
+            .find_map(|name| std::env::var(name).ok())
+            .filter(|url| !url.is_empty())?;
+
+        let no_proxy = std::env::var("NO_PROXY")
+            .or_else(|_| std::env::var("no_proxy"))
+            .unwrap_or_default();
+        if is_excluded(target.host().unwrap_or_default(), &no_proxy) {
+            debug!("not using a proxy for {} due to NO_PROXY", target);
+            return None;
+        }
+
+        Some(Self::parse(&url))
+    }
+
+    /// Opens a TCP connection to the proxy and establishes a tunnel to the host of `target`.
+    pub(crate) async fn tunnel(&self, target: &Uri) -> io::Result<TcpStream> {
+        let host = target
+            .host()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing host"))?;
+        let port = target.port_u16().unwrap_or(match target.scheme_str() {
+            Some("https") => 443,
+            _ => 80,
+        });
+
+        debug!(
+            "tunneling to {}:{} through proxy {}",
+            host, port, self.authority
+        );
+        let mut stream = TcpStream::connect(&self.authority).await?;
+        stream.set_nodelay(true)?;
+
+        let mut request = format!(
+            "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+            host = host,
+            port = port
+        );
+        if let Some(authorization) = &self.authorization {
+            request.push_str(&format!("Proxy-Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // read byte by byte, so nothing the server sends through the tunnel is consumed
+        let mut response = Vec::new();
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_RESPONSE_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "proxy response too large",
+                ));
+            }
+            response.push(stream.read_u8().await?);
+        }
+
+        let status_line = String::from_utf8_lossy(&response);
+        let status_line = status_line.lines().next().unwrap_or_default();
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') => Ok(stream),
+            _ => Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("proxy refused tunnel: {}", status_line),
+            )),
+        }
+    }
+}
+
+/// Opens a TCP connection to the host of `target`, tunneled through `proxy` if one is set.
+#[cfg(feature = "tls")]
+pub(crate) async fn connect(target: &Uri, proxy: Option<&Proxy>) -> io::Result<TcpStream> {
+    if let Some(proxy) = proxy {
+        return proxy.tunnel(target).await;
+    }
+
+    let host = target
+        .host()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = target.port_u16().unwrap_or(match target.scheme_str() {
+        Some("https") => 443,
+        _ => 80,
+    });
+
+    let stream = TcpStream::connect((host, port)).await?;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+/// Connector tunneling every connection through an HTTP proxy.
+#[derive(Debug, Clone)]
+pub(crate) struct ProxyConnector(pub(crate) Proxy);
+
+impl tower::Service<Uri> for ProxyConnector {
+    type Response = TcpStream;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let proxy = self.0.clone();
+        Box::pin(async move { proxy.tunnel(&uri).await })
+    }
+}
+
+/// Returns whether `host` matches an entry of the comma separated `no_proxy` list.
+///
+/// Entries match the host itself and all of its subdomains, with or without a leading dot,
+/// and `*` matches every host.
+fn is_excluded(host: &str, no_proxy: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+}
+
+/// Decodes `%XX` escapes in the credentials of a proxy URL.
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    decoded
+}
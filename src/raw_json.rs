@@ -0,0 +1,282 @@
+//! The proto3 JSON mapping without the special cases of the well-known types, as used by
+//! [`JsonMapping::Raw`](crate::invoke::JsonMapping::Raw).
+//!
+//! Every message is mapped to an object of its fields, so a `google.protobuf.Timestamp` is
+//! `{"seconds": "1714557600", "nanos": 5}` instead of `"2024-05-01T10:00:00.000000005Z"`.
+//! Scalars, enums, lists and maps are mapped like the canonical encoding does.
+
+use base64::Engine;
+use prost_reflect::{DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor};
+use serde::de::Error as _;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+
+/// Converts `message` to JSON, field by field.
+///
+/// Fields that are not set are left out, and fields are named by their JSON names.
+pub(crate) fn to_value(message: &DynamicMessage) -> Value {
+    let fields = message
+        .fields()
+        .map(|(field, value)| {
+            let value = value_to_json(&field.kind(), value);
+            (field.json_name().to_string(), value)
+        })
+        .collect::<Map<_, _>>();
+    Value::Object(fields)
+}
+
+/// Converts a JSON object of the fields of a message of the type `descriptor` to the message.
+///
+/// Fields are looked up by their JSON names or the names in the proto file, and `null`
+/// leaves a field unset.
+pub(crate) fn from_value(
+    descriptor: MessageDescriptor,
+    value: &Value,
+) -> Result<DynamicMessage, serde_json::Error> {
+    let Value::Object(fields) = value else {
+        return Err(invalid("an object", value));
+    };
+
+    let mut message = DynamicMessage::new(descriptor.clone());
+    for (name, value) in fields {
+        let field = descriptor
+            .get_field_by_json_name(name)
+            .or_else(|| descriptor.get_field_by_name(name))
+            .ok_or_else(|| {
+                serde_json::Error::custom(format!(
+                    "unknown field `{}` of message {}",
+                    name,
+                    descriptor.full_name()
+                ))
+            })?;
+        if value.is_null() {
+            continue;
+        }
+        let value = field_from_json(&field, value)?;
+        message.set_field(&field, value);
+    }
+    Ok(message)
+}
+
+/// Converts the `value` of a field of the `kind`, which is the kind of the elements of lists
+/// and the entry message of maps.
+fn value_to_json(kind: &Kind, value: &prost_reflect::Value) -> Value {
+    use prost_reflect::Value as Proto;
+
+    match value {
+        Proto::Bool(value) => Value::Bool(*value),
+        Proto::I32(value) => Value::from(*value),
+        Proto::U32(value) => Value::from(*value),
+        // 64-bit integers are strings since JavaScript numbers cannot hold them
+        Proto::I64(value) => Value::String(value.to_string()),
+        Proto::U64(value) => Value::String(value.to_string()),
+        Proto::F32(value) => float_to_json(f64::from(*value)),
+        Proto::F64(value) => float_to_json(*value),
+        Proto::String(value) => Value::String(value.clone()),
+        Proto::Bytes(value) => {
+            Value::String(base64::engine::general_purpose::STANDARD.encode(value))
+        }
+        Proto::EnumNumber(number) => match kind {
+            Kind::Enum(descriptor) => match descriptor.get_value(*number) {
+                Some(value) => Value::String(value.name().to_string()),
+                // unknown values are kept as numbers
+                None => Value::from(*number),
+            },
+            _ => Value::from(*number),
+        },
+        Proto::Message(message) => to_value(message),
+        Proto::List(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| value_to_json(kind, value))
+                .collect(),
+        ),
+        Proto::Map(entries) => {
+            let value_kind = match kind {
+                Kind::Message(entry) => entry.map_entry_value_field().kind(),
+                _ => kind.clone(),
+            };
+            Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (map_key_to_string(key), value_to_json(&value_kind, value)))
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn float_to_json(value: f64) -> Value {
+    match Number::from_f64(value) {
+        Some(number) => Value::Number(number),
+        None if value.is_nan() => Value::String("NaN".to_string()),
+        None if value > 0.0 => Value::String("Infinity".to_string()),
+        None => Value::String("-Infinity".to_string()),
+    }
+}
+
+fn map_key_to_string(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(key) => key.to_string(),
+        MapKey::I32(key) => key.to_string(),
+        MapKey::I64(key) => key.to_string(),
+        MapKey::U32(key) => key.to_string(),
+        MapKey::U64(key) => key.to_string(),
+        MapKey::String(key) => key.clone(),
+    }
+}
+
+fn field_from_json(
+    field: &FieldDescriptor,
+    value: &Value,
+) -> Result<prost_reflect::Value, serde_json::Error> {
+    if field.is_map() {
+        let Value::Object(entries) = value else {
+            return Err(invalid("an object", value));
+        };
+        let Kind::Message(entry) = field.kind() else {
+            unreachable!("map fields are of map entry messages")
+        };
+        let key_kind = entry.map_entry_key_field().kind();
+        let value_kind = entry.map_entry_value_field().kind();
+        let entries = entries
+            .iter()
+            .map(|(key, value)| {
+                Ok((
+                    map_key_from_string(&key_kind, key)?,
+                    kind_from_json(&value_kind, value)?,
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, serde_json::Error>>()?;
+        Ok(prost_reflect::Value::Map(entries))
+    } else if field.is_list() {
+        let Value::Array(values) = value else {
+            return Err(invalid("an array", value));
+        };
+        let kind = field.kind();
+        let values = values
+            .iter()
+            .map(|value| kind_from_json(&kind, value))
+            .collect::<Result<_, _>>()?;
+        Ok(prost_reflect::Value::List(values))
+    } else {
+        kind_from_json(&field.kind(), value)
+    }
+}
+
+fn kind_from_json(kind: &Kind, value: &Value) -> Result<prost_reflect::Value, serde_json::Error> {
+    use prost_reflect::Value as Proto;
+
+    match kind {
+        Kind::Double => float_from_json(value).map(Proto::F64),
+        Kind::Float => float_from_json(value).map(|value| Proto::F32(value as f32)),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => integer_from_json(value).map(Proto::I32),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => integer_from_json(value).map(Proto::I64),
+        Kind::Uint32 | Kind::Fixed32 => integer_from_json(value).map(Proto::U32),
+        Kind::Uint64 | Kind::Fixed64 => integer_from_json(value).map(Proto::U64),
+        Kind::Bool => value
+            .as_bool()
+            .map(Proto::Bool)
+            .ok_or_else(|| invalid("a boolean", value)),
+        Kind::String => value
+            .as_str()
+            .map(|value| Proto::String(value.to_string()))
+            .ok_or_else(|| invalid("a string", value)),
+        Kind::Bytes => {
+            let encoded = value.as_str().ok_or_else(|| invalid("a string", value))?;
+            bytes_from_base64(encoded).map(|bytes| Proto::Bytes(bytes.into()))
+        }
+        Kind::Message(descriptor) => from_value(descriptor.clone(), value).map(Proto::Message),
+        Kind::Enum(descriptor) => match value {
+            Value::String(name) => descriptor
+                .get_value_by_name(name)
+                .map(|value| Proto::EnumNumber(value.number()))
+                .ok_or_else(|| {
+                    serde_json::Error::custom(format!(
+                        "unknown value `{}` of enum {}",
+                        name,
+                        descriptor.full_name()
+                    ))
+                }),
+            // unknown numbers are kept, like the canonical encoding does
+            _ => integer_from_json(value).map(Proto::EnumNumber),
+        },
+    }
+}
+
+fn float_from_json(value: &Value) -> Result<f64, serde_json::Error> {
+    match value {
+        Value::Number(number) => number.as_f64().ok_or_else(|| invalid("a number", value)),
+        Value::String(text) => match text.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => text.parse().map_err(|_| invalid("a number", value)),
+        },
+        _ => Err(invalid("a number", value)),
+    }
+}
+
+/// Reads an integer from a JSON number or a string, like `1`, `1.0` or `"1"`.
+fn integer_from_json<T>(value: &Value) -> Result<T, serde_json::Error>
+where
+    T: TryFrom<i64> + TryFrom<u64> + std::str::FromStr,
+{
+    let integer = match value {
+        Value::Number(number) => {
+            if let Some(integer) = number.as_u64() {
+                T::try_from(integer).ok()
+            } else if let Some(integer) = number.as_i64() {
+                T::try_from(integer).ok()
+            } else {
+                // floats without a fraction, like `1.0` or `1e3`
+                number
+                    .as_f64()
+                    .filter(|float| float.fract() == 0.0 && float.abs() < 2f64.powi(63))
+                    .and_then(|float| T::try_from(float as i64).ok())
+            }
+        }
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    };
+    integer.ok_or_else(|| invalid("an integer in range", value))
+}
+
+/// Decodes standard or URL-safe base64, with or without padding.
+fn bytes_from_base64(encoded: &str) -> Result<Vec<u8>, serde_json::Error> {
+    use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+
+    let config =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    let standard = GeneralPurpose::new(&base64::alphabet::STANDARD, config);
+    let url_safe = GeneralPurpose::new(&base64::alphabet::URL_SAFE, config);
+
+    standard
+        .decode(encoded)
+        .or_else(|_| url_safe.decode(encoded))
+        .map_err(|error| serde_json::Error::custom(format!("invalid base64: {}", error)))
+}
+
+fn map_key_from_string(kind: &Kind, key: &str) -> Result<MapKey, serde_json::Error> {
+    let key_value = Value::String(key.to_string());
+    match kind {
+        Kind::Bool => match key {
+            "true" => Ok(MapKey::Bool(true)),
+            "false" => Ok(MapKey::Bool(false)),
+            _ => Err(invalid("a boolean", &key_value)),
+        },
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+            integer_from_json(&key_value).map(MapKey::I32)
+        }
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+            integer_from_json(&key_value).map(MapKey::I64)
+        }
+        Kind::Uint32 | Kind::Fixed32 => integer_from_json(&key_value).map(MapKey::U32),
+        Kind::Uint64 | Kind::Fixed64 => integer_from_json(&key_value).map(MapKey::U64),
+        _ => Ok(MapKey::String(key.to_string())),
+    }
+}
+
+fn invalid(expected: &str, value: &Value) -> serde_json::Error {
+    serde_json::Error::custom(format!("expected {}, found {}", expected, value))
+}
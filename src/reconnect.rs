@@ -0,0 +1,114 @@
+//! Retrying reflection calls that failed because the connection to the server broke.
+
+use std::time::Duration;
+
+/// How a [`ReflectionClient`](crate::reflection::ReflectionClient) retries calls failing with a
+/// connection-level error, e.g. while the server is restarting.
+///
+/// The client re-establishes the connection for each retry, waiting an exponentially growing
+/// delay between the attempts: the first retry waits the initial backoff, and every further
+/// retry doubles the delay, up to the maximum backoff. Calls failing with a gRPC status sent
+/// by the server, such as `UNIMPLEMENTED`, are never retried.
+///
+/// # Example
+///
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// use grpc_ease::reconnect::ReconnectPolicy;
+/// use grpc_ease::reflection::ReflectionClient;
+/// use std::time::Duration;
+///
+/// let client = ReflectionClient::builder("http://localhost:50051")
+///     .reconnect(
+///         ReconnectPolicy::default()
+///             .max_retries(5)
+///             .max_backoff(Duration::from_secs(10)),
+///     )
+///     .build()
+///     .await?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl ReconnectPolicy {
+    /// The number of retries of the default policy.
+    pub const DEFAULT_MAX_RETRIES: u32 = 3;
+    /// The delay before the first retry of the default policy.
+    pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+    /// The maximum delay between retries of the default policy.
+    pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+    /// Returns a policy that never retries, so connection failures are returned immediately.
+    pub fn disabled() -> Self {
+        Self::default().max_retries(0)
+    }
+
+    /// Sets the maximum number of retries after the initial attempt.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum delay between two retries.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Returns the delay before retry number `retry`, counting from zero, or `None` if the
+    /// policy allows no further retries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::reconnect::ReconnectPolicy;
+    /// use std::time::Duration;
+    ///
+    /// let policy = ReconnectPolicy::default()
+    ///     .max_retries(4)
+    ///     .initial_backoff(Duration::from_millis(100))
+    ///     .max_backoff(Duration::from_millis(300));
+    /// assert_eq!(policy.backoff(0), Some(Duration::from_millis(100)));
+    /// assert_eq!(policy.backoff(1), Some(Duration::from_millis(200)));
+    /// assert_eq!(policy.backoff(3), Some(Duration::from_millis(300)));
+    /// assert_eq!(policy.backoff(4), None);
+    /// assert_eq!(ReconnectPolicy::disabled().backoff(0), None);
+    /// ```
+    pub fn backoff(&self, retry: u32) -> Option<Duration> {
+        if retry >= self.max_retries {
+            return None;
+        }
+
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+        let backoff = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff);
+        Some(backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    /// Retries [`DEFAULT_MAX_RETRIES`](Self::DEFAULT_MAX_RETRIES) times, starting with a delay
+    /// of [`DEFAULT_INITIAL_BACKOFF`](Self::DEFAULT_INITIAL_BACKOFF) and waiting at most
+    /// [`DEFAULT_MAX_BACKOFF`](Self::DEFAULT_MAX_BACKOFF) between retries.
+    fn default() -> Self {
+        Self {
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
@@ -1,229 +1,5722 @@
-use crate::service_info::{MethodInfo, ServiceInfo};
+#[cfg(feature = "prost-reflect")]
+use crate::any_json::{self, AnyTypes};
+use crate::auth::Credentials;
+#[cfg(feature = "transport")]
+use crate::builder::{ReflectionClientBuilder, DEFAULT_CONNECT_TIMEOUT};
+use crate::cache::DescriptorCache;
+use crate::catalog::OfflineCatalog;
+use crate::comments::{Source, SERVICE_METHOD};
+use crate::dependencies::{file_name, DependencyResolver};
+use crate::describe::SymbolDescription;
+use crate::diff;
+#[cfg(feature = "transport")]
+use crate::diff::SchemaDiff;
+use crate::error::{self, ReflectionError, ServiceListError};
+use crate::example::{self, ExampleOptions};
+use crate::fingerprint::Fingerprint;
+#[cfg(feature = "prost-reflect")]
+use crate::health::{self, HealthStatus, HealthStream};
+use crate::interceptor::SharedInterceptor;
+use crate::invoke::{self, BytesCodec, CallOptions, Deadline, Stopwatch};
+#[cfg(feature = "prost-reflect")]
+use crate::invoke::{
+    BytesEncoding, CallResponse, CallStats, DynamicCodec, EncodedCodec, JsonLines, JsonMapping,
+    JsonStream, MessageFormat,
+};
+use crate::lookup;
+use crate::message_info::{self, EnumInfo, MessageInfo, TypeInfo};
+use crate::openapi::{self, OpenApiDocument};
+use crate::options::{self, CustomOptions};
+use crate::proto_printer;
+use crate::reconnect::ReconnectPolicy;
+use crate::retry::RetryPolicy;
+use crate::rich_status::RichStatus;
+#[cfg(feature = "prost-reflect")]
+use crate::rich_status::StatusDetail;
+use crate::service_info::{
+    ExtensionNumbers, IdempotencyLevel, MethodInfo, ServiceInfo, Syntax, INFRASTRUCTURE_SERVICES,
+};
+use crate::watch::SchemaChangeEvent;
+use futures_util::future::join_all;
 use prost::Message;
-use serde_json::Value;
-use std::error::Error;
+#[cfg(feature = "prost-reflect")]
+use prost_reflect::ReflectMessage;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
+#[cfg(all(unix, feature = "transport"))]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_stream::StreamExt;
-use tonic::{transport::Channel, Request, Response};
-use tonic_reflection::pb::{
-    server_reflection_client::ServerReflectionClient, server_reflection_request::MessageRequest,
-    server_reflection_response::MessageResponse, ServerReflectionRequest,
+#[cfg(feature = "transport")]
+use tonic::transport::Channel;
+use tonic::{
+    body::BoxBody,
+    client::{Grpc, GrpcService},
+    codec::{CompressionEncoding, ProstCodec, Streaming},
+    codegen::{http::uri::PathAndQuery, Body, Bytes, StdError},
+    metadata::{KeyAndValueRef, KeyRef, MetadataMap},
+    Code, GrpcMethod, Request, Response, Status,
 };
+/// The requests and responses of the reflection protocol, as sent with
+/// [`ReflectionClient::raw_request`].
+pub use tonic_reflection::pb::{
+    server_reflection_request::MessageRequest, server_reflection_response::MessageResponse,
+};
+use tonic_reflection::pb::{ExtensionRequest, ServerReflectionRequest, ServerReflectionResponse};
 use tracing::{debug, trace};
 
-pub struct ReflectionClient {
-    client: ServerReflectionClient<Channel>,
+/// The deadline of [`ReflectionClient::ping`].
+#[cfg(feature = "transport")]
+pub const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A version of the gRPC server reflection protocol.
+///
+/// Both versions use the same messages and only differ in the name of the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReflectionVersion {
+    /// The `grpc.reflection.v1.ServerReflection` service
+    V1,
+    /// The `grpc.reflection.v1alpha.ServerReflection` service, which older servers expose
+    V1Alpha,
+}
+
+impl ReflectionVersion {
+    /// Returns the fully qualified name of the reflection service of this version.
+    pub fn service_name(&self) -> &'static str {
+        match self {
+            Self::V1 => "grpc.reflection.v1.ServerReflection",
+            Self::V1Alpha => "grpc.reflection.v1alpha.ServerReflection",
+        }
+    }
+
+    fn path(&self) -> &'static str {
+        match self {
+            Self::V1 => "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+            Self::V1Alpha => "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+        }
+    }
+}
+
+/// The parts of the reflection protocol a server implements, as reported by
+/// [`ReflectionClient::protocol_info`].
+#[cfg(feature = "transport")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolInfo {
+    /// Whether the server implements [`ReflectionVersion::V1`]
+    pub v1: bool,
+    /// Whether the server implements [`ReflectionVersion::V1Alpha`]
+    pub v1alpha: bool,
+    /// Whether the server answers `FileContainingExtension` requests
+    pub file_containing_extension: bool,
+    /// Whether the server answers `AllExtensionNumbersOfType` requests
+    pub all_extension_numbers: bool,
+}
+
+/// A client for the gRPC server reflection service.
+///
+/// The client speaks [`ReflectionVersion::V1`] of the reflection protocol, and falls back to
+/// [`ReflectionVersion::V1Alpha`] if the server does not implement it. The version the server
+/// answered with is remembered, so only the first call pays for the fallback.
+///
+/// The client is generic over the underlying gRPC service, like tonic's generated clients.
+/// With the `transport` feature, it defaults to tonic's [`Channel`], which the constructors
+/// below connect; any other service, e.g. a gRPC-Web client, can be used through
+/// [`ReflectionClient::from_service`].
+///
+/// # Cancellation
+///
+/// Every call can be cancelled by dropping its future, e.g. when it loses a
+/// [`tokio::select!`] against a cancellation signal or a timeout. The requests in flight are
+/// cancelled along with it, so is a reflection stream kept open with
+/// [`reuse_stream`](crate::builder::ReflectionClientBuilder::reuse_stream), which is reopened
+/// by the next call. Only the descriptors of completed fetches are cached, and the client
+/// stays usable for further calls.
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// # let endpoint =
+/// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+///
+/// // stalls the first descriptor fetch, after probing the v1 service and listing the services
+/// let requests = Arc::new(AtomicUsize::new(0));
+/// let stalled = Arc::new(AtomicBool::new(false));
+/// let (counter, flag) = (requests.clone(), stalled.clone());
+/// let stall_first_fetch = tower::ServiceBuilder::new().and_then(move |response| {
+///     let stall = counter.fetch_add(1, Ordering::SeqCst) == 2;
+///     let flag = flag.clone();
+///     async move {
+///         if stall {
+///             flag.store(true, Ordering::SeqCst);
+///             std::future::pending::<()>().await;
+///         }
+///         Ok::<_, tonic::transport::Error>(response)
+///     }
+/// });
+/// let mut client = ReflectionClient::builder(&endpoint)
+///     .build_with_layer(stall_first_fetch)
+///     .await?;
+///
+/// let (cancel, cancelled) = tokio::sync::oneshot::channel::<()>();
+/// tokio::spawn(async move {
+///     tokio::time::sleep(Duration::from_millis(100)).await;
+///     let _ = cancel.send(());
+/// });
+/// tokio::select! {
+///     _ = client.list_services() => unreachable!("the descriptor fetch is stalled"),
+///     _ = cancelled => {}
+/// }
+/// assert!(stalled.load(Ordering::SeqCst));
+///
+/// let services = client.list_services().await?;
+/// assert_eq!(services[0].service, "ServerReflection");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+// the default type only exists with the `transport` feature
+pub struct ReflectionClient<
+    #[cfg(feature = "transport")] T = Channel,
+    #[cfg(not(feature = "transport"))] T,
+> {
+    replicas: Vec<Replica<T>>,
+    /// The index of the replica that answered the last call, which is tried first next time
+    current: usize,
+    /// The reflection version the server answered with, if a call succeeded already
+    version: Option<ReflectionVersion>,
+    /// The value of the `host` field of the reflection requests
+    pub(crate) host: String,
+    /// Whether requests without per-call metadata share one long-lived reflection stream
+    pub(crate) reuse_stream: bool,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) reconnect: ReconnectPolicy,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) metadata: MetadataMap,
+    pub(crate) credentials: Option<Credentials>,
+    pub(crate) interceptor: Option<SharedInterceptor>,
+    /// The cached file descriptors, unless caching is disabled
+    pub(crate) cache: Option<DescriptorCache>,
+    /// The maximum number of files fetched at once while listing services
+    pub(crate) list_concurrency: usize,
+    /// Whether listings include the [`INFRASTRUCTURE_SERVICES`]
+    pub(crate) include_infrastructure: bool,
+    /// The encoding requests are compressed with, unless the server rejected it
+    pub(crate) send_compression: Option<CompressionEncoding>,
+    /// Whether the server rejected compressed requests, shared with the clones of the client
+    compression_rejected: Arc<AtomicBool>,
+}
+
+impl<T: Clone> Clone for ReflectionClient<T> {
+    /// Returns a client sharing the connections and the descriptor cache of this one, which
+    /// opens its own reflection streams.
+    fn clone(&self) -> Self {
+        Self {
+            replicas: self.replicas.iter().map(Replica::clone).collect(),
+            current: self.current,
+            version: self.version,
+            host: self.host.clone(),
+            reuse_stream: self.reuse_stream,
+            request_timeout: self.request_timeout,
+            reconnect: self.reconnect,
+            retry: self.retry.clone(),
+            metadata: self.metadata.clone(),
+            credentials: self.credentials.clone(),
+            interceptor: self.interceptor.clone(),
+            cache: self.cache.clone(),
+            list_concurrency: self.list_concurrency,
+            include_infrastructure: self.include_infrastructure,
+            send_compression: self.send_compression,
+            compression_rejected: Arc::clone(&self.compression_rejected),
+        }
+    }
+}
+
+/// One of the servers a [`ReflectionClient`] sends its calls to.
+struct Replica<T> {
+    endpoint: String,
+    client: Grpc<T>,
+    /// A client sharing the connection which compresses its requests, if requests are
+    /// compressed
+    compressed: Option<Grpc<T>>,
+    /// The reflection stream kept open for further requests, if streams are reused
+    stream: Option<OpenStream>,
+}
+
+impl<T: Clone> Clone for Replica<T> {
+    /// Returns a replica sharing the connection, without the open stream.
+    fn clone(&self) -> Self {
+        Self {
+            endpoint: self.endpoint.clone(),
+            client: self.client.clone(),
+            compressed: self.compressed.clone(),
+            stream: None,
+        }
+    }
+}
+
+/// A reflection stream kept open by a [`Replica`] to send further requests over.
+struct OpenStream {
+    sender: mpsc::UnboundedSender<ServerReflectionRequest>,
+    inbound: Streaming<ServerReflectionResponse>,
+}
+
+impl<T> Replica<T>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// Opens a reflection stream using `version` of the reflection service, like the
+    /// `server_reflection_info` method of tonic's generated client.
+    ///
+    /// The requests are compressed if `compressed` is set and the replica compresses requests.
+    async fn server_reflection_info(
+        &mut self,
+        version: ReflectionVersion,
+        mut request: Request<
+            impl tokio_stream::Stream<Item = ServerReflectionRequest> + Send + 'static,
+        >,
+        compressed: bool,
+    ) -> Result<Response<Streaming<ServerReflectionResponse>>, Status> {
+        let client = match &mut self.compressed {
+            Some(client) if compressed => client,
+            _ => &mut self.client,
+        };
+        client
+            .ready()
+            .await
+            .map_err(|error| Status::from_error(error.into()))?;
+
+        request.extensions_mut().insert(GrpcMethod::new(
+            version.service_name(),
+            "ServerReflectionInfo",
+        ));
+        client
+            .streaming(
+                request,
+                PathAndQuery::from_static(version.path()),
+                ProstCodec::default(),
+            )
+            .await
+    }
 }
 
+#[cfg(feature = "transport")]
 impl ReflectionClient {
     /// Creates a new instance of the client, connecting to the specified endpoint.
     ///
+    /// This waits for the connection to the server to be established, including the TCP
+    /// handshake; see [`ReflectionClient::new_lazy`] for a constructor that does not. Gives up
+    /// connecting after [`DEFAULT_CONNECT_TIMEOUT`]; use [`ReflectionClient::builder`] to
+    /// configure a different connect timeout.
+    ///
+    /// Endpoints using the `https` scheme are connected over TLS (rustls) when the crate is
+    /// built with the `tls` feature, verifying the server certificate against the system's
+    /// native root certificates and using the endpoint's host name for SNI.
+    ///
     /// # Arguments
     ///
     /// * `endpoint` - A `String` containing the server endpoint URL.
     ///
     /// # Returns
     ///
-    /// * `Result<Self, Box<dyn Error>>` - A result containing the newly created client instance
+    /// * `Result<Self, ReflectionError>` - A result containing the newly created client instance
     ///   or an error if the connection fails.
     ///
     /// # Errors
     ///
-    /// This function will return an error if the endpoint URL is invalid or if the connection
-    /// to the server cannot be established.
+    /// This function will return an error if the endpoint URL is invalid, if the endpoint uses
+    /// the `https` scheme but the crate was built without the `tls` feature, or if the connection
+    /// to the server cannot be established within the connect timeout.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```no_run
     /// # tokio_test::block_on(async {
     /// let client = grpc_ease::reflection::ReflectionClient::new("http://localhost:50051".to_string()).await?;
-    /// # })
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # });
     /// ```
-    pub async fn new(endpoint: String) -> Result<Self, Box<dyn Error>> {
-        let channel = Channel::from_shared(endpoint)?.connect().await?;
-        Ok(Self {
-            client: ServerReflectionClient::new(channel),
-        })
+    pub async fn new(endpoint: String) -> Result<Self, ReflectionError> {
+        ReflectionClientBuilder::new(endpoint)
+            .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
+            .build()
+            .await
     }
 
-    async fn make_request(
-        &mut self,
-        request: ServerReflectionRequest,
-    ) -> Result<MessageResponse, Box<dyn Error>> {
-        let request = Request::new(tokio_stream::once(request));
-        let mut inbound = self
-            .client
-            .server_reflection_info(request)
-            .await?
-            .into_inner();
+    /// Creates a new instance of the client, connecting over the Unix domain socket at `path`.
+    ///
+    /// Behaves like [`ReflectionClient::new`], but uses the socket instead of a TCP connection,
+    /// so it also waits for the connection to be established.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the socket cannot be connected to within the
+    /// connect timeout.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use tokio::net::UnixListener;
+    /// use tokio_stream::wrappers::UnixListenerStream;
+    ///
+    /// let path = std::env::temp_dir().join(format!("grpc-ease-{}.sock", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let listener = UnixListener::bind(&path)?;
+    /// let reflection = tonic_reflection::server::Builder::configure()
+    ///     .register_encoded_file_descriptor_set(tonic_reflection::pb::FILE_DESCRIPTOR_SET)
+    ///     .build()?;
+    /// tokio::spawn(
+    ///     tonic::transport::Server::builder()
+    ///         .add_service(reflection)
+    ///         .serve_with_incoming(UnixListenerStream::new(listener)),
+    /// );
+    ///
+    /// let mut client = ReflectionClient::new_uds(&path).await?;
+    /// let services = client.list_services().await?;
+    /// assert_eq!(services[0].service, "ServerReflection");
+    /// let files = client
+    ///     .get_file_descriptor("grpc.reflection.v1alpha.ServerReflection".to_string())
+    ///     .await?;
+    /// assert_eq!(files[0].name(), "reflection.proto");
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(unix)]
+    pub async fn new_uds(path: impl Into<PathBuf>) -> Result<Self, ReflectionError> {
+        ReflectionClientBuilder::new("http://localhost")
+            .unix_socket(path)
+            .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
+            .build()
+            .await
+    }
 
-        if let Some(response) = inbound.next().await {
-            return Ok(response?.message_response.expect("some MessageResponse"));
-        }
+    /// Creates a new instance of the client without connecting to the specified endpoint.
+    ///
+    /// Unlike [`ReflectionClient::new`], this does not block on the TCP handshake: the
+    /// connection is established by the first call made with the client, which fails if the
+    /// server cannot be reached. This allows creating the client before the server is up.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the endpoint URL is invalid, or if the endpoint
+    /// uses the `https` scheme but the crate was built without the `tls` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// // nothing is listening yet, but creating the client succeeds
+    /// let mut client = ReflectionClient::new_lazy("http://127.0.0.1:1".to_string())?;
+    /// assert!(client.list_services().await.is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn new_lazy(endpoint: String) -> Result<Self, ReflectionError> {
+        ReflectionClientBuilder::new(endpoint)
+            .connect_timeout(DEFAULT_CONNECT_TIMEOUT)
+            .build_lazy()
+    }
 
-        Err("No response received".into())
+    /// Creates a builder for a client connecting to `endpoint`, allowing connection settings
+    /// such as TLS client certificates to be configured before connecting.
+    ///
+    /// See [`ReflectionClientBuilder`] for the available settings.
+    pub fn builder(endpoint: impl Into<String>) -> ReflectionClientBuilder {
+        ReflectionClientBuilder::new(endpoint)
     }
 
-    /// Retrieves a list of services available on the server along with their methods.
+    /// Creates a new instance of the client for several replicas of the same server, failing
+    /// over to the next replica when one cannot be reached.
     ///
-    /// This function sends a `ServerReflectionRequest` to the server to list all available services.
-    /// For each service, it fetches the `FileDescriptorProto` to gather detailed information about the
-    /// service, including its methods.
+    /// Connects to the endpoints in order, like [`ReflectionClient::new`], and succeeds as long
+    /// as at least one of them can be connected to; the others are connected to on demand once
+    /// a call fails over to them. Each call is sent to the replica that answered the last call,
+    /// and is retried on the other replicas in order if that one fails with a connection
+    /// error.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// * `Result<Vec<ServiceInfo>, Box<dyn Error>>` - A result containing a vector of `ServiceInfo`
-    ///   objects, each representing a service and its methods, if the request is successful
+    /// This function will return an error if any endpoint URL is invalid, and
+    /// [`ReflectionError::AllEndpointsFailed`] listing the failure of each endpoint if none of
+    /// them can be connected to. Calls made with the client also fail with
+    /// [`ReflectionError::AllEndpointsFailed`] once every replica fails.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// // the first replica is down, but the second one answers
+    /// let down = "http://127.0.0.1:1".to_string();
+    /// let mut client = ReflectionClient::new_multi(vec![down.clone(), endpoint]).await?;
+    /// assert!(client.list_services().await.is_ok());
+    ///
+    /// let result = ReflectionClient::new_multi(vec![down.clone(), down]).await;
+    /// match result {
+    ///     Err(ReflectionError::AllEndpointsFailed { failures }) => assert_eq!(failures.len(), 2),
+    ///     _ => panic!("expected every endpoint to fail"),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn new_multi(endpoints: Vec<String>) -> Result<Self, ReflectionError> {
+        let mut replicas = Vec::new();
+        let mut current = None;
+        let mut failures = Vec::new();
+
+        for endpoint in endpoints {
+            let builder =
+                ReflectionClientBuilder::new(&endpoint).connect_timeout(DEFAULT_CONNECT_TIMEOUT);
+            let client = match builder.clone().build().await {
+                Ok(client) => {
+                    current.get_or_insert(replicas.len());
+                    client
+                }
+                Err(error) if error.is_connection_error() => {
+                    debug!("failed to connect to replica {}: {}", endpoint, error);
+                    failures.push((endpoint, error));
+                    builder.build_lazy()?
+                }
+                Err(error) => return Err(error),
+            };
+            replicas.extend(client.replicas);
+        }
+
+        let Some(current) = current else {
+            return Err(ReflectionError::AllEndpointsFailed { failures });
+        };
+
+        let mut client = Self::with_replicas(replicas);
+        client.current = current;
+        Ok(client)
+    }
+
+    /// Connects to the servers at both endpoints and computes the differences between the
+    /// schema of the server at `old` and the one of the server at `new`, e.g. of the
+    /// production and the staging deployment of a service.
     ///
     /// # Errors
     ///
-    /// This function will return an error if:
-    /// - The request to the server fails
-    /// - The response from the server is not a `ListServicesResponse`
-    /// - The file descriptors cannot be retrieved or decoded
-    /// - The service or method names are missing in the descriptors
+    /// This function will return an error if connecting to either server fails, or the
+    /// schema of either server cannot be retrieved, as described for
+    /// [`get_all_file_descriptors`](Self::get_all_file_descriptors).
     ///
     /// # Example
     ///
     /// ```
     /// # tokio_test::block_on(async {
-    /// let mut client = grpc_ease::reflection::ReflectionClient::connect("http://localhost:50051").await?;
-    /// let services = client.list_services().await?;
-    /// for service in services {
-    ///     println!("Service: {}.{}", service.package, service.service);
-    ///     for method in service.methods {
-    ///         println!("  Method: {}", method.name);
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, ServiceDescriptorProto};
+    ///
+    /// // serves a file defining the services
+    /// let serve = |services: &[&str]| {
+    ///     let file = FileDescriptorProto {
+    ///         name: Some("example.proto".to_string()),
+    ///         package: Some("example".to_string()),
+    ///         service: services
+    ///             .iter()
+    ///             .map(|name| ServiceDescriptorProto {
+    ///                 name: Some(name.to_string()),
+    ///                 ..Default::default()
+    ///             })
+    ///             .collect(),
+    ///         ..Default::default()
+    ///     };
+    ///     async move {
+    /// #         let endpoint = support::serve_files(vec![file]).await?;
+    ///         Ok::<_, Box<dyn std::error::Error>>(endpoint)
     ///     }
-    /// }
-    /// # })
+    /// };
+    ///
+    /// let production = serve(&["Orders"]).await?;
+    /// let staging = serve(&["Orders", "Refunds"]).await?;
+    /// let diff = ReflectionClient::diff_endpoints(production, staging).await?;
+    /// assert_eq!(diff.added_services, ["example.Refunds"]);
+    /// assert_eq!(diff.to_string(), "+ service example.Refunds");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
     /// ```
+    pub async fn diff_endpoints(old: String, new: String) -> Result<SchemaDiff, ReflectionError> {
+        let old = Self::new(old).await?.get_all_file_descriptors().await?;
+        let new = Self::new(new).await?.get_all_file_descriptors().await?;
+        Ok(diff::diff(&old, &new))
+    }
+}
+
+#[cfg(feature = "grpc-web")]
+impl ReflectionClient<tonic_web_wasm_client::Client> {
+    /// Creates a new instance of the client talking gRPC-Web to the server at `base_url`
+    /// using the browser's `fetch` API, for use in WebAssembly.
     ///
-    /// # Structs
+    /// Apart from the transport, the client behaves like one connected with
+    /// [`ReflectionClient::from_service`]; the server, or a proxy in front of it, must accept
+    /// gRPC-Web requests.
+    pub fn new_grpc_web(base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
+        let mut client = Self::from_service(tonic_web_wasm_client::Client::new(base_url.clone()));
+        client.replicas[0].endpoint = base_url;
+        client
+    }
+}
+
+impl<T> ReflectionClient<T>
+where
+    T: GrpcService<BoxBody>,
+    T::Error: Into<StdError>,
+    T::ResponseBody: Body<Data = Bytes> + Send + 'static,
+    <T::ResponseBody as Body>::Error: Into<StdError> + Send,
+{
+    /// Creates a new instance of the client sending its requests through `service`, such as a
+    /// channel configured by the caller or a gRPC-Web client.
     ///
-    /// * [`ServiceInfo`] - Represents information about a service, including its package name,
-    ///   service name, and methods
-    /// * [`MethodInfo`] - Represents information about a method, including its name.
-    pub async fn list_services(&mut self) -> Result<Vec<ServiceInfo>, Box<dyn Error>> {
-        let response = self
-            .make_request(ServerReflectionRequest {
-                host: "".to_string(),
-                message_request: Some(MessageRequest::ListServices(String::new())),
-            })
-            .await?;
+    /// Connection settings of the [`ReflectionClientBuilder`], such as the user agent, TLS,
+    /// or the authority, have to be configured on the service by the caller; in particular,
+    /// requests carry the user agent of the service, e.g. the one set with
+    /// [`Endpoint::user_agent`](tonic::transport::Endpoint::user_agent), rather than
+    /// [`DEFAULT_USER_AGENT`](crate::builder::DEFAULT_USER_AGENT). No metadata or credentials
+    /// are attached to the requests. Connection failures are not retried, since retries rely
+    /// on tokio's timer, which is not available on every platform such services are used on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use tonic::transport::Endpoint;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let channel = Endpoint::from_shared(endpoint)?.connect().await?;
+    /// let mut client = ReflectionClient::from_service(channel);
+    /// assert_eq!(client.list_services().await?[0].service, "ServerReflection");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn from_service(service: T) -> Self {
+        let mut client = Self::with_replicas(vec![Replica {
+            endpoint: String::new(),
+            client: Grpc::new(service),
+            compressed: None,
+            stream: None,
+        }]);
+        client.reconnect = ReconnectPolicy::disabled();
+        client
+    }
 
-        if let MessageResponse::ListServicesResponse(services_response) = response {
-            let mut services_info = Vec::new();
-
-            for service in services_response.service {
-                let descriptors = self.get_file_descriptor(service.name.clone()).await?;
-
-                for file_descriptor in descriptors {
-                    for service in file_descriptor.service {
-                        let methods: Vec<MethodInfo> = service
-                            .method
-                            .into_iter()
-                            .map(|method| {
-                                let name = method.name.ok_or_else(|| {
-                                    format!("Method name is missing for service {:?}", service.name)
-                                })?;
-                                let request = method.input_type.ok_or_else(|| {
-                                    format!(
-                                        "Request type is missing for method {:?} in service {:?}",
-                                        name, service.name
-                                    )
-                                })?;
-                                let response = method.output_type.ok_or_else(|| {
-                                    format!(
-                                        "Response type is missing for method {:?} in service {:?}",
-                                        name, service.name
-                                    )
-                                })?;
-                                Ok(MethodInfo {
-                                    name,
-                                    request,
-                                    response,
-                                })
-                            })
-                            .collect::<Result<Vec<MethodInfo>, Box<dyn Error>>>()?;
+    #[cfg(feature = "transport")]
+    pub(crate) fn with_client(
+        endpoint: String,
+        client: Grpc<T>,
+        compressed: Option<Grpc<T>>,
+    ) -> Self {
+        Self::with_replicas(vec![Replica {
+            endpoint,
+            client,
+            compressed,
+            stream: None,
+        }])
+    }
 
-                        let package = file_descriptor.package.clone().ok_or_else(|| {
-                            format!("Package name is missing for service {:?}", service.name)
-                        })?;
+    fn with_replicas(replicas: Vec<Replica<T>>) -> Self {
+        Self {
+            replicas,
+            current: 0,
+            version: None,
+            host: String::new(),
+            reuse_stream: false,
+            request_timeout: None,
+            reconnect: ReconnectPolicy::default(),
+            retry: RetryPolicy::default(),
+            metadata: MetadataMap::new(),
+            credentials: None,
+            interceptor: None,
+            cache: Some(DescriptorCache::default()),
+            list_concurrency: 1,
+            include_infrastructure: true,
+            send_compression: None,
+            compression_rejected: Arc::new(AtomicBool::new(false)),
+        }
+    }
 
-                        let service_name = service.name.ok_or_else(|| {
-                            format!("Service name is missing for package {}", package)
-                        })?;
+    /// Returns the encoding requests are compressed with, `None` once the server rejected it.
+    fn send_compression(&self) -> Option<CompressionEncoding> {
+        self.send_compression
+            .filter(|_| !self.compression_rejected.load(Ordering::Relaxed))
+    }
 
-                        services_info.push(ServiceInfo {
-                            package,
-                            service: service_name,
-                            methods,
-                        });
-                    }
+    /// Stops compressing requests if `status` rejects them for their compression, returning
+    /// whether it does, so the request can be sent again uncompressed.
+    fn compression_rejected(&self, status: &Status) -> bool {
+        let Some(encoding) = self.send_compression() else {
+            return false;
+        };
+        if !error::is_compression_rejection(status) {
+            return false;
+        }
+        debug!(
+            "the server does not accept requests compressed with {:?}, sending them uncompressed",
+            encoding
+        );
+        self.compression_rejected.store(true, Ordering::Relaxed);
+        true
+    }
+
+    /// Returns a reflection request for `message_request`, addressed to the configured host.
+    fn request(&self, message_request: MessageRequest) -> ServerReflectionRequest {
+        ServerReflectionRequest {
+            host: self.host.clone(),
+            message_request: Some(message_request),
+        }
+    }
+
+    /// Sends `request` and waits for the response, retrying connection failures according to
+    /// the reconnect policy, and transient statuses according to the retry policy.
+    ///
+    /// tonic's channel re-establishes a broken connection on the next call, so retrying the
+    /// call is enough to reconnect.
+    async fn make_request(
+        &mut self,
+        request: ServerReflectionRequest,
+        overrides: &MetadataMap,
+    ) -> Result<MessageResponse, ReflectionError> {
+        let mut reconnects = 0;
+        let mut retries = 0;
+        loop {
+            let error = match self.try_replicas(&request, overrides).await {
+                Err(error) => error,
+                result => return result,
+            };
+
+            let backoff = match &error {
+                error if error.is_connection_error() => {
+                    reconnects += 1;
+                    self.reconnect.backoff(reconnects - 1)
+                }
+                ReflectionError::Status(status) if self.retry.is_retryable(status.code()) => {
+                    retries += 1;
+                    self.retry.backoff(retries - 1)
+                }
+                _ => None,
+            };
+            let Some(backoff) = backoff else {
+                return Err(error);
+            };
+
+            debug!("{}, retrying in {:?}", error, backoff);
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Sends `request` to the replicas in turn, starting with the one that answered the last
+    /// call, until one of them does not fail with a connection error.
+    async fn try_replicas(
+        &mut self,
+        request: &ServerReflectionRequest,
+        overrides: &MetadataMap,
+    ) -> Result<MessageResponse, ReflectionError> {
+        if self.replicas.len() == 1 {
+            return self.attempt(0, request.clone(), overrides).await;
+        }
+
+        let mut failures = Vec::new();
+        for offset in 0..self.replicas.len() {
+            let replica = (self.current + offset) % self.replicas.len();
+            match self.attempt(replica, request.clone(), overrides).await {
+                Err(error) if error.is_connection_error() => {
+                    let endpoint = self.replicas[replica].endpoint.clone();
+                    debug!("replica {} failed: {}", endpoint, error);
+                    failures.push((endpoint, error));
+                }
+                result => {
+                    self.current = replica;
+                    return result;
                 }
             }
+        }
+
+        Err(ReflectionError::AllEndpointsFailed { failures })
+    }
+
+    /// Makes a single attempt at sending `request` to `replica`, giving up once the request
+    /// timeout elapses.
+    ///
+    /// The timeout covers the whole exchange, from opening the reflection stream until the
+    /// response message arrives.
+    async fn attempt(
+        &mut self,
+        replica: usize,
+        request: ServerReflectionRequest,
+        overrides: &MetadataMap,
+    ) -> Result<MessageResponse, ReflectionError> {
+        let Some(timeout) = self.request_timeout else {
+            return self.exchange(replica, request, overrides).await;
+        };
+
+        match tokio::time::timeout(timeout, self.exchange(replica, request, overrides)).await {
+            Ok(Err(ReflectionError::Status(status))) if error::is_timeout_expiry(&status) => {
+                Err(ReflectionError::DeadlineExceeded { timeout })
+            }
+            Ok(response) => response,
+            Err(_) => Err(ReflectionError::DeadlineExceeded { timeout }),
+        }
+    }
 
-            Ok(services_info)
+    async fn exchange(
+        &mut self,
+        replica: usize,
+        request: ServerReflectionRequest,
+        overrides: &MetadataMap,
+    ) -> Result<MessageResponse, ReflectionError> {
+        trace!("sending reflection request for host {:?}", request.host);
+        let response = if self.reuse_stream && overrides.is_empty() {
+            self.exchange_on_open_stream(replica, request).await
         } else {
-            Err("Expected a ListServicesResponse variant".into())
+            self.exchange_on_new_stream(replica, request, overrides)
+                .await
+        };
+
+        match response {
+            Ok(response) => error_response_to_status(response),
+            #[cfg(feature = "tls")]
+            Err(error) => Err(handshake_error(&self.replicas[replica].endpoint, error)),
+            #[cfg(not(feature = "tls"))]
+            Err(error) => Err(error),
         }
     }
 
-    /// Retrieves the file descriptors for the specified symbol from the server.
+    /// Sends `request` over a new reflection stream to `replica`, which is closed after the
+    /// request.
+    async fn exchange_on_new_stream(
+        &mut self,
+        replica: usize,
+        request: ServerReflectionRequest,
+        overrides: &MetadataMap,
+    ) -> Result<MessageResponse, ReflectionError> {
+        let timeout = self.request_timeout;
+        let mut inbound = self
+            .open_stream(
+                replica,
+                || tokio_stream::once(request.clone()),
+                overrides,
+                timeout,
+            )
+            .await?;
+
+        // some servers split large responses across several messages, so every message up to
+        // the end of the stream belongs to the response
+        let mut response = None;
+        while let Some(message) = inbound.next().await {
+            let Some(message) = message?.message_response else {
+                continue;
+            };
+            response = Some(match response {
+                None => message,
+                Some(response) => merge_responses(response, message),
+            });
+        }
+
+        response.ok_or(ReflectionError::NoResponse)
+    }
+
+    /// Sends `request` over the reflection stream kept open to `replica`, opening a new one
+    /// if there is none or the server closed it.
     ///
-    /// This function sends a `ServerReflectionRequest` to the server to fetch the
-    /// `FileDescriptorProto` for the provided symbol. It decodes the received file
-    /// descriptors and returns them as a vector.
+    /// The stream is taken out of the replica while waiting for the response and only put
+    /// back once the response arrived, so a call that fails, times out or is cancelled never
+    /// leaves a stream with an unanswered request behind.
+    async fn exchange_on_open_stream(
+        &mut self,
+        replica: usize,
+        request: ServerReflectionRequest,
+    ) -> Result<MessageResponse, ReflectionError> {
+        if let Some(mut stream) = self.replicas[replica].stream.take() {
+            if stream.sender.send(request.clone()).is_ok() {
+                if let Some(response) = stream.inbound.next().await {
+                    let response = response?;
+                    self.replicas[replica].stream = Some(stream);
+                    return response.message_response.ok_or(ReflectionError::NoResponse);
+                }
+            }
+            debug!(
+                "reflection stream to {} was closed, reopening",
+                self.replicas[replica].endpoint
+            );
+        }
+
+        let mut sender = None;
+        // the deadline covers each exchange rather than the long-lived stream
+        let mut inbound = self
+            .open_stream(
+                replica,
+                || {
+                    let (tx, rx) = mpsc::unbounded_channel();
+                    let _ = tx.send(request.clone());
+                    sender = Some(tx);
+                    UnboundedReceiverStream::new(rx)
+                },
+                &MetadataMap::new(),
+                None,
+            )
+            .await?;
+        let response = inbound.next().await.ok_or(ReflectionError::NoResponse)??;
+        if let Some(sender) = sender {
+            self.replicas[replica].stream = Some(OpenStream { sender, inbound });
+        }
+        response.message_response.ok_or(ReflectionError::NoResponse)
+    }
+
+    /// Opens a reflection stream to `replica` sending the requests of the stream returned by
+    /// `make_stream`.
     ///
-    /// # Arguments
+    /// Until a call succeeded, the stream is first opened with the v1 reflection service,
+    /// and, if the server does not implement it, again with the v1alpha service, using a
+    /// fresh request stream.
+    async fn open_stream<S>(
+        &mut self,
+        replica: usize,
+        mut make_stream: impl FnMut() -> S,
+        overrides: &MetadataMap,
+        timeout: Option<Duration>,
+    ) -> Result<Streaming<ServerReflectionResponse>, ReflectionError>
+    where
+        S: tokio_stream::Stream<Item = ServerReflectionRequest> + Send + 'static,
+    {
+        let versions = match self.version {
+            Some(version) => vec![version],
+            None => vec![ReflectionVersion::V1, ReflectionVersion::V1Alpha],
+        };
+
+        let mut versions = versions.into_iter().peekable();
+        while let Some(version) = versions.next() {
+            trace!("opening reflection stream to {}", version.service_name());
+            let request = self.prepare(make_stream(), overrides, timeout)?;
+            let compressed = self.send_compression().is_some();
+            let mut response = self.replicas[replica]
+                .server_reflection_info(version, request, compressed)
+                .await;
+            if matches!(&response, Err(status) if self.compression_rejected(status)) {
+                let request = self.prepare(make_stream(), overrides, timeout)?;
+                response = self.replicas[replica]
+                    .server_reflection_info(version, request, false)
+                    .await;
+            }
+            match response {
+                Err(status)
+                    if status.code() == Code::Unimplemented && versions.peek().is_some() =>
+                {
+                    debug!(
+                        "{} is not implemented, falling back",
+                        version.service_name()
+                    );
+                }
+                Err(status) => return Err(status.into()),
+                Ok(response) => {
+                    self.version = Some(version);
+                    return Ok(response.into_inner());
+                }
+            }
+        }
+
+        unreachable!("the loop returns on the last version")
+    }
+
+    /// Wraps `stream` into a streaming request carrying the client's metadata, overridden by
+    /// `overrides`, the `timeout` and the credentials, and passes it through the interceptor.
+    fn prepare<S>(
+        &self,
+        stream: S,
+        overrides: &MetadataMap,
+        timeout: Option<Duration>,
+    ) -> Result<Request<S>, ReflectionError> {
+        let mut request = Request::new(stream);
+        *request.metadata_mut() = merge_metadata(&self.metadata, overrides);
+        if let Some(timeout) = timeout {
+            request.set_timeout(timeout);
+        }
+        if let Some(credentials) = &self.credentials {
+            credentials.apply(request.metadata_mut())?;
+        }
+        if let Some(interceptor) = &self.interceptor {
+            request = interceptor.intercept(request)?;
+        }
+        Ok(request)
+    }
+
+    /// Returns the version of the reflection protocol the server answered with, or `None`
+    /// if no call succeeded yet.
     ///
-    /// * `symbol` - A `String` containing the fully qualified name of the symbol
-    ///   whose file descriptors are being requested.
+    /// # Example
     ///
-    /// # Returns
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::{ReflectionClient, ReflectionVersion};
     ///
-    /// * `Result<Vec<prost_types::FileDescriptorProto>, Box<dyn Error>>` - A result containing
-    ///   a vector of `FileDescriptorProto` objects if the request is successful, or an error
-    ///   if the request fails or the response is not of the expected type.
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// // this version of tonic's reflection server only implements v1alpha
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// assert_eq!(client.reflection_version(), None);
+    /// client.list_services().await?;
+    /// assert_eq!(client.reflection_version(), Some(ReflectionVersion::V1Alpha));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn reflection_version(&self) -> Option<ReflectionVersion> {
+        self.version
+    }
+
+    /// Removes all cached file descriptors, so they are fetched from the server again.
+    ///
+    /// File descriptors are cached by the symbol or file name they were requested for, so
+    /// repeated lookups, e.g. by [`get_file_descriptor`](Self::get_file_descriptor) or
+    /// [`list_services`](Self::list_services), do not send further requests. Calls passing
+    /// their own metadata bypass the cache. Caching can be disabled, or limited to a maximum
+    /// age, on the [`ReflectionClientBuilder`](crate::builder::ReflectionClientBuilder).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use tonic::codegen::http;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let requests = Arc::new(AtomicUsize::new(0));
+    /// let counter = requests.clone();
+    /// let count_requests = tower::ServiceBuilder::new().map_request(
+    ///     move |request: http::Request<tonic::body::BoxBody>| {
+    ///         counter.fetch_add(1, Ordering::SeqCst);
+    ///         request
+    ///     },
+    /// );
+    /// let mut client = ReflectionClient::builder(&endpoint)
+    ///     .build_with_layer(count_requests)
+    ///     .await?;
+    /// let symbol = "grpc.reflection.v1alpha.ServerReflection";
+    ///
+    /// // probing the v1 reflection service, and fetching the file once
+    /// client.get_file_descriptor(symbol.to_string()).await?;
+    /// client.get_file_descriptor(symbol.to_string()).await?;
+    /// assert_eq!(requests.load(Ordering::SeqCst), 2);
+    ///
+    /// client.clear_cache();
+    /// client.get_file_descriptor(symbol.to_string()).await?;
+    /// assert_eq!(requests.load(Ordering::SeqCst), 3);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn clear_cache(&mut self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Checks that the reflection service is reachable, returning the round-trip time.
+    ///
+    /// This sends a single `ListServices` request, the lightest request the reflection
+    /// service answers, and waits for the response at most [`PING_TIMEOUT`], or the request
+    /// timeout if it is shorter. Unlike other calls, connection failures are not retried, so
+    /// an unreachable server is reported right away.
     ///
     /// # Errors
     ///
     /// This function will return an error if:
-    /// - The request to the server fails.
-    /// - The response from the server is not a `FileDescriptorResponse`.
-    /// - The file descriptors cannot be decoded.
+    /// - The server cannot be reached
+    /// - No response arrives within the deadline, as [`ReflectionError::DeadlineExceeded`]
+    /// - The server answers with an error status, e.g. because it does not expose the
+    ///   reflection service
     ///
     /// # Example
     ///
     /// ```
     /// # tokio_test::block_on(async {
-    /// let mut client = grpc_ease::reflection::ReflectionClient::new("http://localhost:50051".to_string()).await?;
-    /// let descriptors = client.get_file_descriptor("my.package.MyService".to_string()).await?;
-    /// for descriptor in descriptors {
-    ///     println!("{:?}", descriptor);
-    /// }
-    /// # })
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let round_trip = client.ping().await?;
+    /// println!("server answered within {:?}", round_trip);
+    ///
+    /// let mut client = ReflectionClient::new_lazy("http://127.0.0.1:1".to_string())?;
+    /// assert!(client.ping().await.unwrap_err().is_connection_error());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
     /// ```
-    pub async fn get_file_descriptor(
-        &mut self,
-        symbol: String,
-    ) -> Result<Vec<prost_types::FileDescriptorProto>, Box<dyn Error>> {
-        let response = self
-            .make_request(ServerReflectionRequest {
-                host: "".to_string(),
-                message_request: Some(MessageRequest::FileContainingSymbol(symbol)),
-            })
-            .await?;
+    #[cfg(feature = "transport")]
+    pub async fn ping(&mut self) -> Result<Duration, ReflectionError> {
+        let request = self.request(MessageRequest::ListServices(String::new()));
 
-        if let MessageResponse::FileDescriptorResponse(descriptor_response) = response {
-            let mut descriptors = Vec::new();
-            for file_descriptor_proto in descriptor_response.file_descriptor_proto {
-                let file_descriptor =
-                    prost_types::FileDescriptorProto::decode(&file_descriptor_proto[..])?;
-                descriptors.push(file_descriptor);
-            }
-            Ok(descriptors)
-        } else {
-            Err("Expected a FileDescriptorResponse variant".into())
+        let start = std::time::Instant::now();
+        let response = tokio::time::timeout(
+            PING_TIMEOUT,
+            self.try_replicas(&request, &MetadataMap::new()),
+        )
+        .await
+        .map_err(|_| ReflectionError::DeadlineExceeded {
+            timeout: PING_TIMEOUT,
+        })??;
+
+        match response {
+            MessageResponse::ListServicesResponse(_) => Ok(start.elapsed()),
+            _ => Err(ReflectionError::UnexpectedResponse {
+                expected: "ListServicesResponse",
+            }),
         }
     }
+
+    /// Probes which parts of the reflection protocol the server implements.
+    ///
+    /// Each version of the reflection service is probed with a `ListServices` request, and
+    /// the extension requests are probed with the first version the server implements,
+    /// asking for the extensions of `google.protobuf.MethodOptions`. A request counts as
+    /// supported unless the server answers it with `UNIMPLEMENTED`; note that some servers,
+    /// like tonic's, answer unsupported extension requests with `NOT_FOUND` instead, which
+    /// cannot be told apart from a server not knowing the probed extension.
+    ///
+    /// Every probe waits at most [`PING_TIMEOUT`] and is not retried, so an unreachable server
+    /// is reported right away.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The server cannot be reached
+    /// - A probe does not complete within the deadline, as
+    ///   [`ReflectionError::DeadlineExceeded`]
+    /// - A probe fails with a status other than `UNIMPLEMENTED`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let info = client.protocol_info().await?;
+    /// assert!(!info.v1);
+    /// assert!(info.v1alpha);
+    /// assert!(info.all_extension_numbers);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "transport")]
+    pub async fn protocol_info(&mut self) -> Result<ProtocolInfo, ReflectionError> {
+        let list_services = || MessageRequest::ListServices(String::new());
+        let v1 = self.probe(ReflectionVersion::V1, list_services()).await?;
+        let v1alpha = self
+            .probe(ReflectionVersion::V1Alpha, list_services())
+            .await?;
+
+        let version = match (v1, v1alpha) {
+            (true, _) => ReflectionVersion::V1,
+            (false, true) => ReflectionVersion::V1Alpha,
+            (false, false) => {
+                return Ok(ProtocolInfo {
+                    v1,
+                    v1alpha,
+                    file_containing_extension: false,
+                    all_extension_numbers: false,
+                })
+            }
+        };
+
+        let extendee = "google.protobuf.MethodOptions";
+        let file_containing_extension = self
+            .probe(
+                version,
+                MessageRequest::FileContainingExtension(ExtensionRequest {
+                    containing_type: extendee.to_string(),
+                    extension_number: 1,
+                }),
+            )
+            .await?;
+        let all_extension_numbers = self
+            .probe(
+                version,
+                MessageRequest::AllExtensionNumbersOfType(extendee.to_string()),
+            )
+            .await?;
+
+        Ok(ProtocolInfo {
+            v1,
+            v1alpha,
+            file_containing_extension,
+            all_extension_numbers,
+        })
+    }
+
+    /// Sends `request` to `version` of the reflection service of the current replica once,
+    /// returning whether the server implements it.
+    #[cfg(feature = "transport")]
+    async fn probe(
+        &mut self,
+        version: ReflectionVersion,
+        request: MessageRequest,
+    ) -> Result<bool, ReflectionError> {
+        let request = self.request(request);
+        let replica = self.current;
+        let exchange = async {
+            let request = self.prepare(
+                tokio_stream::once(request),
+                &MetadataMap::new(),
+                Some(PING_TIMEOUT),
+            )?;
+            // uncompressed, as rejected compression would look like an unimplemented service
+            let mut inbound = self.replicas[replica]
+                .server_reflection_info(version, request, false)
+                .await?
+                .into_inner();
+            let response = inbound.next().await.ok_or(ReflectionError::NoResponse)??;
+            error_response_to_status(
+                response
+                    .message_response
+                    .ok_or(ReflectionError::NoResponse)?,
+            )
+        };
+
+        let response = tokio::time::timeout(PING_TIMEOUT, exchange)
+            .await
+            .map_err(|_| ReflectionError::DeadlineExceeded {
+                timeout: PING_TIMEOUT,
+            })?;
+        match response {
+            Ok(_) => Ok(true),
+            Err(ReflectionError::Status(status)) if status.code() == Code::Unimplemented => {
+                Ok(false)
+            }
+            Err(ReflectionError::Status(status)) if status.code() == Code::NotFound => Ok(true),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Sends `request` as is and returns the response of the server, for requests the other
+    /// methods do not cover.
+    ///
+    /// The request is sent like those of the other methods: with the configured host,
+    /// metadata and credentials, and retried according to the reconnect and retry policies.
+    /// Error responses are returned as [`ReflectionError::Status`], and the file descriptors
+    /// of responses are not decoded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails, or the server sends an error response
+    /// - The server closes the stream without responding, as [`ReflectionError::NoResponse`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::{MessageRequest, MessageResponse, ReflectionClient};
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let response = client
+    ///     .raw_request(MessageRequest::FileByFilename("reflection.proto".to_string()))
+    ///     .await?;
+    /// assert!(matches!(response, MessageResponse::FileDescriptorResponse(_)));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn raw_request(
+        &mut self,
+        request: MessageRequest,
+    ) -> Result<MessageResponse, ReflectionError> {
+        let request = self.request(request);
+        self.make_request(request, &MetadataMap::new()).await
+    }
+
+    /// Retrieves a list of services available on the server along with their methods.
+    ///
+    /// This function sends a `ServerReflectionRequest` to the server to list all available services.
+    /// For each service, it fetches the `FileDescriptorProto` to gather detailed information about the
+    /// service, including its methods. Services defined in a file that was already fetched for
+    /// another service are not fetched again, and each service is listed once. The files are
+    /// fetched one at a time, unless a higher
+    /// [`list_concurrency`](crate::builder::ReflectionClientBuilder::list_concurrency) is configured; either
+    /// way, the services are sorted by package and service name.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<ServiceInfo>, ReflectionError>` - A result containing a vector of `ServiceInfo`
+    ///   objects, each representing a service and its methods, if the request is successful
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The response from the server is not a `ListServicesResponse`
+    /// - The file descriptors cannot be retrieved or decoded
+    /// - The service or method names are missing in the descriptors
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let mut client = grpc_ease::reflection::ReflectionClient::new("http://localhost:50051".to_string()).await?;
+    /// let services = client.list_services().await?;
+    /// for service in services {
+    ///     println!("Service: {}.{}", service.package, service.service);
+    ///     for method in service.methods {
+    ///         println!("  Method: {}", method.name);
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # });
+    /// ```
+    ///
+    /// Services sharing a file are fetched with a single request:
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, FileDescriptorSet, ServiceDescriptorProto};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use tonic::codegen::http;
+    ///
+    /// let service = |name: &str| ServiceDescriptorProto {
+    ///     name: Some(name.to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let files = FileDescriptorSet {
+    ///     file: vec![FileDescriptorProto {
+    ///         name: Some("billing.proto".to_string()),
+    ///         package: Some("billing".to_string()),
+    ///         service: vec![service("Invoices"), service("Payments")],
+    ///         ..Default::default()
+    ///     }],
+    /// };
+    ///
+    /// # let endpoint = support::serve_files(files.file).await?;
+    ///
+    /// let requests = Arc::new(AtomicUsize::new(0));
+    /// let counter = requests.clone();
+    /// let count_requests = tower::ServiceBuilder::new().map_request(
+    ///     move |request: http::Request<tonic::body::BoxBody>| {
+    ///         counter.fetch_add(1, Ordering::SeqCst);
+    ///         request
+    ///     },
+    /// );
+    /// let mut client = ReflectionClient::builder(&endpoint)
+    ///     .build_with_layer(count_requests)
+    ///     .await?;
+    ///
+    /// let services = client.list_services().await?;
+    /// let names: Vec<_> = services.iter().map(|service| &service.service).collect();
+    /// assert_eq!(names, ["Invoices", "Payments", "ServerReflection"]);
+    /// // probing the v1 reflection service, listing the services, and fetching billing.proto
+    /// // and the file of the reflection service
+    /// assert_eq!(requests.load(Ordering::SeqCst), 4);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// # Structs
+    ///
+    /// * [`ServiceInfo`] - Represents information about a service, including its package name,
+    ///   service name, and methods
+    /// * [`MethodInfo`] - Represents information about a method, including its name.
+    pub async fn list_services(&mut self) -> Result<Vec<ServiceInfo>, ReflectionError>
+    where
+        T: Clone,
+    {
+        self.list_services_with_metadata(MetadataMap::new()).await
+    }
+
+    /// Retrieves a list of services like [`list_services`](Self::list_services), sending
+    /// `metadata` along with the client's own metadata.
+    ///
+    /// Entries of `metadata` replace the client's entries with the same key for this call
+    /// only, which allows tools serving several tenants to reuse one client.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`list_services`](Self::list_services).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use tonic::metadata::MetadataMap;
+    /// use tonic::{Request, Status};
+    ///
+    /// // a server only answering requests of the "acme" tenant
+    /// let check_tenant = |request: Request<()>| match request.metadata().get("x-tenant-id") {
+    ///     Some(tenant) if tenant == "acme" => Ok(request),
+    ///     _ => Err(Status::permission_denied("unknown tenant")),
+    /// };
+    /// # let reflection = support::reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]);
+    /// # let (incoming, endpoint) = support::bind().await?;
+    /// # tokio::spawn(
+    /// #     tonic::transport::Server::builder()
+    /// #         .layer(tonic::service::interceptor(check_tenant))
+    /// #         .add_service(reflection)
+    /// #         .serve_with_incoming(incoming),
+    /// # );
+    ///
+    /// let mut client = ReflectionClient::builder(&endpoint)
+    ///     .insert_metadata("x-tenant-id", "default")?
+    ///     .build()
+    ///     .await?;
+    /// assert!(client.list_services().await.is_err());
+    ///
+    /// let mut metadata = MetadataMap::new();
+    /// metadata.insert("x-tenant-id", "acme".parse()?);
+    /// assert!(client.list_services_with_metadata(metadata).await.is_ok());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn list_services_with_metadata(
+        &mut self,
+        metadata: MetadataMap,
+    ) -> Result<Vec<ServiceInfo>, ReflectionError>
+    where
+        T: Clone,
+    {
+        let (services, _) = self.collect_services(&metadata, "", false).await?;
+        Ok(services)
+    }
+
+    /// Retrieves a list of services like [`list_services`](Self::list_services), but lists
+    /// the services that could be resolved even if others could not.
+    ///
+    /// Each service whose file descriptor could not be fetched or is malformed is returned
+    /// along with the error instead of failing the whole call, leaving out the other services
+    /// of the same file. Only failing to list the service names fails the call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
+    ///
+    /// // a service whose method lacks its request and response types
+    /// let broken = FileDescriptorProto {
+    ///     name: Some("broken.proto".to_string()),
+    ///     package: Some("broken".to_string()),
+    ///     service: vec![ServiceDescriptorProto {
+    ///         name: Some("Broken".to_string()),
+    ///         method: vec![MethodDescriptorProto {
+    ///             name: Some("Call".to_string()),
+    ///             ..Default::default()
+    ///         }],
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// # let reflection =
+    /// #     support::reflection_with(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET], vec![broken]);
+    /// # let endpoint =
+    /// #     support::serve(tonic::transport::Server::builder().add_service(reflection)).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// assert!(client.list_services().await.is_err());
+    ///
+    /// let (services, failures) = client.list_services_lenient().await?;
+    /// assert_eq!(services.len(), 1);
+    /// assert_eq!(services[0].service, "ServerReflection");
+    /// assert_eq!(failures.len(), 1);
+    /// assert_eq!(failures[0].service, "broken.Broken");
+    /// assert!(matches!(failures[0].error, ReflectionError::MalformedDescriptor(_)));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn list_services_lenient(
+        &mut self,
+    ) -> Result<(Vec<ServiceInfo>, Vec<ServiceListError>), ReflectionError>
+    where
+        T: Clone,
+    {
+        self.collect_services(&MetadataMap::new(), "", true).await
+    }
+
+    /// Retrieves a list of the services in `package` and its subpackages, like
+    /// [`list_services`](Self::list_services), fetching only the descriptors of those services.
+    ///
+    /// The package matches whole package components, with or without a trailing dot: `billing`
+    /// and `billing.` match `billing.Payments` and `billing.v1.Invoices`, but not
+    /// `billingx.Refunds`. An empty package matches every service. If no service matches, an
+    /// empty list is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, ServiceDescriptorProto};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use tonic::codegen::http;
+    ///
+    /// let file = |package: &str, service: &str| FileDescriptorProto {
+    ///     name: Some(format!("{}.proto", package)),
+    ///     package: Some(package.to_string()),
+    ///     service: vec![ServiceDescriptorProto {
+    ///         name: Some(service.to_string()),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// # let endpoint = support::serve_files(vec![
+    /// #     file("billing", "Payments"),
+    /// #     file("billing.v1", "Invoices"),
+    /// #     file("billingx", "Refunds"),
+    /// #     file("shop", "Orders"),
+    /// # ])
+    /// # .await?;
+    ///
+    /// let requests = Arc::new(AtomicUsize::new(0));
+    /// let counter = requests.clone();
+    /// let count_requests = tower::ServiceBuilder::new().map_request(
+    ///     move |request: http::Request<tonic::body::BoxBody>| {
+    ///         counter.fetch_add(1, Ordering::SeqCst);
+    ///         request
+    ///     },
+    /// );
+    /// let mut client = ReflectionClient::builder(&endpoint)
+    ///     .build_with_layer(count_requests)
+    ///     .await?;
+    ///
+    /// let services = client.list_services_in_package("billing").await?;
+    /// let names: Vec<_> = services.iter().map(|service| &service.service).collect();
+    /// assert_eq!(names, ["Payments", "Invoices"]);
+    /// // probing the v1 reflection service, listing the services, and fetching the two files
+    /// assert_eq!(requests.load(Ordering::SeqCst), 4);
+    ///
+    /// assert!(client.list_services_in_package("inventory").await?.is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn list_services_in_package(
+        &mut self,
+        package: &str,
+    ) -> Result<Vec<ServiceInfo>, ReflectionError>
+    where
+        T: Clone,
+    {
+        let (services, _) = self
+            .collect_services(&MetadataMap::new(), package, false)
+            .await?;
+        Ok(services)
+    }
+
+    /// Lists the services in `package` on the server, failing on the first service that
+    /// cannot be resolved, or, if `lenient`, returning those services along with their errors.
+    async fn collect_services(
+        &mut self,
+        metadata: &MetadataMap,
+        package: &str,
+        lenient: bool,
+    ) -> Result<(Vec<ServiceInfo>, Vec<ServiceListError>), ReflectionError>
+    where
+        T: Clone,
+    {
+        let mut services_info = Vec::new();
+        let mut failures = Vec::new();
+        let mut fetched_files = HashSet::new();
+        let mut listed_services = HashSet::new();
+
+        let concurrency = self.list_concurrency.max(1);
+        let mut names = self
+            .service_names(metadata)
+            .await?
+            .into_iter()
+            .filter(|name| in_package(name, package))
+            .peekable();
+
+        while names.peek().is_some() {
+            // services listed along with another one defined in the same file are skipped
+            let batch: Vec<String> = names
+                .by_ref()
+                .filter(|name| !listed_services.contains(name))
+                .take(concurrency)
+                .collect();
+            let fetched = if let [name] = batch.as_slice() {
+                vec![
+                    self.file_descriptor_containing(name.clone(), metadata)
+                        .await,
+                ]
+            } else {
+                join_all(batch.iter().cloned().map(|name| {
+                    let mut client = self.clone();
+                    async move { client.file_descriptor_containing(name, metadata).await }
+                }))
+                .await
+            };
+
+            for (name, descriptors) in batch.into_iter().zip(fetched) {
+                match descriptors
+                    .and_then(|descriptors| new_services(descriptors, &mut fetched_files))
+                {
+                    Ok(services) => {
+                        for service in services {
+                            if listed_services.insert(service.full_name()) {
+                                services_info.push(service);
+                            }
+                        }
+                    }
+                    Err(error) if lenient => failures.push(ServiceListError {
+                        service: name,
+                        error,
+                    }),
+                    Err(error) => return Err(error),
+                }
+            }
+        }
+
+        services_info.sort_by(|a, b| (&a.package, &a.service).cmp(&(&b.package, &b.service)));
+        Ok((services_info, failures))
+    }
+
+    /// Retrieves the fully qualified names of the services available on the server, e.g.
+    /// `grpc.health.v1.Health`.
+    ///
+    /// Unlike [`list_services`](Self::list_services), this sends a single request and does not
+    /// fetch the file descriptors of the services, so it is much cheaper, and succeeds even if
+    /// some of the descriptors are broken. Like `list_services`, it leaves out the
+    /// infrastructure services if the client is configured to.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The response from the server is not a `ListServicesResponse`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let names = client.list_service_names().await?;
+    /// assert!(names.iter().any(|name| name == "grpc.reflection.v1alpha.ServerReflection"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn list_service_names(&mut self) -> Result<Vec<String>, ReflectionError> {
+        self.service_names(&MetadataMap::new()).await
+    }
+
+    async fn service_names(
+        &mut self,
+        metadata: &MetadataMap,
+    ) -> Result<Vec<String>, ReflectionError> {
+        let request = self.request(MessageRequest::ListServices(String::new()));
+        match self.make_request(request, metadata).await? {
+            MessageResponse::ListServicesResponse(response) => Ok(response
+                .service
+                .into_iter()
+                .map(|service| service.name)
+                .filter(|name| {
+                    self.include_infrastructure || !INFRASTRUCTURE_SERVICES.contains(&name.as_str())
+                })
+                .collect()),
+            _ => Err(ReflectionError::UnexpectedResponse {
+                expected: "ListServicesResponse",
+            }),
+        }
+    }
+
+    /// Retrieves the service `full_name` along with its methods, e.g. `shop.Orders`, fetching
+    /// only the file defining it.
+    ///
+    /// A leading dot of the name is ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The server does not know a service of that name, as a
+    ///   [`ReflectionError::TypeNotFound`] suggesting the most similar service names
+    /// - The file descriptor cannot be decoded, or the service or method names are missing
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let service = client
+    ///     .get_service("grpc.reflection.v1alpha.ServerReflection")
+    ///     .await?;
+    /// assert_eq!(service.methods[0].name, "ServerReflectionInfo");
+    ///
+    /// let error = client
+    ///     .get_service("grpc.reflection.v1alpha.ServerReflect")
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     ReflectionError::TypeNotFound { suggestions, .. }
+    ///         if suggestions == ["grpc.reflection.v1alpha.ServerReflection"]
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get_service(&mut self, full_name: &str) -> Result<ServiceInfo, ReflectionError> {
+        let name = full_name.trim_start_matches('.');
+        match self
+            .file_descriptor_containing(name.to_string(), &MetadataMap::new())
+            .await
+        {
+            Ok(files) => {
+                if let Some((_, file, service)) = lookup::services(&files)
+                    .into_iter()
+                    .find(|(service_name, ..)| service_name == name)
+                {
+                    let index = file
+                        .service
+                        .iter()
+                        .position(|candidate| std::ptr::eq(candidate, service))
+                        .unwrap_or_default();
+                    return service_info(file, index, service.clone());
+                }
+            }
+            Err(ReflectionError::NotFound { .. }) => {}
+            Err(error) => return Err(error),
+        }
+
+        let names = self.service_names(&MetadataMap::new()).await?;
+        Err(ReflectionError::TypeNotFound {
+            suggestions: lookup::suggestions(name, names.iter().map(String::as_str)),
+            name: name.to_string(),
+            kind: "service",
+        })
+    }
+
+    /// Retrieves the method at `path` along with the service defining it, fetching only the
+    /// file defining the service.
+    ///
+    /// The path is either the fully qualified name of the method, e.g.
+    /// `shop.Orders.PlaceOrder`, or its HTTP path, e.g. `/shop.Orders/PlaceOrder`, as used to
+    /// invoke it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`get_service`](Self::get_service), and:
+    /// - [`ReflectionError::InvalidMethodPath`] if the path names no service and method
+    /// - [`ReflectionError::MethodNotFound`], listing the methods of the service, if the
+    ///   service does not define the method
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let (service, method) = client
+    ///     .get_method("/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo")
+    ///     .await?;
+    /// assert_eq!(service.service, "ServerReflection");
+    /// assert_eq!(method.request, "grpc.reflection.v1alpha.ServerReflectionRequest");
+    ///
+    /// let error = client
+    ///     .get_method("grpc.reflection.v1alpha.ServerReflection.Info")
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     ReflectionError::MethodNotFound { methods, .. } if methods == ["ServerReflectionInfo"]
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get_method(
+        &mut self,
+        path: &str,
+    ) -> Result<(ServiceInfo, MethodInfo), ReflectionError> {
+        let (service_name, method_name) =
+            parse_method_path(path).ok_or_else(|| ReflectionError::InvalidMethodPath {
+                path: path.to_string(),
+            })?;
+        let service = self.get_service(service_name).await?;
+
+        match service.method(method_name) {
+            Some(method) => {
+                let method = method.clone();
+                Ok((service, method))
+            }
+            None => Err(ReflectionError::MethodNotFound {
+                service: service_name.trim_start_matches('.').to_string(),
+                method: method_name.to_string(),
+                methods: service
+                    .methods
+                    .into_iter()
+                    .map(|method| method.name)
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Retrieves the file descriptors for the specified symbol from the server.
+    ///
+    /// This function sends a `ServerReflectionRequest` to the server to fetch the
+    /// `FileDescriptorProto` for the provided symbol. It decodes the received file
+    /// descriptors and returns them as a vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - A `String` containing the fully qualified name of the symbol
+    ///   whose file descriptors are being requested.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<prost_types::FileDescriptorProto>, ReflectionError>` - A result containing
+    ///   a vector of `FileDescriptorProto` objects if the request is successful, or an error
+    ///   if the request fails or the response is not of the expected type.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails, including error responses sent by the server, which
+    ///   are returned as [`ReflectionError::Status`] with the code and message of the response.
+    /// - The server does not know the symbol, as [`ReflectionError::NotFound`].
+    /// - The response from the server is not a `FileDescriptorResponse`.
+    /// - The file descriptors cannot be decoded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let mut client = grpc_ease::reflection::ReflectionClient::new("http://localhost:50051".to_string()).await?;
+    /// let descriptors = client.get_file_descriptor("my.package.MyService".to_string()).await?;
+    /// for descriptor in descriptors {
+    ///     println!("{:?}", descriptor);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # });
+    /// ```
+    ///
+    /// Servers may split the response across several messages, in which case the file
+    /// descriptors of all of them are returned:
+    ///
+    /// ```
+    /// # use std::pin::Pin;
+    /// # use prost::Message;
+    /// # use tonic::{Request, Response, Status, Streaming};
+    /// # use tonic_reflection::pb::server_reflection_response::MessageResponse;
+    /// # use tonic_reflection::pb::server_reflection_server::{ServerReflection, ServerReflectionServer};
+    /// # use tonic_reflection::pb::{FileDescriptorResponse, ServerReflectionRequest, ServerReflectionResponse};
+    /// #
+    /// # // a reflection server answering every request with three messages
+    /// # struct SplittingServer;
+    /// #
+    /// # #[tonic::async_trait]
+    /// # impl ServerReflection for SplittingServer {
+    /// #     type ServerReflectionInfoStream = Pin<Box<
+    /// #         dyn tokio_stream::Stream<Item = Result<ServerReflectionResponse, Status>> + Send,
+    /// #     >>;
+    /// #
+    /// #     async fn server_reflection_info(
+    /// #         &self,
+    /// #         _request: Request<Streaming<ServerReflectionRequest>>,
+    /// #     ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+    /// #         let messages = ["a.proto", "b.proto", "c.proto"].map(|name| {
+    /// #             let file = prost_types::FileDescriptorProto {
+    /// #                 name: Some(name.to_string()),
+    /// #                 ..Default::default()
+    /// #             };
+    /// #             Ok(ServerReflectionResponse {
+    /// #                 valid_host: String::new(),
+    /// #                 original_request: None,
+    /// #                 message_response: Some(MessageResponse::FileDescriptorResponse(
+    /// #                     FileDescriptorResponse {
+    /// #                         file_descriptor_proto: vec![file.encode_to_vec()],
+    /// #                     },
+    /// #                 )),
+    /// #             })
+    /// #         });
+    /// #         Ok(Response::new(Box::pin(tokio_stream::iter(messages))))
+    /// #     }
+    /// # }
+    /// #
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(ServerReflectionServer::new(SplittingServer)),
+    /// # )
+    /// # .await?;
+    /// let mut client = grpc_ease::reflection::ReflectionClient::new(endpoint).await?;
+    /// let descriptors = client.get_file_descriptor("my.package.MyService".to_string()).await?;
+    /// let names: Vec<_> = descriptors.iter().map(|descriptor| descriptor.name()).collect();
+    /// assert_eq!(names, ["a.proto", "b.proto", "c.proto"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// Servers answering with an error response instead of failing the call are handled the
+    /// same way:
+    ///
+    /// ```
+    /// # use std::pin::Pin;
+    /// # use tonic::{Request, Response, Status, Streaming};
+    /// # use tonic_reflection::pb::server_reflection_response::MessageResponse;
+    /// # use tonic_reflection::pb::server_reflection_server::{ServerReflection, ServerReflectionServer};
+    /// # use tonic_reflection::pb::{ErrorResponse, ServerReflectionRequest, ServerReflectionResponse};
+    /// use grpc_ease::error::ReflectionError;
+    /// #
+    /// # // a reflection server answering every request with a NOT_FOUND error response
+    /// # struct NotFoundServer;
+    /// #
+    /// # #[tonic::async_trait]
+    /// # impl ServerReflection for NotFoundServer {
+    /// #     type ServerReflectionInfoStream = Pin<Box<
+    /// #         dyn tokio_stream::Stream<Item = Result<ServerReflectionResponse, Status>> + Send,
+    /// #     >>;
+    /// #
+    /// #     async fn server_reflection_info(
+    /// #         &self,
+    /// #         _request: Request<Streaming<ServerReflectionRequest>>,
+    /// #     ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+    /// #         let response = ServerReflectionResponse {
+    /// #             valid_host: String::new(),
+    /// #             original_request: None,
+    /// #             message_response: Some(MessageResponse::ErrorResponse(ErrorResponse {
+    /// #                 error_code: tonic::Code::NotFound as i32,
+    /// #                 error_message: "symbol not found".to_string(),
+    /// #             })),
+    /// #         };
+    /// #         Ok(Response::new(Box::pin(tokio_stream::once(Ok(response)))))
+    /// #     }
+    /// # }
+    /// #
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(ServerReflectionServer::new(NotFoundServer)),
+    /// # )
+    /// # .await?;
+    /// let mut client = grpc_ease::reflection::ReflectionClient::new(endpoint).await?;
+    /// let error = client
+    ///     .get_file_descriptor("does.not.Exist".to_string())
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::NotFound { name } if name == "does.not.Exist"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get_file_descriptor(
+        &mut self,
+        symbol: String,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        self.file_descriptor_containing(symbol, &MetadataMap::new())
+            .await
+    }
+
+    /// Retrieves the file descriptor of the file with the given name, such as an entry of the
+    /// `dependency` list of another file descriptor.
+    ///
+    /// Depending on the server, the response may also contain the descriptors of the file's
+    /// transitive dependencies, which are not returned by other requests yet.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The server does not know the file, as [`ReflectionError::NotFound`]
+    /// - The response from the server is not a `FileDescriptorResponse`
+    /// - The file descriptors cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let descriptors = client
+    ///     .get_file_by_filename("reflection.proto".to_string())
+    ///     .await?;
+    /// assert_eq!(descriptors[0].package(), "grpc.reflection.v1alpha");
+    ///
+    /// let error = client
+    ///     .get_file_by_filename("missing.proto".to_string())
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::NotFound { name } if name == "missing.proto"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get_file_by_filename(
+        &mut self,
+        name: String,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        self.file_descriptors(
+            MessageRequest::FileByFilename(name.clone()),
+            name,
+            &MetadataMap::new(),
+        )
+        .await
+    }
+
+    /// Retrieves the file descriptor of the file containing `symbol` along with the
+    /// descriptors of all files it imports, directly or transitively.
+    ///
+    /// Many servers only send the file containing the symbol, so the imports missing from the
+    /// response are requested by file name until all of them are known. Each file is returned
+    /// once, and dependencies come before the files importing them, which is the order
+    /// descriptor pools expect files to be added in.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - Any of the requests to the server fails
+    /// - The server does not know the symbol or one of the imported files, as
+    ///   [`ReflectionError::NotFound`]
+    /// - A file descriptor has no name, or the imports form a cycle, as
+    ///   [`ReflectionError::MalformedDescriptor`]
+    /// - The file descriptors cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+    ///
+    /// // a.proto imports b.proto, which imports c.proto
+    /// let file = |name: &str, dependency: &[&str]| FileDescriptorProto {
+    ///     name: Some(format!("{}.proto", name)),
+    ///     package: Some("example".to_string()),
+    ///     dependency: dependency.iter().map(|name| format!("{}.proto", name)).collect(),
+    ///     message_type: vec![DescriptorProto {
+    ///         name: Some(name.to_uppercase()),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// let files = FileDescriptorSet {
+    ///     file: vec![file("c", &[]), file("b", &["c"]), file("a", &["b", "c"])],
+    /// };
+    ///
+    /// # let endpoint = support::serve_files(files.file).await?;
+    ///
+    /// // tonic's reflection server only sends the file containing the symbol
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let descriptors = client
+    ///     .get_file_descriptors_transitive("example.A".to_string())
+    ///     .await?;
+    /// let names: Vec<_> = descriptors.iter().map(|descriptor| descriptor.name()).collect();
+    /// assert_eq!(names, ["c.proto", "b.proto", "a.proto"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get_file_descriptors_transitive(
+        &mut self,
+        symbol: String,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        let mut resolver = DependencyResolver::new();
+        let mut files = self.get_file_descriptor(symbol).await?.into_iter();
+        // the first file defines the symbol, any further ones are only kept if it imports them
+        if let Some(file) = files.next() {
+            resolver.add_seed(file)?;
+        }
+        for file in files {
+            resolver.add(file)?;
+        }
+
+        self.resolve(&mut resolver).await?;
+        resolver.into_sorted()
+    }
+
+    /// Builds a descriptor pool from the file defining `symbol` and the files it imports,
+    /// directly or transitively, as fetched by
+    /// [`get_file_descriptors_transitive`](Self::get_file_descriptors_transitive).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`get_file_descriptors_transitive`](Self::get_file_descriptors_transitive), naming the
+    /// unresolvable import if the server does not know an imported file, and
+    /// [`ReflectionError::MalformedDescriptor`] if the files do not form a valid pool, e.g.
+    /// because a type they refer to is not defined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_reflect::DynamicMessage;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let pool = client
+    ///     .descriptor_pool_for("grpc.reflection.v1alpha.ServerReflection")
+    ///     .await?;
+    /// let request = pool
+    ///     .get_message_by_name("grpc.reflection.v1alpha.ServerReflectionRequest")
+    ///     .unwrap();
+    /// let mut message = DynamicMessage::new(request);
+    /// message.set_field_by_name(
+    ///     "list_services",
+    ///     prost_reflect::Value::String(String::new()),
+    /// );
+    /// assert!(message.has_field_by_name("list_services"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn descriptor_pool_for(
+        &mut self,
+        symbol: &str,
+    ) -> Result<prost_reflect::DescriptorPool, ReflectionError> {
+        let files = self
+            .get_file_descriptors_transitive(symbol.trim_start_matches('.').to_string())
+            .await?;
+        descriptor_pool(files)
+    }
+
+    /// Builds a descriptor pool from the files defining the services of the server and the
+    /// files they import, directly or transitively, fetching each file once.
+    ///
+    /// Services left out of listings, like the infrastructure services if the client is
+    /// built with
+    /// [`include_infrastructure_services(false)`](crate::builder::ReflectionClientBuilder::include_infrastructure_services),
+    /// are left out of the pool as well, unless another file imports their files.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`descriptor_pool_for`](Self::descriptor_pool_for), and if the services cannot be
+    /// listed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, FileDescriptorSet, ServiceDescriptorProto};
+    ///
+    /// // two services in files importing the same file, which is added to the pool once
+    /// let file = |name: &str, dependency: &[&str], service: Option<&str>| FileDescriptorProto {
+    ///     name: Some(format!("{}.proto", name)),
+    ///     package: Some("example".to_string()),
+    ///     dependency: dependency.iter().map(|name| format!("{}.proto", name)).collect(),
+    ///     service: service
+    ///         .into_iter()
+    ///         .map(|service| ServiceDescriptorProto {
+    ///             name: Some(service.to_string()),
+    ///             ..Default::default()
+    ///         })
+    ///         .collect(),
+    ///     ..Default::default()
+    /// };
+    /// let files = FileDescriptorSet {
+    ///     file: vec![
+    ///         file("common", &[], None),
+    ///         file("orders", &["common"], Some("Orders")),
+    ///         file("billing", &["common"], Some("Billing")),
+    ///     ],
+    /// };
+    ///
+    /// # let endpoint = support::serve_files(files.file).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let pool = client.descriptor_pool_all().await?;
+    /// let mut files: Vec<_> = pool.files().map(|file| file.name().to_string()).collect();
+    /// files.sort();
+    /// // tonic's reflection server lists its own service as well
+    /// assert_eq!(
+    ///     files,
+    ///     ["billing.proto", "common.proto", "orders.proto", "reflection.proto"]
+    /// );
+    /// assert!(pool.get_service_by_name("example.Orders").is_some());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn descriptor_pool_all(
+        &mut self,
+    ) -> Result<prost_reflect::DescriptorPool, ReflectionError> {
+        let files = self.get_all_file_descriptors().await?;
+        descriptor_pool(files)
+    }
+
+    /// Calls the unary method `method`, e.g. `/shop.Orders/Place`, with the JSON encoding of
+    /// its request message, returning the canonical protobuf JSON encoding of the response.
+    ///
+    /// The request and response types are resolved by reflection, so the method can be called
+    /// without generated code. The call is sent over the connection of the client, with its
+    /// metadata, credentials and interceptor, and the metadata of `options` on top. If the
+    /// call fails, it is retried according to the retry policy of `options`, if any.
+    /// Once the call completes, a `call completed` tracing event at the debug level reports
+    /// its status, duration, time to the first response and request and response sizes.
+    ///
+    /// Well-known types like `Timestamp` take their canonical JSON forms, unless the
+    /// [`JsonMapping::Raw`] of `options` maps them field by field for debugging. An `Any` is
+    /// its message with an `@type` field, whose type is resolved through reflection if the
+    /// descriptors of the method do not define it, or only its `@type` and base64 encoded
+    /// `value` if the server cannot describe the type either.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The method path is invalid, as [`ReflectionError::InvalidMethodPath`]
+    /// - The server does not know the service, or its descriptors cannot be retrieved, like
+    ///   with [`descriptor_pool_for`](Self::descriptor_pool_for)
+    /// - The service has no such method, as [`ReflectionError::MethodNotFound`]
+    /// - The method streams its requests or responses, as
+    ///   [`ReflectionError::StreamingMismatch`]
+    /// - The request is not a JSON encoding of the request message, or the response has no
+    ///   JSON encoding, as [`ReflectionError::InvalidJson`]
+    /// - The call fails, as [`ReflectionError::Call`] holding its status, or
+    ///   [`ReflectionError::Retried`] if it was retried
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use serde_json::json;
+    /// use tonic::Code;
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let response = client
+    ///     .call_unary(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         json!({ "service": "" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(response, json!({ "status": "SERVING" }));
+    ///
+    /// // the server does not know the service whose health is checked
+    /// let error = client
+    ///     .call_unary(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         json!({ "service": "shop.Orders" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::Call { status, .. } if status.code() == Code::NotFound));
+    ///
+    /// let error = client
+    ///     .call_unary(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         json!({ "serviceName": "" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::InvalidJson { .. }));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    ///
+    /// A response holding `Any`s of a type the server describes among its other services, and
+    /// of one it does not know:
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost::Message;
+    /// use serde_json::json;
+    /// use tonic_reflection::pb::{ListServiceResponse, ServiceResponse};
+    ///
+    /// let services = ListServiceResponse {
+    ///     service: vec![ServiceResponse { name: "shop.Orders".to_string() }],
+    /// };
+    /// let reply = support::Reply {
+    ///     text: "placed".to_string(),
+    ///     details: vec![
+    ///         prost_types::Any {
+    ///             type_url: "type.googleapis.com/grpc.reflection.v1alpha.ListServiceResponse"
+    ///                 .to_string(),
+    ///             value: services.encode_to_vec(),
+    ///         },
+    ///         prost_types::Any {
+    ///             type_url: "type.googleapis.com/shop.Secret".to_string(),
+    ///             value: vec![0x08, 0x2a],
+    ///         },
+    ///     ],
+    /// };
+    /// // service Fixed {
+    /// //   rpc Get(Reply) returns (Reply);
+    /// // }
+    /// //
+    /// // message Reply {
+    /// //   string text = 1;
+    /// //   repeated google.protobuf.Any details = 2;
+    /// // }
+    /// # let endpoint = support::serve_fixed(support::Fixed::new(reply)).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let response = client
+    ///     .call_unary("/fixture.Fixed/Get", json!({}), CallOptions::default())
+    ///     .await?;
+    /// assert_eq!(
+    ///     response,
+    ///     json!({
+    ///         "text": "placed",
+    ///         "details": [
+    ///             {
+    ///                 "@type": "type.googleapis.com/grpc.reflection.v1alpha.ListServiceResponse",
+    ///                 "service": [{ "name": "shop.Orders" }],
+    ///             },
+    ///             { "@type": "type.googleapis.com/shop.Secret", "value": "CCo=" },
+    ///         ],
+    ///     })
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_unary(
+        &mut self,
+        method: &str,
+        request: serde_json::Value,
+        options: CallOptions,
+    ) -> Result<serde_json::Value, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        let (mapping, bytes) = (options.json_mapping, options.bytes_encoding);
+        let request = options.request_from_json(method.input(), request)?;
+        let response = self.call_unary_dynamic(&method, request, options).await?;
+        self.response_to_json(response, mapping, bytes).await
+    }
+
+    /// Calls the unary method `method` like [`call_unary`](Self::call_unary), with the request
+    /// and response in the protobuf text format, e.g. as stored in textproto fixtures.
+    ///
+    /// The response is rendered by [`MessageFormat::render`] and other formats can be mixed
+    /// in with [`call_unary_formatted`](Self::call_unary_formatted).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`call_unary`](Self::call_unary), except
+    /// that a request that is not a message of the request type in the text format is a
+    /// [`ReflectionError::InvalidTextFormat`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::invoke::{CallOptions, MessageFormat};
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let response = client
+    ///     .call_unary_text(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         r#"service: """#,
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(response, "status: SERVING");
+    ///
+    /// // text format in, JSON out
+    /// let response = client
+    ///     .call_unary_formatted(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         r#"service: """#,
+    ///         MessageFormat::Text,
+    ///         MessageFormat::Json,
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(response, "{\n  \"status\": \"SERVING\"\n}");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_unary_text(
+        &mut self,
+        method: &str,
+        request: &str,
+        options: CallOptions,
+    ) -> Result<String, ReflectionError>
+    where
+        T: Clone,
+    {
+        let text = MessageFormat::Text;
+        self.call_unary_formatted(method, request, text, text, options)
+            .await
+    }
+
+    /// Calls the unary method `method` like [`call_unary`](Self::call_unary), with the request
+    /// parsed in the format `input` and the response rendered in the format `output`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`call_unary`](Self::call_unary), and
+    /// those of [`MessageFormat::parse`] and [`MessageFormat::render`].
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_unary_formatted(
+        &mut self,
+        method: &str,
+        request: &str,
+        input: MessageFormat,
+        output: MessageFormat,
+        options: CallOptions,
+    ) -> Result<String, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        let request = input.parse(method.input(), request)?;
+        let response = self.call_unary_dynamic(&method, request, options).await?;
+        output.render(&response)
+    }
+
+    /// Calls the unary method `method` like [`call_unary`](Self::call_unary), returning the
+    /// response along with the headers, trailers, status and duration of the call, e.g. to
+    /// debug authentication or routing.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`call_unary`](Self::call_unary), except
+    /// that a call failing with a status returns a [`CallResponse`] holding it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use serde_json::json;
+    /// use tonic::Code;
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let response = client
+    ///     .call_unary_with_response(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         json!({ "service": "" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(response.status, Code::Ok);
+    /// assert_eq!(response.message, json!({ "status": "SERVING" }));
+    /// assert_eq!(response.headers.get("content-type").unwrap(), "application/grpc");
+    /// // the empty request encodes to nothing, the status to two bytes
+    /// assert_eq!((response.request_size, response.response_size), (0, 2));
+    /// assert!(response.time_to_first_response.unwrap() <= response.duration);
+    ///
+    /// let response = client
+    ///     .call_unary_with_response(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         json!({ "service": "shop.Orders" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(response.status, Code::NotFound);
+    /// assert_eq!(response.message, json!(null));
+    /// assert_eq!(response.trailers.get("grpc-status").unwrap(), "5");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_unary_with_response(
+        &mut self,
+        method: &str,
+        request: serde_json::Value,
+        options: CallOptions,
+    ) -> Result<CallResponse, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        let (path, path_and_query) = call_path(&method, false, false)?;
+        let request = options.request_from_json(method.input(), request)?;
+        let request_size = request.encoded_len();
+
+        let deadline = self.deadline(&options);
+        let stopwatch = Stopwatch::start();
+        // a server streaming call keeps the headers apart from the trailers
+        let response = self
+            .send_call(&path, &options, deadline, request, |mut client, request| {
+                let path_and_query = path_and_query.clone();
+                let codec = DynamicCodec::new(method.output());
+                async move {
+                    client
+                        .server_streaming(request, path_and_query, codec)
+                        .await
+                }
+            })
+            .await?;
+        let (mapping, bytes) = (options.json_mapping, options.bytes_encoding);
+        let mut response = CallResponse::collect(response, stopwatch, deadline, |message| {
+            self.response_to_json(message, mapping, bytes)
+        })
+        .await?;
+        response.request_size = request_size;
+        response.trace(&path);
+        Ok(response)
+    }
+
+    /// Calls the unary method `method` `n` times with the same JSON `request`, at most
+    /// `concurrency` calls at a time, returning the statistics of the calls, e.g. for a quick
+    /// check of the latency of a server under load.
+    ///
+    /// The method is resolved and the request converted once, and each call is sent like by
+    /// [`call_unary_dynamic`](Self::call_unary_dynamic), on clones of the client sharing its
+    /// connection. Failed calls are counted by their status code rather than ending the batch.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the method cannot be resolved or the request
+    /// cannot be converted, like [`call_unary`](Self::call_unary) does, without calling it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use serde_json::json;
+    /// use tonic::Code;
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let check = "/grpc.health.v1.Health/Check";
+    /// let stats = client
+    ///     .call_unary_n(check, json!({}), 20, 4, CallOptions::default())
+    ///     .await?;
+    /// assert_eq!(stats.calls, 20);
+    /// assert_eq!(stats.latencies.len(), 20);
+    /// assert!(stats.p50() <= stats.p99());
+    ///
+    /// // the health service does not know the service
+    /// let request = json!({ "service": "shop.Orders" });
+    /// let stats = client
+    ///     .call_unary_n(check, request, 5, 2, CallOptions::default())
+    ///     .await?;
+    /// assert_eq!(stats.failed(), 5);
+    /// assert_eq!(stats.errors[&Code::NotFound], 5);
+    /// assert_eq!(stats.p50(), None);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_unary_n(
+        &mut self,
+        method: &str,
+        request: serde_json::Value,
+        n: usize,
+        concurrency: usize,
+        options: CallOptions,
+    ) -> Result<CallStats, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        call_path(&method, false, false)?;
+        let request = options.request_from_json(method.input(), request)?;
+
+        let stopwatch = Stopwatch::start();
+        let calls = (0..n).map(|_| {
+            let mut client = self.clone();
+            let (method, request, options) = (&method, request.clone(), options.clone());
+            async move {
+                let stopwatch = Stopwatch::start();
+                let response = client.call_unary_dynamic(method, request, options).await;
+                (response.map(drop), stopwatch.elapsed())
+            }
+        });
+        let mut calls = futures_util::StreamExt::buffer_unordered(
+            futures_util::stream::iter(calls),
+            concurrency.max(1),
+        );
+
+        let mut stats = CallStats::default();
+        while let Some((response, latency)) = calls.next().await {
+            stats.calls += 1;
+            match response {
+                Ok(()) => stats.latencies.push(latency),
+                Err(error) => *stats.errors.entry(error_code(&error)).or_default() += 1,
+            }
+        }
+        stats.latencies.sort();
+        stats.duration = stopwatch.elapsed();
+        Ok(stats)
+    }
+
+    /// Resolves the method of the path `path`, e.g. `/shop.Orders/Place`, to its descriptor,
+    /// e.g. for [`call_unary_dynamic`](Self::call_unary_dynamic).
+    ///
+    /// The descriptor belongs to a pool built like by
+    /// [`descriptor_pool_for`](Self::descriptor_pool_for) for the service, which holds the
+    /// request and response types as well.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The method path is invalid, as [`ReflectionError::InvalidMethodPath`]
+    /// - The server does not know the service, or its descriptors cannot be retrieved, like
+    ///   with [`descriptor_pool_for`](Self::descriptor_pool_for)
+    /// - The service has no such method, as [`ReflectionError::MethodNotFound`]
+    #[cfg(feature = "prost-reflect")]
+    pub async fn method_descriptor(
+        &mut self,
+        path: &str,
+    ) -> Result<prost_reflect::MethodDescriptor, ReflectionError> {
+        let (service_name, method_name) =
+            parse_method_path(path).ok_or_else(|| ReflectionError::InvalidMethodPath {
+                path: path.to_string(),
+            })?;
+        let service_name = service_name.trim_start_matches('.');
+        let pool = self.descriptor_pool_for(service_name).await?;
+        let service =
+            pool.get_service_by_name(service_name)
+                .ok_or_else(|| ReflectionError::NotFound {
+                    name: service_name.to_string(),
+                })?;
+
+        let method = service
+            .methods()
+            .find(|method| method.name() == method_name);
+        method.ok_or_else(|| ReflectionError::MethodNotFound {
+            service: service_name.to_string(),
+            method: method_name.to_string(),
+            methods: service
+                .methods()
+                .map(|method| method.name().to_string())
+                .collect(),
+        })
+    }
+
+    /// Calls the unary `method` with the message `request`, returning the response message,
+    /// like [`call_unary`](Self::call_unary) without converting from and to JSON.
+    ///
+    /// Messages are encoded and decoded as they are, so values JSON cannot represent exactly,
+    /// like NaN floats, unknown enum numbers or unknown fields, are kept.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The method streams its requests or responses, as
+    ///   [`ReflectionError::StreamingMismatch`]
+    /// - `request` is not a message of the request type of the method, as
+    ///   [`ReflectionError::MessageTypeMismatch`]
+    /// - The call fails, as [`ReflectionError::Call`] holding its status
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_reflect::{DynamicMessage, Value};
+    /// use tonic_health::pb::health_check_response::ServingStatus;
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let check = client.method_descriptor("/grpc.health.v1.Health/Check").await?;
+    /// let mut request = DynamicMessage::new(check.input());
+    /// request.set_field_by_name("service", Value::String(String::new()));
+    ///
+    /// let response = client
+    ///     .call_unary_dynamic(&check, request, CallOptions::default())
+    ///     .await?;
+    /// assert_eq!(
+    ///     response.get_field_by_name("status").unwrap().as_enum_number(),
+    ///     Some(ServingStatus::Serving as i32)
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_unary_dynamic(
+        &mut self,
+        method: &prost_reflect::MethodDescriptor,
+        request: prost_reflect::DynamicMessage,
+        options: CallOptions,
+    ) -> Result<prost_reflect::DynamicMessage, ReflectionError>
+    where
+        T: Clone,
+    {
+        let (path, path_and_query) = call_path(method, false, false)?;
+        if request.descriptor() != method.input() {
+            return Err(ReflectionError::MessageTypeMismatch {
+                expected: method.input().full_name().to_string(),
+                actual: request.descriptor().full_name().to_string(),
+            });
+        }
+
+        // retries send the request as it was encoded for the first attempt
+        let request = Bytes::from(request.encode_to_vec());
+        let idempotency_level = method
+            .method_descriptor_proto()
+            .options
+            .as_ref()
+            .map_or(IdempotencyLevel::Unknown, |options| {
+                options.idempotency_level().into()
+            });
+        let stopwatch = Stopwatch::start();
+        let response = retrying(&path, options.retry.as_ref(), idempotency_level, || {
+            let deadline = self.deadline(&options);
+            self.send_call(
+                &path,
+                &options,
+                deadline,
+                request.clone(),
+                |mut client, request| {
+                    let path_and_query = path_and_query.clone();
+                    let codec = EncodedCodec::new(method.output());
+                    async move { client.unary(request, path_and_query, codec).await }
+                },
+            )
+        })
+        .await?;
+        match response {
+            Ok(response) => {
+                let response = response.into_inner();
+                let elapsed = stopwatch.elapsed();
+                let response_size = response.encoded_len();
+                invoke::trace_call(
+                    &path,
+                    Code::Ok,
+                    elapsed,
+                    Some(elapsed),
+                    request.len(),
+                    response_size,
+                );
+                Ok(response)
+            }
+            Err((status, attempts)) => {
+                let elapsed = stopwatch.elapsed();
+                invoke::trace_call(&path, status.code(), elapsed, None, request.len(), 0);
+                Err(self.retried_error(path, status, attempts).await)
+            }
+        }
+    }
+
+    /// Calls the server streaming method `method`, e.g. `/shop.Orders/Watch`, with the JSON
+    /// encoding of its request message, returning the stream of the canonical protobuf JSON
+    /// encodings of the responses.
+    ///
+    /// The call is sent like by [`call_unary`](Self::call_unary). Each response is decoded and
+    /// converted to JSON as the stream is polled, so responses are not buffered. If the call
+    /// fails while streaming, the stream ends with the status of the call, whose metadata
+    /// holds the trailers sent by the server; if it succeeds, the trailers are available from
+    /// [`JsonStream::trailers`] after the end of the stream. Dropping the stream cancels the
+    /// call, as does the [`CallHandle`](invoke::CallHandle) of [`JsonStream::handle`].
+    ///
+    /// Unlike for [`call_unary`](Self::call_unary), the types of `Any`s are not resolved
+    /// through reflection, so `Any`s of types the descriptors of the method do not define are
+    /// only their `@type` and base64 encoded `value`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`call_unary`](Self::call_unary), and
+    /// [`ReflectionError::StreamingMismatch`] if the server does not stream the responses of
+    /// the method or the client streams its requests, without calling it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use serde_json::json;
+    /// use tokio_stream::StreamExt;
+    /// use tonic_health::ServingStatus;
+    ///
+    /// let (mut reporter, health) = tonic_health::server::health_reporter();
+    /// reporter
+    ///     .set_service_status("shop.Orders", ServingStatus::Serving)
+    ///     .await;
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let mut statuses = client
+    ///     .call_server_streaming(
+    ///         "/grpc.health.v1.Health/Watch",
+    ///         json!({ "service": "shop.Orders" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(statuses.next().await.unwrap()?, json!({ "status": "SERVING" }));
+    ///
+    /// reporter
+    ///     .set_service_status("shop.Orders", ServingStatus::NotServing)
+    ///     .await;
+    /// assert_eq!(statuses.next().await.unwrap()?, json!({ "status": "NOT_SERVING" }));
+    ///
+    /// // checking the health is a unary call
+    /// let error = client
+    ///     .call_server_streaming(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         json!({ "service": "shop.Orders" }),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     ReflectionError::StreamingMismatch { server_streaming: false, .. }
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_server_streaming(
+        &mut self,
+        method: &str,
+        request: serde_json::Value,
+        options: CallOptions,
+    ) -> Result<JsonStream, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        call_path(&method, false, true)?;
+        let request = options.request_from_json(method.input(), request)?;
+        self.send_server_streaming(&method, request, options).await
+    }
+
+    /// Sends the server streaming call of `method` with the converted `request`.
+    #[cfg(feature = "prost-reflect")]
+    async fn send_server_streaming(
+        &mut self,
+        method: &prost_reflect::MethodDescriptor,
+        request: prost_reflect::DynamicMessage,
+        options: CallOptions,
+    ) -> Result<JsonStream, ReflectionError>
+    where
+        T: Clone,
+    {
+        let (path, path_and_query) = call_path(method, false, true)?;
+        let request_size = request.encoded_len();
+
+        let deadline = self.deadline(&options);
+        let stopwatch = Stopwatch::start();
+        let response = self
+            .send_call(&path, &options, deadline, request, |mut client, request| {
+                let path_and_query = path_and_query.clone();
+                let codec = DynamicCodec::new(method.output());
+                async move {
+                    client
+                        .server_streaming(request, path_and_query, codec)
+                        .await
+                }
+            })
+            .await?;
+        match response {
+            Ok(response) => Ok(JsonStream::new(
+                response,
+                deadline,
+                options.json_mapping,
+                options.bytes_encoding,
+                path,
+                stopwatch,
+                request_size,
+            )),
+            Err(status) => {
+                let elapsed = stopwatch.elapsed();
+                invoke::trace_call(&path, status.code(), elapsed, None, request_size, 0);
+                Err(self.call_error(path, status).await)
+            }
+        }
+    }
+
+    /// Calls the client streaming method `method`, e.g. `/storage.Files/Upload`, with the
+    /// JSON encodings of its request messages, returning the canonical protobuf JSON encoding
+    /// of the response.
+    ///
+    /// The call is sent like by [`call_unary`](Self::call_unary). Each request is converted
+    /// to its message as the call sends it, and the call completes once `requests` ends. A
+    /// request that cannot be converted cancels the call rather than ending the stream early,
+    /// so the server does not handle the requests before it as a complete upload. Requests
+    /// in a file or stdin can be streamed with
+    /// [`call_client_streaming_json_lines`](Self::call_client_streaming_json_lines).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`call_unary`](Self::call_unary), and:
+    /// - [`ReflectionError::StreamingMismatch`] if the client of the method does not stream
+    ///   its requests or the server streams its responses, without calling it
+    /// - [`ReflectionError::InvalidStreamRequest`] with the index of the first request that
+    ///   is not a JSON encoding of the request message
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use serde_json::json;
+    ///
+    /// // service Files {
+    /// //   rpc Upload(stream Chunk) returns (Summary);
+    /// // }
+    /// # let endpoint = support::serve_storage().await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// // "hello" and "world", encoded as base64
+    /// let chunks = vec![json!({ "data": "aGVsbG8=" }), json!({ "data": "d29ybGQ=" })];
+    /// let summary = client
+    ///     .call_client_streaming(
+    ///         "/storage.Files/Upload",
+    ///         tokio_stream::iter(chunks),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(summary, json!({ "size": "10" }));
+    ///
+    /// let chunks = vec![json!({ "data": "aGVsbG8=" }), json!({ "size": 5 })];
+    /// let error = client
+    ///     .call_client_streaming(
+    ///         "/storage.Files/Upload",
+    ///         tokio_stream::iter(chunks),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::InvalidStreamRequest { index: 1, .. }));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_client_streaming(
+        &mut self,
+        method: &str,
+        requests: impl tokio_stream::Stream<Item = serde_json::Value> + Send + 'static,
+        options: CallOptions,
+    ) -> Result<serde_json::Value, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        let (requests, aborted) = invoke::from_json_stream(method.input(), requests, &options);
+        self.send_client_streaming(&method, requests, aborted, options)
+            .await
+    }
+
+    /// Calls the client streaming method `method`, e.g. `/storage.Files/Upload`, with the
+    /// requests read from JSON Lines, returning the canonical protobuf JSON encoding of the
+    /// response.
+    ///
+    /// The call is sent like by [`call_client_streaming`](Self::call_client_streaming). The
+    /// lines are read and converted as the call sends them, throttled if `lines` is, and a
+    /// line that cannot be read or converted cancels the call.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`call_client_streaming`](Self::call_client_streaming), except that requests that
+    /// cannot be converted are reported as [`ReflectionError::InvalidJsonLine`] with the
+    /// number of their line, and:
+    /// - [`ReflectionError::ReadJsonLines`] if reading the lines fails
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::invoke::{CallOptions, JsonLines};
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use serde_json::json;
+    ///
+    /// // service Files {
+    /// //   rpc Upload(stream Chunk) returns (Summary);
+    /// // }
+    /// # let endpoint = support::serve_storage().await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// // "hello" and "world", encoded as base64
+    /// let chunks = "{\"data\": \"aGVsbG8=\"}\n\n{\"data\": \"d29ybGQ=\"}\n";
+    /// let summary = client
+    ///     .call_client_streaming_json_lines(
+    ///         "/storage.Files/Upload",
+    ///         JsonLines::new(chunks.as_bytes()).throttle(100),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// assert_eq!(summary, json!({ "size": "10" }));
+    ///
+    /// let chunks = "{\"data\": \"aGVsbG8=\"}\n\n{\"data\": \"d29ybGQ=\"\n";
+    /// let error = client
+    ///     .call_client_streaming_json_lines(
+    ///         "/storage.Files/Upload",
+    ///         JsonLines::new(chunks.as_bytes()),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::InvalidJsonLine { line: 3, .. }));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_client_streaming_json_lines<R>(
+        &mut self,
+        method: &str,
+        lines: JsonLines<R>,
+        options: CallOptions,
+    ) -> Result<serde_json::Value, ReflectionError>
+    where
+        T: Clone,
+        R: tokio::io::AsyncRead + Unpin + Send + 'static,
+    {
+        let method = self.method_descriptor(method).await?;
+        let (requests, aborted) = lines.into_requests(method.input(), &options);
+        self.send_client_streaming(&method, requests, aborted, options)
+            .await
+    }
+
+    /// Sends the client streaming call of `method` with the converted `requests`, until it
+    /// completes or a request cannot be converted, as reported by `aborted`.
+    #[cfg(feature = "prost-reflect")]
+    async fn send_client_streaming(
+        &mut self,
+        method: &prost_reflect::MethodDescriptor,
+        requests: impl tokio_stream::Stream<Item = prost_reflect::DynamicMessage> + Send + 'static,
+        aborted: tokio::sync::oneshot::Receiver<ReflectionError>,
+        options: CallOptions,
+    ) -> Result<serde_json::Value, ReflectionError>
+    where
+        T: Clone,
+    {
+        let (path, path_and_query) = call_path(method, true, false)?;
+        let (requests, request_size) = invoke::counting_sizes(requests);
+
+        let deadline = self.deadline(&options);
+        let stopwatch = Stopwatch::start();
+        let codec = DynamicCodec::new(method.output());
+        let call = self.send_once(
+            &path,
+            &options,
+            deadline,
+            requests,
+            |mut client, request| async move {
+                client
+                    .client_streaming(request, path_and_query, codec)
+                    .await
+            },
+        );
+        let response = invoke::until_aborted(call, aborted).await?;
+        let elapsed = stopwatch.elapsed();
+        let request_size = request_size.load(Ordering::Relaxed);
+        match response {
+            Ok(response) => {
+                let response = response.into_inner();
+                let response_size = response.encoded_len();
+                invoke::trace_call(
+                    &path,
+                    Code::Ok,
+                    elapsed,
+                    Some(elapsed),
+                    request_size,
+                    response_size,
+                );
+                self.response_to_json(response, options.json_mapping, options.bytes_encoding)
+                    .await
+            }
+            Err(status) => {
+                invoke::trace_call(&path, status.code(), elapsed, None, request_size, 0);
+                Err(self.call_error(path, status).await)
+            }
+        }
+    }
+
+    /// Calls the client streaming method `method` like
+    /// [`call_client_streaming`](Self::call_client_streaming), returning the response along
+    /// with the headers, trailers and status of the call.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`call_client_streaming`](Self::call_client_streaming), except that a call failing
+    /// with a status returns a [`CallResponse`] holding it.
+    #[cfg(feature = "prost-reflect")]
+    pub async fn call_client_streaming_with_response(
+        &mut self,
+        method: &str,
+        requests: impl tokio_stream::Stream<Item = serde_json::Value> + Send + 'static,
+        options: CallOptions,
+    ) -> Result<CallResponse, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.method_descriptor(method).await?;
+        let (path, path_and_query) = call_path(&method, true, false)?;
+        let (requests, aborted) = invoke::from_json_stream(method.input(), requests, &options);
+        let (requests, request_size) = invoke::counting_sizes(requests);
+
+        let deadline = self.deadline(&options);
+        let stopwatch = Stopwatch::start();
+        let codec = DynamicCodec::new(method.output());
+        // a bidirectional streaming call keeps the headers apart from the trailers, and the
+        // requests may still be converted while the response is read
+        let call = async {
+            let response = self
+                .send_once(
+                    &path,
+                    &options,
+                    deadline,
+                    requests,
+                    |mut client, request| async move {
+                        client.streaming(request, path_and_query, codec).await
+                    },
+                )
+                .await?;
+            let (mapping, bytes) = (options.json_mapping, options.bytes_encoding);
+            CallResponse::collect(response, stopwatch, deadline, |message| {
+                self.response_to_json(message, mapping, bytes)
+            })
+            .await
+        };
+        let mut response = invoke::until_aborted(call, aborted).await?;
+        response.request_size = request_size.load(Ordering::Relaxed);
+        response.trace(&path);
+        Ok(response)
+    }
+
+    /// Checks the health of the server, or of its `service` if some, e.g. `shop.Orders`,
+    /// through the standard health checking service, `grpc.health.v1.Health`.
+    ///
+    /// The `Check` method is called dynamically, like by
+    /// [`call_unary_dynamic`](Self::call_unary_dynamic), with the descriptors the server
+    /// reflects, or those bundled with this crate if it does not reflect them. A service the
+    /// health checking service does not know, which it reports with `NOT_FOUND`, is
+    /// [`HealthStatus::ServiceUnknown`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`ReflectionError::Call`] holding the status of the call if
+    /// it fails otherwise, e.g. with `UNIMPLEMENTED` if the server has no health checking
+    /// service.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::health::HealthStatus;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use tonic_health::ServingStatus;
+    ///
+    /// let (mut reporter, health) = tonic_health::server::health_reporter();
+    /// reporter
+    ///     .set_service_status("shop.Orders", ServingStatus::NotServing)
+    ///     .await;
+    /// // no reflection service, so the bundled descriptors are used
+    /// # let endpoint =
+    /// #     support::serve(tonic::transport::Server::builder().add_service(health)).await?;
+    ///
+    /// let mut client = ReflectionClient::new_lazy(endpoint)?;
+    /// assert_eq!(client.check_health(None).await?, HealthStatus::Serving);
+    /// assert_eq!(
+    ///     client.check_health(Some("shop.Orders")).await?,
+    ///     HealthStatus::NotServing
+    /// );
+    /// assert_eq!(
+    ///     client.check_health(Some("shop.Payments")).await?,
+    ///     HealthStatus::ServiceUnknown
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn check_health(
+        &mut self,
+        service: Option<&str>,
+    ) -> Result<HealthStatus, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.health_method("Check").await;
+        let request = health::request(&method, service);
+        match self
+            .call_unary_dynamic(&method, request, CallOptions::default())
+            .await
+        {
+            Ok(response) => Ok(HealthStatus::of_response(&response)),
+            Err(ReflectionError::Call { status, .. }) if status.code() == Code::NotFound => {
+                Ok(HealthStatus::ServiceUnknown)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Watches the health of the server, or of its `service` if some, through the standard
+    /// health checking service, returning the stream of its statuses as they change.
+    ///
+    /// The `Watch` method is called like the `Check` method by
+    /// [`check_health`](Self::check_health), and the stream starts with the current status.
+    /// Services the health checking service does not know are reported as
+    /// [`HealthStatus::ServiceUnknown`] and watched until they are added, though some servers
+    /// fail the call with `NOT_FOUND` instead, like `Check` does.
+    ///
+    /// # Errors
+    ///
+    /// This function will return a [`ReflectionError::Call`] holding the status of the call if
+    /// it fails before streaming, e.g. with `UNIMPLEMENTED` if the server has no health
+    /// checking service.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::health::HealthStatus;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use tokio_stream::StreamExt;
+    /// use tonic_health::ServingStatus;
+    ///
+    /// let (mut reporter, health) = tonic_health::server::health_reporter();
+    /// reporter
+    ///     .set_service_status("shop.Orders", ServingStatus::NotServing)
+    ///     .await;
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let mut statuses = client.watch_health(Some("shop.Orders")).await?;
+    /// assert_eq!(statuses.next().await.unwrap()?, HealthStatus::NotServing);
+    ///
+    /// reporter
+    ///     .set_service_status("shop.Orders", ServingStatus::Serving)
+    ///     .await;
+    /// assert_eq!(statuses.next().await.unwrap()?, HealthStatus::Serving);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn watch_health(
+        &mut self,
+        service: Option<&str>,
+    ) -> Result<HealthStream, ReflectionError>
+    where
+        T: Clone,
+    {
+        let method = self.health_method("Watch").await;
+        let request = health::request(&method, service);
+        let responses = self
+            .send_server_streaming(&method, request, CallOptions::default())
+            .await?;
+        Ok(HealthStream::new(responses))
+    }
+
+    /// Returns the descriptor of the method `name` of the health checking service, as
+    /// reflected by the server or else bundled.
+    #[cfg(feature = "prost-reflect")]
+    async fn health_method(&mut self, name: &str) -> prost_reflect::MethodDescriptor {
+        let path = format!("/{}/{}", health::HEALTH_SERVICE, name);
+        match self.method_descriptor(&path).await {
+            Ok(method) => method,
+            Err(error) => {
+                debug!("using the bundled health descriptors: {}", error);
+                health::bundled_method(name)
+            }
+        }
+    }
+
+    /// Calls the unary method `path`, e.g. `/shop.Orders/Place`, with the encoded request
+    /// message `request`, returning the encoded response message along with the headers and
+    /// trailers the server sent.
+    ///
+    /// Unlike [`call_unary`](Self::call_unary), this does not resolve any descriptors, so it
+    /// works with servers whose reflection data is incomplete, or that do not offer reflection
+    /// at all, and without the `prost-reflect` feature. The bytes are sent and returned as
+    /// they are, so the server is the only one checking them. The call is sent like by
+    /// [`call_unary`](Self::call_unary), except that it is only retried if the retry policy
+    /// of `options` retries methods not marked as idempotent, since the method is not known.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The method path is invalid, as [`ReflectionError::InvalidMethodPath`]
+    /// - The call fails, or the server sends no response message, as
+    ///   [`ReflectionError::Call`] holding its status, or [`ReflectionError::Retried`] if it
+    ///   was retried
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost::Message;
+    /// use tonic_health::pb::{health_check_response::ServingStatus, HealthCheckRequest, HealthCheckResponse};
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// // no reflection service
+    /// # let endpoint =
+    /// #     support::serve(tonic::transport::Server::builder().add_service(health)).await?;
+    ///
+    /// let mut client = ReflectionClient::new_lazy(endpoint)?;
+    /// let request = HealthCheckRequest { service: String::new() };
+    /// let (response, headers, _trailers) = client
+    ///     .call_raw(
+    ///         "/grpc.health.v1.Health/Check",
+    ///         request.encode_to_vec().into(),
+    ///         CallOptions::default(),
+    ///     )
+    ///     .await?;
+    /// let response = HealthCheckResponse::decode(response)?;
+    /// assert_eq!(response.status(), ServingStatus::Serving);
+    /// assert_eq!(headers.get("content-type").unwrap(), "application/grpc");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn call_raw(
+        &mut self,
+        path: &str,
+        request: Bytes,
+        options: CallOptions,
+    ) -> Result<(Bytes, MetadataMap, MetadataMap), ReflectionError>
+    where
+        T: Clone,
+    {
+        let invalid = || ReflectionError::InvalidMethodPath {
+            path: path.to_string(),
+        };
+        let (service, method) = parse_method_path(path).ok_or_else(invalid)?;
+        let path = format!("/{}/{}", service, method);
+        let path_and_query = PathAndQuery::try_from(path.as_str()).map_err(|_| invalid())?;
+
+        // the idempotency of the method is not known without its descriptor
+        let retry = options.retry.as_ref();
+        let stopwatch = Stopwatch::start();
+        let response = retrying(&path, retry, IdempotencyLevel::Unknown, || async {
+            let deadline = self.deadline(&options);
+            // a server streaming call keeps the headers apart from the trailers
+            let response = self
+                .send_call(
+                    &path,
+                    &options,
+                    deadline,
+                    request.clone(),
+                    |mut client, request| {
+                        let path_and_query = path_and_query.clone();
+                        async move {
+                            client
+                                .server_streaming(request, path_and_query, BytesCodec)
+                                .await
+                        }
+                    },
+                )
+                .await?;
+            let call = async {
+                let (headers, mut responses, _) = response?.into_parts();
+                let response = responses
+                    .message()
+                    .await?
+                    .ok_or_else(|| Status::internal("Missing response message."))?;
+                let trailers = responses.trailers().await?.unwrap_or_default();
+                Ok((response, headers, trailers))
+            };
+            Ok(invoke::until_deadline(deadline, call).await)
+        })
+        .await?;
+        let elapsed = stopwatch.elapsed();
+        match response {
+            Ok(response) => {
+                let response_size = response.0.len();
+                invoke::trace_call(
+                    &path,
+                    Code::Ok,
+                    elapsed,
+                    Some(elapsed),
+                    request.len(),
+                    response_size,
+                );
+                Ok(response)
+            }
+            Err((status, attempts)) => {
+                invoke::trace_call(&path, status.code(), elapsed, None, request.len(), 0);
+                Err(self.retried_error(path, status, attempts).await)
+            }
+        }
+    }
+
+    /// Returns the [`ReflectionError::Call`] of the method `path` failing with `status`,
+    /// decoding the details of the status.
+    async fn call_error(&mut self, path: String, status: Status) -> ReflectionError {
+        let details = RichStatus::from_status(&status);
+        #[cfg(feature = "prost-reflect")]
+        let details = match details {
+            Some(details) => Some(self.resolve_details(details).await),
+            None => None,
+        };
+        ReflectionError::Call {
+            path,
+            status: Box::new(status),
+            details: details.map(Box::new),
+        }
+    }
+
+    /// Returns the error of the method `path` failing with `status` like
+    /// [`call_error`](Self::call_error), as a [`ReflectionError::Retried`] if the call was
+    /// retried after failing with the `earlier` statuses.
+    async fn retried_error(
+        &mut self,
+        path: String,
+        status: Status,
+        mut earlier: Vec<Status>,
+    ) -> ReflectionError {
+        if earlier.is_empty() {
+            return self.call_error(path, status).await;
+        }
+        earlier.push(status.clone());
+        ReflectionError::Retried {
+            attempts: earlier,
+            source: Box::new(self.call_error(path, status).await),
+        }
+    }
+
+    /// Resolves the details of unknown types of `status` through reflection, leaving those
+    /// the server cannot describe as they are.
+    #[cfg(feature = "prost-reflect")]
+    async fn resolve_details(&mut self, mut status: RichStatus) -> RichStatus {
+        use base64::Engine;
+
+        for detail in &mut status.details {
+            let StatusDetail::Raw { type_url, value } = detail else {
+                continue;
+            };
+            let Ok(descriptor) = self.resolve_type_url_descriptor(type_url).await else {
+                continue;
+            };
+            let message = base64::engine::general_purpose::STANDARD
+                .decode(value.as_bytes())
+                .ok()
+                .and_then(|value| {
+                    prost_reflect::DynamicMessage::decode(descriptor, value.as_slice()).ok()
+                })
+                .and_then(|message| JsonMapping::Canonical.to_json(&message).ok());
+            if let Some(message) = message {
+                *detail = StatusDetail::Resolved {
+                    type_url: std::mem::take(type_url),
+                    message,
+                };
+            }
+        }
+        status
+    }
+
+    /// Converts the `response` of a call to JSON in the `mapping` with its `bytes` in the
+    /// encoding `bytes`, resolving the types of the `Any`s it holds that the descriptors of
+    /// the method do not define through reflection first.
+    ///
+    /// `Any`s of types the server cannot describe either are left encoded.
+    #[cfg(feature = "prost-reflect")]
+    async fn response_to_json(
+        &mut self,
+        response: prost_reflect::DynamicMessage,
+        mapping: JsonMapping,
+        bytes: BytesEncoding,
+    ) -> Result<serde_json::Value, ReflectionError> {
+        let mut types = AnyTypes::new();
+        if mapping == JsonMapping::Canonical {
+            let mut unresolved = HashSet::new();
+            // the messages of resolved types may hold further `Any`s
+            loop {
+                let urls = any_json::unknown_type_urls(&response, &types);
+                let urls = urls
+                    .into_iter()
+                    .filter(|url| !types.contains_key(url) && !unresolved.contains(url))
+                    .collect::<Vec<_>>();
+                if urls.is_empty() {
+                    break;
+                }
+                for url in urls {
+                    match self.resolve_type_url_descriptor(&url).await {
+                        Ok(descriptor) => {
+                            types.insert(url, descriptor);
+                        }
+                        Err(error) => {
+                            debug!("cannot resolve the type {} of an Any: {}", url, error);
+                            unresolved.insert(url);
+                        }
+                    }
+                }
+            }
+        }
+        mapping.to_json_with_types(&response, bytes, &types)
+    }
+
+    /// Returns the deadline of a call with `options` starting now, falling back to the request
+    /// timeout of the client.
+    fn deadline(&self, options: &CallOptions) -> Option<Deadline> {
+        options
+            .deadline
+            .or(self.request_timeout)
+            .map(Deadline::after)
+    }
+
+    /// Sends `message` to the method `path` with `send`, like [`send_once`](Self::send_once),
+    /// sending it again while the server cannot be reached if `options.wait_for_ready` is set.
+    async fn send_call<M, R, F>(
+        &self,
+        path: &str,
+        options: &CallOptions,
+        deadline: Option<Deadline>,
+        message: M,
+        mut send: impl FnMut(Grpc<T>, Request<M>) -> F,
+    ) -> Result<Result<R, Status>, ReflectionError>
+    where
+        T: Clone,
+        M: Clone,
+        F: Future<Output = Result<R, Status>>,
+    {
+        let mut attempts = 0;
+        loop {
+            let response = self
+                .send_once(path, options, deadline, message.clone(), &mut send)
+                .await?;
+            let status = match response {
+                // sent again uncompressed right away
+                Err(status)
+                    if options.compression.is_none() && self.compression_rejected(&status) =>
+                {
+                    continue;
+                }
+                Err(status) if options.wait_for_ready && error::is_transport_failure(&status) => {
+                    status
+                }
+                response => return Ok(response),
+            };
+
+            let backoff = ReconnectPolicy::default()
+                .max_retries(u32::MAX)
+                .backoff(attempts)
+                .unwrap_or(ReconnectPolicy::DEFAULT_MAX_BACKOFF);
+            attempts += 1;
+            debug!(
+                "{} is not reachable: {}, retrying in {:?}",
+                path, status, backoff
+            );
+            let wait = async {
+                tokio::time::sleep(backoff).await;
+                Ok(())
+            };
+            if let Err(status) = invoke::until_deadline(deadline, wait).await {
+                return Ok(Err(status));
+            }
+        }
+    }
+
+    /// Sends `message` to the method `path` with `send`, through a client configured with
+    /// `options`, and waits for `send` to complete until the `deadline`.
+    async fn send_once<M, R, F>(
+        &self,
+        path: &str,
+        options: &CallOptions,
+        deadline: Option<Deadline>,
+        message: M,
+        send: impl FnOnce(Grpc<T>, Request<M>) -> F,
+    ) -> Result<Result<R, Status>, ReflectionError>
+    where
+        T: Clone,
+        F: Future<Output = Result<R, Status>>,
+    {
+        let timeout = deadline.map(|deadline| deadline.remaining());
+        let request = self.prepare(message, &options.metadata, timeout)?;
+        let client = self.call_client(path, options).await?;
+        Ok(invoke::until_deadline(deadline, send(client, request)).await)
+    }
+
+    /// Returns a client of the current replica for a call of the method `path`, with the
+    /// compression and response size limit of `options`, once it is ready.
+    async fn call_client(
+        &self,
+        path: &str,
+        options: &CallOptions,
+    ) -> Result<Grpc<T>, ReflectionError>
+    where
+        T: Clone,
+    {
+        let mut client = self.replicas[self.current].client.clone();
+        if let Some(encoding) = options.compression.or_else(|| self.send_compression()) {
+            client = client.send_compressed(encoding);
+        }
+        if let Some(limit) = options.max_response_size {
+            client = client.max_decoding_message_size(limit);
+        }
+
+        client
+            .ready()
+            .await
+            .map_err(|error| ReflectionError::Call {
+                path: path.to_string(),
+                status: Box::new(Status::from_error(error.into())),
+                details: None,
+            })?;
+        Ok(client)
+    }
+
+    /// Exports the schema of the server as an encoded `FileDescriptorSet`, as written by
+    /// `protoc --descriptor_set_out` and understood by tools like buf and prost-build.
+    ///
+    /// The set holds the files defining the services of the server and the files they import,
+    /// directly or transitively, each once and after the files it imports. Services left out
+    /// of listings are left out like by [`descriptor_pool_all`](Self::descriptor_pool_all).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The services cannot be listed, or the request for a file fails
+    /// - The server does not know an imported file, as [`ReflectionError::NotFound`] naming
+    ///   the file
+    /// - A file descriptor has no name, or the imports form a cycle, as
+    ///   [`ReflectionError::MalformedDescriptor`]
+    /// - The file descriptors cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost::Message;
+    /// use prost_types::FileDescriptorSet;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let encoded = client.export_descriptor_set().await?;
+    /// let set = FileDescriptorSet::decode(&encoded[..])?;
+    /// for service in client.list_services().await? {
+    ///     assert!(set.file.iter().any(|file| {
+    ///         file.package() == service.package
+    ///             && file.service.iter().any(|s| s.name() == service.service)
+    ///     }));
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn export_descriptor_set(&mut self) -> Result<Vec<u8>, ReflectionError> {
+        let files = self.get_all_file_descriptors().await?;
+        Ok(prost_types::FileDescriptorSet { file: files }.encode_to_vec())
+    }
+
+    /// Exports the schema of the server like
+    /// [`export_descriptor_set`](Self::export_descriptor_set) and writes it to the file at
+    /// `path`, e.g. `descriptor_set.bin`, replacing the file if it exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`export_descriptor_set`](Self::export_descriptor_set), and
+    /// [`ReflectionError::WriteFile`] if the file cannot be written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost::Message;
+    /// use prost_types::FileDescriptorSet;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let path = std::env::temp_dir().join(format!("descriptor_set-{}.bin", std::process::id()));
+    /// client.write_descriptor_set(&path).await?;
+    /// let set = FileDescriptorSet::decode(&std::fs::read(&path)?[..])?;
+    /// assert_eq!(set.file[0].name(), "reflection.proto");
+    /// std::fs::remove_file(&path)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "transport")]
+    pub async fn write_descriptor_set(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), ReflectionError> {
+        let encoded = self.export_descriptor_set().await?;
+        let path = path.as_ref();
+        tokio::fs::write(path, encoded)
+            .await
+            .map_err(|source| ReflectionError::WriteFile {
+                path: path.to_path_buf(),
+                source,
+            })
+    }
+
+    /// Computes the [`Fingerprint`] of the schema of the server: the files defining its
+    /// services and the files they import, as exported by
+    /// [`export_descriptor_set`](Self::export_descriptor_set).
+    ///
+    /// The fingerprint stays the same across runs and connections as long as the schema does,
+    /// regardless of the order in which the server lists its services. See [`Fingerprint`]
+    /// for how the files are canonicalized.
+    ///
+    /// The descriptor cache, which the clones of the client share, is cleared first, since
+    /// the cached files would hide changes to their contents.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`export_descriptor_set`](Self::export_descriptor_set).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, ServiceDescriptorProto};
+    ///
+    /// let file = |name: &str| FileDescriptorProto {
+    ///     name: Some(format!("{}.proto", name.to_lowercase())),
+    ///     package: Some("example".to_string()),
+    ///     service: vec![ServiceDescriptorProto {
+    ///         name: Some(name.to_string()),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// // serves the files, whose services the server lists in the order they are registered
+    /// let serve = |files: Vec<FileDescriptorProto>| async move {
+    /// #     let endpoint = support::serve_files(files).await?;
+    ///     Ok::<_, Box<dyn std::error::Error>>(ReflectionClient::new(endpoint).await?)
+    /// };
+    ///
+    /// let mut client = serve(vec![file("Alpha"), file("Beta")]).await?;
+    /// let fingerprint = client.schema_fingerprint().await?;
+    /// assert!(!client.has_changed(&fingerprint).await?);
+    ///
+    /// let mut reordered = serve(vec![file("Beta"), file("Alpha")]).await?;
+    /// assert!(!reordered.has_changed(&fingerprint).await?);
+    ///
+    /// let mut extended = serve(vec![file("Alpha"), file("Beta"), file("Gamma")]).await?;
+    /// assert!(extended.has_changed(&fingerprint).await?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn schema_fingerprint(&mut self) -> Result<Fingerprint, ReflectionError> {
+        self.clear_cache();
+        Ok(Fingerprint::of(&self.get_all_file_descriptors().await?))
+    }
+
+    /// Returns whether the schema of the server changed since `previous` was computed with
+    /// [`schema_fingerprint`](Self::schema_fingerprint).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`schema_fingerprint`](Self::schema_fingerprint).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let schema = support::Schema::new(vec![support::storage()]);
+    /// # let endpoint = schema.serve().await?;
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let fingerprint = client.schema_fingerprint().await?;
+    /// assert!(!client.has_changed(&fingerprint).await?);
+    ///
+    /// // the server adds a method to the service, whose file the client fetched already
+    /// let mut storage = support::storage();
+    /// let mut download = storage.service[0].method[0].clone();
+    /// download.name = Some("Download".to_string());
+    /// storage.service[0].method.push(download);
+    /// # schema.replace(vec![storage]);
+    /// assert!(client.has_changed(&fingerprint).await?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn has_changed(&mut self, previous: &Fingerprint) -> Result<bool, ReflectionError> {
+        Ok(self.schema_fingerprint().await? != *previous)
+    }
+
+    /// Computes the differences between the schema saved in the encoded `FileDescriptorSet`
+    /// at `path`, e.g. as written by [`write_descriptor_set`](Self::write_descriptor_set) or
+    /// `buf build`, and the current schema of the server.
+    ///
+    /// Comparing a server against a baseline checked into the repository lets CI fail on
+    /// [`breaking_changes`](SchemaDiff::breaking_changes).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`get_all_file_descriptors`](Self::get_all_file_descriptors), and:
+    /// - [`ReflectionError::ReadFile`] if the file cannot be read
+    /// - [`ReflectionError::Decode`] if the file is not an encoded `FileDescriptorSet`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let baseline =
+    ///     std::env::temp_dir().join(format!("grpc_ease_baseline-{}.bin", std::process::id()));
+    /// client.write_descriptor_set(&baseline).await?;
+    ///
+    /// // in CI, against the live staging server
+    /// let breaking = client.diff_against_file(&baseline).await?.breaking_changes();
+    /// for finding in &breaking {
+    ///     eprintln!("{}", finding);
+    /// }
+    /// assert!(breaking.is_empty(), "the schema has breaking changes");
+    /// # std::fs::remove_file(baseline)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "transport")]
+    pub async fn diff_against_file(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<SchemaDiff, ReflectionError> {
+        let path = path.as_ref();
+        let encoded = tokio::fs::read(path)
+            .await
+            .map_err(|source| ReflectionError::ReadFile {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        let baseline = prost_types::FileDescriptorSet::decode(&encoded[..])
+            .map_err(ReflectionError::Decode)?;
+        let files = self.get_all_file_descriptors().await?;
+        Ok(diff::diff(&baseline.file, &files))
+    }
+
+    /// Polls the schema of the server every `interval`, emitting an event whenever its
+    /// [`schema_fingerprint`](Self::schema_fingerprint) changes, along with the
+    /// [`diff`](crate::diff::diff) against the schema retrieved before.
+    ///
+    /// The first poll happens right away and only records the schema. Polls that fail, e.g.
+    /// because the server is briefly down, are emitted as [`SchemaChangeEvent::Error`]s
+    /// without ending the stream, which polls until it is dropped.
+    ///
+    /// The watching client does not use the descriptor cache, so each poll sees the current
+    /// schema; clones of the client keep their cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::pin::Pin;
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use std::sync::Arc;
+    /// # use prost::Message;
+    /// # use prost_types::{FileDescriptorProto, ServiceDescriptorProto};
+    /// # use tokio_stream::StreamExt;
+    /// # use tonic::{Request, Response, Status, Streaming};
+    /// # use tonic_reflection::pb::server_reflection_request::MessageRequest;
+    /// # use tonic_reflection::pb::server_reflection_response::MessageResponse;
+    /// # use tonic_reflection::pb::server_reflection_server::{ServerReflection, ServerReflectionServer};
+    /// # use tonic_reflection::pb::{
+    /// #     ErrorResponse, FileDescriptorResponse, ListServiceResponse, ServerReflectionRequest,
+    /// #     ServerReflectionResponse, ServiceResponse,
+    /// # };
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use grpc_ease::watch::SchemaChangeEvent;
+    /// use std::time::Duration;
+    /// #
+    /// # // a reflection server whose schema changes with each listing of its services: the
+    /// # // first listing shows `Orders`, the second fails, and the later ones add `Refunds`
+    /// # struct ChangingServer {
+    /// #     listings: Arc<AtomicUsize>,
+    /// # }
+    /// #
+    /// # fn file(listing: usize) -> FileDescriptorProto {
+    /// #     let services: &[&str] = if listing < 2 { &["Orders"] } else { &["Orders", "Refunds"] };
+    /// #     FileDescriptorProto {
+    /// #         name: Some("shop.proto".to_string()),
+    /// #         package: Some("shop".to_string()),
+    /// #         service: services
+    /// #             .iter()
+    /// #             .map(|name| ServiceDescriptorProto {
+    /// #                 name: Some(name.to_string()),
+    /// #                 ..Default::default()
+    /// #             })
+    /// #             .collect(),
+    /// #         ..Default::default()
+    /// #     }
+    /// # }
+    /// #
+    /// # #[tonic::async_trait]
+    /// # impl ServerReflection for ChangingServer {
+    /// #     type ServerReflectionInfoStream = Pin<Box<
+    /// #         dyn tokio_stream::Stream<Item = Result<ServerReflectionResponse, Status>> + Send,
+    /// #     >>;
+    /// #
+    /// #     async fn server_reflection_info(
+    /// #         &self,
+    /// #         request: Request<Streaming<ServerReflectionRequest>>,
+    /// #     ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+    /// #         let listings = self.listings.clone();
+    /// #         let responses = request.into_inner().map(move |request| {
+    /// #             let request = request?;
+    /// #             let message_response = match request.message_request.clone() {
+    /// #                 Some(MessageRequest::ListServices(_)) => {
+    /// #                     match listings.fetch_add(1, Ordering::SeqCst) {
+    /// #                         1 => MessageResponse::ErrorResponse(ErrorResponse {
+    /// #                             error_code: tonic::Code::Internal as i32,
+    /// #                             error_message: "restarting".to_string(),
+    /// #                         }),
+    /// #                         listing => MessageResponse::ListServicesResponse(ListServiceResponse {
+    /// #                             service: file(listing)
+    /// #                                 .service
+    /// #                                 .iter()
+    /// #                                 .map(|service| ServiceResponse {
+    /// #                                     name: format!("shop.{}", service.name()),
+    /// #                                 })
+    /// #                                 .collect(),
+    /// #                         }),
+    /// #                     }
+    /// #                 }
+    /// #                 _ => MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+    /// #                     file_descriptor_proto: vec![
+    /// #                         file(listings.load(Ordering::SeqCst) - 1).encode_to_vec(),
+    /// #                     ],
+    /// #                 }),
+    /// #             };
+    /// #             Ok(ServerReflectionResponse {
+    /// #                 valid_host: String::new(),
+    /// #                 original_request: Some(request),
+    /// #                 message_response: Some(message_response),
+    /// #             })
+    /// #         });
+    /// #         Ok(Response::new(Box::pin(responses)))
+    /// #     }
+    /// # }
+    /// #
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// # let server = ChangingServer { listings: Arc::new(AtomicUsize::new(0)) };
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder().add_service(ServerReflectionServer::new(server)),
+    /// # )
+    /// # .await?;
+    /// let client = ReflectionClient::new(endpoint).await?;
+    /// let mut events = Box::pin(client.watch(Duration::from_millis(10)));
+    ///
+    /// // the server fails the second poll, and adds a service before the third one
+    /// let Some(SchemaChangeEvent::Error(error)) = events.next().await else {
+    ///     panic!("expected the failed poll");
+    /// };
+    /// assert!(error.to_string().contains("restarting"));
+    ///
+    /// let Some(SchemaChangeEvent::Changed { previous, current, diff }) = events.next().await
+    /// else {
+    ///     panic!("expected the change");
+    /// };
+    /// assert_ne!(previous, current);
+    /// assert_eq!(diff.added_services, ["shop.Refunds"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn watch(
+        mut self,
+        interval: Duration,
+    ) -> impl tokio_stream::Stream<Item = SchemaChangeEvent> {
+        self.cache = None;
+        let snapshot: Option<(Fingerprint, Vec<prost_types::FileDescriptorProto>)> = None;
+        futures_util::stream::unfold(
+            (self, snapshot, false),
+            move |(mut client, mut snapshot, mut wait)| async move {
+                loop {
+                    if wait {
+                        tokio::time::sleep(interval).await;
+                    }
+                    wait = true;
+
+                    let files = match client.get_all_file_descriptors().await {
+                        Ok(files) => files,
+                        Err(error) => {
+                            debug!("failed to poll the schema: {}", error);
+                            let event = SchemaChangeEvent::Error(error);
+                            return Some((event, (client, snapshot, wait)));
+                        }
+                    };
+                    let current = Fingerprint::of(&files);
+                    let event = match &snapshot {
+                        Some((previous, old)) if *previous != current => {
+                            Some(SchemaChangeEvent::Changed {
+                                previous: *previous,
+                                current,
+                                diff: Box::new(diff::diff(old, &files)),
+                            })
+                        }
+                        _ => None,
+                    };
+                    snapshot = Some((current, files));
+                    if let Some(event) = event {
+                        return Some((event, (client, snapshot, wait)));
+                    }
+                }
+            },
+        )
+    }
+
+    /// Retrieves the file descriptors of the files defining the services of the server and of
+    /// the files they import, directly or transitively, each once and after the files it
+    /// imports.
+    ///
+    /// This is the schema of the server as exported by
+    /// [`export_descriptor_set`](Self::export_descriptor_set), without encoding it, e.g. to
+    /// compare it with another schema using [`diff`](crate::diff::diff).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`export_descriptor_set`](Self::export_descriptor_set).
+    pub async fn get_all_file_descriptors(
+        &mut self,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        let mut resolver = DependencyResolver::new();
+        let mut services = HashSet::new();
+        for service in self.service_names(&MetadataMap::new()).await? {
+            // files defining several services are fetched once
+            if services.contains(&service) {
+                continue;
+            }
+            for file in self
+                .file_descriptor_containing(service, &MetadataMap::new())
+                .await?
+            {
+                services.extend(
+                    file.service
+                        .iter()
+                        .map(|service| lookup::qualify(file.package(), service.name())),
+                );
+                resolver.add_seed(file)?;
+            }
+        }
+
+        self.resolve(&mut resolver).await?;
+        resolver.into_sorted()
+    }
+
+    /// Builds an example of the JSON encoding of the request message of the method `path`, e.g.
+    /// `/shop.Orders/Place`, as a template for a request, like
+    /// [`example::example_message`] with the default [`ExampleOptions`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`get_method`](Self::get_method) and
+    /// [`get_file_descriptors_transitive`](Self::get_file_descriptors_transitive).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let request = client
+    ///     .example_request("/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo")
+    ///     .await?;
+    /// assert_eq!(request["host"], "");
+    /// assert_eq!(request["fileByFilename"], "");
+    /// assert!(request["// message_request"].is_string());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn example_request(
+        &mut self,
+        path: &str,
+    ) -> Result<serde_json::Value, ReflectionError> {
+        self.example_request_with_options(path, &ExampleOptions::default())
+            .await
+    }
+
+    /// Builds an example of the JSON encoding of the request message of the method `path`
+    /// like [`example_request`](Self::example_request), with the given `options`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`example_request`](Self::example_request).
+    pub async fn example_request_with_options(
+        &mut self,
+        path: &str,
+        options: &ExampleOptions,
+    ) -> Result<serde_json::Value, ReflectionError> {
+        let (_, method) = self.get_method(path).await?;
+        let files = self
+            .get_file_descriptors_transitive(method.request.clone())
+            .await?;
+        let catalog = OfflineCatalog::new(prost_types::FileDescriptorSet { file: files });
+        let request = catalog.describe_message(&method.request)?;
+        Ok(example::example_message(&request, catalog.files(), options))
+    }
+
+    /// Creates an OpenAPI document describing the unary methods of the services of the
+    /// server as `POST` operations, like [`openapi::from_files`] does for the files of
+    /// [`get_all_file_descriptors`](Self::get_all_file_descriptors).
+    ///
+    /// Services the server does not list are left out, even if a file it serves defines them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`get_all_file_descriptors`](Self::get_all_file_descriptors) and
+    /// [`openapi::from_files`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let document = client.openapi_document().await?;
+    /// // the reflection service only has a bidirectional streaming method
+    /// assert_eq!(
+    ///     document.skipped_methods(),
+    ///     ["/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo"]
+    /// );
+    /// assert_eq!(document.as_json()["paths"], serde_json::json!({}));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn openapi_document(&mut self) -> Result<OpenApiDocument, ReflectionError> {
+        let services: HashSet<String> = self
+            .service_names(&MetadataMap::new())
+            .await?
+            .into_iter()
+            .collect();
+        let files = self.get_all_file_descriptors().await?;
+        openapi::document(&files, |service| services.contains(service))
+    }
+
+    /// Lists the message and enum types in `package_prefix` or one of its subpackages, e.g.
+    /// `shop` for `shop.Order` and `shop.v1.Item`, or all types if the prefix is empty.
+    ///
+    /// Reflection cannot list types, so they are indexed from
+    /// [`get_all_file_descriptors`](Self::get_all_file_descriptors): the files defining the
+    /// services of the server and the files they import, including the types nested in
+    /// messages but not the entries of maps. Types defined in files no service reaches are
+    /// missing. The index is built once and kept in the descriptor cache, so it expires and
+    /// is cleared along with the cached files.
+    ///
+    /// The types are sorted by their fully qualified names.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`get_all_file_descriptors`](Self::get_all_file_descriptors).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::message_info::{TypeInfo, TypeKind};
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{
+    ///     DescriptorProto, EnumDescriptorProto, FileDescriptorProto, ServiceDescriptorProto,
+    /// };
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use tonic::codegen::http;
+    ///
+    /// let shop = FileDescriptorProto {
+    ///     name: Some("shop.proto".to_string()),
+    ///     package: Some("shop".to_string()),
+    ///     message_type: vec![DescriptorProto {
+    ///         name: Some("Order".to_string()),
+    ///         enum_type: vec![EnumDescriptorProto {
+    ///             name: Some("Status".to_string()),
+    ///             ..Default::default()
+    ///         }],
+    ///         ..Default::default()
+    ///     }],
+    ///     service: vec![ServiceDescriptorProto {
+    ///         name: Some("Orders".to_string()),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// # let endpoint = support::serve_files(vec![shop]).await?;
+    ///
+    /// let requests = Arc::new(AtomicUsize::new(0));
+    /// let counter = requests.clone();
+    /// let count_requests = tower::ServiceBuilder::new().map_request(
+    ///     move |request: http::Request<tonic::body::BoxBody>| {
+    ///         counter.fetch_add(1, Ordering::SeqCst);
+    ///         request
+    ///     },
+    /// );
+    /// let mut client = ReflectionClient::builder(&endpoint)
+    ///     .build_with_layer(count_requests)
+    ///     .await?;
+    ///
+    /// let types = client.list_types("shop").await?;
+    /// let type_info = |full_name: &str, kind| TypeInfo {
+    ///     full_name: full_name.to_string(),
+    ///     kind,
+    ///     file_name: "shop.proto".to_string(),
+    /// };
+    /// assert_eq!(
+    ///     types,
+    ///     [
+    ///         type_info("shop.Order", TypeKind::Message),
+    ///         type_info("shop.Order.Status", TypeKind::Enum),
+    ///     ]
+    /// );
+    ///
+    /// // the index is built once, and includes the types of the reflection service
+    /// let sent = requests.load(Ordering::SeqCst);
+    /// let reflection_types = client.list_types("grpc.reflection").await?;
+    /// assert!(reflection_types
+    ///     .iter()
+    ///     .any(|type_info| type_info.full_name == "grpc.reflection.v1alpha.ServerReflectionRequest"));
+    /// assert_eq!(requests.load(Ordering::SeqCst), sent);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn list_types(
+        &mut self,
+        package_prefix: &str,
+    ) -> Result<Vec<TypeInfo>, ReflectionError> {
+        let types = match self.cache.as_ref().and_then(DescriptorCache::types) {
+            Some(types) => types,
+            None => {
+                let mut types: Vec<TypeInfo> = self
+                    .get_all_file_descriptors()
+                    .await?
+                    .iter()
+                    .flat_map(TypeInfo::defined_in)
+                    .collect();
+                types.sort();
+                if let Some(cache) = &self.cache {
+                    cache.insert_types(&types);
+                }
+                types
+            }
+        };
+
+        Ok(types
+            .into_iter()
+            .filter(|type_info| in_package(&type_info.full_name, package_prefix))
+            .collect())
+    }
+
+    /// Completes `seeds` with the files they import, directly or transitively, requesting the
+    /// missing ones by file name, and orders them so each file comes after the files it
+    /// imports.
+    ///
+    /// Files already fetched are taken from the descriptor cache, and files the server sends
+    /// along without any seed importing them are left out. This is how
+    /// [`get_file_descriptors_transitive`](Self::get_file_descriptors_transitive) completes
+    /// the file containing a symbol.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - Any of the requests to the server fails
+    /// - The server does not know one of the imported files, as [`ReflectionError::NotFound`]
+    /// - A file descriptor has no name, or the imports form a cycle, as
+    ///   [`ReflectionError::MalformedDescriptor`]
+    /// - The file descriptors cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{FileDescriptorProto, FileDescriptorSet};
+    ///
+    /// let file = |name: &str, dependency: &[&str]| FileDescriptorProto {
+    ///     name: Some(format!("{}.proto", name)),
+    ///     dependency: dependency.iter().map(|name| format!("{}.proto", name)).collect(),
+    ///     ..Default::default()
+    /// };
+    /// // a diamond: left and right both import base
+    /// let files = FileDescriptorSet {
+    ///     file: vec![file("base", &[]), file("left", &["base"]), file("right", &["base"])],
+    /// };
+    ///
+    /// # let endpoint = support::serve_files(files.file).await?;
+    ///
+    /// // a local file importing files of the server
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let descriptors = client
+    ///     .resolve_dependencies(vec![file("app", &["left", "right"])])
+    ///     .await?;
+    /// let names: Vec<_> = descriptors.iter().map(|descriptor| descriptor.name()).collect();
+    /// assert_eq!(names, ["base.proto", "left.proto", "right.proto", "app.proto"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn resolve_dependencies(
+        &mut self,
+        seeds: Vec<prost_types::FileDescriptorProto>,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        let mut resolver = DependencyResolver::new();
+        for seed in seeds {
+            resolver.add_seed(seed)?;
+        }
+
+        self.resolve(&mut resolver).await?;
+        resolver.into_sorted()
+    }
+
+    /// Fetches the files missing from `resolver` until the imports of its seeds are
+    /// complete.
+    async fn resolve(&mut self, resolver: &mut DependencyResolver) -> Result<(), ReflectionError> {
+        loop {
+            let missing = resolver.missing();
+            if missing.is_empty() {
+                return Ok(());
+            }
+
+            for dependency in missing {
+                trace!("fetching missing import {:?}", dependency);
+                for file in self.get_file_by_filename(dependency.clone()).await? {
+                    resolver.add(file)?;
+                }
+                // guards against looping forever on servers answering with other files
+                if !resolver.contains(&dependency) {
+                    return Err(ReflectionError::NotFound { name: dependency });
+                }
+            }
+        }
+    }
+
+    /// Retrieves the definition of the message `symbol`, e.g. `my.package.Outer.Inner`, which
+    /// may be nested in other messages. A leading dot, as in the type names of fields, is
+    /// ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The file containing the symbol does not define a message of that name, e.g. because
+    ///   the symbol is an enum, as [`ReflectionError::TypeNotFound`], suggesting similar
+    ///   message names
+    /// - The server knows neither the symbol nor any of its enclosing scopes, as
+    ///   [`ReflectionError::NotFound`]
+    /// - The file descriptors cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet};
+    ///
+    /// let files = FileDescriptorSet {
+    ///     file: vec![FileDescriptorProto {
+    ///         name: Some("shop.proto".to_string()),
+    ///         package: Some("shop".to_string()),
+    ///         message_type: vec![DescriptorProto {
+    ///             name: Some("Order".to_string()),
+    ///             nested_type: vec![DescriptorProto {
+    ///                 name: Some("Item".to_string()),
+    ///                 ..Default::default()
+    ///             }],
+    ///             enum_type: vec![EnumDescriptorProto {
+    ///                 name: Some("Status".to_string()),
+    ///                 ..Default::default()
+    ///             }],
+    ///             ..Default::default()
+    ///         }],
+    ///         ..Default::default()
+    ///     }],
+    /// };
+    ///
+    /// # let endpoint = support::serve_files(files.file).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let item = client.find_message(".shop.Order.Item".to_string()).await?;
+    /// assert_eq!(item.name(), "Item");
+    /// let status = client.find_enum("shop.Order.Status".to_string()).await?;
+    /// assert_eq!(status.name(), "Status");
+    ///
+    /// let error = client
+    ///     .find_message("shop.Order.Iten".to_string())
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     ReflectionError::TypeNotFound { suggestions, .. } if suggestions == ["shop.Order.Item"]
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn find_message(
+        &mut self,
+        symbol: String,
+    ) -> Result<prost_types::DescriptorProto, ReflectionError> {
+        let name = symbol.trim_start_matches('.').to_string();
+        let files = self.files_near(&name).await?;
+        let messages = lookup::messages(&files);
+
+        match messages
+            .iter()
+            .find(|(message_name, _)| *message_name == name)
+        {
+            Some((_, message)) => Ok((*message).clone()),
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(
+                    &name,
+                    messages.iter().map(|(name, _)| name.as_str()),
+                ),
+                name,
+                kind: "message",
+            }),
+        }
+    }
+
+    /// Retrieves the definition of the enum `symbol`, which may be nested in messages, like
+    /// [`find_message`](Self::find_message) does for messages.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`find_message`](Self::find_message).
+    pub async fn find_enum(
+        &mut self,
+        symbol: String,
+    ) -> Result<prost_types::EnumDescriptorProto, ReflectionError> {
+        let name = symbol.trim_start_matches('.').to_string();
+        let files = self.files_near(&name).await?;
+        let enums = lookup::enums(&files);
+
+        match enums.iter().find(|(enum_name, _)| *enum_name == name) {
+            Some((_, enumeration)) => Ok((*enumeration).clone()),
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(
+                    &name,
+                    enums.iter().map(|(name, _)| name.as_str()),
+                ),
+                name,
+                kind: "enum",
+            }),
+        }
+    }
+
+    /// Describes the message `symbol`, e.g. `shop.Order`, with its fields and the messages
+    /// nested in it, like [`find_message`](Self::find_message) finds its definition.
+    ///
+    /// The enum types of the fields are described as well, fetching the files defining them
+    /// if needed; see [`describe_enum`](Self::describe_enum).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`find_message`](Self::find_message).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::message_info::{FieldType, Label};
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::field_descriptor_proto::{Label as ProtoLabel, Type};
+    /// use prost_types::{
+    ///     DescriptorProto, FieldDescriptorProto, FileDescriptorProto, OneofDescriptorProto,
+    /// };
+    ///
+    /// let field = |name: &str, number, label: ProtoLabel, kind: Type, type_name: Option<&str>| {
+    ///     FieldDescriptorProto {
+    ///         name: Some(name.to_string()),
+    ///         number: Some(number),
+    ///         label: Some(label.into()),
+    ///         r#type: Some(kind.into()),
+    ///         type_name: type_name.map(str::to_string),
+    ///         ..Default::default()
+    ///     }
+    /// };
+    /// // a tree node containing further nodes
+    /// let node = DescriptorProto {
+    ///     name: Some("Node".to_string()),
+    ///     field: vec![
+    ///         field("display_name", 1, ProtoLabel::Optional, Type::String, None),
+    ///         field("children", 2, ProtoLabel::Repeated, Type::Message, Some(".tree.Node")),
+    ///         field("size", 3, ProtoLabel::Optional, Type::Message, Some(".tree.Node.Size")),
+    ///     ],
+    ///     nested_type: vec![DescriptorProto {
+    ///         name: Some("Size".to_string()),
+    ///         field: vec![field("bytes", 1, ProtoLabel::Optional, Type::Uint64, None)],
+    ///         ..Default::default()
+    ///     }],
+    ///     oneof_decl: vec![OneofDescriptorProto {
+    ///         name: Some("kind".to_string()),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// let tree = FileDescriptorProto {
+    ///     name: Some("tree.proto".to_string()),
+    ///     package: Some("tree".to_string()),
+    ///     message_type: vec![node],
+    ///     ..Default::default()
+    /// };
+    /// # let endpoint = support::serve_files(vec![tree]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let node = client.describe_message("tree.Node").await?;
+    /// assert_eq!(node.full_name, "tree.Node");
+    /// assert_eq!(node.oneofs[0].name, "kind");
+    ///
+    /// let display_name = &node.fields[0];
+    /// assert_eq!((display_name.number, display_name.field_type), (1, FieldType::String));
+    /// assert_eq!(display_name.json_name, "displayName");
+    /// let children = &node.fields[1];
+    /// assert_eq!(children.label, Label::Repeated);
+    /// assert_eq!(children.type_name.as_deref(), Some("tree.Node"));
+    ///
+    /// assert_eq!(node.nested_messages[0].full_name, "tree.Node.Size");
+    /// assert_eq!(node.nested_messages[0].fields[0].field_type, FieldType::Uint64);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn describe_message(&mut self, symbol: &str) -> Result<MessageInfo, ReflectionError> {
+        let name = symbol.trim_start_matches('.').to_string();
+        let files = self.files_near(&name).await?;
+        let messages = lookup::messages(&files);
+
+        match messages
+            .iter()
+            .find(|(message_name, _)| *message_name == name)
+        {
+            Some((full_name, message)) => {
+                let enums = self.enums_of(message, &files).await?;
+                let (source, syntax) = files
+                    .iter()
+                    .find_map(|file| Some((Source::message(file, message)?, Syntax::of(file))))
+                    .unwrap_or((Source::default(), Syntax::Proto2));
+                Ok(MessageInfo::new(
+                    full_name.clone(),
+                    message,
+                    &enums,
+                    &source,
+                    syntax,
+                ))
+            }
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(
+                    &name,
+                    messages.iter().map(|(name, _)| name.as_str()),
+                ),
+                name,
+                kind: "message",
+            }),
+        }
+    }
+
+    /// Describes the message type of a `google.protobuf.Any`, given its type URL, e.g.
+    /// `type.googleapis.com/shop.Order`, so its payload can be decoded.
+    ///
+    /// Everything up to the last `/` of the URL is ignored, and the remaining message name is
+    /// looked up on the server like with [`describe_message`](Self::describe_message).
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`describe_message`](Self::describe_message), and
+    /// [`ReflectionError::InvalidTypeUrl`] if the URL does not end with a message name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let response = client
+    ///     .resolve_type_url("type.googleapis.com/grpc.reflection.v1alpha.ListServiceResponse")
+    ///     .await?;
+    /// assert_eq!(response.fields[0].name, "service");
+    ///
+    /// let error = client.resolve_type_url("type.googleapis.com/").await.unwrap_err();
+    /// assert!(matches!(error, ReflectionError::InvalidTypeUrl { .. }));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn resolve_type_url(&mut self, url: &str) -> Result<MessageInfo, ReflectionError> {
+        let name = type_url_name(url)?;
+        self.describe_message(name).await
+    }
+
+    /// Resolves the message type of a `google.protobuf.Any` like
+    /// [`resolve_type_url`](Self::resolve_type_url), returning its descriptor, which decodes
+    /// the payload into a `DynamicMessage`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`descriptor_pool_for`](Self::descriptor_pool_for), and:
+    /// - [`ReflectionError::InvalidTypeUrl`] if the URL does not end with a message name
+    /// - [`ReflectionError::TypeNotFound`] if the name is not a message, e.g. an enum
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost::Message;
+    /// use prost_reflect::DynamicMessage;
+    /// use tonic_reflection::pb::{ListServiceResponse, ServiceResponse};
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// // an `Any` holding a message the client has no generated code for
+    /// let any = prost_types::Any {
+    ///     type_url: "type.googleapis.com/grpc.reflection.v1alpha.ListServiceResponse".to_string(),
+    ///     value: ListServiceResponse {
+    ///         service: vec![ServiceResponse { name: "shop.Orders".to_string() }],
+    ///     }
+    ///     .encode_to_vec(),
+    /// };
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let descriptor = client.resolve_type_url_descriptor(&any.type_url).await?;
+    /// let message = DynamicMessage::decode(descriptor, &any.value[..])?;
+    /// let services = message.get_field_by_name("service").unwrap();
+    /// assert_eq!(services.as_list().unwrap().len(), 1);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    #[cfg(feature = "prost-reflect")]
+    pub async fn resolve_type_url_descriptor(
+        &mut self,
+        url: &str,
+    ) -> Result<prost_reflect::MessageDescriptor, ReflectionError> {
+        let name = type_url_name(url)?;
+        let pool = self.descriptor_pool_for(name).await?;
+        pool.get_message_by_name(name)
+            .ok_or_else(|| ReflectionError::TypeNotFound {
+                name: name.to_string(),
+                kind: "message",
+                suggestions: Vec::new(),
+            })
+    }
+
+    /// Describes the enum `symbol`, e.g. `shop.Order.Status`, with its values, like
+    /// [`find_enum`](Self::find_enum) finds its definition.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`find_message`](Self::find_message).
+    ///
+    /// # Example
+    ///
+    /// The enum types of fields are described along with messages, even if they are defined
+    /// in another file:
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use prost_types::field_descriptor_proto::Type;
+    /// use prost_types::{
+    ///     DescriptorProto, EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto,
+    ///     FieldDescriptorProto, FileDescriptorProto,
+    /// };
+    ///
+    /// let value = |name: &str, number| EnumValueDescriptorProto {
+    ///     name: Some(name.to_string()),
+    ///     number: Some(number),
+    ///     ..Default::default()
+    /// };
+    /// let status = FileDescriptorProto {
+    ///     name: Some("jobs/status.proto".to_string()),
+    ///     package: Some("jobs".to_string()),
+    ///     enum_type: vec![EnumDescriptorProto {
+    ///         name: Some("Status".to_string()),
+    ///         value: vec![value("UNKNOWN", 0), value("STARTED", 1), value("RUNNING", 1)],
+    ///         options: Some(EnumOptions {
+    ///             allow_alias: Some(true),
+    ///             ..Default::default()
+    ///         }),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// let job = FileDescriptorProto {
+    ///     name: Some("jobs/job.proto".to_string()),
+    ///     package: Some("jobs".to_string()),
+    ///     dependency: vec!["jobs/status.proto".to_string()],
+    ///     message_type: vec![DescriptorProto {
+    ///         name: Some("Job".to_string()),
+    ///         field: vec![FieldDescriptorProto {
+    ///             name: Some("status".to_string()),
+    ///             number: Some(1),
+    ///             r#type: Some(Type::Enum.into()),
+    ///             type_name: Some(".jobs.Status".to_string()),
+    ///             ..Default::default()
+    ///         }],
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    /// # let endpoint = support::serve_files(vec![status, job]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let status = client.describe_enum("jobs.Status").await?;
+    /// assert_eq!(
+    ///     status.values,
+    ///     [
+    ///         ("UNKNOWN".to_string(), 0),
+    ///         ("STARTED".to_string(), 1),
+    ///         ("RUNNING".to_string(), 1),
+    ///     ]
+    /// );
+    ///
+    /// let job = client.describe_message("jobs.Job").await?;
+    /// assert_eq!(job.fields[0].enum_info, Some(status));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn describe_enum(&mut self, symbol: &str) -> Result<EnumInfo, ReflectionError> {
+        let name = symbol.trim_start_matches('.').to_string();
+        let files = self.files_near(&name).await?;
+        let enums = lookup::enums(&files);
+
+        match enums.iter().find(|(enum_name, _)| *enum_name == name) {
+            Some((full_name, enumeration)) => Ok(EnumInfo::new(full_name.clone(), enumeration)),
+            None => Err(ReflectionError::TypeNotFound {
+                suggestions: lookup::suggestions(
+                    &name,
+                    enums.iter().map(|(name, _)| name.as_str()),
+                ),
+                name,
+                kind: "enum",
+            }),
+        }
+    }
+
+    /// Describes `symbol`, whatever kind of definition it names: a service, a method, a
+    /// message or an enum.
+    ///
+    /// Methods are named like services and types, e.g. `shop.Orders.Place`, or by their HTTP
+    /// path, e.g. `/shop.Orders/Place`, and are described along with their request and
+    /// response messages. Map entries are not symbols of their own, they are described as the
+    /// map fields of their messages.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as [`get_service`](Self::get_service) and
+    /// [`describe_message`](Self::describe_message), and
+    /// [`ReflectionError::TypeNotFound`] of the kind `symbol` if the server defines no such
+    /// symbol, suggesting the most similar names of services, methods and types, for which
+    /// every file of the server is fetched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::describe::SymbolDescription;
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// # let reflection = support::reflection(&[
+    /// #     tonic_health::pb::FILE_DESCRIPTOR_SET,
+    /// #     tonic_reflection::pb::FILE_DESCRIPTOR_SET,
+    /// # ]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let health = client.describe("grpc.health.v1.Health").await?;
+    /// assert!(matches!(health, SymbolDescription::Service(_)));
+    ///
+    /// let check = client.describe("/grpc.health.v1.Health/Check").await?;
+    /// assert_eq!(
+    ///     check.to_string(),
+    ///     "grpc.health.v1.Health.Check\n  \
+    ///      rpc Check(grpc.health.v1.HealthCheckRequest) returns (grpc.health.v1.HealthCheckResponse)\n\
+    ///      \n\
+    ///      message grpc.health.v1.HealthCheckRequest {\n  \
+    ///        string service = 1;\n\
+    ///      }\n\
+    ///      \n\
+    ///      message grpc.health.v1.HealthCheckResponse {\n  \
+    ///        grpc.health.v1.HealthCheckResponse.ServingStatus status = 1;\n\
+    ///      }"
+    /// );
+    ///
+    /// let status = client
+    ///     .describe("grpc.health.v1.HealthCheckResponse.ServingStatus")
+    ///     .await?;
+    /// assert!(matches!(status, SymbolDescription::Enum(_)));
+    ///
+    /// let error = client.describe("grpc.health.v1.Helth").await.unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     ReflectionError::TypeNotFound { kind: "symbol", suggestions, .. }
+    ///         if suggestions == ["grpc.health.v1.Health"]
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn describe(&mut self, symbol: &str) -> Result<SymbolDescription, ReflectionError> {
+        let name = match symbol
+            .strip_prefix('/')
+            .and_then(|_| parse_method_path(symbol))
+        {
+            Some((service, method)) => lookup::qualify(service.trim_start_matches('.'), method),
+            None => symbol.trim_start_matches('.').to_string(),
+        };
+        let files = match self.files_near(&name).await {
+            Ok(files) => files,
+            Err(ReflectionError::NotFound { .. }) => Vec::new(),
+            Err(error) => return Err(error),
+        };
+
+        let services = lookup::services(&files);
+        if services.iter().any(|(service, ..)| *service == name) {
+            return Ok(SymbolDescription::Service(self.get_service(&name).await?));
+        }
+        let is_method = name.rsplit_once('.').is_some_and(|(scope, method)| {
+            services.iter().any(|(service, _, descriptor)| {
+                service == scope
+                    && descriptor
+                        .method
+                        .iter()
+                        .any(|candidate| candidate.name() == method)
+            })
+        });
+        if is_method {
+            let (service, method) = self.get_method(&name).await?;
+            let request = self.describe_message(&method.request).await?;
+            let response = self.describe_message(&method.response).await?;
+            return Ok(SymbolDescription::Method {
+                service: service.full_name(),
+                method,
+                request: Box::new(request),
+                response: Box::new(response),
+            });
+        }
+        if lookup::messages(&files)
+            .iter()
+            .any(|(message, descriptor)| {
+                *message == name && !message_info::is_map_entry(descriptor)
+            })
+        {
+            return Ok(SymbolDescription::Message(
+                self.describe_message(&name).await?,
+            ));
+        }
+        if lookup::enums(&files)
+            .iter()
+            .any(|(enumeration, _)| *enumeration == name)
+        {
+            return Ok(SymbolDescription::Enum(self.describe_enum(&name).await?));
+        }
+
+        let files = self.get_all_file_descriptors().await?;
+        let mut symbols = Vec::new();
+        for (service, _, descriptor) in lookup::services(&files) {
+            symbols.extend(
+                descriptor
+                    .method
+                    .iter()
+                    .map(|method| lookup::qualify(&service, method.name())),
+            );
+            symbols.push(service);
+        }
+        symbols.extend(
+            files
+                .iter()
+                .flat_map(TypeInfo::defined_in)
+                .map(|type_info| type_info.full_name),
+        );
+        Err(ReflectionError::TypeNotFound {
+            suggestions: lookup::suggestions(&name, symbols.iter().map(String::as_str)),
+            name,
+            kind: "symbol",
+        })
+    }
+
+    /// Renders the file defining `symbol`, e.g. a service or message, as `.proto` source text
+    /// with [`render_file`](crate::proto_printer::render_file).
+    ///
+    /// The first file of the server's response is rendered, which is the one defining the
+    /// symbol, even if the server sends the files it imports along with it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return the same errors as
+    /// [`get_file_descriptor`](Self::get_file_descriptor), and
+    /// [`ReflectionError::NotFound`] if the server responds without any file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let source = client
+    ///     .describe_as_proto("grpc.reflection.v1alpha.ServerReflection")
+    ///     .await?;
+    /// assert!(source.starts_with("syntax = \"proto3\";\n\npackage grpc.reflection.v1alpha;\n"));
+    /// assert!(source.contains(
+    ///     "  rpc ServerReflectionInfo(stream .grpc.reflection.v1alpha.ServerReflectionRequest) \
+    ///      returns (stream .grpc.reflection.v1alpha.ServerReflectionResponse);\n"
+    /// ));
+    /// protox_parse::parse("reflection.proto", &source).unwrap();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn describe_as_proto(&mut self, symbol: &str) -> Result<String, ReflectionError> {
+        let name = symbol.trim_start_matches('.').to_string();
+        let files = self.get_file_descriptor(name.clone()).await?;
+        match files.first() {
+            Some(file) => Ok(proto_printer::render_file(file)),
+            None => Err(ReflectionError::NotFound { name }),
+        }
+    }
+
+    /// Describes the enum types of the fields of `message`, found in `files` or fetched from
+    /// the server, leaving out those the server does not know.
+    async fn enums_of(
+        &mut self,
+        message: &prost_types::DescriptorProto,
+        files: &[prost_types::FileDescriptorProto],
+    ) -> Result<HashMap<String, EnumInfo>, ReflectionError> {
+        let describe = |files: &[prost_types::FileDescriptorProto]| {
+            lookup::enums(files)
+                .into_iter()
+                .map(|(name, enumeration)| (name.clone(), EnumInfo::new(name, enumeration)))
+                .collect::<Vec<_>>()
+        };
+
+        let mut enums: HashMap<String, EnumInfo> = describe(files).into_iter().collect();
+        for enum_type in MessageInfo::enum_types(message) {
+            if enums.contains_key(&enum_type) {
+                continue;
+            }
+            match self
+                .file_descriptor_containing(enum_type, &MetadataMap::new())
+                .await
+            {
+                Ok(files) => enums.extend(describe(&files)),
+                Err(ReflectionError::NotFound { .. }) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(enums)
+    }
+
+    /// Retrieves the file descriptors containing `name`, or, if the server does not know it,
+    /// those containing its closest enclosing scope the server knows, in which similar names
+    /// can be looked up.
+    async fn files_near(
+        &mut self,
+        name: &str,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        let mut scope = name;
+        loop {
+            match self
+                .file_descriptor_containing(scope.to_string(), &MetadataMap::new())
+                .await
+            {
+                Err(ReflectionError::NotFound { .. }) => match scope.rsplit_once('.') {
+                    Some((parent, _)) => scope = parent,
+                    None => {
+                        return Err(ReflectionError::NotFound {
+                            name: name.to_string(),
+                        })
+                    }
+                },
+                result => return result,
+            }
+        }
+    }
+
+    /// Retrieves the file descriptor of the file defining the extension with the given field
+    /// number of `containing_type`, the fully qualified name of the extended message.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The server does not know the extension, or does not support extension lookups, as
+    ///   [`ReflectionError::NotFound`]
+    /// - The response from the server is not a `FileDescriptorResponse`
+    /// - The file descriptors cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// // tonic's reflection server does not support extension lookups
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let error = client
+    ///     .get_file_containing_extension("google.protobuf.MethodOptions".to_string(), 50000)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(error, ReflectionError::NotFound { .. }));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn get_file_containing_extension(
+        &mut self,
+        containing_type: String,
+        extension_number: i32,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        let name = format!("extension {} of {}", extension_number, containing_type);
+        self.file_descriptors(
+            MessageRequest::FileContainingExtension(ExtensionRequest {
+                containing_type,
+                extension_number,
+            }),
+            name,
+            &MetadataMap::new(),
+        )
+        .await
+    }
+
+    /// Retrieves the field numbers of all extensions of the message type `type_name`, e.g.
+    /// `google.protobuf.MethodOptions`, known to the server.
+    ///
+    /// Servers without extension support may answer with an empty list.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails, e.g. with `UNIMPLEMENTED` if the server does not
+    ///   support the request
+    /// - The server does not know the message type, as [`ReflectionError::NotFound`]
+    /// - The response from the server is not an `AllExtensionNumbersResponse`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// // tonic's reflection server does not support extensions and reports none
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let extensions = client
+    ///     .all_extension_numbers("google.protobuf.MethodOptions".to_string())
+    ///     .await?;
+    /// assert!(extensions.extension_numbers.is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn all_extension_numbers(
+        &mut self,
+        type_name: String,
+    ) -> Result<ExtensionNumbers, ReflectionError> {
+        let request = self.request(MessageRequest::AllExtensionNumbersOfType(type_name.clone()));
+        let response = match self.make_request(request, &MetadataMap::new()).await {
+            Err(ReflectionError::Status(status)) if status.code() == Code::NotFound => {
+                return Err(ReflectionError::NotFound { name: type_name });
+            }
+            response => response?,
+        };
+
+        match response {
+            MessageResponse::AllExtensionNumbersResponse(response) => Ok(ExtensionNumbers {
+                base_type_name: response.base_type_name,
+                extension_numbers: response.extension_number,
+            }),
+            _ => Err(ReflectionError::UnexpectedResponse {
+                expected: "AllExtensionNumbersResponse",
+            }),
+        }
+    }
+
+    /// Retrieves the custom options of the service `full_name`, e.g.
+    /// `option (acl.audience) = "internal";`.
+    ///
+    /// The file defining the service is fetched on each call, bypassing the cache, since the
+    /// custom options are only kept in its encoding.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The server does not know the service, as [`ReflectionError::NotFound`]
+    /// - The response from the server is not a `FileDescriptorResponse`
+    /// - The file descriptors or options cannot be decoded
+    pub async fn service_options(
+        &mut self,
+        full_name: &str,
+    ) -> Result<CustomOptions, ReflectionError> {
+        let name = full_name.trim_start_matches('.');
+        match options::find(&self.encoded_files_containing(name).await?, name, None)? {
+            Some(Ok(options)) => Ok(options),
+            Some(Err(_)) | None => Err(ReflectionError::NotFound {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    /// Retrieves the custom options of the method at `path`, e.g.
+    /// `option (acl.required_role) = "admin";`, given like to
+    /// [`get_method`](Self::get_method).
+    ///
+    /// The file defining the method is fetched on each call, bypassing the cache, since the
+    /// custom options are only kept in its encoding. Decode them with
+    /// [`decode_options`](Self::decode_options).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - [`ReflectionError::InvalidMethodPath`] if the path names no service and method
+    /// - The server does not know the service, as [`ReflectionError::NotFound`]
+    /// - [`ReflectionError::MethodNotFound`], listing the methods of the service, if the
+    ///   service does not define the method
+    /// - The response from the server is not a `FileDescriptorResponse`
+    /// - The file descriptors or options cannot be decoded
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::reflection::ReflectionClient;
+    ///
+    /// # let endpoint =
+    /// #     support::serve_reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]).await?;
+    ///
+    /// // the reflection service declares no custom options, and tonic's reflection server
+    /// // would drop them anyway
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let options = client
+    ///     .method_options("grpc.reflection.v1alpha.ServerReflection.ServerReflectionInfo")
+    ///     .await?;
+    /// assert_eq!(options.extendee, "google.protobuf.MethodOptions");
+    /// assert!(options.is_empty());
+    /// assert!(client.decode_options(&options).await?.is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub async fn method_options(&mut self, path: &str) -> Result<CustomOptions, ReflectionError> {
+        let (service, method) =
+            parse_method_path(path).ok_or_else(|| ReflectionError::InvalidMethodPath {
+                path: path.to_string(),
+            })?;
+        let service = service.trim_start_matches('.');
+
+        let files = self.encoded_files_containing(service).await?;
+        match options::find(&files, service, Some(method))? {
+            Some(Ok(options)) => Ok(options),
+            Some(Err(methods)) => Err(ReflectionError::MethodNotFound {
+                service: service.to_string(),
+                method: method.to_string(),
+                methods,
+            }),
+            None => Err(ReflectionError::NotFound {
+                name: service.to_string(),
+            }),
+        }
+    }
+
+    /// Decodes the custom `options` into JSON, keyed by the fully qualified names of their
+    /// extensions, e.g. `acl.required_role`, like [`CustomOptions::decode`].
+    ///
+    /// The extensions are looked up on the server by their field numbers, along with the
+    /// files they import for the types of their values. Options whose extensions the server
+    /// does not know are left out, and remain available as raw values in `options`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if:
+    /// - The request to the server fails
+    /// - The response from the server is not a `FileDescriptorResponse`
+    /// - The file descriptors or the values of the options cannot be decoded
+    pub async fn decode_options(
+        &mut self,
+        options: &CustomOptions,
+    ) -> Result<BTreeMap<String, serde_json::Value>, ReflectionError> {
+        let mut decoded = BTreeMap::new();
+        for number in options.numbers() {
+            let files = match self
+                .get_file_containing_extension(options.extendee.clone(), number as i32)
+                .await
+            {
+                Ok(files) => files,
+                Err(ReflectionError::NotFound { .. }) => continue,
+                Err(error) => return Err(error),
+            };
+            let files = self.resolve_dependencies(files).await?;
+            if let Some((name, extension)) = options::extension(&files, &options.extendee, number) {
+                if let Some(value) = options.decode(extension, &files)? {
+                    decoded.insert(name, value);
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    /// Fetches the encoded file descriptors containing `symbol`, without decoding or caching
+    /// them.
+    async fn encoded_files_containing(
+        &mut self,
+        symbol: &str,
+    ) -> Result<Vec<Vec<u8>>, ReflectionError> {
+        let request = self.request(MessageRequest::FileContainingSymbol(symbol.to_string()));
+        match self.make_request(request, &MetadataMap::new()).await {
+            Ok(MessageResponse::FileDescriptorResponse(response)) => {
+                Ok(response.file_descriptor_proto)
+            }
+            Ok(_) => Err(ReflectionError::UnexpectedResponse {
+                expected: "FileDescriptorResponse",
+            }),
+            Err(ReflectionError::Status(status)) if status.code() == Code::NotFound => {
+                Err(ReflectionError::NotFound {
+                    name: symbol.to_string(),
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    async fn file_descriptor_containing(
+        &mut self,
+        symbol: String,
+        metadata: &MetadataMap,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        self.file_descriptors(
+            MessageRequest::FileContainingSymbol(symbol.clone()),
+            symbol,
+            metadata,
+        )
+        .await
+    }
+
+    /// Sends `request`, which asks for the file descriptors containing `name`, and decodes the
+    /// file descriptors of the response.
+    async fn file_descriptors(
+        &mut self,
+        request: MessageRequest,
+        name: String,
+        metadata: &MetadataMap,
+    ) -> Result<Vec<prost_types::FileDescriptorProto>, ReflectionError> {
+        // per-call metadata may change the response, e.g. by selecting a tenant
+        let cache = self.cache.as_ref().filter(|_| metadata.is_empty());
+        if let Some(descriptors) = cache.and_then(|cache| cache.get(&request)) {
+            trace!("using cached file descriptors for {:?}", name);
+            return Ok(descriptors);
+        }
+
+        let message_request = request.clone();
+        let request = self.request(request);
+        let response = match self.make_request(request, metadata).await {
+            // error responses end up here as well, since some servers, like tonic's, fail the
+            // call instead of sending one
+            Err(ReflectionError::Status(status)) if status.code() == Code::NotFound => {
+                return Err(ReflectionError::NotFound { name });
+            }
+            response => response?,
+        };
+
+        match response {
+            MessageResponse::FileDescriptorResponse(descriptor_response) => {
+                let mut descriptors = Vec::new();
+                for file_descriptor_proto in descriptor_response.file_descriptor_proto {
+                    let file_descriptor =
+                        prost_types::FileDescriptorProto::decode(&file_descriptor_proto[..])?;
+                    descriptors.push(file_descriptor);
+                }
+                if let Some(cache) = self.cache.as_ref().filter(|_| metadata.is_empty()) {
+                    cache.insert(&message_request, &descriptors);
+                }
+                Ok(descriptors)
+            }
+            _ => Err(ReflectionError::UnexpectedResponse {
+                expected: "FileDescriptorResponse",
+            }),
+        }
+    }
+}
+
+/// Turns an error response sent by the server into a [`ReflectionError::Status`] carrying its
+/// code and message.
+fn error_response_to_status(response: MessageResponse) -> Result<MessageResponse, ReflectionError> {
+    match response {
+        MessageResponse::ErrorResponse(error) => {
+            Err(tonic::Status::new(Code::from(error.error_code), error.error_message).into())
+        }
+        response => Ok(response),
+    }
+}
+
+/// Returns the message name a type URL like `type.googleapis.com/package.Message` ends with.
+fn type_url_name(url: &str) -> Result<&str, ReflectionError> {
+    let name = url.rsplit_once('/').map_or(url, |(_, name)| name);
+    if name.is_empty() {
+        return Err(ReflectionError::InvalidTypeUrl {
+            url: url.to_string(),
+        });
+    }
+    Ok(name)
+}
+
+/// Splits a method path of the form `package.Service.Method` or `/package.Service/Method`
+/// into the fully qualified service name and the method name.
+pub(crate) fn parse_method_path(path: &str) -> Option<(&str, &str)> {
+    let (service, method) = match path.strip_prefix('/') {
+        Some(path) => path.split_once('/')?,
+        None => path.rsplit_once('.')?,
+    };
+
+    let valid = |name: &str| !name.is_empty() && !name.contains('/');
+    (valid(service) && valid(method) && !method.contains('.')).then_some((service, method))
+}
+
+/// Returns whether the fully qualified `name` is in `package` or one of its subpackages,
+/// ignoring a trailing dot of the package.
+pub(crate) fn in_package(name: &str, package: &str) -> bool {
+    let package = package.strip_suffix('.').unwrap_or(package);
+    package.is_empty()
+        || name
+            .strip_prefix(package)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// Returns the services defined in those of `descriptors` that are not in `fetched_files` yet,
+/// adding the files to it once all of their services are resolved.
+fn new_services(
+    descriptors: Vec<prost_types::FileDescriptorProto>,
+    fetched_files: &mut HashSet<String>,
+) -> Result<Vec<ServiceInfo>, ReflectionError> {
+    let mut files = HashSet::new();
+    let mut services = Vec::new();
+    for file_descriptor in descriptors {
+        let name = file_name(&file_descriptor)?;
+        if fetched_files.contains(&name) || !files.insert(name) {
+            continue;
+        }
+        services.extend(services_in(file_descriptor)?);
+    }
+
+    fetched_files.extend(files);
+    Ok(services)
+}
+
+/// Returns the services defined in `file_descriptor`.
+fn services_in(
+    mut file_descriptor: prost_types::FileDescriptorProto,
+) -> Result<Vec<ServiceInfo>, ReflectionError> {
+    std::mem::take(&mut file_descriptor.service)
+        .into_iter()
+        .enumerate()
+        .map(|(index, service)| service_info(&file_descriptor, index, service))
+        .collect()
+}
+
+/// Returns the information about `service`, defined in `file` at `index`.
+pub(crate) fn service_info(
+    file: &prost_types::FileDescriptorProto,
+    index: usize,
+    service: prost_types::ServiceDescriptorProto,
+) -> Result<ServiceInfo, ReflectionError> {
+    let source = Source::service(file, index);
+    let deprecated = service
+        .options
+        .as_ref()
+        .is_some_and(|options| options.deprecated());
+    let methods: Vec<MethodInfo> = service
+        .method
+        .into_iter()
+        .enumerate()
+        .map(|(index, method)| {
+            let mut method = MethodInfo::try_from(method).map_err(|error| match error {
+                ReflectionError::MalformedDescriptor(message) => {
+                    ReflectionError::MalformedDescriptor(format!(
+                        "{} in service {:?}",
+                        message, service.name
+                    ))
+                }
+                error => error,
+            })?;
+            let comments = source.child(SERVICE_METHOD, index).comments();
+            method.leading_comments = comments.leading;
+            method.trailing_comments = comments.trailing;
+            Ok(method)
+        })
+        .collect::<Result<Vec<MethodInfo>, ReflectionError>>()?;
+
+    let package = file.package.clone().ok_or_else(|| {
+        ReflectionError::MalformedDescriptor(format!(
+            "Package name is missing for service {:?}",
+            service.name
+        ))
+    })?;
+
+    let service_name = service.name.ok_or_else(|| {
+        ReflectionError::MalformedDescriptor(format!(
+            "Service name is missing for package {}",
+            package
+        ))
+    })?;
+
+    let comments = source.comments();
+    Ok(ServiceInfo {
+        package,
+        service: service_name,
+        methods,
+        deprecated,
+        file_name: file_name(file)?,
+        syntax: Syntax::of(file),
+        leading_comments: comments.leading,
+        trailing_comments: comments.trailing,
+    })
+}
+
+/// Returns the path of `method`, e.g. `/shop.Orders/Place`, for a call streaming the requests
+/// and responses as given, or a [`ReflectionError::StreamingMismatch`] if the method streams
+/// differently.
+#[cfg(feature = "prost-reflect")]
+fn call_path(
+    method: &prost_reflect::MethodDescriptor,
+    client_streaming: bool,
+    server_streaming: bool,
+) -> Result<(String, PathAndQuery), ReflectionError> {
+    let path = format!("/{}/{}", method.parent_service().full_name(), method.name());
+    if method.is_client_streaming() != client_streaming
+        || method.is_server_streaming() != server_streaming
+    {
+        return Err(ReflectionError::StreamingMismatch {
+            path,
+            client_streaming: method.is_client_streaming(),
+            server_streaming: method.is_server_streaming(),
+        });
+    }
+
+    match PathAndQuery::try_from(path.as_str()) {
+        Ok(path_and_query) => Ok((path, path_and_query)),
+        Err(_) => Err(ReflectionError::InvalidMethodPath { path }),
+    }
+}
+
+/// Runs `attempt` for a call of the method `path` with the `idempotency_level` until it
+/// succeeds or fails with a status the retry `policy` does not retry, returning the status of
+/// the last attempt along with those of the earlier ones.
+async fn retrying<R, F>(
+    path: &str,
+    policy: Option<&RetryPolicy>,
+    idempotency_level: IdempotencyLevel,
+    mut attempt: impl FnMut() -> F,
+) -> Result<Result<R, (Status, Vec<Status>)>, ReflectionError>
+where
+    F: Future<Output = Result<Result<R, Status>, ReflectionError>>,
+{
+    // only retried calls are timed, which platforms without a clock cannot
+    let policy = policy
+        .filter(|policy| policy.is_retryable_method(idempotency_level))
+        .map(|policy| (policy, Stopwatch::start()));
+    let mut earlier = Vec::new();
+    loop {
+        let status = match attempt().await? {
+            Ok(response) => return Ok(Ok(response)),
+            Err(status) => status,
+        };
+
+        let backoff = policy.and_then(|(policy, started)| {
+            let backoff = policy.backoff(earlier.len() as u32)?;
+            let retry = policy.is_retryable(status.code())
+                && policy.is_within_budget(started.elapsed() + backoff);
+            retry.then_some(backoff)
+        });
+        let Some(backoff) = backoff else {
+            return Ok(Err((status, earlier)));
+        };
+        debug!("{} failed: {}, retrying in {:?}", path, status, backoff);
+        earlier.push(status);
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+/// Returns the status code a call failed with, `UNKNOWN` if it failed without a status.
+#[cfg(feature = "prost-reflect")]
+fn error_code(error: &ReflectionError) -> Code {
+    match error {
+        ReflectionError::Call { status, .. } => status.code(),
+        ReflectionError::Retried { source, .. } => error_code(source),
+        _ => Code::Unknown,
+    }
+}
+
+/// Reports a request failing because the server rejected the TLS handshake like a handshake
+/// failing while connecting.
+///
+/// With TLS 1.3, the client considers the handshake complete before the server verifies the
+/// client identity, so a server requiring mutual TLS only rejects a client without one, or
+/// with an untrusted one, once the first request is sent.
+#[cfg(feature = "tls")]
+fn handshake_error(endpoint: &str, error: ReflectionError) -> ReflectionError {
+    match error {
+        ReflectionError::Status(status)
+            if error::caused_by::<tokio_rustls::rustls::Error>(&*status)
+                || error::caused_by_tls_alert(&*status) =>
+        {
+            ReflectionError::TlsHandshake {
+                endpoint: endpoint.to_string(),
+                source: status,
+            }
+        }
+        error => error,
+    }
+}
+
+/// Builds a descriptor pool from `files`, each of which comes after the files it imports.
+#[cfg(feature = "prost-reflect")]
+fn descriptor_pool(
+    files: Vec<prost_types::FileDescriptorProto>,
+) -> Result<prost_reflect::DescriptorPool, ReflectionError> {
+    prost_reflect::DescriptorPool::from_file_descriptor_set(prost_types::FileDescriptorSet {
+        file: files,
+    })
+    .map_err(|error| {
+        ReflectionError::MalformedDescriptor(format!("Invalid file descriptors: {}", error))
+    })
+}
+
+/// Merges `next`, a further message of a response split across several messages, into
+/// `response`.
+///
+/// File descriptors and services are collected from all messages; of other responses, which
+/// are never split, the first message is kept.
+fn merge_responses(response: MessageResponse, next: MessageResponse) -> MessageResponse {
+    match (response, next) {
+        (
+            MessageResponse::FileDescriptorResponse(mut response),
+            MessageResponse::FileDescriptorResponse(next),
+        ) => {
+            response
+                .file_descriptor_proto
+                .extend(next.file_descriptor_proto);
+            MessageResponse::FileDescriptorResponse(response)
+        }
+        (
+            MessageResponse::ListServicesResponse(mut response),
+            MessageResponse::ListServicesResponse(next),
+        ) => {
+            response.service.extend(next.service);
+            MessageResponse::ListServicesResponse(response)
+        }
+        (response, _) => {
+            debug!("ignoring further message of a reflection response");
+            response
+        }
+    }
+}
+
+/// Returns `metadata` with the entries of `overrides` replacing those with the same key.
+fn merge_metadata(metadata: &MetadataMap, overrides: &MetadataMap) -> MetadataMap {
+    let mut merged = metadata.clone();
+    for key in overrides.keys() {
+        match key {
+            KeyRef::Ascii(key) => {
+                merged.remove(key);
+            }
+            KeyRef::Binary(key) => {
+                merged.remove_bin(key);
+            }
+        }
+    }
+    for entry in overrides.iter() {
+        match entry {
+            KeyAndValueRef::Ascii(key, value) => {
+                merged.append(key.clone(), value.clone());
+            }
+            KeyAndValueRef::Binary(key, value) => {
+                merged.append_bin(key.clone(), value.clone());
+            }
+        }
+    }
+
+    merged
 }
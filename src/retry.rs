@@ -0,0 +1,290 @@
+//! Retrying reflection calls that failed with a transient gRPC status.
+
+use crate::service_info::IdempotencyLevel;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tonic::Code;
+
+/// How a [`ReflectionClient`](crate::reflection::ReflectionClient) retries calls the server
+/// answered with a transient gRPC status, e.g. `UNAVAILABLE` during a rolling deploy.
+///
+/// Only statuses with one of the retryable codes are retried, by default `UNAVAILABLE` and
+/// `RESOURCE_EXHAUSTED`; any other status, like `NOT_FOUND` or `INVALID_ARGUMENT`, is returned
+/// right away. Between the attempts, the client waits an exponentially growing delay like
+/// with the [`ReconnectPolicy`](crate::reconnect::ReconnectPolicy), shortened by a random
+/// fraction of up to the jitter, so clients failing at the same time do not retry in lockstep.
+///
+/// The default policy does not retry at all. Connection failures are retried according to the
+/// reconnect policy instead.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use grpc_ease::retry::RetryPolicy;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use tonic::{Code, Status};
+///
+/// // a server that is unavailable for the first two requests
+/// let requests = Arc::new(AtomicUsize::new(0));
+/// let counter = requests.clone();
+/// let unavailable_at_first = tonic::service::interceptor(move |request| {
+///     match counter.fetch_add(1, Ordering::SeqCst) {
+///         0 | 1 => Err(Status::unavailable("deploying")),
+///         _ => Ok(request),
+///     }
+/// });
+///
+/// # let reflection = support::reflection(&[tonic_reflection::pb::FILE_DESCRIPTOR_SET]);
+/// # let (incoming, endpoint) = support::bind().await?;
+/// # tokio::spawn(
+/// #     tonic::transport::Server::builder()
+/// #         .layer(unavailable_at_first)
+/// #         .add_service(reflection)
+/// #         .serve_with_incoming(incoming),
+/// # );
+///
+/// let mut client = ReflectionClient::new(endpoint.clone()).await?;
+/// let error = client.list_service_names().await.unwrap_err();
+/// assert!(matches!(error, grpc_ease::error::ReflectionError::Status(status) if status.code() == Code::Unavailable));
+///
+/// let mut client = ReflectionClient::builder(&endpoint)
+///     .retry(
+///         RetryPolicy::default()
+///             .max_retries(3)
+///             .initial_backoff(Duration::from_millis(10)),
+///     )
+///     .build()
+///     .await?;
+/// assert!(client.list_service_names().await.is_ok());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: f64,
+    retryable_codes: Vec<Code>,
+    budget: Option<Duration>,
+    retry_non_idempotent: bool,
+}
+
+impl RetryPolicy {
+    /// The delay before the first retry of the default policy.
+    pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+    /// The maximum delay between retries of the default policy.
+    pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+    /// The maximum fraction by which the default policy shortens each delay.
+    pub const DEFAULT_JITTER: f64 = 0.2;
+    /// The status codes retried by the default policy.
+    pub const DEFAULT_RETRYABLE_CODES: &'static [Code] =
+        &[Code::Unavailable, Code::ResourceExhausted];
+
+    /// Sets the maximum number of retries after the initial attempt.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum delay between two retries.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum fraction, between `0.0` and `1.0`, by which each delay is shortened at
+    /// random. A jitter of `0.0` makes the delays deterministic.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the status codes that are retried, replacing the default ones.
+    pub fn retryable_codes(mut self, codes: impl IntoIterator<Item = Code>) -> Self {
+        self.retryable_codes = codes.into_iter().collect();
+        self
+    }
+
+    /// Sets how long after the first attempt retries may start, across all retries. A retry
+    /// whose delay would end after the budget is not made.
+    ///
+    /// The budget is not enforced on platforms without a clock, like `wasm32-unknown-unknown`.
+    pub fn budget(mut self, budget: Duration) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sets whether method calls are retried even if their method is not marked as
+    /// `IDEMPOTENT` or `NO_SIDE_EFFECTS`, which is off by default since such calls may take
+    /// effect more than once.
+    ///
+    /// This only applies to calls with a policy of their
+    /// [`CallOptions`](crate::invoke::CallOptions), as reflection requests have no side
+    /// effects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "prost-reflect")]
+    /// # tokio_test::block_on(async {
+    /// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+    /// use grpc_ease::error::ReflectionError;
+    /// use grpc_ease::invoke::CallOptions;
+    /// use grpc_ease::reflection::ReflectionClient;
+    /// use grpc_ease::retry::RetryPolicy;
+    /// use serde_json::json;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use tonic::service::interceptor::InterceptedService;
+    /// use tonic::{Code, Status};
+    ///
+    /// // a health service that is unavailable for the first two checks
+    /// let checks = Arc::new(AtomicUsize::new(0));
+    /// let counter = checks.clone();
+    /// let (_, health) = tonic_health::server::health_reporter();
+    /// let health = InterceptedService::new(health, move |request| {
+    ///     match counter.fetch_add(1, Ordering::SeqCst) {
+    ///         0 | 1 => Err(Status::unavailable("deploying")),
+    ///         _ => Ok(request),
+    ///     }
+    /// });
+    ///
+    /// # let reflection = support::reflection(&[tonic_health::pb::FILE_DESCRIPTOR_SET]);
+    /// # let endpoint = support::serve(
+    /// #     tonic::transport::Server::builder()
+    /// #         .add_service(health)
+    /// #         .add_service(reflection),
+    /// # )
+    /// # .await?;
+    ///
+    /// let mut client = ReflectionClient::new(endpoint).await?;
+    /// let policy = RetryPolicy::default()
+    ///     .max_retries(3)
+    ///     .initial_backoff(Duration::from_millis(10));
+    /// let check = |policy: RetryPolicy| {
+    ///     let mut options = CallOptions::default();
+    ///     options.retry = Some(policy);
+    ///     (json!({}), options)
+    /// };
+    ///
+    /// // Check is not marked as idempotent
+    /// let (request, options) = check(policy.clone());
+    /// let error = client
+    ///     .call_unary("/grpc.health.v1.Health/Check", request, options)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     error,
+    ///     ReflectionError::Call { status, .. } if status.code() == Code::Unavailable
+    /// ));
+    ///
+    /// checks.store(0, Ordering::SeqCst);
+    /// let (request, options) = check(policy.clone().retry_non_idempotent(true));
+    /// let response = client
+    ///     .call_unary("/grpc.health.v1.Health/Check", request, options)
+    ///     .await?;
+    /// assert_eq!(response, json!({ "status": "SERVING" }));
+    ///
+    /// checks.store(0, Ordering::SeqCst);
+    /// let (request, options) = check(policy.max_retries(1).retry_non_idempotent(true));
+    /// let error = client
+    ///     .call_unary("/grpc.health.v1.Health/Check", request, options)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(
+    ///     &error,
+    ///     ReflectionError::Retried { attempts, .. } if attempts.len() == 2
+    /// ));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// # }).unwrap();
+    /// ```
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Returns whether calls failing with `code` are retried.
+    pub fn is_retryable(&self, code: Code) -> bool {
+        self.retryable_codes.contains(&code)
+    }
+
+    /// Returns whether calls of methods with the `idempotency_level` are retried.
+    pub fn is_retryable_method(&self, idempotency_level: IdempotencyLevel) -> bool {
+        self.retry_non_idempotent || idempotency_level != IdempotencyLevel::Unknown
+    }
+
+    /// Returns whether a retry may start once `elapsed` passed since the first attempt.
+    pub fn is_within_budget(&self, elapsed: Duration) -> bool {
+        self.budget.is_none_or(|budget| elapsed <= budget)
+    }
+
+    /// Returns the delay before retry number `retry`, counting from zero, or `None` if the
+    /// policy allows no further retries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::retry::RetryPolicy;
+    /// use std::time::Duration;
+    ///
+    /// let policy = RetryPolicy::default()
+    ///     .max_retries(2)
+    ///     .initial_backoff(Duration::from_millis(100))
+    ///     .jitter(0.5);
+    /// let backoff = policy.backoff(1).unwrap();
+    /// assert!(backoff > Duration::from_millis(100) && backoff <= Duration::from_millis(200));
+    /// assert_eq!(policy.backoff(2), None);
+    /// assert_eq!(RetryPolicy::default().backoff(0), None);
+    /// ```
+    pub fn backoff(&self, retry: u32) -> Option<Duration> {
+        if retry >= self.max_retries {
+            return None;
+        }
+
+        let factor = 2u32.checked_pow(retry).unwrap_or(u32::MAX);
+        let backoff = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+        Some(backoff.mul_f64(1.0 - self.jitter * random_fraction()))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Never retries, but retries statuses with one of the
+    /// [`DEFAULT_RETRYABLE_CODES`](Self::DEFAULT_RETRYABLE_CODES) once the number of retries
+    /// is raised with [`max_retries`](Self::max_retries).
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Self::DEFAULT_INITIAL_BACKOFF,
+            max_backoff: Self::DEFAULT_MAX_BACKOFF,
+            jitter: Self::DEFAULT_JITTER,
+            retryable_codes: Self::DEFAULT_RETRYABLE_CODES.to_vec(),
+            budget: None,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// Returns a random number in `[0, 1)`, from the randomly seeded keys of std's hasher, which
+/// is random enough to spread out retries.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
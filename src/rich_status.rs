@@ -0,0 +1,220 @@
+//! Decoding the `google.rpc.Status` details servers attach to failed calls, in the
+//! `grpc-status-details-bin` trailer.
+//!
+//! The standard detail messages of `google/rpc/error_details.proto`, like `BadRequest` or
+//! `RetryInfo`, are decoded to the types of tonic-types, which are re-exported here. Details
+//! of other types are resolved through reflection by the calls of the
+//! [`ReflectionClient`](crate::reflection::ReflectionClient), with the `prost-reflect` feature.
+
+use base64::Engine;
+use prost::Message;
+use std::fmt;
+use tonic_types::RpcStatusExt;
+pub use tonic_types::{
+    BadRequest, DebugInfo, ErrorDetail, ErrorInfo, FieldViolation, Help, HelpLink,
+    LocalizedMessage, PreconditionFailure, PreconditionViolation, QuotaFailure, QuotaViolation,
+    RequestInfo, ResourceInfo, RetryInfo,
+};
+
+/// The status of a failed call along with its decoded details
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::rich_status::{ErrorDetail, RichStatus, StatusDetail};
+/// use prost::Message;
+/// use tonic::Code;
+///
+/// // as sent by a server rejecting a request
+/// let details = tonic_types::Status {
+///     code: Code::InvalidArgument.into(),
+///     message: "Invalid order".to_string(),
+///     details: vec![
+///         prost_types::Any {
+///             type_url: "type.googleapis.com/google.rpc.BadRequest".to_string(),
+///             value: tonic_types::pb::BadRequest {
+///                 field_violations: vec![tonic_types::pb::bad_request::FieldViolation {
+///                     field: "quantity".to_string(),
+///                     description: "must be positive".to_string(),
+///                 }],
+///             }
+///             .encode_to_vec(),
+///         },
+///         prost_types::Any {
+///             type_url: "type.googleapis.com/shop.OrderRejection".to_string(),
+///             value: vec![0x08, 0x01],
+///         },
+///     ],
+/// };
+/// let status = tonic::Status::with_details(
+///     Code::InvalidArgument,
+///     "Invalid order",
+///     details.encode_to_vec().into(),
+/// );
+///
+/// let rich = RichStatus::from_status(&status).unwrap();
+/// assert_eq!(rich.code, Code::InvalidArgument);
+/// assert!(matches!(
+///     &rich.details[0],
+///     StatusDetail::Standard(ErrorDetail::BadRequest(bad_request))
+///         if bad_request.field_violations[0].field == "quantity"
+/// ));
+/// assert!(matches!(&rich.details[1], StatusDetail::Raw { value, .. } if value == "CAE="));
+/// assert_eq!(
+///     rich.to_string(),
+///     "InvalidArgument: Invalid order (bad request: quantity: must be positive; \
+///      type.googleapis.com/shop.OrderRejection: CAE=)"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RichStatus {
+    /// The status code of the call
+    pub code: tonic::Code,
+    /// The message of the status
+    pub message: String,
+    /// The details, in the order the server sent them
+    pub details: Vec<StatusDetail>,
+}
+
+/// A detail of a [`RichStatus`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum StatusDetail {
+    /// One of the standard detail messages of `google/rpc/error_details.proto`
+    Standard(ErrorDetail),
+    /// A message of another type, resolved through reflection
+    Resolved {
+        /// The type URL of the message, e.g. `type.googleapis.com/shop.OrderRejection`
+        type_url: String,
+        /// The canonical protobuf JSON encoding of the message
+        message: serde_json::Value,
+    },
+    /// A message of a type that is not known
+    Raw {
+        /// The type URL of the message, e.g. `type.googleapis.com/shop.OrderRejection`
+        type_url: String,
+        /// The encoded message, encoded as base64
+        value: String,
+    },
+}
+
+impl RichStatus {
+    /// Decodes the details of `status`, or returns `None` if it has none or they are not an
+    /// encoded `google.rpc.Status`.
+    ///
+    /// Details of types other than the standard ones, and standard ones that cannot be
+    /// decoded, are [`StatusDetail::Raw`].
+    pub fn from_status(status: &tonic::Status) -> Option<Self> {
+        if status.details().is_empty() {
+            return None;
+        }
+        let decoded = tonic_types::Status::decode(status.details()).ok()?;
+
+        let details = decoded
+            .details
+            .into_iter()
+            .map(|any| {
+                let single = tonic_types::Status {
+                    details: vec![any],
+                    ..Default::default()
+                };
+                match single.check_error_details_vec() {
+                    Ok(mut details) if details.len() == 1 => {
+                        StatusDetail::Standard(details.remove(0))
+                    }
+                    _ => {
+                        let any = single.details.into_iter().next().unwrap_or_default();
+                        StatusDetail::Raw {
+                            type_url: any.type_url,
+                            value: base64::engine::general_purpose::STANDARD.encode(any.value),
+                        }
+                    }
+                }
+            })
+            .collect();
+
+        Some(Self {
+            code: status.code(),
+            message: status.message().to_string(),
+            details,
+        })
+    }
+}
+
+impl fmt::Display for RichStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)?;
+        if !self.details.is_empty() {
+            let details: Vec<String> = self.details.iter().map(ToString::to_string).collect();
+            write!(f, " ({})", details.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StatusDetail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Writes `label: ` followed by the `items`, separated by commas.
+        fn list<T>(
+            f: &mut fmt::Formatter<'_>,
+            label: &str,
+            items: &[T],
+            item: impl Fn(&T) -> String,
+        ) -> fmt::Result {
+            let items: Vec<String> = items.iter().map(item).collect();
+            write!(f, "{}: {}", label, items.join(", "))
+        }
+
+        match self {
+            Self::Standard(ErrorDetail::RetryInfo(info)) => match info.retry_delay {
+                Some(delay) => write!(f, "retry after {:?}", delay),
+                None => write!(f, "retry"),
+            },
+            Self::Standard(ErrorDetail::DebugInfo(info)) => {
+                write!(f, "debug info: {}", info.detail)
+            }
+            Self::Standard(ErrorDetail::QuotaFailure(failure)) => {
+                list(f, "quota failure", &failure.violations, |violation| {
+                    format!("{}: {}", violation.subject, violation.description)
+                })
+            }
+            Self::Standard(ErrorDetail::ErrorInfo(info)) => {
+                write!(f, "error info: {} ({})", info.reason, info.domain)
+            }
+            Self::Standard(ErrorDetail::PreconditionFailure(failure)) => list(
+                f,
+                "precondition failure",
+                &failure.violations,
+                |violation| {
+                    format!(
+                        "{} {}: {}",
+                        violation.r#type, violation.subject, violation.description
+                    )
+                },
+            ),
+            Self::Standard(ErrorDetail::BadRequest(bad_request)) => list(
+                f,
+                "bad request",
+                &bad_request.field_violations,
+                |violation| format!("{}: {}", violation.field, violation.description),
+            ),
+            Self::Standard(ErrorDetail::RequestInfo(info)) => {
+                write!(f, "request {}", info.request_id)
+            }
+            Self::Standard(ErrorDetail::ResourceInfo(info)) => write!(
+                f,
+                "resource {} {}: {}",
+                info.resource_type, info.resource_name, info.description
+            ),
+            Self::Standard(ErrorDetail::Help(help)) => list(f, "help", &help.links, |link| {
+                format!("{} ({})", link.description, link.url)
+            }),
+            Self::Standard(ErrorDetail::LocalizedMessage(message)) => {
+                write!(f, "{} ({})", message.message, message.locale)
+            }
+            Self::Standard(detail) => write!(f, "{:?}", detail),
+            Self::Resolved { type_url, message } => write!(f, "{}: {}", type_url, message),
+            Self::Raw { type_url, value } => write!(f, "{}: {}", type_url, value),
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! Generating JSON Schemas of messages, e.g. to drive forms that edit requests.
+
+use crate::catalog::OfflineCatalog;
+use crate::message_info::{EnumInfo, FieldInfo, FieldType, Label, MessageInfo, WellKnownType};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+/// The JSON Schema dialect of the generated schemas.
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Returns the JSON Schema (draft 2020-12) of the protobuf JSON encoding of `message`, looking
+/// up the message and enum types its fields refer to in `files`.
+///
+/// The schema follows the canonical JSON mapping of protobuf:
+/// - properties are named by the JSON names of the fields, e.g. `orderId`
+/// - 64-bit integers are strings of digits, `bytes` are base64 strings and enums are strings
+///   of their value names
+/// - repeated fields are arrays and maps are objects whose `additionalProperties` are the
+///   values
+/// - well-known types have their special representations, e.g. a `Timestamp` is a
+///   `date-time` string and an `Int32Value` is a plain integer
+///
+/// Every other message type is defined once in `$defs`, keyed by its fully qualified name,
+/// and referenced with `$ref`, while references to `message` itself point at the root `#`.
+/// Recursive messages therefore give finite schemas. Types not found in `files` are left
+/// unconstrained, and that at most one field of a oneof is set is not expressed.
+///
+/// The comments of messages and fields become the `description`s of their schemas.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::catalog::OfflineCatalog;
+/// use grpc_ease::schema::message_to_json_schema;
+/// use prost_types::field_descriptor_proto::{Label, Type};
+/// use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet};
+/// use serde_json::json;
+///
+/// let field = |name: &str, number, label: Label, r#type: Type, type_name: Option<&str>| {
+///     FieldDescriptorProto {
+///         name: Some(name.to_string()),
+///         number: Some(number),
+///         label: Some(label.into()),
+///         r#type: Some(r#type.into()),
+///         type_name: type_name.map(str::to_string),
+///         ..Default::default()
+///     }
+/// };
+/// // message Node {
+/// //   string name = 1;
+/// //   repeated Node children = 2;
+/// //   google.protobuf.Timestamp created = 3;
+/// //   Owner owner = 4;
+/// //   int64 size = 5;
+/// // }
+/// // message Owner {
+/// //   Node home = 1;
+/// // }
+/// let node = DescriptorProto {
+///     name: Some("Node".to_string()),
+///     field: vec![
+///         field("name", 1, Label::Optional, Type::String, None),
+///         field("children", 2, Label::Repeated, Type::Message, Some(".tree.Node")),
+///         field("created", 3, Label::Optional, Type::Message, Some(".google.protobuf.Timestamp")),
+///         field("owner", 4, Label::Optional, Type::Message, Some(".tree.Owner")),
+///         field("size", 5, Label::Optional, Type::Int64, None),
+///     ],
+///     ..Default::default()
+/// };
+/// let owner = DescriptorProto {
+///     name: Some("Owner".to_string()),
+///     field: vec![field("home", 1, Label::Optional, Type::Message, Some(".tree.Node"))],
+///     ..Default::default()
+/// };
+/// let tree = FileDescriptorProto {
+///     name: Some("tree.proto".to_string()),
+///     package: Some("tree".to_string()),
+///     syntax: Some("proto3".to_string()),
+///     message_type: vec![node, owner],
+///     ..Default::default()
+/// };
+/// let catalog = OfflineCatalog::new(FileDescriptorSet { file: vec![tree] });
+/// let node = catalog.describe_message("tree.Node")?;
+///
+/// let schema = message_to_json_schema(&node, catalog.files());
+/// assert_eq!(schema["$schema"], "https://json-schema.org/draft/2020-12/schema");
+/// assert_eq!(schema["type"], "object");
+/// let properties = &schema["properties"];
+/// assert_eq!(properties["name"], json!({ "type": "string" }));
+/// assert_eq!(properties["children"], json!({ "type": "array", "items": { "$ref": "#" } }));
+/// assert_eq!(properties["created"], json!({ "type": "string", "format": "date-time" }));
+/// assert_eq!(properties["owner"], json!({ "$ref": "#/$defs/tree.Owner" }));
+/// assert_eq!(properties["size"]["type"], "string");
+///
+/// // the owner refers back to the node, which ends the recursion
+/// let owner = &schema["$defs"]["tree.Owner"];
+/// assert_eq!(owner["properties"]["home"], json!({ "$ref": "#" }));
+/// # Ok::<(), grpc_ease::error::ReflectionError>(())
+/// ```
+pub fn message_to_json_schema(message: &MessageInfo, files: &[FileDescriptorProto]) -> Value {
+    let catalog = OfflineCatalog::new(FileDescriptorSet {
+        file: files.to_vec(),
+    });
+    let mut generator = Generator::new(&catalog, "#/$defs/");
+    generator.root = Some(message);
+
+    let mut schema = generator.message(message);
+    let definitions = generator.definitions();
+    if let Value::Object(root) = &mut schema {
+        root.insert("$schema".to_string(), json!(DIALECT));
+        if !definitions.is_empty() {
+            root.insert(
+                "$defs".to_string(),
+                Value::Object(definitions.into_iter().collect()),
+            );
+        }
+    }
+    schema
+}
+
+/// Returns the JSON Schema of the protobuf JSON encoding of the message `descriptor`, like
+/// [`message_to_json_schema`], looking up the types its fields refer to in its pool.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "prost-reflect")]
+/// # {
+/// use grpc_ease::schema::descriptor_to_json_schema;
+/// use prost_reflect::DescriptorPool;
+///
+/// let pool = DescriptorPool::decode(tonic_reflection::pb::FILE_DESCRIPTOR_SET)?;
+/// let request = pool
+///     .get_message_by_name("grpc.reflection.v1alpha.ServerReflectionRequest")
+///     .unwrap();
+///
+/// let schema = descriptor_to_json_schema(&request);
+/// assert_eq!(schema["properties"]["fileContainingSymbol"]["type"], "string");
+/// assert_eq!(
+///     schema["properties"]["fileContainingExtension"]["$ref"],
+///     "#/$defs/grpc.reflection.v1alpha.ExtensionRequest"
+/// );
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "prost-reflect")]
+pub fn descriptor_to_json_schema(descriptor: &prost_reflect::MessageDescriptor) -> Value {
+    let catalog = OfflineCatalog::new(FileDescriptorSet {
+        file: descriptor
+            .parent_pool()
+            .file_descriptor_protos()
+            .cloned()
+            .collect(),
+    });
+    match catalog.describe_message(descriptor.full_name()) {
+        Ok(message) => message_to_json_schema(&message, catalog.files()),
+        // the pool of a descriptor always defines it
+        Err(_) => json!({ "$schema": DIALECT }),
+    }
+}
+
+/// Generates the schemas of messages, defining the messages they refer to separately
+pub(crate) struct Generator<'a> {
+    catalog: &'a OfflineCatalog,
+    /// The prefix of the references to the definitions, e.g. `#/$defs/`
+    prefix: &'static str,
+    /// The message whose schema is generated, which is referenced as `#`, if its schema is
+    /// the root of the document
+    root: Option<&'a MessageInfo>,
+    /// The messages referenced so far, which are defined in `$defs`
+    referenced: HashSet<String>,
+    /// The referenced messages not yet defined
+    pending: VecDeque<String>,
+}
+
+impl<'a> Generator<'a> {
+    /// Creates a generator looking up the types of fields in `catalog`, referring to the
+    /// definitions of messages as `prefix` followed by their fully qualified names.
+    pub(crate) fn new(catalog: &'a OfflineCatalog, prefix: &'static str) -> Self {
+        Self {
+            catalog,
+            prefix,
+            root: None,
+            referenced: HashSet::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Defines the messages referenced so far, and the messages they refer to, keyed by
+    /// their fully qualified names.
+    pub(crate) fn definitions(&mut self) -> BTreeMap<String, Value> {
+        // every message is queued once, when it is first referenced, so this ends
+        let mut definitions = BTreeMap::new();
+        while let Some(name) = self.pending.pop_front() {
+            let definition = match self.describe_message(&name) {
+                Some(message) => self.message(&message),
+                None => json!({}),
+            };
+            definitions.insert(name, definition);
+        }
+        definitions
+    }
+
+    fn message(&mut self, message: &MessageInfo) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        let oneof_fields = message.oneofs.iter().flat_map(|oneof| &oneof.fields);
+        for field in message.fields.iter().chain(oneof_fields) {
+            let mut schema = self.field(field);
+            describe(&mut schema, field.leading_comments.as_deref());
+            if field.label == Label::Required {
+                required.push(json!(field.json_name));
+            }
+            properties.insert(field.json_name.clone(), schema);
+        }
+
+        let mut schema = json!({
+            "type": "object",
+            "title": message.full_name,
+            "properties": properties,
+        });
+        if !required.is_empty() {
+            schema["required"] = Value::Array(required);
+        }
+        describe(&mut schema, message.leading_comments.as_deref());
+        schema
+    }
+
+    fn field(&mut self, field: &FieldInfo) -> Value {
+        if let Some(map) = &field.map {
+            let values = self.value(map.value_type, map.value_type_name.as_deref(), None);
+            return json!({ "type": "object", "additionalProperties": values });
+        }
+
+        let value = self.value(
+            field.field_type,
+            field.type_name.as_deref(),
+            field.enum_info.as_ref(),
+        );
+        match field.label {
+            Label::Repeated => json!({ "type": "array", "items": value }),
+            _ => value,
+        }
+    }
+
+    /// Returns the schema of a single value of `field_type`, whose message or enum types are
+    /// named `type_name`, using the definition `enum_info` of enums if already known.
+    pub(crate) fn value(
+        &mut self,
+        field_type: FieldType,
+        type_name: Option<&str>,
+        enum_info: Option<&EnumInfo>,
+    ) -> Value {
+        if let Some(well_known_type) = type_name.and_then(WellKnownType::from_type_name) {
+            return well_known(well_known_type);
+        }
+
+        match (field_type, type_name) {
+            (FieldType::Message | FieldType::Group, Some(name)) => self.reference(name),
+            (FieldType::Message | FieldType::Group, None) => json!({ "type": "object" }),
+            (FieldType::Enum, _) => {
+                let described = match (enum_info, type_name) {
+                    (None, Some(name)) => self.catalog.describe_enum(name).ok(),
+                    _ => None,
+                };
+                match enum_info.or(described.as_ref()) {
+                    Some(enumeration) => {
+                        let mut names: Vec<&str> = Vec::new();
+                        for (name, _) in &enumeration.values {
+                            if !names.contains(&name.as_str()) {
+                                names.push(name);
+                            }
+                        }
+                        json!({ "type": "string", "title": enumeration.full_name, "enum": names })
+                    }
+                    None => json!({ "type": "string" }),
+                }
+            }
+            (scalar_type, _) => scalar(scalar_type),
+        }
+    }
+
+    /// Returns a reference to the message `name`, queueing its definition if it is the first.
+    fn reference(&mut self, name: &str) -> Value {
+        if self.root.is_some_and(|root| root.full_name == name) {
+            return json!({ "$ref": "#" });
+        }
+        if self.referenced.insert(name.to_string()) {
+            self.pending.push_back(name.to_string());
+        }
+        json!({ "$ref": format!("{}{}", self.prefix, name) })
+    }
+
+    /// Describes the message `name`, which may be nested in the root message even if the
+    /// root is not defined in the files.
+    fn describe_message(&self, name: &str) -> Option<MessageInfo> {
+        fn nested<'a>(message: &'a MessageInfo, name: &str) -> Option<&'a MessageInfo> {
+            message.nested_messages.iter().find_map(|nested_message| {
+                if nested_message.full_name == name {
+                    Some(nested_message)
+                } else {
+                    nested(nested_message, name)
+                }
+            })
+        }
+
+        match self.root.and_then(|root| nested(root, name)) {
+            Some(message) => Some(message.clone()),
+            None => self.catalog.describe_message(name).ok(),
+        }
+    }
+}
+
+/// Returns the schema of a value of the scalar `field_type`.
+fn scalar(field_type: FieldType) -> Value {
+    match field_type {
+        FieldType::Double | FieldType::Float => json!({ "type": "number" }),
+        FieldType::Int32 | FieldType::Sint32 | FieldType::Sfixed32 => {
+            json!({ "type": "integer", "format": "int32" })
+        }
+        FieldType::Uint32 | FieldType::Fixed32 => {
+            json!({ "type": "integer", "format": "uint32", "minimum": 0 })
+        }
+        // 64-bit integers are strings, since JSON numbers lose precision beyond 2^53
+        FieldType::Int64 | FieldType::Sint64 | FieldType::Sfixed64 => {
+            json!({ "type": "string", "format": "int64", "pattern": "^-?[0-9]+$" })
+        }
+        FieldType::Uint64 | FieldType::Fixed64 => {
+            json!({ "type": "string", "format": "uint64", "pattern": "^[0-9]+$" })
+        }
+        FieldType::Bool => json!({ "type": "boolean" }),
+        FieldType::String => json!({ "type": "string" }),
+        FieldType::Bytes => json!({ "type": "string", "contentEncoding": "base64" }),
+        FieldType::Group | FieldType::Message => json!({ "type": "object" }),
+        FieldType::Enum => json!({ "type": "string" }),
+    }
+}
+
+/// Returns the schema of the special JSON representation of `well_known_type`.
+pub(crate) fn well_known(well_known_type: WellKnownType) -> Value {
+    match well_known_type {
+        WellKnownType::Any => json!({
+            "type": "object",
+            "properties": { "@type": { "type": "string" } },
+            "required": ["@type"],
+        }),
+        WellKnownType::Timestamp => json!({ "type": "string", "format": "date-time" }),
+        WellKnownType::Duration => {
+            json!({ "type": "string", "pattern": "^-?[0-9]+(\\.[0-9]{1,9})?s$" })
+        }
+        WellKnownType::Empty => json!({ "type": "object", "maxProperties": 0 }),
+        // the paths of the mask, separated by commas, e.g. `name,address.city`
+        WellKnownType::FieldMask => json!({ "type": "string" }),
+        WellKnownType::Struct => json!({ "type": "object" }),
+        WellKnownType::Value => json!({}),
+        WellKnownType::ListValue => json!({ "type": "array" }),
+        WellKnownType::NullValue => json!({ "type": "null" }),
+        WellKnownType::DoubleValue => scalar(FieldType::Double),
+        WellKnownType::FloatValue => scalar(FieldType::Float),
+        WellKnownType::Int64Value => scalar(FieldType::Int64),
+        WellKnownType::UInt64Value => scalar(FieldType::Uint64),
+        WellKnownType::Int32Value => scalar(FieldType::Int32),
+        WellKnownType::UInt32Value => scalar(FieldType::Uint32),
+        WellKnownType::BoolValue => scalar(FieldType::Bool),
+        WellKnownType::StringValue => scalar(FieldType::String),
+        WellKnownType::BytesValue => scalar(FieldType::Bytes),
+    }
+}
+
+/// Sets the `description` of `schema` to `comments`, if there are any.
+pub(crate) fn describe(schema: &mut Value, comments: Option<&str>) {
+    let Some(comments) = comments else {
+        return;
+    };
+    let description = comments
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let description = description.trim();
+    if let (Value::Object(schema), false) = (schema, description.is_empty()) {
+        schema.insert("description".to_string(), json!(description));
+    }
+}
@@ -1,22 +1,802 @@
+use crate::error::ReflectionError;
+use crate::lookup::strip_leading_dot;
+use prost_types::{method_options, FileDescriptorProto, MethodDescriptorProto};
+use std::fmt;
+
+/// The fully qualified names of the well-known infrastructure services, which are left out of
+/// listings if the client is built with
+/// [`include_infrastructure_services(false)`](crate::builder::ReflectionClientBuilder::include_infrastructure_services)
+pub const INFRASTRUCTURE_SERVICES: &[&str] = &[
+    "grpc.reflection.v1.ServerReflection",
+    "grpc.reflection.v1alpha.ServerReflection",
+    "grpc.health.v1.Health",
+];
+
 /// Represents information about an RPC method
-#[derive(Debug)]
+///
+/// Methods are ordered by name first.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use prost_types::{FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
+///
+/// let method = |name: &str, client_streaming, server_streaming| MethodDescriptorProto {
+///     name: Some(name.to_string()),
+///     input_type: Some(".chat.Message".to_string()),
+///     output_type: Some(".chat.Message".to_string()),
+///     client_streaming: Some(client_streaming),
+///     server_streaming: Some(server_streaming),
+///     ..Default::default()
+/// };
+/// let chat = FileDescriptorProto {
+///     name: Some("chat.proto".to_string()),
+///     package: Some("chat".to_string()),
+///     service: vec![ServiceDescriptorProto {
+///         name: Some("Chat".to_string()),
+///         method: vec![
+///             method("Send", false, false),
+///             method("Subscribe", false, true),
+///             method("Upload", true, false),
+///             method("Talk", true, true),
+///         ],
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+/// # let endpoint = support::serve_files(vec![chat]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let service = client.get_service("chat.Chat").await?;
+/// let shapes: Vec<_> = service
+///     .methods
+///     .iter()
+///     .map(|method| {
+///         let name = method.name.as_str();
+///         (name, method.is_client_streaming, method.is_server_streaming)
+///     })
+///     .collect();
+/// assert_eq!(
+///     shapes,
+///     [
+///         ("Send", false, false),
+///         ("Subscribe", false, true),
+///         ("Upload", true, false),
+///         ("Talk", true, true),
+///     ]
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MethodInfo {
+    // the derived ordering compares the fields in the order they are declared
     /// The name of the RPC method
     pub name: String,
-    /// The name of the request message
+    /// The fully qualified name of the request message, e.g. `billing.v1.ChargeRequest`
     pub request: String,
-    /// The name of the response message
+    /// The fully qualified name of the response message
     pub response: String,
+    /// Whether the client sends a stream of requests
+    pub is_client_streaming: bool,
+    /// Whether the server answers with a stream of responses
+    pub is_server_streaming: bool,
+    /// Whether the method is marked with `option deprecated = true`
+    pub deprecated: bool,
+    /// The side effects of the method, as declared with `option idempotency_level`
+    pub idempotency_level: IdempotencyLevel,
+    /// The comment preceding the method in its `.proto` file, if the server keeps comments
+    pub leading_comments: Option<String>,
+    /// The comment following the method on the same or the next line, if the server keeps
+    /// comments
+    pub trailing_comments: Option<String>,
+}
+
+/// The side effects of an RPC method, which tell whether a failed call is safe to retry
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::service_info::{IdempotencyLevel, MethodInfo};
+/// use prost_types::method_options::IdempotencyLevel::{Idempotent, NoSideEffects};
+/// use prost_types::{method_options, MethodDescriptorProto, MethodOptions};
+///
+/// let method = |options| MethodDescriptorProto {
+///     name: Some("Get".to_string()),
+///     input_type: Some(".shop.GetOrderRequest".to_string()),
+///     output_type: Some(".shop.Order".to_string()),
+///     options,
+///     ..Default::default()
+/// };
+/// let level = |level: method_options::IdempotencyLevel| MethodOptions {
+///     idempotency_level: Some(level.into()),
+///     ..Default::default()
+/// };
+///
+/// let get = MethodInfo::try_from(method(Some(level(NoSideEffects))))?;
+/// assert_eq!(get.idempotency_level, IdempotencyLevel::NoSideEffects);
+/// let put = MethodInfo::try_from(method(Some(level(Idempotent))))?;
+/// assert_eq!(put.idempotency_level, IdempotencyLevel::Idempotent);
+/// let post = MethodInfo::try_from(method(None))?;
+/// assert_eq!(post.idempotency_level, IdempotencyLevel::Unknown);
+/// # Ok::<(), grpc_ease::error::ReflectionError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IdempotencyLevel {
+    /// The method may have side effects, which is the default
+    #[default]
+    Unknown,
+    /// The method has no side effects, like an HTTP `GET`
+    NoSideEffects,
+    /// The method may have side effects, but calling it repeatedly has the same effect as
+    /// calling it once, like an HTTP `PUT`
+    Idempotent,
+}
+
+impl From<method_options::IdempotencyLevel> for IdempotencyLevel {
+    fn from(level: method_options::IdempotencyLevel) -> Self {
+        match level {
+            method_options::IdempotencyLevel::IdempotencyUnknown => Self::Unknown,
+            method_options::IdempotencyLevel::NoSideEffects => Self::NoSideEffects,
+            method_options::IdempotencyLevel::Idempotent => Self::Idempotent,
+        }
+    }
+}
+
+impl MethodInfo {
+    /// Returns the path of the method defined by `service`, e.g. `/billing.v1.Payments/Charge`,
+    /// which is the path the method is invoked with, e.g. by tonic's `Grpc::unary`.
+    ///
+    /// The path is built from the package of the file defining the service, which is what the
+    /// server routes by, even if the service is listed under another name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::service_info::{MethodInfo, ServiceInfo, Syntax};
+    /// use prost_types::MethodDescriptorProto;
+    ///
+    /// let method = MethodInfo::try_from(MethodDescriptorProto {
+    ///     name: Some("Charge".to_string()),
+    ///     input_type: Some(".billing.v1.ChargeRequest".to_string()),
+    ///     output_type: Some(".billing.v1.ChargeResponse".to_string()),
+    ///     ..Default::default()
+    /// })?;
+    /// let mut service = ServiceInfo {
+    ///     package: "billing.v1".to_string(),
+    ///     service: "Payments".to_string(),
+    ///     methods: vec![method.clone()],
+    ///     deprecated: false,
+    ///     file_name: "billing/v1/payments.proto".to_string(),
+    ///     syntax: Syntax::Proto3,
+    ///     leading_comments: None,
+    ///     trailing_comments: None,
+    /// };
+    /// assert_eq!(method.full_path(&service), "/billing.v1.Payments/Charge");
+    ///
+    /// service.package = String::new();
+    /// assert_eq!(method.full_path(&service), "/Payments/Charge");
+    /// # Ok::<(), grpc_ease::error::ReflectionError>(())
+    /// ```
+    pub fn full_path(&self, service: &ServiceInfo) -> String {
+        format!("/{}/{}", service.full_name(), self.name)
+    }
+}
+
+impl fmt::Display for MethodInfo {
+    /// Formats the signature of the method like in a `.proto` file, e.g.
+    /// `rpc Watch(shop.WatchRequest) returns (stream shop.Order)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::service_info::MethodInfo;
+    /// use prost_types::MethodDescriptorProto;
+    ///
+    /// let method = |name: &str, client_streaming, server_streaming| {
+    ///     MethodInfo::try_from(MethodDescriptorProto {
+    ///         name: Some(name.to_string()),
+    ///         input_type: Some(".chat.Message".to_string()),
+    ///         output_type: Some(".chat.Message".to_string()),
+    ///         client_streaming: Some(client_streaming),
+    ///         server_streaming: Some(server_streaming),
+    ///         ..Default::default()
+    ///     })
+    /// };
+    /// assert_eq!(
+    ///     method("Send", false, false)?.to_string(),
+    ///     "rpc Send(chat.Message) returns (chat.Message)"
+    /// );
+    /// assert_eq!(
+    ///     method("Subscribe", false, true)?.to_string(),
+    ///     "rpc Subscribe(chat.Message) returns (stream chat.Message)"
+    /// );
+    /// assert_eq!(
+    ///     method("Upload", true, false)?.to_string(),
+    ///     "rpc Upload(stream chat.Message) returns (chat.Message)"
+    /// );
+    /// assert_eq!(
+    ///     method("Talk", true, true)?.to_string(),
+    ///     "rpc Talk(stream chat.Message) returns (stream chat.Message)"
+    /// );
+    /// # Ok::<(), grpc_ease::error::ReflectionError>(())
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stream = |streaming| if streaming { "stream " } else { "" };
+        write!(
+            f,
+            "rpc {}({}{}) returns ({}{})",
+            self.name,
+            stream(self.is_client_streaming),
+            self.request,
+            stream(self.is_server_streaming),
+            self.response
+        )
+    }
+}
+
+impl TryFrom<MethodDescriptorProto> for MethodInfo {
+    type Error = ReflectionError;
+
+    /// Builds the information about the method described by `method`.
+    ///
+    /// The request and response types are the input and output types without the leading dot
+    /// of their absolute references, so they can be looked up with
+    /// [`find_message`](crate::reflection::ReflectionClient::find_message) right away.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReflectionError::MalformedDescriptor`] if the name, input type, or output
+    /// type of the method is missing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::service_info::MethodInfo;
+    /// use prost_types::MethodDescriptorProto;
+    ///
+    /// let method = MethodInfo::try_from(MethodDescriptorProto {
+    ///     name: Some("Charge".to_string()),
+    ///     input_type: Some(".billing.v1.ChargeRequest".to_string()),
+    ///     output_type: Some(".billing.v1.ChargeResponse".to_string()),
+    ///     server_streaming: Some(true),
+    ///     ..Default::default()
+    /// })?;
+    /// assert_eq!(method.name, "Charge");
+    /// assert_eq!(method.request, "billing.v1.ChargeRequest");
+    /// assert_eq!(method.response, "billing.v1.ChargeResponse");
+    /// assert!(!method.is_client_streaming);
+    /// assert!(method.is_server_streaming);
+    ///
+    /// // types of the root package and of deeply nested packages
+    /// let method = MethodInfo::try_from(MethodDescriptorProto {
+    ///     name: Some("Ping".to_string()),
+    ///     input_type: Some(".Empty".to_string()),
+    ///     output_type: Some(".acme.platform.health.v1.internal.Pong".to_string()),
+    ///     ..Default::default()
+    /// })?;
+    /// assert_eq!(method.request, "Empty");
+    /// assert_eq!(method.response, "acme.platform.health.v1.internal.Pong");
+    ///
+    /// let missing_output = MethodDescriptorProto {
+    ///     name: Some("Refund".to_string()),
+    ///     input_type: Some(".billing.v1.RefundRequest".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert!(MethodInfo::try_from(missing_output).is_err());
+    /// # Ok::<(), grpc_ease::error::ReflectionError>(())
+    /// ```
+    fn try_from(method: MethodDescriptorProto) -> Result<Self, Self::Error> {
+        let is_client_streaming = method.client_streaming();
+        let is_server_streaming = method.server_streaming();
+        let deprecated = method
+            .options
+            .as_ref()
+            .is_some_and(|options| options.deprecated());
+        let idempotency_level = method
+            .options
+            .as_ref()
+            .map_or(IdempotencyLevel::Unknown, |options| {
+                options.idempotency_level().into()
+            });
+        let name = method.name.ok_or_else(|| {
+            ReflectionError::MalformedDescriptor("Method name is missing".to_string())
+        })?;
+        let request = method.input_type.ok_or_else(|| {
+            ReflectionError::MalformedDescriptor(format!(
+                "Request type is missing for method {:?}",
+                name
+            ))
+        })?;
+        let response = method.output_type.ok_or_else(|| {
+            ReflectionError::MalformedDescriptor(format!(
+                "Response type is missing for method {:?}",
+                name
+            ))
+        })?;
+
+        Ok(Self {
+            name,
+            request: strip_leading_dot(request),
+            response: strip_leading_dot(response),
+            is_client_streaming,
+            is_server_streaming,
+            deprecated,
+            idempotency_level,
+            leading_comments: None,
+            trailing_comments: None,
+        })
+    }
 }
 
 /// Represents information about a gRPC service, including its package name,
 /// service name, and a list of RPC methods
-#[derive(Debug)]
+///
+/// Services are ordered by package and service name first, like
+/// [`list_services`](crate::reflection::ReflectionClient::list_services) sorts them.
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use prost_types::{
+///     FileDescriptorProto, MethodDescriptorProto, MethodOptions, ServiceDescriptorProto,
+///     ServiceOptions,
+/// };
+///
+/// let method = |name: &str, options| MethodDescriptorProto {
+///     name: Some(name.to_string()),
+///     input_type: Some(".shop.Order".to_string()),
+///     output_type: Some(".shop.Order".to_string()),
+///     options,
+///     ..Default::default()
+/// };
+/// let deprecated = MethodOptions {
+///     deprecated: Some(true),
+///     ..Default::default()
+/// };
+/// let service = |name: &str, options, method| ServiceDescriptorProto {
+///     name: Some(name.to_string()),
+///     method,
+///     options,
+///     ..Default::default()
+/// };
+/// let shop = FileDescriptorProto {
+///     name: Some("shop.proto".to_string()),
+///     package: Some("shop".to_string()),
+///     service: vec![
+///         service(
+///             "Orders",
+///             None,
+///             vec![method("Place", None), method("PlaceLegacy", Some(deprecated))],
+///         ),
+///         service(
+///             "Checkout",
+///             Some(ServiceOptions {
+///                 deprecated: Some(true),
+///                 ..Default::default()
+///             }),
+///             vec![method("Pay", None)],
+///         ),
+///     ],
+///     ..Default::default()
+/// };
+/// # let endpoint = support::serve_files(vec![shop]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let orders = client.get_service("shop.Orders").await?;
+/// assert!(!orders.deprecated);
+/// let methods: Vec<_> = orders.methods.iter().map(|m| (m.name.as_str(), m.deprecated)).collect();
+/// assert_eq!(methods, [("Place", false), ("PlaceLegacy", true)]);
+///
+/// let checkout = client.get_service("shop.Checkout").await?;
+/// assert!(checkout.deprecated);
+/// assert!(!checkout.methods[0].deprecated);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+///
+/// With the `serde` feature, services can be serialized, e.g. to JSON, and read back:
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use grpc_ease::service_info::ServiceInfo;
+/// use serde_json::json;
+///
+/// let listing = json!({
+///     "package": "shop",
+///     "service": "Orders",
+///     "methods": [{
+///         "name": "Watch",
+///         "request": "shop.WatchRequest",
+///         "response": "shop.Order",
+///         "is_client_streaming": false,
+///         "is_server_streaming": true,
+///         "deprecated": false,
+///         "idempotency_level": "no_side_effects",
+///         "leading_comments": null,
+///         "trailing_comments": null,
+///     }],
+///     "deprecated": true,
+///     "file_name": "shop/orders.proto",
+///     "syntax": "proto3",
+///     "leading_comments": " Places and tracks orders.\n",
+///     "trailing_comments": null,
+/// });
+/// let service: ServiceInfo = serde_json::from_value(listing.clone()).unwrap();
+/// assert_eq!(service.methods[0].name, "Watch");
+/// assert_eq!(serde_json::to_value(&service).unwrap(), listing);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ServiceInfo {
+    // the derived ordering compares the fields in the order they are declared
     /// The package name of the gRPC service
     pub package: String,
     /// The name of the gRPC service
     pub service: String,
     /// A list of RPC methods available in the service
     pub methods: Vec<MethodInfo>,
+    /// Whether the service is marked with `option deprecated = true`
+    pub deprecated: bool,
+    /// The name of the file defining the service, e.g. `shop/v1/orders.proto`, which can be
+    /// fetched with [`get_file_by_filename`](crate::reflection::ReflectionClient::get_file_by_filename)
+    pub file_name: String,
+    /// The syntax of the file defining the service
+    pub syntax: Syntax,
+    /// The comment preceding the service in its `.proto` file, if the server keeps comments
+    ///
+    /// Comments are kept as recorded by protoc, with the leading space and the line break of
+    /// each line. See [`MessageInfo`](crate::message_info::MessageInfo) for an example.
+    pub leading_comments: Option<String>,
+    /// The comment following the opening line of the service, if the server keeps comments
+    pub trailing_comments: Option<String>,
+}
+
+impl fmt::Display for ServiceInfo {
+    /// Formats the fully qualified name of the service, followed by the signature of each
+    /// method on an indented line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::service_info::{MethodInfo, ServiceInfo, Syntax};
+    /// use prost_types::MethodDescriptorProto;
+    ///
+    /// let method = |name: &str, request: &str, response: &str, server_streaming| {
+    ///     MethodInfo::try_from(MethodDescriptorProto {
+    ///         name: Some(name.to_string()),
+    ///         input_type: Some(request.to_string()),
+    ///         output_type: Some(response.to_string()),
+    ///         server_streaming: Some(server_streaming),
+    ///         ..Default::default()
+    ///     })
+    /// };
+    /// let mut service = ServiceInfo {
+    ///     package: "shop".to_string(),
+    ///     service: "Orders".to_string(),
+    ///     methods: vec![
+    ///         method("Place", ".shop.PlaceRequest", ".shop.Order", false)?,
+    ///         method("Watch", ".shop.WatchRequest", ".shop.Order", true)?,
+    ///     ],
+    ///     deprecated: false,
+    ///     file_name: "shop.proto".to_string(),
+    ///     syntax: Syntax::Proto3,
+    ///     leading_comments: None,
+    ///     trailing_comments: None,
+    /// };
+    /// assert_eq!(
+    ///     service.to_string(),
+    ///     "shop.Orders\n  \
+    ///      rpc Place(shop.PlaceRequest) returns (shop.Order)\n  \
+    ///      rpc Watch(shop.WatchRequest) returns (stream shop.Order)"
+    /// );
+    ///
+    /// service.methods.clear();
+    /// assert_eq!(service.to_string(), "shop.Orders");
+    /// # Ok::<(), grpc_ease::error::ReflectionError>(())
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.full_name())?;
+        for method in &self.methods {
+            write!(f, "\n  {}", method)?;
+        }
+        Ok(())
+    }
+}
+
+/// The syntax a `.proto` file is written in, which determines e.g. whether singular fields
+/// track their presence
+///
+/// # Example
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+/// use grpc_ease::reflection::ReflectionClient;
+/// use grpc_ease::service_info::Syntax;
+/// use prost_types::{FileDescriptorProto, ServiceDescriptorProto};
+///
+/// let file = |name: &str, syntax: Option<&str>, service: &str| FileDescriptorProto {
+///     name: Some(name.to_string()),
+///     package: Some("shop".to_string()),
+///     syntax: syntax.map(str::to_string),
+///     service: vec![ServiceDescriptorProto {
+///         name: Some(service.to_string()),
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+/// // files without a syntax statement are proto2
+/// let legacy = file("shop/legacy.proto", None, "Legacy");
+/// let orders = file("shop/orders.proto", Some("proto3"), "Orders");
+/// # let endpoint = support::serve_files(vec![legacy, orders]).await?;
+///
+/// let mut client = ReflectionClient::new(endpoint).await?;
+/// let legacy = client.get_service("shop.Legacy").await?;
+/// assert_eq!(legacy.file_name, "shop/legacy.proto");
+/// assert_eq!(legacy.syntax, Syntax::Proto2);
+/// let orders = client.get_service("shop.Orders").await?;
+/// assert_eq!(orders.file_name, "shop/orders.proto");
+/// assert_eq!(orders.syntax, Syntax::Proto3);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// # }).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Syntax {
+    /// `syntax = "proto2"`, which is also assumed if a file has no syntax statement
+    Proto2,
+    /// `syntax = "proto3"`
+    Proto3,
+    /// `edition = "..."`, whose features are declared by the file
+    Editions,
+}
+
+impl Syntax {
+    /// Returns the syntax of `file`.
+    pub(crate) fn of(file: &FileDescriptorProto) -> Self {
+        match file.syntax() {
+            "proto3" => Self::Proto3,
+            "editions" => Self::Editions,
+            _ => Self::Proto2,
+        }
+    }
+}
+
+impl ServiceInfo {
+    /// Returns the fully qualified name of the service, e.g. `billing.v1.Payments`, or just
+    /// its name if it is defined in the root package.
+    pub fn full_name(&self) -> String {
+        if self.package.is_empty() {
+            self.service.clone()
+        } else {
+            format!("{}.{}", self.package, self.service)
+        }
+    }
+
+    /// Returns the method named `name`, e.g. `Charge`, if the service defines it.
+    ///
+    /// Method names are case-sensitive, just like the paths the server routes by.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use grpc_ease::service_info::{MethodInfo, ServiceInfo, Syntax};
+    /// use prost_types::MethodDescriptorProto;
+    ///
+    /// let method = |name: &str| {
+    ///     MethodInfo::try_from(MethodDescriptorProto {
+    ///         name: Some(name.to_string()),
+    ///         input_type: Some(".billing.v1.ChargeRequest".to_string()),
+    ///         output_type: Some(".billing.v1.ChargeResponse".to_string()),
+    ///         ..Default::default()
+    ///     })
+    /// };
+    /// let mut service = ServiceInfo {
+    ///     package: "billing.v1".to_string(),
+    ///     service: "Payments".to_string(),
+    ///     methods: vec![method("Charge")?, method("Refund")?],
+    ///     deprecated: false,
+    ///     file_name: "billing/v1/payments.proto".to_string(),
+    ///     syntax: Syntax::Proto3,
+    ///     leading_comments: None,
+    ///     trailing_comments: None,
+    /// };
+    /// assert_eq!(service.method("Refund"), Some(&service.methods[1]));
+    /// assert_eq!(service.method("refund"), None);
+    /// assert_eq!(
+    ///     service.method_paths(),
+    ///     ["/billing.v1.Payments/Charge", "/billing.v1.Payments/Refund"]
+    /// );
+    ///
+    /// service.package = String::new();
+    /// assert_eq!(service.method_paths(), ["/Payments/Charge", "/Payments/Refund"]);
+    /// # Ok::<(), grpc_ease::error::ReflectionError>(())
+    /// ```
+    pub fn method(&self, name: &str) -> Option<&MethodInfo> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+
+    /// Returns the paths of the methods of the service, in declaration order, e.g.
+    /// `/billing.v1.Payments/Charge`, as built by [`MethodInfo::full_path`].
+    pub fn method_paths(&self) -> Vec<String> {
+        self.methods
+            .iter()
+            .map(|method| method.full_path(self))
+            .collect()
+    }
+}
+
+/// Services organized by the segments of their packages, e.g. `billing` containing `v1`
+/// containing the `billing.v1.Ledger` service
+///
+/// The root node stands for the root package, and holds the services defined without a
+/// package.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::service_info::{PackageTree, ServiceInfo, Syntax};
+///
+/// let service = |package: &str, service: &str| ServiceInfo {
+///     package: package.to_string(),
+///     service: service.to_string(),
+///     methods: Vec::new(),
+///     deprecated: false,
+///     file_name: format!("{}.proto", service.to_lowercase()),
+///     syntax: Syntax::Proto3,
+///     leading_comments: None,
+///     trailing_comments: None,
+/// };
+/// let tree = PackageTree::from_services(&[
+///     service("billing.v1", "Ledger"),
+///     service("billing", "Admin"),
+///     service("", "Status"),
+///     service("billing.v1", "Invoices"),
+///     service("auth", "Tokens"),
+/// ]);
+///
+/// assert_eq!(tree.services[0].service, "Status");
+/// let billing = &tree.packages[1];
+/// assert_eq!((billing.name.as_str(), billing.package.as_str()), ("billing", "billing"));
+/// let v1 = &billing.packages[0];
+/// assert_eq!((v1.name.as_str(), v1.package.as_str()), ("v1", "billing.v1"));
+/// let services: Vec<_> = v1.services.iter().map(|s| s.service.as_str()).collect();
+/// assert_eq!(services, ["Invoices", "Ledger"]);
+///
+/// let names: Vec<_> = tree.iter().map(|(name, _)| name).collect();
+/// assert_eq!(
+///     names,
+///     [
+///         "Status",
+///         "auth.Tokens",
+///         "billing.Admin",
+///         "billing.v1.Invoices",
+///         "billing.v1.Ledger",
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackageTree {
+    /// The last segment of the package, e.g. `v1`, which is empty for the root package
+    pub name: String,
+    /// The full name of the package, e.g. `billing.v1`, which is empty for the root package
+    pub package: String,
+    /// The packages nested in the package, sorted by name
+    pub packages: Vec<PackageTree>,
+    /// The services defined in the package itself, sorted by name
+    pub services: Vec<ServiceInfo>,
+}
+
+impl PackageTree {
+    /// Organizes `services` by their packages.
+    pub fn from_services(services: &[ServiceInfo]) -> Self {
+        let mut root = Self::default();
+        for service in services {
+            let mut node = &mut root;
+            for segment in service
+                .package
+                .split('.')
+                .filter(|segment| !segment.is_empty())
+            {
+                let index = match node.packages.iter().position(|child| child.name == segment) {
+                    Some(index) => index,
+                    None => {
+                        let package = if node.package.is_empty() {
+                            segment.to_string()
+                        } else {
+                            format!("{}.{}", node.package, segment)
+                        };
+                        node.packages.push(Self {
+                            name: segment.to_string(),
+                            package,
+                            ..Self::default()
+                        });
+                        node.packages.len() - 1
+                    }
+                };
+                node = &mut node.packages[index];
+            }
+            node.services.push(service.clone());
+        }
+        root.sort();
+        root
+    }
+
+    /// Returns an iterator walking the tree depth-first, yielding the fully qualified name of
+    /// each service along with the service, in the order the services are listed by
+    /// [`ReflectionClient::list_services`](crate::reflection::ReflectionClient::list_services).
+    ///
+    /// The services of a package come before those of its nested packages.
+    pub fn iter(&self) -> PackageTreeIter<'_> {
+        PackageTreeIter {
+            packages: vec![self],
+            services: [].iter(),
+        }
+    }
+
+    fn sort(&mut self) {
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+        self.services.sort_by(|a, b| a.service.cmp(&b.service));
+        for package in &mut self.packages {
+            package.sort();
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a PackageTree {
+    type Item = (String, &'a ServiceInfo);
+    type IntoIter = PackageTreeIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The iterator returned by [`PackageTree::iter`]
+#[derive(Debug, Clone)]
+pub struct PackageTreeIter<'a> {
+    /// The packages left to walk, the next one last
+    packages: Vec<&'a PackageTree>,
+    services: std::slice::Iter<'a, ServiceInfo>,
+}
+
+impl<'a> Iterator for PackageTreeIter<'a> {
+    type Item = (String, &'a ServiceInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(service) = self.services.next() {
+                return Some((service.full_name(), service));
+            }
+            let package = self.packages.pop()?;
+            self.services = package.services.iter();
+            self.packages.extend(package.packages.iter().rev());
+        }
+    }
+}
+
+/// The field numbers of the extensions of a message type known to the server
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtensionNumbers {
+    /// The fully qualified name of the extended message type, as reported by the server
+    ///
+    /// Some servers leave this empty.
+    pub base_type_name: String,
+    /// The field numbers of the extensions of the message type
+    pub extension_numbers: Vec<i32>,
 }
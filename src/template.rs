@@ -0,0 +1,214 @@
+//! Substituting variables for the placeholders of JSON request templates.
+//!
+//! A placeholder is a name between double braces, like `{{user_id}}` or `{{ user_id }}`,
+//! where names consist of ASCII letters, digits, `_`, `-` and `.`. A string that is nothing
+//! but a placeholder takes the value of the variable, which may be of any JSON type, while
+//! placeholders within longer strings are replaced by the variable as text, strings without
+//! their quotes. Placeholders are substituted in object keys too; braces that do not enclose
+//! a name are left as they are.
+
+use crate::error::ReflectionError;
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+
+/// Substitutes the `variables` for the placeholders of the JSON `request`, along with the
+/// built-in variables:
+/// - `uuid`, a random version 4 UUID, the same for all of its placeholders in the request
+/// - `now`, the current time as an RFC 3339 string in UTC, like `2024-05-01T10:00:00Z`
+/// - `now_unix`, the current time as a number of seconds since the Unix epoch
+///
+/// Variables of the same names take precedence over the built-ins. The current time is not
+/// available on platforms without a clock, like `wasm32-unknown-unknown`.
+///
+/// # Errors
+///
+/// This function will return a [`ReflectionError::UnresolvedPlaceholders`] listing the
+/// placeholders of unknown variables, if any.
+///
+/// # Example
+///
+/// ```
+/// use grpc_ease::error::ReflectionError;
+/// use grpc_ease::template::substitute;
+/// use serde_json::json;
+/// use std::collections::HashMap;
+///
+/// let variables = HashMap::from([
+///     ("user_id".to_string(), json!("u-42")),
+///     ("quantity".to_string(), json!(3)),
+/// ]);
+/// let template = json!({
+///     "userId": "{{user_id}}",
+///     "quantity": "{{ quantity }}",
+///     "note": "{{quantity}} for {{user_id}}",
+///     "requestId": "{{uuid}}",
+/// });
+///
+/// let request = substitute(template, &variables)?;
+/// assert_eq!(request["userId"], json!("u-42"));
+/// assert_eq!(request["quantity"], json!(3));
+/// assert_eq!(request["note"], json!("3 for u-42"));
+/// assert_eq!(request["requestId"].as_str().unwrap().len(), 36);
+///
+/// let error = substitute(json!(["{{user}}", "{{ quantity}}", "{{usr}}"]), &variables)
+///     .unwrap_err();
+/// assert!(matches!(
+///     error,
+///     ReflectionError::UnresolvedPlaceholders { placeholders }
+///         if placeholders == ["{{user}}", "{{usr}}"]
+/// ));
+/// # Ok::<(), ReflectionError>(())
+/// ```
+pub fn substitute(
+    request: Value,
+    variables: &HashMap<String, Value>,
+) -> Result<Value, ReflectionError> {
+    let builtins = builtins();
+    let lookup = |name: &str| variables.get(name).or_else(|| builtins.get(name));
+    let mut unresolved = Vec::new();
+    let request = substitute_value(request, &lookup, &mut unresolved);
+
+    if unresolved.is_empty() {
+        Ok(request)
+    } else {
+        Err(ReflectionError::UnresolvedPlaceholders {
+            placeholders: unresolved,
+        })
+    }
+}
+
+fn substitute_value<'a>(
+    value: Value,
+    lookup: &impl Fn(&str) -> Option<&'a Value>,
+    unresolved: &mut Vec<String>,
+) -> Value {
+    match value {
+        Value::String(text) => {
+            if let [(0, end, name)] = placeholders(&text)[..] {
+                if end == text.len() {
+                    if let Some(value) = lookup(name) {
+                        return value.clone();
+                    }
+                }
+            }
+            Value::String(interpolate(&text, lookup, unresolved))
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| substitute_value(value, lookup, unresolved))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = interpolate(&key, lookup, unresolved);
+                    (key, substitute_value(value, lookup, unresolved))
+                })
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Replaces the placeholders of `text` by their variables as text, collecting those of
+/// unknown variables, which are left as they are, in `unresolved`.
+fn interpolate<'a>(
+    text: &str,
+    lookup: &impl Fn(&str) -> Option<&'a Value>,
+    unresolved: &mut Vec<String>,
+) -> String {
+    let mut interpolated = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (start, end, name) in placeholders(text) {
+        let Some(value) = lookup(name) else {
+            let placeholder = format!("{{{{{}}}}}", name);
+            if !unresolved.contains(&placeholder) {
+                unresolved.push(placeholder);
+            }
+            continue;
+        };
+        interpolated.push_str(&text[copied..start]);
+        match value {
+            Value::String(value) => interpolated.push_str(value),
+            value => interpolated.push_str(&value.to_string()),
+        }
+        copied = end;
+    }
+    interpolated.push_str(&text[copied..]);
+    interpolated
+}
+
+/// Returns the start and end of each placeholder of `text`, along with its name.
+fn placeholders(text: &str) -> Vec<(usize, usize, &str)> {
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    };
+
+    let mut placeholders = Vec::new();
+    let mut from = 0;
+    while let Some(open) = text[from..].find("{{").map(|open| from + open) {
+        let Some(close) = text[open + 2..].find("}}").map(|close| open + 2 + close) else {
+            break;
+        };
+        let name = text[open + 2..close].trim();
+        if is_name(name) {
+            placeholders.push((open, close + 2, name));
+            from = close + 2;
+        } else {
+            // e.g. the inner braces of `{{{name}}}`
+            from = open + 1;
+        }
+    }
+    placeholders
+}
+
+/// Returns the built-in variables, with the current time where the platform has a clock.
+fn builtins() -> HashMap<&'static str, Value> {
+    let mut builtins = clock();
+    builtins.insert("uuid", Value::String(uuid_v4()));
+    builtins
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn clock() -> HashMap<&'static str, Value> {
+    let now = prost_types::Timestamp::from(std::time::SystemTime::now());
+    let seconds = prost_types::Timestamp {
+        seconds: now.seconds,
+        nanos: 0,
+    };
+    HashMap::from([
+        ("now", Value::String(seconds.to_string())),
+        ("now_unix", Value::from(now.seconds)),
+    ])
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn clock() -> HashMap<&'static str, Value> {
+    HashMap::new()
+}
+
+/// Returns a random version 4 UUID in its hyphenated form, from the randomly seeded keys of
+/// std's hasher.
+fn uuid_v4() -> String {
+    let random = |_| RandomState::new().build_hasher().finish().to_be_bytes();
+    let mut bytes: Vec<u8> = (0..2).flat_map(random).collect();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
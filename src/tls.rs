@@ -0,0 +1,146 @@
+//! TLS plumbing that goes beyond what tonic's `ClientTlsConfig` offers.
+
+use crate::proxy::{self, Proxy};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::{
+    self,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+use tonic::transport::Uri;
+use tower::Service;
+
+/// h2 alpn in plain format for rustls.
+const ALPN_H2: &[u8] = b"h2";
+
+/// Connector establishing TLS connections without verifying the server certificate.
+///
+/// The connector expects plain `http` URIs naming the host and port to connect to, so tonic
+/// does not attempt to layer its own TLS on top of the connection.
+#[derive(Clone)]
+pub(crate) struct InsecureConnector {
+    connector: TlsConnector,
+    domain: ServerName<'static>,
+    proxy: Option<Proxy>,
+}
+
+impl InsecureConnector {
+    /// Creates a connector verifying nothing but the handshake signatures, presenting the
+    /// given PEM encoded client identity and tunneling through `proxy` if they are set.
+    pub(crate) fn new(
+        domain: &str,
+        identity: Option<&(Vec<u8>, Vec<u8>)>,
+        proxy: Option<Proxy>,
+    ) -> Result<Self, rustls::Error> {
+        let provider = Arc::new(ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyServerCert(provider)));
+
+        let mut config = match identity {
+            Some((cert, key)) => {
+                let certs = rustls_pemfile::certs(&mut cert.as_slice())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| rustls::Error::General("invalid client certificate".into()))?;
+                let key = rustls_pemfile::private_key(&mut key.as_slice())
+                    .ok()
+                    .flatten()
+                    .ok_or_else(|| rustls::Error::General("invalid client private key".into()))?;
+                builder.with_client_auth_cert(certs, key)?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols.push(ALPN_H2.into());
+
+        let domain = ServerName::try_from(domain)
+            .map_err(|_| rustls::Error::General(format!("invalid server name {:?}", domain)))?
+            .to_owned();
+
+        Ok(Self {
+            connector: TlsConnector::from(Arc::new(config)),
+            domain,
+            proxy,
+        })
+    }
+}
+
+impl Service<Uri> for InsecureConnector {
+    type Response = TlsStream<TcpStream>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connector = self.connector.clone();
+        let domain = self.domain.clone();
+        let proxy = self.proxy.clone();
+
+        Box::pin(async move {
+            let tcp = proxy::connect(&uri, proxy.as_ref()).await?;
+            connector.connect(domain, tcp).await
+        })
+    }
+}
+
+/// Certificate verifier accepting any server certificate, while still checking handshake
+/// signatures so the connection is at least bound to the presented certificate.
+#[derive(Debug)]
+struct AcceptAnyServerCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyServerCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,22 @@
+//! Events of watching the schema of a server for changes.
+
+use crate::diff::SchemaDiff;
+use crate::error::ReflectionError;
+use crate::fingerprint::Fingerprint;
+
+/// An event of [`ReflectionClient::watch`](crate::reflection::ReflectionClient::watch)
+#[derive(Debug)]
+pub enum SchemaChangeEvent {
+    /// The schema of the server changed since the previous successful poll
+    Changed {
+        /// The fingerprint of the schema before the change
+        previous: Fingerprint,
+        /// The fingerprint of the schema after the change
+        current: Fingerprint,
+        /// The differences between the schema before and after the change
+        diff: Box<SchemaDiff>,
+    },
+    /// Polling the server failed, e.g. because it was briefly down; the next poll is
+    /// compared against the last schema retrieved
+    Error(ReflectionError),
+}
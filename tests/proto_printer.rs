@@ -0,0 +1,224 @@
+//! Rendering descriptors compiled from a fixture exercising the corners of proto2, which must
+//! come out as the fixture itself.
+
+use grpc_ease::proto_printer::render_file;
+use prost_types::field_descriptor_proto::Type;
+use prost_types::file_options::OptimizeMode;
+use prost_types::method_options::IdempotencyLevel;
+use prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    UninterpretedOption,
+};
+
+const GNARLY: &str = include_str!("fixtures/gnarly.proto");
+
+/// The fully qualified names of the messages and enums of a file, with leading dots
+#[derive(Default)]
+struct Names {
+    messages: Vec<String>,
+    enums: Vec<String>,
+}
+
+impl Names {
+    fn collect(&mut self, scope: &str, messages: &[DescriptorProto]) {
+        for message in messages {
+            let name = format!("{}.{}", scope, message.name());
+            self.collect(&name, &message.nested_type);
+            self.enums.extend(
+                message
+                    .enum_type
+                    .iter()
+                    .map(|enumeration| format!("{}.{}", name, enumeration.name())),
+            );
+            self.messages.push(name);
+        }
+    }
+
+    /// Resolves the type of `field` declared in `scope` like protoc, looking up relative names
+    /// from the innermost scope outwards.
+    fn resolve(&self, scope: &str, field: &mut FieldDescriptorProto) {
+        if field.json_name.is_none() && field.extendee.is_none() {
+            field.json_name = Some(json_name(field.name()));
+        }
+        if let Some(options) = &mut field.options {
+            options.packed = flag(&mut options.uninterpreted_option, "packed");
+            options.deprecated = flag(&mut options.uninterpreted_option, "deprecated");
+        }
+        let Some(type_name) = field.type_name.clone() else {
+            return;
+        };
+
+        let mut candidates = Vec::new();
+        if type_name.starts_with('.') {
+            candidates.push(type_name);
+        } else {
+            let mut scope = scope;
+            loop {
+                candidates.push(format!("{}.{}", scope, type_name));
+                match scope.rsplit_once('.') {
+                    Some((outer, _)) => scope = outer,
+                    None => break,
+                }
+            }
+        }
+        let resolved = candidates
+            .into_iter()
+            .find(|name| self.messages.contains(name) || self.enums.contains(name))
+            .unwrap_or_else(|| panic!("{} is not defined", field.type_name()));
+        if field.r#type.is_none() {
+            let kind = if self.enums.contains(&resolved) {
+                Type::Enum
+            } else {
+                Type::Message
+            };
+            field.set_type(kind);
+        }
+        field.type_name = Some(resolved);
+    }
+
+    fn resolve_message(&self, scope: &str, message: &mut DescriptorProto) {
+        let name = format!("{}.{}", scope, message.name());
+        if let Some(options) = &mut message.options {
+            options.deprecated = flag(&mut options.uninterpreted_option, "deprecated");
+        }
+        for field in message.field.iter_mut().chain(&mut message.extension) {
+            self.resolve(&name, field);
+        }
+        for nested in &mut message.nested_type {
+            self.resolve_message(&name, nested);
+        }
+        message.enum_type.iter_mut().for_each(interpret_enum);
+    }
+}
+
+/// Removes the uninterpreted option `name` from `options` and returns its value, as written.
+fn take(options: &mut Vec<UninterpretedOption>, name: &str) -> Option<String> {
+    let index = options
+        .iter()
+        .position(|option| option.name.len() == 1 && option.name[0].name_part == name)?;
+    let option = options.remove(index);
+    match option.string_value {
+        Some(value) => Some(String::from_utf8(value).expect("the value is UTF-8")),
+        None => Some(option.identifier_value().to_string()),
+    }
+}
+
+fn flag(options: &mut Vec<UninterpretedOption>, name: &str) -> Option<bool> {
+    take(options, name).map(|value| value == "true")
+}
+
+fn interpret_enum(enumeration: &mut EnumDescriptorProto) {
+    if let Some(options) = &mut enumeration.options {
+        options.allow_alias = flag(&mut options.uninterpreted_option, "allow_alias");
+        options.deprecated = flag(&mut options.uninterpreted_option, "deprecated");
+    }
+    for value in &mut enumeration.value {
+        if let Some(options) = &mut value.options {
+            options.deprecated = flag(&mut options.uninterpreted_option, "deprecated");
+        }
+    }
+}
+
+/// Interprets the standard options of `file` the fixture sets, which protox-parse leaves
+/// uninterpreted unlike protoc.
+fn interpret(file: &mut FileDescriptorProto) {
+    if let Some(options) = &mut file.options {
+        let uninterpreted = &mut options.uninterpreted_option;
+        options.java_package = take(uninterpreted, "java_package");
+        options.go_package = take(uninterpreted, "go_package");
+        options.optimize_for = take(uninterpreted, "optimize_for")
+            .and_then(|mode| OptimizeMode::from_str_name(&mode))
+            .map(Into::into);
+        options.java_multiple_files = flag(uninterpreted, "java_multiple_files");
+    }
+    file.enum_type.iter_mut().for_each(interpret_enum);
+    for service in &mut file.service {
+        if let Some(options) = &mut service.options {
+            options.deprecated = flag(&mut options.uninterpreted_option, "deprecated");
+        }
+        for method in &mut service.method {
+            if let Some(options) = &mut method.options {
+                options.deprecated = flag(&mut options.uninterpreted_option, "deprecated");
+                options.idempotency_level =
+                    take(&mut options.uninterpreted_option, "idempotency_level")
+                        .and_then(|level| IdempotencyLevel::from_str_name(&level))
+                        .map(Into::into);
+            }
+        }
+    }
+}
+
+/// Returns the JSON name protoc derives from the field name `name`.
+fn json_name(name: &str) -> String {
+    let mut json_name = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        match c {
+            '_' => upper = true,
+            c if upper => {
+                json_name.extend(c.to_uppercase());
+                upper = false;
+            }
+            c => json_name.push(c),
+        }
+    }
+    json_name
+}
+
+/// Parses `source` into the descriptor protoc compiles it to, which unlike the one of
+/// protox-parse has resolved type names, the JSON names of all fields and interpreted options.
+fn compile(name: &str, source: &str) -> FileDescriptorProto {
+    let mut file = protox_parse::parse(name, source).expect("the source parses");
+    let package = format!(".{}", file.package());
+    let mut names = Names::default();
+    names.collect(&package, &file.message_type);
+    names.enums.extend(
+        file.enum_type
+            .iter()
+            .map(|enumeration| format!("{}.{}", package, enumeration.name())),
+    );
+
+    for message in &mut file.message_type {
+        names.resolve_message(&package, message);
+    }
+    for extension in &mut file.extension {
+        names.resolve(&package, extension);
+    }
+    interpret(&mut file);
+    file
+}
+
+#[test]
+fn files_render_as_the_source_they_were_compiled_from() {
+    let file = compile("gnarly/v1/gnarly.proto", GNARLY);
+    let rendered = render_file(&file);
+    assert_eq!(rendered, GNARLY);
+
+    // without comments, the rendered source still compiles to the same definitions
+    let stripped = FileDescriptorProto {
+        source_code_info: None,
+        ..file.clone()
+    };
+    let recompiled = compile("gnarly/v1/gnarly.proto", &render_file(&stripped));
+    assert_eq!(
+        FileDescriptorProto {
+            source_code_info: None,
+            ..recompiled
+        },
+        stripped
+    );
+}
+
+#[test]
+fn files_of_editions_are_not_rendered_as_a_guessed_edition() {
+    let file = FileDescriptorProto {
+        name: Some("edition.proto".to_string()),
+        syntax: Some("editions".to_string()),
+        ..Default::default()
+    };
+    let rendered = render_file(&file);
+    assert!(rendered.starts_with("// unsupported: "), "{}", rendered);
+    assert!(!rendered.contains("2023"), "{}", rendered);
+    // rather than compiling to a file of another edition or syntax, the source fails to
+    assert!(protox_parse::parse("edition.proto", &rendered).is_err());
+}
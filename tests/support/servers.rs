@@ -0,0 +1,755 @@
+// In-process servers shared by the examples of the crate, which include this file with
+//
+//     # mod support { include!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/support/servers.rs")); }
+//
+// rather than each setting up its own listener and reflection service.
+
+use prost_types::field_descriptor_proto::Type;
+use prost_types::{
+    DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    MethodDescriptorProto, ServiceDescriptorProto,
+};
+use std::error::Error;
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::server::{ClientStreamingService, Grpc, NamedService};
+use tonic::transport::server::Router;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+use tonic_reflection::server::{ServerReflection, ServerReflectionServer};
+
+/// Binds a free local port, returning the incoming connections and the endpoint of the port,
+/// for servers with layers, which [`serve`] does not take.
+pub async fn bind() -> std::io::Result<(TcpListenerStream, String)> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let endpoint = format!("http://{}", listener.local_addr()?);
+    Ok((TcpListenerStream::new(listener), endpoint))
+}
+
+/// Serves `router` on a free local port, returning the endpoint of the server.
+pub async fn serve(router: Router) -> std::io::Result<String> {
+    let (incoming, endpoint) = bind().await?;
+    tokio::spawn(router.serve_with_incoming(incoming));
+    Ok(endpoint)
+}
+
+/// Returns a reflection service for the encoded file descriptor sets `encoded`.
+pub fn reflection(encoded: &[&[u8]]) -> ServerReflectionServer<impl ServerReflection> {
+    reflection_with(encoded, Vec::new())
+}
+
+/// Returns a reflection service for `files`.
+pub fn reflection_for(
+    files: Vec<FileDescriptorProto>,
+) -> ServerReflectionServer<impl ServerReflection> {
+    reflection_with(&[], files)
+}
+
+/// Returns a reflection service for the encoded file descriptor sets `encoded` along with
+/// `files`.
+pub fn reflection_with(
+    encoded: &[&[u8]],
+    files: Vec<FileDescriptorProto>,
+) -> ServerReflectionServer<impl ServerReflection> {
+    let mut builder = tonic_reflection::server::Builder::configure();
+    for set in encoded {
+        builder = builder.register_encoded_file_descriptor_set(set);
+    }
+    if !files.is_empty() {
+        builder = builder.register_file_descriptor_set(FileDescriptorSet { file: files });
+    }
+    builder.build().expect("the fixture descriptors are valid")
+}
+
+/// Serves reflection for the encoded file descriptor sets `encoded`, returning the endpoint
+/// of the server.
+pub async fn serve_reflection(encoded: &[&[u8]]) -> Result<String, Box<dyn Error>> {
+    Ok(serve(Server::builder().add_service(reflection(encoded))).await?)
+}
+
+/// Serves reflection for `files`, returning the endpoint of the server.
+pub async fn serve_files(files: Vec<FileDescriptorProto>) -> Result<String, Box<dyn Error>> {
+    Ok(serve(Server::builder().add_service(reflection_for(files))).await?)
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Chunk {
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Summary {
+    #[prost(uint64, tag = "1")]
+    pub size: u64,
+}
+
+/// Sums up the sizes of the uploaded chunks.
+struct Upload;
+
+impl ClientStreamingService<Chunk> for Upload {
+    type Response = Summary;
+    type Future = BoxFuture<Response<Summary>, Status>;
+
+    fn call(&mut self, request: Request<Streaming<Chunk>>) -> Self::Future {
+        Box::pin(async move {
+            let mut chunks = request.into_inner();
+            let mut size = 0;
+            while let Some(chunk) = chunks.next().await {
+                size += chunk?.data.len() as u64;
+            }
+            Ok(Response::new(Summary { size }))
+        })
+    }
+}
+
+/// The `storage.Files` service of [`storage`], whose client streaming `Upload` method sums up
+/// the sizes of the chunks
+#[derive(Clone)]
+pub struct Files;
+
+impl NamedService for Files {
+    const NAME: &'static str = "storage.Files";
+}
+
+impl Service<http::Request<tonic::transport::Body>> for Files {
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(
+        &mut self,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<tonic::transport::Body>) -> Self::Future {
+        Box::pin(async move {
+            let mut grpc = Grpc::new(tonic::codec::ProstCodec::default());
+            Ok(grpc.client_streaming(Upload, request).await)
+        })
+    }
+}
+
+/// Returns the descriptor of `storage.proto`:
+///
+/// ```proto
+/// service Files {
+///   rpc Upload(stream Chunk) returns (Summary);
+/// }
+///
+/// message Chunk {
+///   bytes data = 1;
+/// }
+///
+/// message Summary {
+///   uint64 size = 1;
+/// }
+/// ```
+pub fn storage() -> FileDescriptorProto {
+    let field = |name: &str, r#type: Type| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(1),
+        r#type: Some(r#type.into()),
+        ..Default::default()
+    };
+    let message = |name: &str, field| DescriptorProto {
+        name: Some(name.to_string()),
+        field: vec![field],
+        ..Default::default()
+    };
+
+    FileDescriptorProto {
+        name: Some("storage.proto".to_string()),
+        package: Some("storage".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![
+            message("Chunk", field("data", Type::Bytes)),
+            message("Summary", field("size", Type::Uint64)),
+        ],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Files".to_string()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Upload".to_string()),
+                input_type: Some(".storage.Chunk".to_string()),
+                output_type: Some(".storage.Summary".to_string()),
+                client_streaming: Some(true),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// Serves the `storage.Files` service with reflection for [`storage`], returning the endpoint
+/// of the server.
+pub async fn serve_storage() -> Result<String, Box<dyn Error>> {
+    let router = Server::builder()
+        .add_service(Files)
+        .add_service(reflection_for(vec![storage()]));
+    Ok(serve(router).await?)
+}
+
+/// An encoded message of any type, which is decoded by skipping all of its fields
+#[derive(Clone, Debug, Default)]
+pub struct Encoded(pub Vec<u8>);
+
+impl prost::Message for Encoded {
+    fn encode_raw<B: prost::bytes::BufMut>(&self, buf: &mut B) {
+        buf.put_slice(&self.0);
+    }
+
+    fn merge_field<B: prost::bytes::Buf>(
+        &mut self,
+        tag: u32,
+        wire_type: prost::encoding::WireType,
+        buf: &mut B,
+        ctx: prost::encoding::DecodeContext,
+    ) -> Result<(), prost::DecodeError> {
+        prost::encoding::skip_field(wire_type, tag, buf, ctx)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// The `fixture.Fixed` service of [`fixture`], whose unary `Get` method answers every request
+/// with the same reply
+///
+/// The headers of the response are sent right away and the reply after the [`delay`](Self::delay),
+/// so the server does not end the call once its deadline passes, as tonic servers do for
+/// handlers that take too long.
+#[derive(Clone, Default)]
+pub struct Fixed {
+    reply: Vec<u8>,
+    delay: std::time::Duration,
+    compression: Option<tonic::codec::CompressionEncoding>,
+    requests: std::sync::Arc<std::sync::Mutex<Vec<http::HeaderMap>>>,
+}
+
+impl Fixed {
+    /// Answers with `reply`, the `fixture.Reply`.
+    pub fn new(reply: impl prost::Message) -> Self {
+        Self {
+            reply: reply.encode_to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Sends the reply `delay` after the headers.
+    pub fn delay(mut self, delay: std::time::Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Compresses the reply with `encoding` if the client accepts it, and accepts requests
+    /// compressed with it besides gzip.
+    pub fn send_compressed(mut self, encoding: tonic::codec::CompressionEncoding) -> Self {
+        self.compression = Some(encoding);
+        self
+    }
+
+    /// Returns the headers of the requests received so far.
+    pub fn requests(&self) -> Vec<http::HeaderMap> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl tonic::server::ServerStreamingService<Encoded> for Fixed {
+    type Response = Encoded;
+    type ResponseStream =
+        std::pin::Pin<Box<dyn tokio_stream::Stream<Item = Result<Encoded, Status>> + Send>>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: Request<Encoded>) -> Self::Future {
+        let headers = request.metadata().clone().into_headers();
+        self.requests.lock().unwrap().push(headers);
+        let (reply, delay) = (self.reply.clone(), self.delay);
+        // a unary response is a stream of one message on the wire
+        let reply = futures_util::stream::once(async move {
+            tokio::time::sleep(delay).await;
+            Ok(Encoded(reply))
+        });
+        Box::pin(async move { Ok(Response::new(Box::pin(reply) as Self::ResponseStream)) })
+    }
+}
+
+impl NamedService for Fixed {
+    const NAME: &'static str = "fixture.Fixed";
+}
+
+impl Service<http::Request<tonic::transport::Body>> for Fixed {
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = std::convert::Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(
+        &mut self,
+        _: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<tonic::transport::Body>) -> Self::Future {
+        let fixed = self.clone();
+        Box::pin(async move {
+            let codec = tonic::codec::ProstCodec::default();
+            let mut grpc =
+                Grpc::new(codec).accept_compressed(tonic::codec::CompressionEncoding::Gzip);
+            if let Some(encoding) = fixed.compression {
+                grpc = grpc.send_compressed(encoding).accept_compressed(encoding);
+            }
+            Ok(grpc.server_streaming(fixed, request).await)
+        })
+    }
+}
+
+/// Returns the descriptor of `google/protobuf/any.proto`.
+pub fn any() -> FileDescriptorProto {
+    let field = |name: &str, number, r#type: Type| FieldDescriptorProto {
+        name: Some(name.to_string()),
+        number: Some(number),
+        r#type: Some(r#type.into()),
+        json_name: Some(if name == "type_url" { "typeUrl" } else { name }.to_string()),
+        ..Default::default()
+    };
+    FileDescriptorProto {
+        name: Some("google/protobuf/any.proto".to_string()),
+        package: Some("google.protobuf".to_string()),
+        syntax: Some("proto3".to_string()),
+        message_type: vec![DescriptorProto {
+            name: Some("Any".to_string()),
+            field: vec![
+                field("type_url", 1, Type::String),
+                field("value", 2, Type::Bytes),
+            ],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// The reply of [`Fixed`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Reply {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(message, repeated, tag = "2")]
+    pub details: Vec<prost_types::Any>,
+}
+
+/// Returns the descriptor of `fixture.proto`:
+///
+/// ```proto
+/// import "google/protobuf/any.proto";
+///
+/// service Fixed {
+///   rpc Get(Reply) returns (Reply);
+/// }
+///
+/// message Reply {
+///   string text = 1;
+///   repeated google.protobuf.Any details = 2;
+/// }
+/// ```
+pub fn fixture() -> FileDescriptorProto {
+    FileDescriptorProto {
+        name: Some("fixture.proto".to_string()),
+        package: Some("fixture".to_string()),
+        dependency: vec!["google/protobuf/any.proto".to_string()],
+        syntax: Some("proto3".to_string()),
+        message_type: vec![DescriptorProto {
+            name: Some("Reply".to_string()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("text".to_string()),
+                    number: Some(1),
+                    r#type: Some(Type::String.into()),
+                    json_name: Some("text".to_string()),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("details".to_string()),
+                    number: Some(2),
+                    label: Some(prost_types::field_descriptor_proto::Label::Repeated.into()),
+                    r#type: Some(Type::Message.into()),
+                    type_name: Some(".google.protobuf.Any".to_string()),
+                    json_name: Some("details".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }],
+        service: vec![ServiceDescriptorProto {
+            name: Some("Fixed".to_string()),
+            method: vec![MethodDescriptorProto {
+                name: Some("Get".to_string()),
+                input_type: Some(".fixture.Reply".to_string()),
+                output_type: Some(".fixture.Reply".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+/// Returns a router for `fixed` with reflection for [`fixture`] and the reflection service
+/// itself.
+pub fn fixed_router(fixed: Fixed) -> Router {
+    let reflection = reflection_with(
+        &[tonic_reflection::pb::FILE_DESCRIPTOR_SET],
+        vec![any(), fixture()],
+    );
+    Server::builder().add_service(fixed).add_service(reflection)
+}
+
+/// Serves the [`fixed_router`] for `fixed`, returning the endpoint of the server.
+pub async fn serve_fixed(fixed: Fixed) -> Result<String, Box<dyn Error>> {
+    Ok(serve(fixed_router(fixed)).await?)
+}
+
+/// An HTTP proxy tunneling `CONNECT` requests for any host to the `upstream` address
+#[derive(Clone)]
+pub struct ConnectProxy {
+    upstream: String,
+    authorization: Option<String>,
+    requests: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+impl ConnectProxy {
+    /// Tunnels to the `host:port` `upstream`, e.g. the endpoint of a server without the
+    /// scheme.
+    pub fn new(upstream: &str) -> Self {
+        Self {
+            upstream: upstream.trim_start_matches("http://").to_string(),
+            authorization: None,
+            requests: Default::default(),
+        }
+    }
+
+    /// Refuses tunnels with `407 Proxy Authentication Required` unless the
+    /// `Proxy-Authorization` header is `authorization`.
+    pub fn require(mut self, authorization: &str) -> Self {
+        self.authorization = Some(authorization.to_string());
+        self
+    }
+
+    /// Returns the heads of the `CONNECT` requests received so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Serves the proxy on a free local port, returning its `http://` URL.
+    pub async fn serve(&self) -> std::io::Result<String> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let proxy = self.clone();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                let proxy = proxy.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    while !head.ends_with(b"\r\n\r\n") {
+                        head.push(client.read_u8().await?);
+                    }
+                    let head = String::from_utf8_lossy(&head).into_owned();
+                    proxy.requests.lock().unwrap().push(head.clone());
+
+                    let authorized = proxy.authorization.as_ref().is_none_or(|expected| {
+                        head.lines().any(|line| {
+                            line.split_once(':').is_some_and(|(name, value)| {
+                                name.eq_ignore_ascii_case("proxy-authorization")
+                                    && value.trim() == expected
+                            })
+                        })
+                    });
+                    if !authorized {
+                        let refusal = b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n";
+                        return client.write_all(refusal).await;
+                    }
+                    let mut upstream = tokio::net::TcpStream::connect(&proxy.upstream).await?;
+                    client
+                        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                        .await?;
+                    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+                    Ok::<_, std::io::Error>(())
+                });
+            }
+        });
+        Ok(url)
+    }
+}
+
+/// A local port connections to which are never established, since the accept queue of its
+/// listener is full, for as long as it is kept
+pub struct Blackhole {
+    /// The endpoint of the port, e.g. `http://127.0.0.1:40123`
+    pub endpoint: String,
+    _listener: tokio::net::TcpListener,
+    _queued: Vec<tokio::net::TcpStream>,
+}
+
+/// Binds a [`Blackhole`], filling the accept queue of its listener with connections that are
+/// never accepted.
+pub async fn blackhole() -> std::io::Result<Blackhole> {
+    let socket = tokio::net::TcpSocket::new_v4()?;
+    socket.bind("127.0.0.1:0".parse().unwrap())?;
+    let address = socket.local_addr()?;
+    let listener = socket.listen(1)?;
+
+    let mut queued = Vec::new();
+    let pending = std::time::Duration::from_millis(100);
+    while let Ok(connected) =
+        tokio::time::timeout(pending, tokio::net::TcpStream::connect(address)).await
+    {
+        queued.push(connected?);
+        if queued.len() > 64 {
+            return Err(std::io::Error::other("the accept queue does not fill up"));
+        }
+    }
+    Ok(Blackhole {
+        endpoint: format!("http://{}", address),
+        _listener: listener,
+        _queued: queued,
+    })
+}
+
+/// An HTTP/2 frame received by a [`Silent`] server
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub kind: u8,
+    pub flags: u8,
+    pub stream: u32,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub const SETTINGS: u8 = 0x4;
+    pub const PING: u8 = 0x6;
+    pub const WINDOW_UPDATE: u8 = 0x8;
+    pub const ACK: u8 = 0x1;
+}
+
+/// A server accepting HTTP/2 connections and recording the frames its clients send, without
+/// ever answering them: neither requests, nor settings, nor pings
+#[derive(Clone, Default)]
+pub struct Silent {
+    frames: std::sync::Arc<std::sync::Mutex<Vec<Frame>>>,
+    closed: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Silent {
+    /// Serves on a free local port, returning the endpoint of the server.
+    pub async fn serve(&self) -> std::io::Result<String> {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        let silent = self.clone();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                let silent = silent.clone();
+                tokio::spawn(async move {
+                    let mut preface = [0; 24];
+                    let read = async {
+                        client.read_exact(&mut preface).await?;
+                        loop {
+                            let mut header = [0; 9];
+                            client.read_exact(&mut header).await?;
+                            let length = u32::from_be_bytes([0, header[0], header[1], header[2]]);
+                            let mut payload = vec![0; length as usize];
+                            client.read_exact(&mut payload).await?;
+                            silent.frames.lock().unwrap().push(Frame {
+                                kind: header[3],
+                                flags: header[4],
+                                stream: u32::from_be_bytes([
+                                    header[5], header[6], header[7], header[8],
+                                ]) & 0x7fff_ffff,
+                                payload,
+                            });
+                        }
+                    };
+                    let _: std::io::Result<()> = read.await;
+                    silent
+                        .closed
+                        .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                });
+            }
+        });
+        Ok(endpoint)
+    }
+
+    /// Returns the frames received so far, over all connections.
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames.lock().unwrap().clone()
+    }
+
+    /// Returns the number of connections the clients closed so far.
+    pub fn closed(&self) -> usize {
+        self.closed.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Returns the number of pings received so far, not counting acknowledgements.
+    pub fn pings(&self) -> usize {
+        self.frames()
+            .iter()
+            .filter(|frame| frame.kind == Frame::PING && frame.flags & Frame::ACK == 0)
+            .count()
+    }
+
+    /// Returns the initial stream window the clients announced in their settings, or the
+    /// default of 65535 bytes if they did not.
+    pub fn stream_window(&self) -> u32 {
+        const INITIAL_WINDOW_SIZE: u16 = 0x4;
+
+        self.frames()
+            .iter()
+            .filter(|frame| frame.kind == Frame::SETTINGS && frame.flags & Frame::ACK == 0)
+            .flat_map(|frame| frame.payload.chunks_exact(6).map(<[u8]>::to_vec))
+            .filter(|setting| u16::from_be_bytes([setting[0], setting[1]]) == INITIAL_WINDOW_SIZE)
+            .map(|setting| u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]))
+            .next_back()
+            .unwrap_or(65535)
+    }
+
+    /// Returns the connection window the clients opened, the default of 65535 bytes plus
+    /// their window updates of the connection.
+    pub fn connection_window(&self) -> u32 {
+        self.frames()
+            .iter()
+            .filter(|frame| frame.kind == Frame::WINDOW_UPDATE && frame.stream == 0)
+            .map(|frame| {
+                let increment = &frame.payload[..4];
+                u32::from_be_bytes([increment[0], increment[1], increment[2], increment[3]])
+                    & 0x7fff_ffff
+            })
+            .fold(65535, |window, increment| window + increment)
+    }
+}
+
+/// A v1alpha reflection service for files, recording the `host` of every request it receives
+#[derive(Clone, Default)]
+pub struct Schema {
+    files: std::sync::Arc<std::sync::Mutex<Vec<FileDescriptorProto>>>,
+    hosts: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+impl Schema {
+    /// Describes the services of `files`, which import no other files.
+    pub fn new(files: Vec<FileDescriptorProto>) -> Self {
+        Self {
+            files: std::sync::Arc::new(std::sync::Mutex::new(files)),
+            ..Default::default()
+        }
+    }
+
+    /// Describes `files` from now on, in place of the files described so far.
+    pub fn replace(&self, files: Vec<FileDescriptorProto>) {
+        *self.files.lock().unwrap() = files;
+    }
+
+    /// Returns the `host` of the requests received so far.
+    pub fn hosts(&self) -> Vec<String> {
+        self.hosts.lock().unwrap().clone()
+    }
+
+    /// Serves on a free local port, returning the endpoint of the server.
+    pub async fn serve(&self) -> std::io::Result<String> {
+        serve(Server::builder().add_service(ServerReflectionServer::new(self.clone()))).await
+    }
+
+    fn respond(
+        &self,
+        request: tonic_reflection::pb::ServerReflectionRequest,
+    ) -> tonic_reflection::pb::ServerReflectionResponse {
+        use prost::Message;
+        use tonic_reflection::pb::server_reflection_request::MessageRequest;
+        use tonic_reflection::pb::server_reflection_response::MessageResponse;
+        use tonic_reflection::pb::{
+            ErrorResponse, FileDescriptorResponse, ListServiceResponse, ServiceResponse,
+        };
+
+        self.hosts.lock().unwrap().push(request.host.clone());
+        let files = self.files.lock().unwrap().clone();
+        let qualify =
+            |file: &FileDescriptorProto, name: &str| format!("{}.{}", file.package(), name);
+        let found = |file: Option<&FileDescriptorProto>| match file {
+            Some(file) => MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+                file_descriptor_proto: vec![file.encode_to_vec()],
+            }),
+            None => MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: tonic::Code::NotFound as i32,
+                error_message: "not found".to_string(),
+            }),
+        };
+
+        let response = match &request.message_request {
+            Some(MessageRequest::ListServices(_)) => {
+                MessageResponse::ListServicesResponse(ListServiceResponse {
+                    service: files
+                        .iter()
+                        .flat_map(|file| file.service.iter().map(move |service| (file, service)))
+                        .map(|(file, service)| ServiceResponse {
+                            name: qualify(file, service.name()),
+                        })
+                        .collect(),
+                })
+            }
+            Some(MessageRequest::FileByFilename(name)) => {
+                found(files.iter().find(|file| file.name() == name))
+            }
+            Some(MessageRequest::FileContainingSymbol(symbol)) => {
+                found(files.iter().find(|file| {
+                    let names = file.service.iter().map(|service| service.name());
+                    let mut names =
+                        names.chain(file.message_type.iter().map(|message| message.name()));
+                    names.any(|name| qualify(file, name) == *symbol)
+                }))
+            }
+            _ => MessageResponse::ErrorResponse(ErrorResponse {
+                error_code: tonic::Code::Unimplemented as i32,
+                error_message: "not implemented".to_string(),
+            }),
+        };
+        tonic_reflection::pb::ServerReflectionResponse {
+            valid_host: request.host.clone(),
+            original_request: Some(request),
+            message_response: Some(response),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl ServerReflection for Schema {
+    type ServerReflectionInfoStream = std::pin::Pin<
+        Box<
+            dyn tokio_stream::Stream<
+                    Item = Result<tonic_reflection::pb::ServerReflectionResponse, Status>,
+                > + Send,
+        >,
+    >;
+
+    // the responses are results with a `Status`, as the trait requires
+    #[allow(clippy::result_large_err)]
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<tonic_reflection::pb::ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let schema = self.clone();
+        let responses = request
+            .into_inner()
+            .map(move |request| request.map(|request| schema.respond(request)));
+        Ok(Response::new(Box::pin(responses)))
+    }
+}
@@ -0,0 +1,159 @@
+//! Connecting to in-process servers with certificates issued by a throwaway CA, which neither
+//! the system nor the client trusts unless told to.
+#![cfg(feature = "tls")]
+
+// shared with the examples, which use more of it than the tests here
+#[allow(dead_code)]
+#[path = "support/servers.rs"]
+mod support;
+
+use grpc_ease::error::ReflectionError;
+use grpc_ease::reflection::ReflectionClient;
+use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, Server, ServerTlsConfig};
+
+/// A CA along with an identity for the server and one for the client, both issued by it
+struct Pki {
+    ca: String,
+    server: (String, String),
+    client: (String, String),
+}
+
+impl Pki {
+    fn generate() -> Result<Self, rcgen::Error> {
+        let mut params = CertificateParams::new(Vec::new())?;
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "grpc-ease test CA");
+        let ca_key = KeyPair::generate()?;
+        let ca = params.self_signed(&ca_key)?;
+
+        let issue = |names: Vec<String>, purpose| -> Result<(String, String), rcgen::Error> {
+            let mut params = CertificateParams::new(names)?;
+            params.extended_key_usages = vec![purpose];
+            let key = KeyPair::generate()?;
+            let cert = params.signed_by(&key, &ca, &ca_key)?;
+            Ok((cert.pem(), key.serialize_pem()))
+        };
+        Ok(Self {
+            server: issue(
+                vec!["127.0.0.1".to_string()],
+                ExtendedKeyUsagePurpose::ServerAuth,
+            )?,
+            client: issue(
+                vec!["grpc-ease".to_string()],
+                ExtendedKeyUsagePurpose::ClientAuth,
+            )?,
+            ca: ca.pem(),
+        })
+    }
+
+    /// Serves reflection over TLS, requiring clients to present an identity issued by the CA
+    /// if `mutual` is set, and returns the `https` endpoint of the server.
+    async fn serve(&self, mutual: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let (cert, key) = &self.server;
+        let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+        if mutual {
+            tls = tls.client_ca_root(Certificate::from_pem(&self.ca));
+        }
+        let router = Server::builder()
+            .tls_config(tls)?
+            .add_service(support::reflection(&[
+                tonic_reflection::pb::FILE_DESCRIPTOR_SET,
+            ]));
+        let endpoint = support::serve(router).await?;
+        Ok(endpoint.replace("http://", "https://"))
+    }
+}
+
+#[tokio::test]
+async fn server_certificates_are_verified_against_the_configured_cas() {
+    let pki = Pki::generate().unwrap();
+    let endpoint = pki.serve(false).await.unwrap();
+
+    // the system does not trust the CA
+    let error = ReflectionClient::builder(&endpoint)
+        .build()
+        .await
+        .err()
+        .expect("the server certificate is not trusted");
+    assert!(
+        matches!(error, ReflectionError::TlsHandshake { .. }),
+        "{:?}",
+        error
+    );
+
+    let mut client = ReflectionClient::builder(&endpoint)
+        .ca_certificate(&pki.ca)
+        .build()
+        .await
+        .unwrap();
+    assert!(!client.list_service_names().await.unwrap().is_empty());
+
+    let mut client = ReflectionClient::builder(&endpoint)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .await
+        .unwrap();
+    assert!(!client.list_service_names().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn tls_configurations_replace_the_configured_cas() {
+    let pki = Pki::generate().unwrap();
+    let endpoint = pki.serve(false).await.unwrap();
+
+    let tls = ClientTlsConfig::new().ca_certificate(Certificate::from_pem(&pki.ca));
+    let mut client = ReflectionClient::builder(&endpoint)
+        .tls(tls)
+        .build()
+        .await
+        .unwrap();
+    assert!(!client.list_service_names().await.unwrap().is_empty());
+
+    // the CA is ignored in favor of the configuration, which does not trust it
+    let error = ReflectionClient::builder(&endpoint)
+        .ca_certificate(&pki.ca)
+        .tls(ClientTlsConfig::new())
+        .build()
+        .await
+        .err()
+        .expect("the server certificate is not trusted");
+    assert!(
+        matches!(error, ReflectionError::TlsHandshake { .. }),
+        "{:?}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn servers_requiring_mutual_tls_accept_the_configured_identity() {
+    let pki = Pki::generate().unwrap();
+    let endpoint = pki.serve(true).await.unwrap();
+
+    // trusted by the client, but rejected by the server for lack of an identity
+    let error = async {
+        let mut client = ReflectionClient::builder(&endpoint)
+            .ca_certificate(&pki.ca)
+            .build()
+            .await?;
+        client.list_service_names().await
+    }
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(error, ReflectionError::TlsHandshake { .. }),
+        "{:?}",
+        error
+    );
+
+    let (cert, key) = &pki.client;
+    let mut client = ReflectionClient::builder(&endpoint)
+        .ca_certificate(&pki.ca)
+        .identity(cert.as_bytes(), key.as_bytes())
+        .build()
+        .await
+        .unwrap();
+    assert!(!client.list_service_names().await.unwrap().is_empty());
+}